@@ -0,0 +1,38 @@
+-- Tables as they existed before the first manual migration. Existing
+-- databases already have them, so every statement is a no-op there.
+CREATE TABLE IF NOT EXISTS allocators
+(
+    id serial NOT NULL,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    installation_id bigint,
+    multisig_address text,
+    verifiers_gh_handles text,
+    multisig_threshold integer,
+    allocation_amount_type text,
+    address text,
+    tooling text,
+    PRIMARY KEY (id),
+    UNIQUE (owner, repo)
+);
+
+CREATE TABLE IF NOT EXISTS allocation_amounts
+(
+    id serial NOT NULL,
+    allocator_id integer NOT NULL REFERENCES allocators (id) ON DELETE CASCADE,
+    quantity_option text NOT NULL,
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE IF NOT EXISTS applications
+(
+    id text NOT NULL,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    pr_number bigint NOT NULL,
+    application text,
+    updated_at timestamp with time zone NOT NULL DEFAULT now(),
+    sha text,
+    path text,
+    PRIMARY KEY (id, owner, repo, pr_number)
+);
@@ -0,0 +1,5 @@
+ALTER TABLE allocators
+    DROP COLUMN IF EXISTS data_types,
+    DROP COLUMN IF EXISTS required_sps,
+    DROP COLUMN IF EXISTS required_replicas,
+    DROP COLUMN IF EXISTS registry_file_path;
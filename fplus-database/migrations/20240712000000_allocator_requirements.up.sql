@@ -0,0 +1,5 @@
+ALTER TABLE allocators
+    ADD COLUMN IF NOT EXISTS data_types text[],
+    ADD COLUMN IF NOT EXISTS required_sps text,
+    ADD COLUMN IF NOT EXISTS required_replicas text,
+    ADD COLUMN IF NOT EXISTS registry_file_path text;
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS application_owner_repo_issue_number_pr_number;
+
+ALTER TABLE applications
+    DROP COLUMN IF EXISTS issue_number;
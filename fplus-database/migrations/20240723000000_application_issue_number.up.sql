@@ -0,0 +1,12 @@
+ALTER TABLE applications
+    ADD COLUMN IF NOT EXISTS issue_number bigint;
+
+UPDATE applications SET issue_number = (application::json->>'Issue Number')::bigint
+WHERE issue_number IS NULL;
+
+ALTER TABLE applications
+    ALTER COLUMN issue_number SET NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS application_owner_repo_issue_number_pr_number
+ON applications USING btree
+(owner, repo, issue_number, pr_number);
@@ -1,6 +1,6 @@
-CREATE TABLE autoallocations
+CREATE TABLE IF NOT EXISTS autoallocations
 (
     evm_wallet_address character varying(42) NOT NULL,
     last_allocation timestamp with time zone NOT NULL,
     PRIMARY KEY (evm_wallet_address)
-);
\ No newline at end of file
+);
@@ -0,0 +1,2 @@
+ALTER TABLE allocators
+    DROP COLUMN IF EXISTS client_contract_address;
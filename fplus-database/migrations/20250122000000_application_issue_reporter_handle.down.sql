@@ -0,0 +1,2 @@
+ALTER TABLE applications
+    DROP COLUMN IF EXISTS issue_reporter_handle;
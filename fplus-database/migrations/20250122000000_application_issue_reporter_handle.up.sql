@@ -0,0 +1,2 @@
+ALTER TABLE applications
+    ADD COLUMN IF NOT EXISTS issue_reporter_handle text;
@@ -1,4 +1,4 @@
-CREATE TABLE comparable_applications
+CREATE TABLE IF NOT EXISTS comparable_applications
 (
     client_address text NOT NULL,
     application jsonb NOT NULL,
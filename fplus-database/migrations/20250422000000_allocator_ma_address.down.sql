@@ -0,0 +1,2 @@
+ALTER TABLE allocators
+    DROP COLUMN IF EXISTS ma_address;
@@ -0,0 +1,2 @@
+ALTER TABLE allocators
+    DROP COLUMN IF EXISTS comment_templates;
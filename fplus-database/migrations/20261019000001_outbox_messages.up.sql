@@ -0,0 +1,22 @@
+-- Side effects (GitHub comments, labels, merges) committed together with the
+-- database change that caused them and applied afterwards by the outbox worker.
+CREATE TABLE IF NOT EXISTS outbox_messages
+(
+    id bigserial NOT NULL,
+    aggregate text NOT NULL,
+    kind text NOT NULL,
+    payload text NOT NULL,
+    status text NOT NULL DEFAULT 'pending',
+    attempts integer NOT NULL DEFAULT 0,
+    last_error text,
+    next_attempt_at timestamp with time zone NOT NULL DEFAULT now(),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    updated_at timestamp with time zone NOT NULL DEFAULT now(),
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX IF NOT EXISTS outbox_messages_status_next_attempt_at_idx
+    ON outbox_messages (status, next_attempt_at);
+
+CREATE INDEX IF NOT EXISTS outbox_messages_aggregate_idx
+    ON outbox_messages (aggregate, id);
@@ -0,0 +1,19 @@
+-- Previous states of application rows, written in the same transaction as
+-- every update, merge and deletion. Rows are kept after the application is
+-- deleted, so there is no foreign key to applications.
+CREATE TABLE IF NOT EXISTS application_versions
+(
+    application_id text NOT NULL,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    version integer NOT NULL,
+    pr_number bigint NOT NULL,
+    application text,
+    sha text,
+    path text,
+    operation text NOT NULL,
+    actor text,
+    reason text NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    PRIMARY KEY (application_id, owner, repo, version)
+);
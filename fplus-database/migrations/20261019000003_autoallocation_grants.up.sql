@@ -0,0 +1,18 @@
+-- Every autoallocation granted, with the Gitcoin Passport score it was granted
+-- for. Lifetime caps per EVM wallet and per client address are summed from it.
+CREATE TABLE IF NOT EXISTS autoallocation_grants
+(
+    id bigserial NOT NULL,
+    evm_wallet_address character varying(42) NOT NULL,
+    client_fil_address text NOT NULL,
+    amount bigint NOT NULL,
+    score double precision NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX IF NOT EXISTS autoallocation_grants_evm_wallet_address_idx
+    ON autoallocation_grants (evm_wallet_address);
+
+CREATE INDEX IF NOT EXISTS autoallocation_grants_client_fil_address_idx
+    ON autoallocation_grants (client_fil_address);
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS autoallocation_transactions;
+
+ALTER TABLE autoallocation_grants
+    DROP COLUMN IF EXISTS status;
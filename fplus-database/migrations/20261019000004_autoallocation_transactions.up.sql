@@ -0,0 +1,30 @@
+-- Grants are pending until their transaction is confirmed. Existing grants were
+-- confirmed synchronously.
+ALTER TABLE autoallocation_grants
+    ADD COLUMN IF NOT EXISTS status text NOT NULL DEFAULT 'granted';
+
+-- Transactions sent for autoallocation grants. A stuck transaction is replaced
+-- by a new row with the same nonce and higher fees.
+CREATE TABLE IF NOT EXISTS autoallocation_transactions
+(
+    id bigserial NOT NULL,
+    grant_id bigint NOT NULL,
+    tx_hash text NOT NULL,
+    nonce bigint NOT NULL,
+    raw_transaction text NOT NULL,
+    max_fee_per_gas text NOT NULL,
+    max_priority_fee_per_gas text NOT NULL,
+    status text NOT NULL DEFAULT 'pending',
+    gas_used bigint,
+    block_number bigint,
+    last_error text,
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    updated_at timestamp with time zone NOT NULL DEFAULT now(),
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX IF NOT EXISTS autoallocation_transactions_status_idx
+    ON autoallocation_transactions (status);
+
+CREATE INDEX IF NOT EXISTS autoallocation_transactions_grant_id_idx
+    ON autoallocation_transactions (grant_id);
@@ -0,0 +1,16 @@
+DELETE FROM autoallocations WHERE network <> 'mainnet';
+
+ALTER TABLE autoallocations
+    DROP CONSTRAINT IF EXISTS autoallocations_pkey;
+
+ALTER TABLE autoallocations
+    ADD PRIMARY KEY (evm_wallet_address);
+
+ALTER TABLE autoallocations
+    DROP COLUMN IF EXISTS network;
+
+ALTER TABLE autoallocation_transactions
+    DROP COLUMN IF EXISTS network;
+
+ALTER TABLE autoallocation_grants
+    DROP COLUMN IF EXISTS network;
@@ -0,0 +1,17 @@
+-- Autoallocations are sent per Filecoin network. Existing rows were all sent
+-- on mainnet.
+ALTER TABLE autoallocation_grants
+    ADD COLUMN IF NOT EXISTS network text NOT NULL DEFAULT 'mainnet';
+
+ALTER TABLE autoallocation_transactions
+    ADD COLUMN IF NOT EXISTS network text NOT NULL DEFAULT 'mainnet';
+
+-- The cooldown between autoallocations is kept per network.
+ALTER TABLE autoallocations
+    ADD COLUMN IF NOT EXISTS network text NOT NULL DEFAULT 'mainnet';
+
+ALTER TABLE autoallocations
+    DROP CONSTRAINT IF EXISTS autoallocations_pkey;
+
+ALTER TABLE autoallocations
+    ADD PRIMARY KEY (evm_wallet_address, network);
@@ -0,0 +1,14 @@
+-- Signed KYC and autoallocation messages that were already accepted. A row is
+-- kept until the message expires, after which the message is rejected anyway.
+CREATE TABLE IF NOT EXISTS used_signatures
+(
+    signature_hash text NOT NULL,
+    kind text NOT NULL,
+    signer text NOT NULL,
+    expires_at timestamp with time zone NOT NULL,
+    used_at timestamp with time zone NOT NULL DEFAULT now(),
+    PRIMARY KEY (signature_hash)
+);
+
+CREATE INDEX IF NOT EXISTS used_signatures_expires_at_idx
+    ON used_signatures (expires_at);
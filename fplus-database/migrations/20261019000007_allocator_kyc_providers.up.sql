@@ -0,0 +1,2 @@
+ALTER TABLE allocators
+    ADD COLUMN IF NOT EXISTS kyc_providers text;
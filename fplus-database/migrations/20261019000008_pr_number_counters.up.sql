@@ -0,0 +1,15 @@
+-- Last pull request number handed out per repository by allocators which keep
+-- their applications in the database. Numbers are allocated by incrementing
+-- the row, so concurrent allocations never get the same number.
+CREATE TABLE IF NOT EXISTS pr_number_counters
+(
+    owner text NOT NULL,
+    repo text NOT NULL,
+    last_pr_number bigint NOT NULL,
+    PRIMARY KEY (owner, repo)
+);
+
+INSERT INTO pr_number_counters (owner, repo, last_pr_number)
+SELECT owner, repo, MAX(pr_number)
+FROM applications
+GROUP BY owner, repo;
@@ -0,0 +1,4 @@
+-- Incremented by every write of the application file, so a commit can check
+-- that the row is still at the version it was based on.
+ALTER TABLE applications
+    ADD COLUMN IF NOT EXISTS version bigint NOT NULL DEFAULT 0;
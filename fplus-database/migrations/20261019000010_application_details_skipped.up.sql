@@ -0,0 +1,15 @@
+-- Applications whose JSON could not be normalized by the backfill, so it
+-- does not pick them up again. The JSON stays the source of truth.
+CREATE TABLE IF NOT EXISTS application_details_skipped
+(
+    application_id text NOT NULL,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    pr_number bigint NOT NULL,
+    reason text NOT NULL,
+    skipped_at timestamp with time zone NOT NULL DEFAULT now(),
+    PRIMARY KEY (application_id, owner, repo, pr_number),
+    FOREIGN KEY (application_id, owner, repo, pr_number)
+        REFERENCES applications (id, owner, repo, pr_number)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
@@ -0,0 +1,16 @@
+-- Last history version handed out per application. Versions are allocated by
+-- incrementing the row inside the write's transaction, which keeps concurrent
+-- writes of one application from taking the same number.
+CREATE TABLE IF NOT EXISTS application_version_counters
+(
+    application_id text NOT NULL,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    last_version integer NOT NULL,
+    PRIMARY KEY (application_id, owner, repo)
+);
+
+INSERT INTO application_version_counters (application_id, owner, repo, last_version)
+SELECT application_id, owner, repo, MAX(version)
+FROM application_versions
+GROUP BY application_id, owner, repo;
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS autoallocation_cap_locks;
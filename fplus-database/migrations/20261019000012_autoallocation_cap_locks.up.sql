@@ -0,0 +1,9 @@
+-- One row per capped wallet or client address of a network. Granting an
+-- autoallocation locks the rows of its holders before summing their grants, so
+-- concurrent grants of one holder are checked against the cap one at a time.
+CREATE TABLE IF NOT EXISTS autoallocation_cap_locks
+(
+    network text NOT NULL,
+    holder text NOT NULL,
+    PRIMARY KEY (network, holder)
+);
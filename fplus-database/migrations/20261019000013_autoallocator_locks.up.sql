@@ -0,0 +1,19 @@
+-- One row per autoallocator wallet of a network. Picking the nonce of a new
+-- transaction locks the row until the signed transaction is recorded, so
+-- concurrent autoallocations from any instance get distinct nonces.
+CREATE TABLE IF NOT EXISTS autoallocator_nonce_locks
+(
+    network text NOT NULL,
+    wallet_address character varying(42) NOT NULL,
+    PRIMARY KEY (network, wallet_address)
+);
+
+-- Background jobs that must run on a single instance. The holder renews its
+-- lease every round, another instance takes over once it expired.
+CREATE TABLE IF NOT EXISTS leader_leases
+(
+    name text NOT NULL,
+    holder text NOT NULL,
+    expires_at timestamp with time zone NOT NULL,
+    PRIMARY KEY (name)
+);
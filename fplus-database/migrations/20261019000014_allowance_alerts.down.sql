@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS allowance_reports;
+DROP TABLE IF EXISTS allowance_alert_states;
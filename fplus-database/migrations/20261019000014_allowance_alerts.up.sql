@@ -0,0 +1,20 @@
+-- Whether each allowance holder was low at the last check, and since when. An
+-- alert is sent when the row flips to low, so it fires once whichever instance
+-- checks.
+CREATE TABLE IF NOT EXISTS allowance_alert_states
+(
+    kind text NOT NULL,
+    name text NOT NULL,
+    low boolean NOT NULL,
+    changed_at timestamp with time zone NOT NULL DEFAULT now(),
+    PRIMARY KEY (kind, name)
+);
+
+-- The report of the last allowance check, served by the status endpoint.
+CREATE TABLE IF NOT EXISTS allowance_reports
+(
+    name text NOT NULL,
+    report text NOT NULL,
+    checked_at timestamp with time zone NOT NULL DEFAULT now(),
+    PRIMARY KEY (name)
+);
@@ -0,0 +1,3 @@
+-- The initial schema is irreversible: reverting it would drop every table.
+-- Restore a backup instead.
+SELECT 1;
@@ -0,0 +1,35 @@
+-- SQLite version of the initial schema, timestamps are stored as RFC 3339 text.
+CREATE TABLE IF NOT EXISTS allocators
+(
+    id integer PRIMARY KEY AUTOINCREMENT,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    installation_id bigint,
+    multisig_address text,
+    verifiers_gh_handles text,
+    multisig_threshold integer,
+    allocation_amount_type text,
+    address text,
+    tooling text,
+    UNIQUE (owner, repo)
+);
+
+CREATE TABLE IF NOT EXISTS allocation_amounts
+(
+    id integer PRIMARY KEY AUTOINCREMENT,
+    allocator_id integer NOT NULL REFERENCES allocators (id) ON DELETE CASCADE,
+    quantity_option text NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS applications
+(
+    id text NOT NULL,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    pr_number bigint NOT NULL,
+    application text,
+    updated_at text NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    sha text,
+    path text,
+    PRIMARY KEY (id, owner, repo, pr_number)
+);
@@ -0,0 +1,4 @@
+ALTER TABLE allocators DROP COLUMN data_types;
+ALTER TABLE allocators DROP COLUMN required_sps;
+ALTER TABLE allocators DROP COLUMN required_replicas;
+ALTER TABLE allocators DROP COLUMN registry_file_path;
@@ -0,0 +1,5 @@
+-- SQLite has no arrays, data_types is not mapped by the model anyway.
+ALTER TABLE allocators ADD COLUMN data_types text;
+ALTER TABLE allocators ADD COLUMN required_sps text;
+ALTER TABLE allocators ADD COLUMN required_replicas text;
+ALTER TABLE allocators ADD COLUMN registry_file_path text;
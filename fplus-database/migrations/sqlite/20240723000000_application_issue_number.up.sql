@@ -0,0 +1,8 @@
+-- SQLite cannot add a NOT NULL column without a default.
+ALTER TABLE applications ADD COLUMN issue_number bigint NOT NULL DEFAULT 0;
+
+UPDATE applications SET issue_number = CAST(json_extract(application, '$."Issue Number"') AS integer)
+WHERE application IS NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS application_owner_repo_issue_number_pr_number
+ON applications (owner, repo, issue_number, pr_number);
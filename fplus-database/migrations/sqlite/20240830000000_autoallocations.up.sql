@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS autoallocations
+(
+    evm_wallet_address varchar(42) NOT NULL,
+    last_allocation text NOT NULL,
+    PRIMARY KEY (evm_wallet_address)
+);
@@ -0,0 +1 @@
+ALTER TABLE allocators DROP COLUMN client_contract_address;
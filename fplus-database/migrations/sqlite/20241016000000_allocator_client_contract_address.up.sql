@@ -0,0 +1 @@
+ALTER TABLE allocators ADD COLUMN client_contract_address text;
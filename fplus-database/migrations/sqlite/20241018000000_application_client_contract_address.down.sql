@@ -0,0 +1 @@
+ALTER TABLE applications DROP COLUMN client_contract_address;
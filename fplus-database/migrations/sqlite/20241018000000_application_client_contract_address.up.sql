@@ -0,0 +1 @@
+ALTER TABLE applications ADD COLUMN client_contract_address text;
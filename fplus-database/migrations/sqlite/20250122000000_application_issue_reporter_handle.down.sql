@@ -0,0 +1 @@
+ALTER TABLE applications DROP COLUMN issue_reporter_handle;
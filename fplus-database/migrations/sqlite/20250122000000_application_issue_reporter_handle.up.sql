@@ -0,0 +1 @@
+ALTER TABLE applications ADD COLUMN issue_reporter_handle text;
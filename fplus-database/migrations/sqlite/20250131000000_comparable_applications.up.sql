@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS comparable_applications
+(
+    client_address text NOT NULL,
+    application text NOT NULL,
+    PRIMARY KEY (client_address)
+);
@@ -0,0 +1 @@
+ALTER TABLE allocators DROP COLUMN ma_address;
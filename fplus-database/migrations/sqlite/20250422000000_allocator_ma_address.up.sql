@@ -0,0 +1 @@
+ALTER TABLE allocators ADD COLUMN ma_address text;
@@ -0,0 +1 @@
+ALTER TABLE allocators DROP COLUMN application_store;
@@ -0,0 +1 @@
+ALTER TABLE allocators ADD COLUMN application_store text;
@@ -0,0 +1 @@
+ALTER TABLE allocators DROP COLUMN comment_templates;
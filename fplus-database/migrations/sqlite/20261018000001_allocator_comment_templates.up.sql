@@ -0,0 +1 @@
+ALTER TABLE allocators ADD COLUMN comment_templates text;
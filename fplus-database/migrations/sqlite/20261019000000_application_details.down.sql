@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS application_sps_change_signers;
+DROP TABLE IF EXISTS application_sps_change_requests;
+DROP TABLE IF EXISTS application_allocation_signers;
+DROP TABLE IF EXISTS application_allocations;
+DROP TABLE IF EXISTS application_lifecycles;
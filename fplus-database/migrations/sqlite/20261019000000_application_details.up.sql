@@ -0,0 +1,100 @@
+-- Normalized copies of the application JSON, kept in sync with
+-- applications.application. Rows follow their application through merges
+-- (pr_number update) and deletion.
+CREATE TABLE IF NOT EXISTS application_lifecycles
+(
+    application_id text NOT NULL,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    pr_number bigint NOT NULL,
+    state text NOT NULL,
+    is_active boolean NOT NULL,
+    validated_at text,
+    validated_by text,
+    updated_at text,
+    active_request_id text,
+    client_on_chain_address text,
+    multisig_address text,
+    PRIMARY KEY (application_id, owner, repo, pr_number),
+    FOREIGN KEY (application_id, owner, repo, pr_number)
+        REFERENCES applications (id, owner, repo, pr_number)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS application_allocations
+(
+    application_id text NOT NULL,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    pr_number bigint NOT NULL,
+    allocation_id text NOT NULL,
+    request_type text NOT NULL,
+    is_active boolean NOT NULL,
+    amount text NOT NULL,
+    amount_of_datacap_sent_to_contract text,
+    created_at text,
+    updated_at text,
+    PRIMARY KEY (application_id, owner, repo, pr_number, allocation_id),
+    FOREIGN KEY (application_id, owner, repo, pr_number)
+        REFERENCES applications (id, owner, repo, pr_number)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS application_allocation_signers
+(
+    application_id text NOT NULL,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    pr_number bigint NOT NULL,
+    allocation_id text NOT NULL,
+    position integer NOT NULL,
+    github_username text NOT NULL,
+    signing_address text NOT NULL,
+    created_at text,
+    message_cid text,
+    increase_allowance_cid text,
+    PRIMARY KEY (application_id, owner, repo, pr_number, allocation_id, position),
+    FOREIGN KEY (application_id, owner, repo, pr_number, allocation_id)
+        REFERENCES application_allocations (application_id, owner, repo, pr_number, allocation_id)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS application_allocation_signers_github_username
+ON application_allocation_signers (github_username, created_at);
+
+CREATE TABLE IF NOT EXISTS application_sps_change_requests
+(
+    application_id text NOT NULL,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    pr_number bigint NOT NULL,
+    request_id text NOT NULL,
+    is_active boolean NOT NULL,
+    allowed_sps text,
+    max_deviation text,
+    created_at text,
+    updated_at text,
+    PRIMARY KEY (application_id, owner, repo, pr_number, request_id),
+    FOREIGN KEY (application_id, owner, repo, pr_number)
+        REFERENCES applications (id, owner, repo, pr_number)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS application_sps_change_signers
+(
+    application_id text NOT NULL,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    pr_number bigint NOT NULL,
+    request_id text NOT NULL,
+    position integer NOT NULL,
+    github_username text NOT NULL,
+    signing_address text NOT NULL,
+    max_deviation_cid text,
+    add_allowed_sps_cids text,
+    remove_allowed_sps_cids text,
+    PRIMARY KEY (application_id, owner, repo, pr_number, request_id, position),
+    FOREIGN KEY (application_id, owner, repo, pr_number, request_id)
+        REFERENCES application_sps_change_requests (application_id, owner, repo, pr_number, request_id)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
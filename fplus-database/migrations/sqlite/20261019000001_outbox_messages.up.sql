@@ -0,0 +1,21 @@
+-- Side effects (GitHub comments, labels, merges) committed together with the
+-- database change that caused them and applied afterwards by the outbox worker.
+CREATE TABLE IF NOT EXISTS outbox_messages
+(
+    id integer PRIMARY KEY AUTOINCREMENT,
+    aggregate text NOT NULL,
+    kind text NOT NULL,
+    payload text NOT NULL,
+    status text NOT NULL DEFAULT 'pending',
+    attempts integer NOT NULL DEFAULT 0,
+    last_error text,
+    next_attempt_at text NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    created_at text NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at text NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS outbox_messages_status_next_attempt_at_idx
+    ON outbox_messages (status, next_attempt_at);
+
+CREATE INDEX IF NOT EXISTS outbox_messages_aggregate_idx
+    ON outbox_messages (aggregate, id);
@@ -0,0 +1,17 @@
+-- Every autoallocation granted, with the Gitcoin Passport score it was granted
+-- for. Lifetime caps per EVM wallet and per client address are summed from it.
+CREATE TABLE IF NOT EXISTS autoallocation_grants
+(
+    id integer PRIMARY KEY AUTOINCREMENT,
+    evm_wallet_address varchar(42) NOT NULL,
+    client_fil_address text NOT NULL,
+    amount bigint NOT NULL,
+    score real NOT NULL,
+    created_at text NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS autoallocation_grants_evm_wallet_address_idx
+    ON autoallocation_grants (evm_wallet_address);
+
+CREATE INDEX IF NOT EXISTS autoallocation_grants_client_fil_address_idx
+    ON autoallocation_grants (client_fil_address);
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS autoallocation_transactions;
+
+ALTER TABLE autoallocation_grants DROP COLUMN status;
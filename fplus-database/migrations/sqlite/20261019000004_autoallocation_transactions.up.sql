@@ -0,0 +1,24 @@
+ALTER TABLE autoallocation_grants ADD COLUMN status text NOT NULL DEFAULT 'granted';
+
+CREATE TABLE IF NOT EXISTS autoallocation_transactions
+(
+    id integer PRIMARY KEY AUTOINCREMENT,
+    grant_id bigint NOT NULL,
+    tx_hash text NOT NULL,
+    nonce bigint NOT NULL,
+    raw_transaction text NOT NULL,
+    max_fee_per_gas text NOT NULL,
+    max_priority_fee_per_gas text NOT NULL,
+    status text NOT NULL DEFAULT 'pending',
+    gas_used bigint,
+    block_number bigint,
+    last_error text,
+    created_at text NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    updated_at text NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'))
+);
+
+CREATE INDEX IF NOT EXISTS autoallocation_transactions_status_idx
+    ON autoallocation_transactions (status);
+
+CREATE INDEX IF NOT EXISTS autoallocation_transactions_grant_id_idx
+    ON autoallocation_transactions (grant_id);
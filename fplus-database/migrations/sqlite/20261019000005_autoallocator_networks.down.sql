@@ -0,0 +1,14 @@
+CREATE TABLE autoallocations_by_wallet
+(
+    evm_wallet_address varchar(42) NOT NULL,
+    last_allocation text NOT NULL,
+    PRIMARY KEY (evm_wallet_address)
+);
+INSERT INTO autoallocations_by_wallet (evm_wallet_address, last_allocation)
+    SELECT evm_wallet_address, last_allocation FROM autoallocations
+    WHERE network = 'mainnet';
+DROP TABLE autoallocations;
+ALTER TABLE autoallocations_by_wallet RENAME TO autoallocations;
+
+ALTER TABLE autoallocation_transactions DROP COLUMN network;
+ALTER TABLE autoallocation_grants DROP COLUMN network;
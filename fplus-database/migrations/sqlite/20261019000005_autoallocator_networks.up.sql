@@ -0,0 +1,15 @@
+ALTER TABLE autoallocation_grants ADD COLUMN network text NOT NULL DEFAULT 'mainnet';
+ALTER TABLE autoallocation_transactions ADD COLUMN network text NOT NULL DEFAULT 'mainnet';
+
+-- SQLite cannot change a primary key, the table is rebuilt.
+CREATE TABLE autoallocations_by_network
+(
+    evm_wallet_address varchar(42) NOT NULL,
+    network text NOT NULL DEFAULT 'mainnet',
+    last_allocation text NOT NULL,
+    PRIMARY KEY (evm_wallet_address, network)
+);
+INSERT INTO autoallocations_by_network (evm_wallet_address, last_allocation)
+    SELECT evm_wallet_address, last_allocation FROM autoallocations;
+DROP TABLE autoallocations;
+ALTER TABLE autoallocations_by_network RENAME TO autoallocations;
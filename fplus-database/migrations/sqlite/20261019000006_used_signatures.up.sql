@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS used_signatures
+(
+    signature_hash text NOT NULL,
+    kind text NOT NULL,
+    signer text NOT NULL,
+    expires_at text NOT NULL,
+    used_at text NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    PRIMARY KEY (signature_hash)
+);
+
+CREATE INDEX IF NOT EXISTS used_signatures_expires_at_idx
+    ON used_signatures (expires_at);
@@ -0,0 +1 @@
+ALTER TABLE allocators DROP COLUMN kyc_providers;
@@ -0,0 +1 @@
+ALTER TABLE allocators ADD COLUMN kyc_providers text;
@@ -0,0 +1 @@
+ALTER TABLE applications ADD COLUMN version bigint NOT NULL DEFAULT 0;
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS application_details_skipped
+(
+    application_id text NOT NULL,
+    owner text NOT NULL,
+    repo text NOT NULL,
+    pr_number bigint NOT NULL,
+    reason text NOT NULL,
+    skipped_at text NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')),
+    PRIMARY KEY (application_id, owner, repo, pr_number),
+    FOREIGN KEY (application_id, owner, repo, pr_number)
+        REFERENCES applications (id, owner, repo, pr_number)
+        ON UPDATE CASCADE ON DELETE CASCADE
+);
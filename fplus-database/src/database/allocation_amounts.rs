@@ -1,9 +1,8 @@
-use crate::get_database_connection;
 use crate::models::allocation_amounts::{
     ActiveModel, Column, Entity as AllocationAmount, Model as AllocationAmountModel,
     QuantityOptionModel,
 };
-use sea_orm::{entity::*, query::*};
+use sea_orm::{entity::*, query::*, DatabaseConnection};
 
 /**
  * Get all allocation amount rows from the database
@@ -11,9 +10,10 @@ use sea_orm::{entity::*, query::*};
  * # Returns
  * @return Result<Vec<AllocationAmountModel>, sea_orm::DbErr> - The result of the operation
  */
-pub async fn get_allocation_amounts() -> Result<Vec<AllocationAmountModel>, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
-    AllocationAmount::find().all(&conn).await
+pub async fn get_allocation_amounts(
+    db: &DatabaseConnection,
+) -> Result<Vec<AllocationAmountModel>, sea_orm::DbErr> {
+    AllocationAmount::find().all(db).await
 }
 
 /**
@@ -26,15 +26,15 @@ pub async fn get_allocation_amounts() -> Result<Vec<AllocationAmountModel>, sea_
  * @return Result<Vec<String>, sea_orm::DbErr> - The result of the operation
  */
 pub async fn get_allocation_quantity_options(
+    db: &DatabaseConnection,
     allocator_id: i32,
 ) -> Result<Vec<String>, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
     let quantity_options = AllocationAmount::find()
         .select_only()
         .column(Column::QuantityOption)
         .filter(Column::AllocatorId.eq(allocator_id))
         .into_model::<QuantityOptionModel>() // You will need to define this struct
-        .all(&conn)
+        .all(db)
         .await?
         .into_iter()
         .map(|model| model.quantity_option)
@@ -54,18 +54,17 @@ pub async fn get_allocation_quantity_options(
  * @return Result<(), sea_orm::DbErr> - The result of the operation
  */
 pub async fn create_allocation_amount(
+    db: &DatabaseConnection,
     allocator_id: i32,
     allocation_amount: String,
 ) -> Result<(), sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
-
     let new_allocation_amount = ActiveModel {
         allocator_id: Set(allocator_id),
         quantity_option: Set(allocation_amount.clone()),
         ..Default::default()
     };
 
-    let insert_result = new_allocation_amount.insert(&conn).await;
+    let insert_result = new_allocation_amount.insert(db).await;
     println!("Allocation amount inserted: {insert_result:?}");
 
     Ok(())
@@ -81,12 +80,12 @@ pub async fn create_allocation_amount(
 * @return Result<(), sea_orm::DbErr> - The result of the operation
 */
 pub async fn delete_allocation_amounts_by_allocator_id(
+    db: &DatabaseConnection,
     allocator_id: i32,
 ) -> Result<(), sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
     AllocationAmount::delete_many()
         .filter(Column::AllocatorId.eq(allocator_id))
-        .exec(&conn)
+        .exec(db)
         .await?;
 
     Ok(())
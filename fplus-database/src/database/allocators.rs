@@ -1,8 +1,7 @@
-use crate::get_database_connection;
 use crate::models::allocators::{
     ActiveModel, Column, Entity as Allocator, Model as AllocatorModel,
 };
-use sea_orm::{entity::*, query::*, DbErr};
+use sea_orm::{entity::*, query::*, DatabaseConnection, DbErr};
 
 /**
  * Get all allocators from the database
@@ -10,9 +9,10 @@ use sea_orm::{entity::*, query::*, DbErr};
  * # Returns
  * @return Result<Vec<AllocatorModel>, sea_orm::DbErr> - The result of the operation
  */
-pub async fn get_allocators() -> Result<Vec<AllocatorModel>, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
-    Allocator::find().all(&conn).await
+pub async fn get_allocators(
+    db: &DatabaseConnection,
+) -> Result<Vec<AllocatorModel>, sea_orm::DbErr> {
+    Allocator::find().all(db).await
 }
 
 /**
@@ -26,14 +26,14 @@ pub async fn get_allocators() -> Result<Vec<AllocatorModel>, sea_orm::DbErr> {
  * @return Result<Option<AllocatorModel>, sea_orm::DbErr> - The result of the operation
  */
 pub async fn get_allocator(
+    db: &DatabaseConnection,
     owner: &str,
     repo: &str,
 ) -> Result<Option<AllocatorModel>, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
     Allocator::find()
         .filter(Column::Owner.eq(owner))
         .filter(Column::Repo.eq(repo))
-        .one(&conn)
+        .one(db)
         .await
 }
 
@@ -58,6 +58,7 @@ pub async fn get_allocator(
  */
 #[allow(clippy::too_many_arguments)]
 pub async fn create_or_update_allocator(
+    db: &DatabaseConnection,
     owner: String,
     repo: String,
     installation_id: Option<i64>,
@@ -73,9 +74,8 @@ pub async fn create_or_update_allocator(
     client_contract_address: Option<String>,
     ma_address: Option<String>,
 ) -> Result<AllocatorModel, sea_orm::DbErr> {
-    let existing_allocator = get_allocator(&owner, &repo).await?;
+    let existing_allocator = get_allocator(db, &owner, &repo).await?;
     if let Some(allocator_model) = existing_allocator {
-        let conn = get_database_connection().await?;
         let mut allocator_active_model = allocator_model.into_active_model();
 
         if installation_id.is_some() {
@@ -141,7 +141,7 @@ pub async fn create_or_update_allocator(
             allocator_active_model.client_contract_address = Set(None);
         }
 
-        let updated_model = allocator_active_model.update(&conn).await?;
+        let updated_model = allocator_active_model.update(db).await?;
 
         Ok(updated_model)
     } else {
@@ -208,10 +208,7 @@ pub async fn create_or_update_allocator(
                 new_allocator.client_contract_address = Set(None);
             }
         }
-        let conn = get_database_connection()
-            .await
-            .expect("Failed to get DB connection");
-        let insert_result = new_allocator.insert(&conn).await?;
+        let insert_result = new_allocator.insert(db).await?;
         println!("Allocator inserted: {insert_result:?}");
         Ok(insert_result)
     }
@@ -226,18 +223,88 @@ pub async fn create_or_update_allocator(
  * @param installation_id: Option<i64> - The installation ID
  */
 pub async fn update_allocator_installation_ids(
+    db: &DatabaseConnection,
     owner: String,
     repo: String,
     installation_id: Option<i64>,
 ) -> Result<(), sea_orm::DbErr> {
-    let existing_allocator = get_allocator(&owner, &repo).await?;
+    let existing_allocator = get_allocator(db, &owner, &repo).await?;
     if let Some(allocator_model) = existing_allocator {
-        let conn = get_database_connection().await?;
         let mut allocator_active_model = allocator_model.into_active_model();
         if installation_id.is_some() {
             allocator_active_model.installation_id = Set(installation_id);
         }
-        allocator_active_model.update(&conn).await?;
+        allocator_active_model.update(db).await?;
+    }
+    Ok(())
+}
+
+/**
+ * Update the application store backend of an allocator in the database
+ *
+ * # Arguments
+ * @param owner: String - The owner of the repository
+ * @param repo: String - The repository name
+ * @param application_store: Option<String> - The application store backend, `None` resets it to the default
+ */
+pub async fn update_allocator_application_store(
+    db: &DatabaseConnection,
+    owner: String,
+    repo: String,
+    application_store: Option<String>,
+) -> Result<(), sea_orm::DbErr> {
+    let existing_allocator = get_allocator(db, &owner, &repo).await?;
+    if let Some(allocator_model) = existing_allocator {
+        let mut allocator_active_model = allocator_model.into_active_model();
+        allocator_active_model.application_store =
+            Set(application_store.map(|store| store.to_lowercase()));
+        allocator_active_model.update(db).await?;
+    }
+    Ok(())
+}
+
+/**
+ * Update the issue comment templates of an allocator in the database
+ *
+ * # Arguments
+ * @param owner: String - The owner of the repository
+ * @param repo: String - The repository name
+ * @param comment_templates: Option<String> - JSON map of comment kinds to templates, `None` restores the defaults
+ */
+pub async fn update_allocator_comment_templates(
+    db: &DatabaseConnection,
+    owner: String,
+    repo: String,
+    comment_templates: Option<String>,
+) -> Result<(), sea_orm::DbErr> {
+    let existing_allocator = get_allocator(db, &owner, &repo).await?;
+    if let Some(allocator_model) = existing_allocator {
+        let mut allocator_active_model = allocator_model.into_active_model();
+        allocator_active_model.comment_templates = Set(comment_templates);
+        allocator_active_model.update(db).await?;
+    }
+    Ok(())
+}
+
+/**
+ * Update the KYC providers of an allocator in the database
+ *
+ * # Arguments
+ * @param owner: String - The owner of the repository
+ * @param repo: String - The repository name
+ * @param kyc_providers: Option<String> - JSON list of KYC provider configurations, `None` restores the default
+ */
+pub async fn update_allocator_kyc_providers(
+    db: &DatabaseConnection,
+    owner: String,
+    repo: String,
+    kyc_providers: Option<String>,
+) -> Result<(), sea_orm::DbErr> {
+    let existing_allocator = get_allocator(db, &owner, &repo).await?;
+    if let Some(allocator_model) = existing_allocator {
+        let mut allocator_active_model = allocator_model.into_active_model();
+        allocator_active_model.kyc_providers = Set(kyc_providers);
+        allocator_active_model.update(db).await?;
     }
     Ok(())
 }
@@ -258,19 +325,19 @@ pub async fn update_allocator_installation_ids(
  * On failure, it returns an error of type DbErr.
  */
 pub async fn update_allocator_threshold(
+    db: &DatabaseConnection,
     owner: &str,
     repo: &str,
     multisig_threshold: i32,
 ) -> Result<AllocatorModel, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
-    let mut existing_allocator = get_allocator(owner, repo)
+    let mut existing_allocator = get_allocator(db, owner, repo)
         .await?
         .ok_or_else(|| DbErr::Custom("Allocator not found".into()))?
         .into_active_model();
 
     existing_allocator.multisig_threshold = Set(Some(multisig_threshold));
 
-    existing_allocator.update(&conn).await
+    existing_allocator.update(db).await
 }
 
 /**
@@ -283,11 +350,14 @@ pub async fn update_allocator_threshold(
  * # Returns
  * @return Result<(), sea_orm::DbErr> - The result of the operation
  */
-pub async fn delete_allocator(owner: &str, repo: &str) -> Result<(), sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
-    let allocator = get_allocator(owner, repo)
+pub async fn delete_allocator(
+    db: &DatabaseConnection,
+    owner: &str,
+    repo: &str,
+) -> Result<(), sea_orm::DbErr> {
+    let allocator = get_allocator(db, owner, repo)
         .await?
         .ok_or(DbErr::Custom("Allocator not found".to_string()))?;
-    allocator.delete(&conn).await?;
+    allocator.delete(db).await?;
     Ok(())
 }
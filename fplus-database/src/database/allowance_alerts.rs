@@ -0,0 +1,116 @@
+use chrono::Utc;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, Statement};
+
+/// Name of the row holding the report of the last check.
+const LATEST_REPORT: &str = "latest";
+
+/**
+ * Record whether an allowance holder is low
+ *
+ * # Arguments
+ * @param kind: &str - The kind of holder
+ * @param name: &str - The holder, unique within its kind
+ * @param low: bool - Whether its allowance is low
+ *
+ * # Returns
+ * @return Result<bool, sea_orm::DbErr> - Whether the holder became low, so an alert is due
+ */
+pub async fn record_allowance_alert_state(
+    db: &DatabaseConnection,
+    kind: &str,
+    name: &str,
+    low: bool,
+) -> Result<bool, DbErr> {
+    // The flip to low is a conditional upsert, only one concurrent check gets
+    // the row affected and sends the alert.
+    let condition = if low {
+        "WHERE allowance_alert_states.low = false"
+    } else {
+        "WHERE allowance_alert_states.low = true"
+    };
+    let exec_res = db
+        .execute(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            format!(
+                "INSERT INTO allowance_alert_states (kind, name, low, changed_at)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (kind, name)
+                DO UPDATE SET low = $3, changed_at = $4
+                {condition}"
+            ),
+            [kind.into(), name.into(), low.into(), Utc::now().into()],
+        ))
+        .await?;
+    Ok(low && exec_res.rows_affected() == 1)
+}
+
+/**
+ * Keep the report of the last allowance check
+ *
+ * # Arguments
+ * @param report: &str - The report, serialized as JSON
+ */
+pub async fn save_allowance_report(db: &DatabaseConnection, report: &str) -> Result<(), DbErr> {
+    db.execute(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "INSERT INTO allowance_reports (name, report, checked_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (name)
+            DO UPDATE SET report = $2, checked_at = $3",
+        [LATEST_REPORT.into(), report.into(), Utc::now().into()],
+    ))
+    .await?;
+    Ok(())
+}
+
+/**
+ * Get the report of the last allowance check
+ *
+ * # Returns
+ * @return Result<Option<String>, sea_orm::DbErr> - The report as JSON, `None` before the first check
+ */
+pub async fn get_allowance_report(db: &DatabaseConnection) -> Result<Option<String>, DbErr> {
+    db.query_one(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "SELECT report FROM allowance_reports WHERE name = $1",
+        [LATEST_REPORT.into()],
+    ))
+    .await?
+    .map(|row| row.try_get::<String>("", "report"))
+    .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::setup_test_environment;
+
+    #[tokio::test]
+    async fn alerts_are_due_once_per_drop() {
+        let db = setup_test_environment().await;
+        let record = |low| record_allowance_alert_state(&db, "allocator", "owner/repo", low);
+        assert!(!record(false).await.unwrap());
+        let (first, second) = futures::join!(record(true), record(true));
+        assert!(first.unwrap() ^ second.unwrap());
+        assert!(!record(true).await.unwrap());
+        assert!(!record(false).await.unwrap());
+        assert!(record(true).await.unwrap());
+        assert!(
+            record_allowance_alert_state(&db, "autoallocator", "owner/repo", true)
+                .await
+                .unwrap()
+        );
+
+        assert_eq!(get_allowance_report(&db).await.unwrap(), None);
+        save_allowance_report(&db, "{\"statuses\":[]}")
+            .await
+            .unwrap();
+        save_allowance_report(&db, "{\"statuses\":[1]}")
+            .await
+            .unwrap();
+        assert_eq!(
+            get_allowance_report(&db).await.unwrap().as_deref(),
+            Some("{\"statuses\":[1]}")
+        );
+    }
+}
@@ -0,0 +1,697 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sea_orm::sea_query::{Expr, Query, SimpleExpr};
+use sea_orm::{entity::*, query::*, ConnectionTrait, DatabaseConnection, DbErr, FromQueryResult};
+use serde::{Deserialize, Serialize};
+
+use crate::models::application_allocation_signers::{self, Entity as ApplicationAllocationSigner};
+use crate::models::application_allocations::{self, Entity as ApplicationAllocation};
+use crate::models::application_details_skipped::{self, Entity as ApplicationDetailsSkipped};
+use crate::models::application_lifecycles::{self, Entity as ApplicationLifecycle};
+use crate::models::application_sps_change_requests::{self, Entity as ApplicationSpsChangeRequest};
+use crate::models::application_sps_change_signers::{self, Entity as ApplicationSpsChangeSigner};
+use crate::models::applications::{Column, Entity as Application, Model as ApplicationModel};
+
+const BACKFILL_BATCH_SIZE: u64 = 100;
+
+/// Outcome of `backfill_application_details`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackfillSummary {
+    pub normalized: usize,
+    pub skipped: usize,
+}
+
+/// The parts of the application JSON that are normalized.
+#[derive(Deserialize)]
+struct ApplicationJson {
+    #[serde(rename = "Lifecycle")]
+    lifecycle: LifecycleJson,
+    #[serde(rename = "Allocation Requests", default)]
+    allocations: Vec<AllocationJson>,
+    #[serde(rename = "Storage Providers Change Requests", default)]
+    sps_change_requests: Option<Vec<SpsChangeRequestJson>>,
+}
+
+#[derive(Deserialize)]
+struct LifecycleJson {
+    #[serde(rename = "State")]
+    state: String,
+    #[serde(rename = "Active")]
+    is_active: bool,
+    #[serde(rename = "Validated At", default)]
+    validated_at: String,
+    #[serde(rename = "Validated By", default)]
+    validated_by: String,
+    #[serde(rename = "Updated At", default)]
+    updated_at: String,
+    #[serde(rename = "Active Request ID")]
+    active_request: Option<String>,
+    #[serde(rename = "On Chain Address", default)]
+    client_on_chain_address: String,
+    #[serde(rename = "Multisig Address", default)]
+    multisig_address: String,
+}
+
+#[derive(Deserialize)]
+struct AllocationJson {
+    #[serde(rename = "ID")]
+    id: String,
+    #[serde(rename = "Request Type")]
+    request_type: String,
+    #[serde(rename = "Created At", default)]
+    created_at: String,
+    #[serde(rename = "Updated At", default)]
+    updated_at: String,
+    #[serde(rename = "Active")]
+    is_active: bool,
+    #[serde(rename = "Allocation Amount")]
+    amount: String,
+    #[serde(rename = "Amount of Datacap Sent to Contract")]
+    amount_of_datacap_sent_to_contract: Option<String>,
+    #[serde(rename = "Signers", default)]
+    signers: Vec<AllocationSignerJson>,
+}
+
+#[derive(Deserialize)]
+struct AllocationSignerJson {
+    #[serde(rename = "Github Username")]
+    github_username: String,
+    #[serde(rename = "Signing Address")]
+    signing_address: String,
+    #[serde(rename = "Created At", default)]
+    created_at: String,
+    #[serde(rename = "Message CID")]
+    message_cid: Option<String>,
+    #[serde(rename = "Increase allowance CID")]
+    increase_allowance_cid: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SpsChangeRequestJson {
+    #[serde(rename = "ID")]
+    id: String,
+    #[serde(rename = "Created At", default)]
+    created_at: String,
+    #[serde(rename = "Updated At", default)]
+    updated_at: String,
+    #[serde(rename = "Active")]
+    is_active: bool,
+    #[serde(rename = "Allowed Storage Providers")]
+    allowed_sps: Option<Vec<u64>>,
+    #[serde(rename = "Max Deviation")]
+    max_deviation: Option<String>,
+    #[serde(rename = "Signers", default)]
+    signers: Vec<SpsChangeSignerJson>,
+}
+
+#[derive(Deserialize)]
+struct SpsChangeSignerJson {
+    #[serde(rename = "Github Username")]
+    github_username: String,
+    #[serde(rename = "Signing Address")]
+    signing_address: String,
+    #[serde(rename = "Set Max Deviation CID")]
+    max_deviation_cid: Option<String>,
+    #[serde(rename = "Add Allowed Storage Providers CID")]
+    add_allowed_sps_cids: Option<HashMap<String, Vec<String>>>,
+    #[serde(rename = "Remove Allowed Storage Providers CID")]
+    remove_allowed_sps_cids: Option<HashMap<String, Vec<String>>>,
+}
+
+/**
+ * Parse the timestamps of the application JSON, written by `Utc::now().to_string()`
+ */
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    value.parse::<DateTime<Utc>>().ok().or_else(|| {
+        NaiveDateTime::parse_from_str(value.trim_end_matches(" UTC"), "%Y-%m-%d %H:%M:%S%.f")
+            .ok()
+            .map(|naive| naive.and_utc())
+    })
+}
+
+fn non_empty(value: String) -> Option<String> {
+    Some(value).filter(|value| !value.is_empty())
+}
+
+fn parse_application_json(application: &ApplicationModel) -> Result<ApplicationJson, String> {
+    let json = application
+        .application
+        .as_deref()
+        .ok_or("The application has no JSON".to_string())?;
+    serde_json::from_str(json).map_err(|e| e.to_string())
+}
+
+/**
+ * Replace the normalized rows of an application with the content of its JSON
+ *
+ * Rows of an application whose JSON is missing or cannot be parsed are only
+ * removed, the JSON stays the source of truth.
+ *
+ * # Arguments
+ * @param db: &C - The connection or transaction to use
+ * @param application: &ApplicationModel - The application row
+ */
+pub async fn sync_application_details<C: ConnectionTrait>(
+    db: &C,
+    application: &ApplicationModel,
+) -> Result<(), DbErr> {
+    let key = (
+        application.id.clone(),
+        application.owner.clone(),
+        application.repo.clone(),
+        application.pr_number,
+    );
+    // Signers are removed through the foreign keys.
+    ApplicationLifecycle::delete_many()
+        .filter(application_lifecycles::Column::ApplicationId.eq(key.0.clone()))
+        .filter(application_lifecycles::Column::Owner.eq(key.1.clone()))
+        .filter(application_lifecycles::Column::Repo.eq(key.2.clone()))
+        .filter(application_lifecycles::Column::PrNumber.eq(key.3))
+        .exec(db)
+        .await?;
+    ApplicationAllocation::delete_many()
+        .filter(application_allocations::Column::ApplicationId.eq(key.0.clone()))
+        .filter(application_allocations::Column::Owner.eq(key.1.clone()))
+        .filter(application_allocations::Column::Repo.eq(key.2.clone()))
+        .filter(application_allocations::Column::PrNumber.eq(key.3))
+        .exec(db)
+        .await?;
+    ApplicationSpsChangeRequest::delete_many()
+        .filter(application_sps_change_requests::Column::ApplicationId.eq(key.0.clone()))
+        .filter(application_sps_change_requests::Column::Owner.eq(key.1.clone()))
+        .filter(application_sps_change_requests::Column::Repo.eq(key.2.clone()))
+        .filter(application_sps_change_requests::Column::PrNumber.eq(key.3))
+        .exec(db)
+        .await?;
+
+    let parsed = match parse_application_json(application) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            log::warn!(
+                "Failed to normalize application {}/{}/{}: {e}",
+                key.1,
+                key.2,
+                key.0
+            );
+            return Ok(());
+        }
+    };
+    let (application_id, owner, repo, pr_number) = key;
+
+    let lifecycle = parsed.lifecycle;
+    application_lifecycles::ActiveModel {
+        application_id: Set(application_id.clone()),
+        owner: Set(owner.clone()),
+        repo: Set(repo.clone()),
+        pr_number: Set(pr_number),
+        state: Set(lifecycle.state),
+        is_active: Set(lifecycle.is_active),
+        validated_at: Set(parse_timestamp(&lifecycle.validated_at)),
+        validated_by: Set(non_empty(lifecycle.validated_by)),
+        updated_at: Set(parse_timestamp(&lifecycle.updated_at)),
+        active_request_id: Set(lifecycle.active_request.and_then(non_empty)),
+        client_on_chain_address: Set(non_empty(lifecycle.client_on_chain_address)),
+        multisig_address: Set(non_empty(lifecycle.multisig_address)),
+    }
+    .insert(db)
+    .await?;
+
+    let mut allocation_ids = HashSet::new();
+    for allocation in parsed.allocations {
+        if !allocation_ids.insert(allocation.id.clone()) {
+            continue;
+        }
+        application_allocations::ActiveModel {
+            application_id: Set(application_id.clone()),
+            owner: Set(owner.clone()),
+            repo: Set(repo.clone()),
+            pr_number: Set(pr_number),
+            allocation_id: Set(allocation.id.clone()),
+            request_type: Set(allocation.request_type),
+            is_active: Set(allocation.is_active),
+            amount: Set(allocation.amount),
+            amount_of_datacap_sent_to_contract: Set(allocation.amount_of_datacap_sent_to_contract),
+            created_at: Set(parse_timestamp(&allocation.created_at)),
+            updated_at: Set(parse_timestamp(&allocation.updated_at)),
+        }
+        .insert(db)
+        .await?;
+        let signers: Vec<_> = allocation
+            .signers
+            .into_iter()
+            .enumerate()
+            .map(
+                |(position, signer)| application_allocation_signers::ActiveModel {
+                    application_id: Set(application_id.clone()),
+                    owner: Set(owner.clone()),
+                    repo: Set(repo.clone()),
+                    pr_number: Set(pr_number),
+                    allocation_id: Set(allocation.id.clone()),
+                    position: Set(position as i32),
+                    github_username: Set(signer.github_username),
+                    signing_address: Set(signer.signing_address),
+                    created_at: Set(parse_timestamp(&signer.created_at)),
+                    message_cid: Set(signer.message_cid),
+                    increase_allowance_cid: Set(signer.increase_allowance_cid),
+                },
+            )
+            .collect();
+        if !signers.is_empty() {
+            ApplicationAllocationSigner::insert_many(signers)
+                .exec(db)
+                .await?;
+        }
+    }
+
+    let mut request_ids = HashSet::new();
+    for request in parsed.sps_change_requests.unwrap_or_default() {
+        if !request_ids.insert(request.id.clone()) {
+            continue;
+        }
+        application_sps_change_requests::ActiveModel {
+            application_id: Set(application_id.clone()),
+            owner: Set(owner.clone()),
+            repo: Set(repo.clone()),
+            pr_number: Set(pr_number),
+            request_id: Set(request.id.clone()),
+            is_active: Set(request.is_active),
+            allowed_sps: Set(request
+                .allowed_sps
+                .map(|sps| serde_json::Value::from(sps).to_string())),
+            max_deviation: Set(request.max_deviation),
+            created_at: Set(parse_timestamp(&request.created_at)),
+            updated_at: Set(parse_timestamp(&request.updated_at)),
+        }
+        .insert(db)
+        .await?;
+        let signers: Vec<_> = request
+            .signers
+            .into_iter()
+            .enumerate()
+            .map(
+                |(position, signer)| application_sps_change_signers::ActiveModel {
+                    application_id: Set(application_id.clone()),
+                    owner: Set(owner.clone()),
+                    repo: Set(repo.clone()),
+                    pr_number: Set(pr_number),
+                    request_id: Set(request.id.clone()),
+                    position: Set(position as i32),
+                    github_username: Set(signer.github_username),
+                    signing_address: Set(signer.signing_address),
+                    max_deviation_cid: Set(signer.max_deviation_cid),
+                    add_allowed_sps_cids: Set(signer
+                        .add_allowed_sps_cids
+                        .and_then(|cids| serde_json::to_string(&cids).ok())),
+                    remove_allowed_sps_cids: Set(signer
+                        .remove_allowed_sps_cids
+                        .and_then(|cids| serde_json::to_string(&cids).ok())),
+                },
+            )
+            .collect();
+        if !signers.is_empty() {
+            ApplicationSpsChangeSigner::insert_many(signers)
+                .exec(db)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Rows of `table` belonging to the current application row.
+fn application_rows<T: EntityTrait>(
+    table: T,
+    application_id: T::Column,
+    owner: T::Column,
+    repo: T::Column,
+    pr_number: T::Column,
+) -> SimpleExpr {
+    Expr::exists(
+        Query::select()
+            .expr(Expr::val(1))
+            .from(table)
+            .and_where(Expr::col((table, application_id)).equals((Application, Column::Id)))
+            .and_where(Expr::col((table, owner)).equals((Application, Column::Owner)))
+            .and_where(Expr::col((table, repo)).equals((Application, Column::Repo)))
+            .and_where(Expr::col((table, pr_number)).equals((Application, Column::PrNumber)))
+            .to_owned(),
+    )
+}
+
+/**
+ * Normalize the applications that have no normalized rows yet
+ *
+ * Applications whose JSON cannot be normalized are recorded in
+ * `application_details_skipped` and not picked up again.
+ *
+ * # Returns
+ * @return Result<BackfillSummary, sea_orm::DbErr> - How many applications were normalized and skipped
+ */
+pub async fn backfill_application_details(
+    db: &DatabaseConnection,
+) -> Result<BackfillSummary, DbErr> {
+    let mut summary = BackfillSummary::default();
+    loop {
+        let applications = Application::find()
+            .filter(Column::Application.is_not_null())
+            .filter(
+                application_rows(
+                    ApplicationLifecycle,
+                    application_lifecycles::Column::ApplicationId,
+                    application_lifecycles::Column::Owner,
+                    application_lifecycles::Column::Repo,
+                    application_lifecycles::Column::PrNumber,
+                )
+                .not(),
+            )
+            .filter(
+                application_rows(
+                    ApplicationDetailsSkipped,
+                    application_details_skipped::Column::ApplicationId,
+                    application_details_skipped::Column::Owner,
+                    application_details_skipped::Column::Repo,
+                    application_details_skipped::Column::PrNumber,
+                )
+                .not(),
+            )
+            .order_by_asc(Column::Id)
+            .order_by_asc(Column::PrNumber)
+            .limit(BACKFILL_BATCH_SIZE)
+            .all(db)
+            .await?;
+        if applications.is_empty() {
+            return Ok(summary);
+        }
+
+        for application in applications {
+            let txn = db.begin().await?;
+            match parse_application_json(&application) {
+                Ok(_) => {
+                    sync_application_details(&txn, &application).await?;
+                    summary.normalized += 1;
+                }
+                Err(reason) => {
+                    log::warn!(
+                        "Skipping application {}/{}/{} #{}: {reason}",
+                        application.owner,
+                        application.repo,
+                        application.id,
+                        application.pr_number
+                    );
+                    application_details_skipped::ActiveModel {
+                        application_id: Set(application.id.clone()),
+                        owner: Set(application.owner.clone()),
+                        repo: Set(application.repo.clone()),
+                        pr_number: Set(application.pr_number),
+                        reason: Set(reason),
+                        skipped_at: Set(Utc::now()),
+                    }
+                    .insert(&txn)
+                    .await?;
+                    summary.skipped += 1;
+                }
+            }
+            txn.commit().await?;
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, FromQueryResult)]
+pub struct SignedAllocation {
+    pub application_id: String,
+    pub owner: String,
+    pub repo: String,
+    pub allocation_id: String,
+    pub request_type: String,
+    pub amount: String,
+    pub github_username: String,
+    pub signing_address: String,
+    pub signed_at: Option<DateTime<Utc>>,
+    pub message_cid: Option<String>,
+}
+
+/**
+ * Get the allocations signed by a verifier within a period
+ *
+ * # Arguments
+ * @param github_username: &str - The GitHub handle of the signer
+ * @param since: DateTime<Utc> - Start of the period, inclusive
+ * @param until: DateTime<Utc> - End of the period, exclusive
+ *
+ * # Returns
+ * @return Result<Vec<SignedAllocation>, sea_orm::DbErr> - The result of the operation
+ */
+pub async fn get_allocations_signed_by(
+    db: &DatabaseConnection,
+    github_username: &str,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Vec<SignedAllocation>, DbErr> {
+    SignedAllocation::find_by_statement(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        r#"
+        SELECT
+            s.application_id,
+            s.owner,
+            s.repo,
+            s.allocation_id,
+            a.request_type,
+            a.amount,
+            s.github_username,
+            s.signing_address,
+            s.created_at AS signed_at,
+            s.message_cid
+        FROM application_allocation_signers s
+        JOIN application_allocations a
+            ON a.application_id = s.application_id
+            AND a.owner = s.owner
+            AND a.repo = s.repo
+            AND a.pr_number = s.pr_number
+            AND a.allocation_id = s.allocation_id
+        WHERE lower(s.github_username) = lower($1)
+            AND s.created_at >= $2
+            AND s.created_at < $3
+        ORDER BY s.created_at
+        "#,
+        [github_username.into(), since.into(), until.into()],
+    ))
+    .all(db)
+    .await
+}
+
+/**
+ * Get the completed allocations of an allocator's applications created within a
+ * period. An allocation appearing in several rows of an application is
+ * returned once.
+ *
+ * # Arguments
+ * @param owner: &str - The owner of the allocator repository
+ * @param repo: &str - The allocator repository name
+ * @param since: DateTime<Utc> - Start of the period, inclusive
+ *
+ * # Returns
+ * @return Result<Vec<application_allocations::Model>, sea_orm::DbErr> - The allocations
+ */
+pub async fn get_allocator_allocations_since(
+    db: &DatabaseConnection,
+    owner: &str,
+    repo: &str,
+    since: DateTime<Utc>,
+) -> Result<Vec<application_allocations::Model>, DbErr> {
+    let allocations = ApplicationAllocation::find()
+        .filter(application_allocations::Column::Owner.eq(owner))
+        .filter(application_allocations::Column::Repo.eq(repo))
+        .filter(application_allocations::Column::IsActive.eq(false))
+        .filter(application_allocations::Column::CreatedAt.gte(since))
+        .all(db)
+        .await?;
+    let mut seen = HashSet::new();
+    Ok(allocations
+        .into_iter()
+        .filter(|allocation| {
+            seen.insert((
+                allocation.application_id.clone(),
+                allocation.allocation_id.clone(),
+            ))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::application_versions::WriteContext;
+    use crate::database::applications;
+    use crate::setup_test_environment;
+
+    fn application_json(state: &str, signer: &str) -> String {
+        serde_json::json!({
+            "Lifecycle": {
+                "State": state,
+                "Active": true,
+                "Updated At": "2024-05-28 09:00:00 UTC",
+                "Active Request ID": "request-1",
+            },
+            "Allocation Requests": [{
+                "ID": "request-1",
+                "Request Type": "First",
+                "Created At": "2024-05-28 09:00:00 UTC",
+                "Active": false,
+                "Allocation Amount": "1PiB",
+                "Amount of Datacap Sent to Contract": null,
+                "Signers": [{
+                    "Github Username": signer,
+                    "Signing Address": "f1signer",
+                    "Created At": "2024-05-28 09:02:51.126 UTC",
+                    "Message CID": "bafy",
+                    "Increase allowance CID": null,
+                }],
+            }],
+        })
+        .to_string()
+    }
+
+    async fn create(db: &DatabaseConnection, id: &str, pr_number: u64, json: String) {
+        applications::create_application(
+            db,
+            id.to_string(),
+            "owner".into(),
+            "repo".into(),
+            pr_number,
+            1,
+            json,
+            format!("applications/{id}.json"),
+            None,
+            "sha".into(),
+        )
+        .await
+        .unwrap();
+    }
+
+    async fn lifecycle(
+        db: &DatabaseConnection,
+        id: &str,
+        pr_number: i64,
+    ) -> Option<application_lifecycles::Model> {
+        ApplicationLifecycle::find_by_id((id.to_string(), "owner".into(), "repo".into(), pr_number))
+            .one(db)
+            .await
+            .unwrap()
+    }
+
+    fn period() -> (DateTime<Utc>, DateTime<Utc>) {
+        (
+            "2024-05-01T00:00:00Z".parse().unwrap(),
+            "2024-06-01T00:00:00Z".parse().unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn details_follow_the_application_writes() {
+        let db = setup_test_environment().await;
+        let (since, until) = period();
+        create(
+            &db,
+            "f1details",
+            1,
+            application_json("ReadyToSign", "alice"),
+        )
+        .await;
+        assert_eq!(
+            lifecycle(&db, "f1details", 1).await.unwrap().state,
+            "ReadyToSign"
+        );
+        let signed = get_allocations_signed_by(&db, "Alice", since, until)
+            .await
+            .unwrap();
+        assert_eq!(signed.len(), 1);
+        assert_eq!(signed[0].amount, "1PiB");
+
+        applications::update_application(
+            &db,
+            "f1details".into(),
+            "owner".into(),
+            "repo".into(),
+            1,
+            application_json("Granted", "bob"),
+            None,
+            "sha2".into(),
+            None,
+            &WriteContext::new("Test"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            lifecycle(&db, "f1details", 1).await.unwrap().state,
+            "Granted"
+        );
+        assert!(get_allocations_signed_by(&db, "alice", since, until)
+            .await
+            .unwrap()
+            .is_empty());
+
+        applications::merge_application_by_pr_number(
+            &db,
+            "owner".into(),
+            "repo".into(),
+            1,
+            &WriteContext::new("Test"),
+        )
+        .await
+        .unwrap();
+        assert!(lifecycle(&db, "f1details", 1).await.is_none());
+        assert_eq!(
+            lifecycle(&db, "f1details", 0).await.unwrap().state,
+            "Granted"
+        );
+        let signed = get_allocations_signed_by(&db, "bob", since, until)
+            .await
+            .unwrap();
+        assert_eq!(signed.len(), 1);
+        assert_eq!(signed[0].application_id, "f1details");
+    }
+
+    #[tokio::test]
+    async fn backfill_normalizes_once_and_skips_unparseable_rows() {
+        let db = setup_test_environment().await;
+        create(&db, "f1legacy", 1, application_json("Submitted", "alice")).await;
+        create(&db, "f1broken", 2, "{\"Lifecycle\": 1}".into()).await;
+        // Written before the details were normalized.
+        ApplicationLifecycle::delete_many().exec(&db).await.unwrap();
+        ApplicationAllocation::delete_many()
+            .exec(&db)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            backfill_application_details(&db).await.unwrap(),
+            BackfillSummary {
+                normalized: 1,
+                skipped: 1
+            }
+        );
+        assert_eq!(
+            lifecycle(&db, "f1legacy", 1).await.unwrap().state,
+            "Submitted"
+        );
+        let skipped = ApplicationDetailsSkipped::find().all(&db).await.unwrap();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].application_id, "f1broken");
+
+        assert_eq!(
+            backfill_application_details(&db).await.unwrap(),
+            BackfillSummary::default()
+        );
+    }
+
+    #[test]
+    fn application_timestamps_are_parsed() {
+        let now = Utc::now();
+        assert_eq!(parse_timestamp(&now.to_string()), Some(now));
+        assert_eq!(
+            parse_timestamp("2024-05-28 09:02:51.126 UTC").map(|t| t.to_rfc3339()),
+            Some("2024-05-28T09:02:51.126+00:00".to_string())
+        );
+        assert!(parse_timestamp("").is_none());
+    }
+}
@@ -0,0 +1,181 @@
+use sea_orm::{entity::*, query::*, ConnectionTrait, DatabaseConnection, DbErr};
+
+use crate::database::outbox::NewOutboxMessage;
+use crate::models::application_versions::{
+    ActiveModel, Column, Entity as ApplicationVersion, Model as ApplicationVersionModel,
+};
+use crate::models::applications::Model as ApplicationModel;
+
+/// Who changes an application row and why, recorded with the previous state
+/// of the row.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteContext {
+    pub actor: Option<String>,
+    pub reason: String,
+    /// Side effects committed together with the change.
+    pub side_effects: Vec<NewOutboxMessage>,
+}
+
+impl WriteContext {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/**
+ * Store the current state of an application row before it is overwritten or deleted
+ *
+ * # Arguments
+ * @param db: &C - The transaction of the write
+ * @param previous: &ApplicationModel - The row as it is before the write
+ * @param operation: &str - The write, one of `update`, `merge` or `delete`
+ * @param context: &WriteContext - Who writes and why
+ */
+pub(crate) async fn record_application_version<C: ConnectionTrait>(
+    db: &C,
+    previous: &ApplicationModel,
+    operation: &str,
+    context: &WriteContext,
+) -> Result<ApplicationVersionModel, DbErr> {
+    // The counter row stays locked until the write commits, so concurrent
+    // writes of the application wait for each other's number.
+    let version = db
+        .query_one(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "INSERT INTO application_version_counters (application_id, owner, repo, last_version)
+                VALUES ($1, $2, $3, 1)
+                ON CONFLICT (application_id, owner, repo)
+                DO UPDATE SET last_version = application_version_counters.last_version + 1
+                RETURNING last_version",
+            [
+                previous.id.clone().into(),
+                previous.owner.clone().into(),
+                previous.repo.clone().into(),
+            ],
+        ))
+        .await?
+        .ok_or_else(|| DbErr::Custom("No application version was allocated.".to_string()))?
+        .try_get::<i32>("", "last_version")?;
+
+    ActiveModel {
+        application_id: Set(previous.id.clone()),
+        owner: Set(previous.owner.clone()),
+        repo: Set(previous.repo.clone()),
+        version: Set(version),
+        pr_number: Set(previous.pr_number),
+        application: Set(previous.application.clone()),
+        sha: Set(previous.sha.clone()),
+        path: Set(previous.path.clone()),
+        operation: Set(operation.to_string()),
+        actor: Set(context.actor.clone()),
+        reason: Set(context.reason.clone()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
+
+/**
+ * Get the recorded versions of an application
+ *
+ * # Arguments
+ * @param id: &str - The ID of the application
+ * @param owner: &str - The owner of the repository
+ * @param repo: &str - The repository name
+ *
+ * # Returns
+ * @return Result<Vec<ApplicationVersionModel>, sea_orm::DbErr> - The versions, oldest first
+ */
+pub async fn get_application_versions(
+    db: &DatabaseConnection,
+    id: &str,
+    owner: &str,
+    repo: &str,
+) -> Result<Vec<ApplicationVersionModel>, DbErr> {
+    ApplicationVersion::find()
+        .filter(Column::ApplicationId.eq(id))
+        .filter(Column::Owner.eq(owner))
+        .filter(Column::Repo.eq(repo))
+        .order_by(Column::Version, Order::Asc)
+        .all(db)
+        .await
+}
+
+/**
+ * Get one recorded version of an application
+ *
+ * # Arguments
+ * @param id: &str - The ID of the application
+ * @param owner: &str - The owner of the repository
+ * @param repo: &str - The repository name
+ * @param version: i32 - The version number
+ *
+ * # Returns
+ * @return Result<Option<ApplicationVersionModel>, sea_orm::DbErr> - The version, if it exists
+ */
+pub async fn get_application_version(
+    db: &DatabaseConnection,
+    id: &str,
+    owner: &str,
+    repo: &str,
+    version: i32,
+) -> Result<Option<ApplicationVersionModel>, DbErr> {
+    ApplicationVersion::find_by_id((id.to_string(), owner.to_string(), repo.to_string(), version))
+        .one(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::applications::{create_application, update_application};
+    use crate::setup_test_environment;
+
+    #[tokio::test]
+    async fn concurrent_writes_get_distinct_versions() {
+        let db = setup_test_environment().await;
+        create_application(
+            &db,
+            "f1versions".into(),
+            "owner".into(),
+            "repo".into(),
+            1,
+            1,
+            "{}".into(),
+            "applications/f1versions.json".into(),
+            None,
+            "sha".into(),
+        )
+        .await
+        .unwrap();
+
+        let context = WriteContext::new("Test");
+        futures::future::try_join_all((0..5).map(|i| {
+            update_application(
+                &db,
+                "f1versions".into(),
+                "owner".into(),
+                "repo".into(),
+                1,
+                format!("{{\"update\": {i}}}"),
+                None,
+                format!("sha{i}"),
+                None,
+                &context,
+            )
+        }))
+        .await
+        .unwrap();
+
+        let versions: Vec<i32> = get_application_versions(&db, "f1versions", "owner", "repo")
+            .await
+            .unwrap()
+            .iter()
+            .map(|version| version.version)
+            .collect();
+        assert_eq!(versions, [1, 2, 3, 4, 5]);
+    }
+}
@@ -1,10 +1,12 @@
-use crate::get_database_connection;
+use crate::database::application_details::sync_application_details;
+use crate::database::application_versions::{record_application_version, WriteContext};
+use crate::database::outbox::enqueue_outbox_messages;
+use crate::dialect::application_is_active;
 use crate::models::applications::{
     ActiveModel, Column, Entity as Application, Model as ApplicationModel,
 };
-use chrono::{DateTime, Utc};
 use sea_orm::prelude::Expr;
-use sea_orm::{entity::*, query::*, DbBackend, DbErr};
+use sea_orm::{entity::*, query::*, DatabaseConnection, DbErr};
 
 /**
  * Get all applications from the database
@@ -12,75 +14,35 @@ use sea_orm::{entity::*, query::*, DbBackend, DbErr};
  * # Returns
  * @return Result<Vec<ApplicationModel>, sea_orm::DbErr> - The result of the operation
  */
-pub async fn get_active_applications() -> Result<Vec<ApplicationModel>, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
-
-    //Get all applications from the database.
-    //Distinct on is not supported in sea_orm yet, so we have to use raw SQL
-    let app_data = JsonValue::find_by_statement(Statement::from_sql_and_values(
-        DbBackend::Postgres,
-        r#"
-            SELECT DISTINCT ON (owner, repo, id) 
-                a.id, 
-                a.owner, 
-                a.repo, 
-                a.pr_number,
-                a.issue_number,
-                a.application, 
-                a.updated_at, 
-                a.sha,
-                a.path,
-                a.client_contract_address,
-                a.issue_reporter_handle
-            FROM 
-                applications a 
-            WHERE 
-                (application::json->'Lifecycle'->>'Active')::boolean IS TRUE
-            ORDER BY 
-                a.owner, 
-                a.repo, 
-                a.id, 
-                a.pr_number DESC
+pub async fn get_active_applications(
+    db: &DatabaseConnection,
+) -> Result<Vec<ApplicationModel>, sea_orm::DbErr> {
+    //Get the active row with the highest PR number of every application.
+    //Distinct on is not supported in sea_orm yet (nor in SQLite), so we use a window function
+    let backend = db.get_database_backend();
+    Application::find()
+        .from_raw_sql(Statement::from_string(
+            backend,
+            format!(
+                r#"
+            SELECT * FROM (
+                SELECT
+                    a.*,
+                    ROW_NUMBER() OVER (
+                        PARTITION BY a.owner, a.repo, a.id
+                        ORDER BY a.pr_number DESC
+                    ) AS latest_rank
+                FROM applications a
+                WHERE {}
+            ) latest
+            WHERE latest_rank = 1
+            ORDER BY owner, repo, id
             "#,
-        [],
-    ))
-    .all(&conn)
-    .await?;
-
-    let applications = app_data
-        .into_iter()
-        .map(|app| ApplicationModel {
-            id: get_string_field(&app, "id").expect("ID must exist"),
-            owner: get_string_field(&app, "owner").expect("Owner must exist"),
-            repo: get_string_field(&app, "repo").expect("Repo must exist"),
-            pr_number: get_i64_field(&app, "pr_number").expect("PR number must exist"),
-            issue_number: get_i64_field(&app, "issue_number").expect("Issue number must exist"),
-            application: get_string_field(&app, "application"),
-            updated_at: parse_datetime_field(&app, "updated_at")
-                .expect("Updated_at must be a valid datetime"),
-            sha: get_string_field(&app, "sha"),
-            path: get_string_field(&app, "path"),
-            client_contract_address: get_string_field(&app, "client_contract_address"),
-            issue_reporter_handle: get_string_field(&app, "issue_reporter_handle"),
-        })
-        .collect();
-
-    Ok(applications)
-}
-
-fn get_string_field(json: &JsonValue, field: &str) -> Option<String> {
-    json.get(field)?.as_str().map(|s| s.to_string())
-}
-
-/// Retrieves an i64 field from a JSON value, if it exists.
-fn get_i64_field(json: &JsonValue, field: &str) -> Option<i64> {
-    json.get(field)?.as_i64()
-}
-
-fn parse_datetime_field(json: &JsonValue, field: &str) -> Option<DateTime<Utc>> {
-    json.get(field)?
-        .as_str()
-        .and_then(|s| s.parse::<DateTime<Utc>>().ok())
+                application_is_active(backend)
+            ),
+        ))
+        .all(db)
+        .await
 }
 
 /**
@@ -94,10 +56,10 @@ fn parse_datetime_field(json: &JsonValue, field: &str) -> Option<DateTime<Utc>>
  * @return Result<Vec<ApplicationModel>, sea_orm::DbErr> - The result of the operation
  */
 pub async fn get_merged_applications(
+    db: &DatabaseConnection,
     owner: Option<String>,
     repo: Option<String>,
 ) -> Result<Vec<ApplicationModel>, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
     let mut query = Application::find().filter(Column::PrNumber.eq(0));
     if let Some(owner) = owner.clone() {
         query = query.filter(Column::Owner.contains(owner));
@@ -113,7 +75,7 @@ pub async fn get_merged_applications(
     query
         .order_by(Column::Owner, Order::Asc)
         .order_by(Column::Repo, Order::Asc)
-        .all(&conn)
+        .all(db)
         .await
 }
 
@@ -128,15 +90,13 @@ pub async fn get_merged_applications(
  * @return Result<Vec<ApplicationModel>, sea_orm::DbErr> - The result of the operation
  */
 pub async fn get_applications_with_open_pull_request(
+    db: &DatabaseConnection,
     owner: Option<String>,
     repo: Option<String>,
 ) -> Result<Vec<ApplicationModel>, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
     let mut query = Application::find()
         .filter(Column::PrNumber.ne(0))
-        .filter(Expr::cust(
-            "(application::json->'Lifecycle'->>'Active')::boolean IS TRUE",
-        ));
+        .filter(Expr::cust(application_is_active(db.get_database_backend())));
 
     if let Some(owner) = owner.clone() {
         query = query.filter(Column::Owner.contains(owner));
@@ -152,7 +112,7 @@ pub async fn get_applications_with_open_pull_request(
     query
         .order_by(Column::Owner, Order::Asc)
         .order_by(Column::Repo, Order::Asc)
-        .all(&conn)
+        .all(db)
         .await
 }
 
@@ -169,12 +129,36 @@ pub async fn get_applications_with_open_pull_request(
  * @return Result<ApplicationModel, sea_orm::DbErr> - The result of the operation
  */
 pub async fn get_application(
+    db: &DatabaseConnection,
     id: String,
     owner: String,
     repo: String,
     pr_number: Option<u64>,
 ) -> Result<ApplicationModel, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
+    find_application(db, id, owner, repo, pr_number)
+        .await?
+        .ok_or(DbErr::Custom("Application not found".to_string()))
+}
+
+/**
+ * Find an application with max pr_number for given id, owner and repo
+ *
+ * # Arguments
+ * @param id: String - The ID of the application
+ * @param owner: String - The owner of the repository
+ * @param repo: String - The repository name
+ * @param pr_number: Option<u64> - Optional PR number to filter by
+ *
+ * # Returns
+ * @return Result<Option<ApplicationModel>, sea_orm::DbErr> - `None` when there is no such application
+ */
+pub async fn find_application(
+    db: &DatabaseConnection,
+    id: String,
+    owner: String,
+    repo: String,
+    pr_number: Option<u64>,
+) -> Result<Option<ApplicationModel>, sea_orm::DbErr> {
     let mut query = Application::find()
         .filter(Column::Id.eq(id))
         .filter(Column::Owner.contains(owner))
@@ -183,13 +167,7 @@ pub async fn get_application(
         query = query.filter(Column::PrNumber.eq(number as i64));
     }
 
-    let application = query
-        .order_by(Column::PrNumber, Order::Desc)
-        .one(&conn)
-        .await?
-        .ok_or(DbErr::Custom("Application not found".to_string()))?;
-
-    Ok(application)
+    query.order_by(Column::PrNumber, Order::Desc).one(db).await
 }
 
 /**
@@ -204,22 +182,56 @@ pub async fn get_application(
  * @return Result<ApplicationModel, sea_orm::DbErr> - The result of the operation
  */
 pub async fn get_application_by_pr_number(
+    db: &DatabaseConnection,
     owner: String,
     repo: String,
     pr_number: u64,
 ) -> Result<ApplicationModel, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
     let application = Application::find()
         .filter(Column::Owner.contains(owner))
         .filter(Column::Repo.contains(repo))
         .filter(Column::PrNumber.eq(pr_number as i64))
-        .one(&conn)
+        .one(db)
         .await?
         .ok_or(DbErr::Custom("Application not found".to_string()))?;
 
     Ok(application)
 }
 
+/**
+ * Allocate the next pull request number for the repository.
+ * Used by allocators which keep their applications only in the database,
+ * where pull request numbers are assigned locally instead of by GitHub.
+ * The number comes from incrementing the repository's counter row, so it is
+ * never handed out twice, even when merged applications reset theirs to 0.
+ *
+ * # Arguments
+ * @param owner: String - The owner of the repository
+ * @param repo: String - The repository name
+ *
+ * # Returns
+ * @return Result<u64, sea_orm::DbErr> - The result of the operation
+ */
+pub async fn get_next_pr_number(
+    db: &DatabaseConnection,
+    owner: String,
+    repo: String,
+) -> Result<u64, sea_orm::DbErr> {
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "INSERT INTO pr_number_counters (owner, repo, last_pr_number)
+                VALUES ($1, $2, 1)
+                ON CONFLICT (owner, repo)
+                DO UPDATE SET last_pr_number = pr_number_counters.last_pr_number + 1
+                RETURNING last_pr_number",
+            [owner.into(), repo.into()],
+        ))
+        .await?
+        .ok_or_else(|| DbErr::Custom("No pull request number was allocated.".to_string()))?;
+    Ok(row.try_get::<i64>("", "last_pr_number")? as u64)
+}
+
 /**
  * Get an application from the database with given issue_number
  *
@@ -232,17 +244,16 @@ pub async fn get_application_by_pr_number(
  * @return Result<ApplicationModel, sea_orm::DbErr> - The result of the operation
  */
 pub async fn get_application_by_issue_number(
+    db: &DatabaseConnection,
     owner: String,
     repo: String,
     issue_number: i64,
 ) -> Result<ApplicationModel, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
-
     Application::find()
         .filter(Column::Owner.eq(owner))
         .filter(Column::Repo.eq(repo))
         .filter(Column::IssueNumber.eq(issue_number))
-        .one(&conn)
+        .one(db)
         .await?
         .ok_or_else(|| DbErr::Custom("Application not found.".to_string()))
 }
@@ -254,21 +265,24 @@ pub async fn get_application_by_issue_number(
  * @param owner: String - The owner of the repository
  * @param repo: String - The repository name
  * @param pr_number: u64 - The PR number
+ * @param context: &WriteContext - Who merges and why, with the side effects of the merge
  *
  * # Returns
  * @return Result<ApplicationModel, sea_orm::DbErr> - The result of the operation
  */
 pub async fn merge_application_by_pr_number(
+    db: &DatabaseConnection,
     owner: String,
     repo: String,
     pr_number: u64,
+    context: &WriteContext,
 ) -> Result<(), sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
     let pr_application =
-        get_application_by_pr_number(owner.clone(), repo.clone(), pr_number).await?;
+        get_application_by_pr_number(db, owner.clone(), repo.clone(), pr_number).await?;
 
     let mut application_active_model: ActiveModel;
     if let Ok(application) = get_application(
+        db,
         pr_application.id.clone(),
         owner.clone(),
         repo.clone(),
@@ -276,16 +290,30 @@ pub async fn merge_application_by_pr_number(
     )
     .await
     {
+        let txn = db.begin().await?;
+        record_application_version(&txn, &pr_application, "merge", context).await?;
+        record_application_version(&txn, &application, "merge", context).await?;
+        let version = application.version + 1;
         application_active_model = application.into_active_model();
         application_active_model.application = Set(pr_application.application.clone());
         application_active_model.sha = Set(pr_application.sha.clone());
-        application_active_model.update(&conn).await?;
+        application_active_model.version = Set(version);
+        let merged = application_active_model.update(&txn).await?;
+        sync_application_details(&txn, &merged).await?;
+        pr_application.delete(&txn).await?;
+        enqueue_outbox_messages(&txn, &context.side_effects).await?;
+        txn.commit().await?;
     } else {
         application_active_model = pr_application.clone().into_active_model();
         application_active_model.pr_number = Set(0);
-        application_active_model.insert(&conn).await?;
+        let txn = db.begin().await?;
+        record_application_version(&txn, &pr_application, "merge", context).await?;
+        let merged = application_active_model.insert(&txn).await?;
+        sync_application_details(&txn, &merged).await?;
+        pr_application.delete(&txn).await?;
+        enqueue_outbox_messages(&txn, &context.side_effects).await?;
+        txn.commit().await?;
     }
-    pr_application.delete(&conn).await?;
     Ok(())
 }
 
@@ -300,12 +328,14 @@ pub async fn merge_application_by_pr_number(
  * @param app_file: String - The application file
  * @param path: Option<String> - The path of the application
  * @param sha: Option<String> - The SHA of the application
+ * @param context: &WriteContext - Who updates and why, with the side effects of the update
  *
  * # Returns
  * @return Result<ApplicationModel, sea_orm::DbErr> - The result of the operation
  */
 #[allow(clippy::too_many_arguments)]
 pub async fn update_application(
+    db: &DatabaseConnection,
     id: String,
     owner: String,
     repo: String,
@@ -314,15 +344,24 @@ pub async fn update_application(
     path: Option<String>,
     file_sha: String,
     client_contract_address: Option<String>,
+    context: &WriteContext,
 ) -> Result<ApplicationModel, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
-
     let existing_application =
-        get_application(id.clone(), owner.clone(), repo.clone(), Some(pr_number)).await?;
+        get_application(db, id.clone(), owner.clone(), repo.clone(), Some(pr_number)).await?;
 
+    // Stores which keep the file in this table already wrote it when committing.
+    let file_changed = existing_application.application.as_deref() != Some(app_file.as_str())
+        || existing_application.sha.as_deref() != Some(file_sha.as_str());
+
+    let txn = db.begin().await?;
+    if file_changed {
+        record_application_version(&txn, &existing_application, "update", context).await?;
+    }
+    let version = existing_application.version + i64::from(file_changed);
     let mut active_application: ActiveModel = existing_application.into_active_model();
     active_application.application = Set(Some(app_file.clone()));
     active_application.sha = Set(Some(file_sha));
+    active_application.version = Set(version);
 
     if let Some(path) = path {
         active_application.path = Set(Some(path));
@@ -334,10 +373,66 @@ pub async fn update_application(
         active_application.client_contract_address = Set(None);
     }
 
-    let updated_application = active_application.update(&conn).await?;
+    let updated_application = active_application.update(&txn).await?;
+    sync_application_details(&txn, &updated_application).await?;
+    enqueue_outbox_messages(&txn, &context.side_effects).await?;
+    txn.commit().await?;
     Ok(updated_application)
 }
 
+/**
+ * Replace the application file of a row, unless the row was written since it
+ * was read. The check and the write are one statement, so of concurrent
+ * commits based on the same version only one succeeds.
+ *
+ * # Arguments
+ * @param application: &ApplicationModel - The row as it was read
+ * @param app_file: String - The new application file
+ * @param file_sha: String - The SHA of the new application file
+ * @param context: &WriteContext - Who writes and why, with the side effects of the write
+ *
+ * # Returns
+ * @return Result<bool, sea_orm::DbErr> - `false` when the row is no longer at the version that was read
+ */
+pub async fn update_application_file_if_unchanged(
+    db: &DatabaseConnection,
+    application: &ApplicationModel,
+    app_file: String,
+    file_sha: String,
+    context: &WriteContext,
+) -> Result<bool, sea_orm::DbErr> {
+    let txn = db.begin().await?;
+    let result = Application::update_many()
+        .col_expr(Column::Application, Expr::value(app_file))
+        .col_expr(Column::Sha, Expr::value(file_sha))
+        .col_expr(Column::Version, Expr::col(Column::Version).add(1))
+        .filter(Column::Id.eq(application.id.clone()))
+        .filter(Column::Owner.eq(application.owner.clone()))
+        .filter(Column::Repo.eq(application.repo.clone()))
+        .filter(Column::PrNumber.eq(application.pr_number))
+        .filter(Column::Version.eq(application.version))
+        .exec(&txn)
+        .await?;
+    if result.rows_affected != 1 {
+        txn.rollback().await?;
+        return Ok(false);
+    }
+    record_application_version(&txn, application, "update", context).await?;
+    enqueue_outbox_messages(&txn, &context.side_effects).await?;
+    let updated = Application::find_by_id((
+        application.id.clone(),
+        application.owner.clone(),
+        application.repo.clone(),
+        application.pr_number,
+    ))
+    .one(&txn)
+    .await?
+    .ok_or_else(|| DbErr::Custom("Application not found".to_string()))?;
+    sync_application_details(&txn, &updated).await?;
+    txn.commit().await?;
+    Ok(true)
+}
+
 /**
  * Create an application in the database
  *
@@ -355,6 +450,7 @@ pub async fn update_application(
  */
 #[allow(clippy::too_many_arguments)]
 pub async fn create_application(
+    db: &DatabaseConnection,
     id: String,
     owner: String,
     repo: String,
@@ -365,8 +461,6 @@ pub async fn create_application(
     issue_reporter_handle: Option<String>,
     file_sha: String,
 ) -> Result<ApplicationModel, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
-
     let new_application = ActiveModel {
         id: Set(id),
         owner: Set(owner),
@@ -377,10 +471,14 @@ pub async fn create_application(
         sha: Set(Some(file_sha)),
         path: Set(Some(path)),
         issue_reporter_handle: Set(issue_reporter_handle),
+        version: Set(0),
         ..Default::default()
     };
 
-    let application = new_application.insert(&conn).await?;
+    let txn = db.begin().await?;
+    let application = new_application.insert(&txn).await?;
+    sync_application_details(&txn, &application).await?;
+    txn.commit().await?;
     Ok(application)
 }
 
@@ -392,100 +490,118 @@ pub async fn create_application(
  * @param owner: String - The owner of the repository
  * @param repo: String - The repository name
  * @param pr_number: u64 - The PR number
+ * @param context: &WriteContext - Who deletes and why
  *
  * # Returns
  * @return Result<(), sea_orm::DbErr> - The result of the operation
  */
 pub async fn delete_application(
+    db: &DatabaseConnection,
     id: String,
     owner: String,
     repo: String,
     pr_number: u64,
+    context: &WriteContext,
 ) -> Result<(), sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
     let application =
-        get_application(id.clone(), owner.clone(), repo.clone(), Some(pr_number)).await?;
-    application.delete(&conn).await?;
+        get_application(db, id.clone(), owner.clone(), repo.clone(), Some(pr_number)).await?;
+    let txn = db.begin().await?;
+    record_application_version(&txn, &application, "delete", context).await?;
+    application.delete(&txn).await?;
+    enqueue_outbox_messages(&txn, &context.side_effects).await?;
+    txn.commit().await?;
     Ok(())
 }
 
 pub async fn get_applications_by_client_id(
+    db: &DatabaseConnection,
     id: &String,
 ) -> Result<Vec<ApplicationModel>, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
     let result = Application::find()
         .filter(Column::Id.eq(id))
-        .all(&conn)
+        .all(db)
         .await?;
     Ok(result)
 }
 
 pub async fn get_applications_by_client_contract_address(
+    db: &DatabaseConnection,
     client_contract_address: &str,
 ) -> Result<Vec<ApplicationModel>, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
     let applications = Application::find()
         .from_raw_sql(Statement::from_sql_and_values(
-            DbBackend::Postgres,
-            "SELECT DISTINCT ON (id) * FROM applications 
-             WHERE client_contract_address = $1
-             ORDER BY id, pr_number DESC",
+            db.get_database_backend(),
+            "SELECT * FROM (
+                SELECT *, ROW_NUMBER() OVER (PARTITION BY id ORDER BY pr_number DESC) AS latest_rank
+                FROM applications
+                WHERE client_contract_address = $1
+            ) latest
+            WHERE latest_rank = 1
+            ORDER BY id",
             [client_contract_address.into()],
         ))
-        .all(&conn)
+        .all(db)
         .await?;
     Ok(applications)
 }
 
 pub async fn get_distinct_applications_by_clients_addresses(
+    db: &DatabaseConnection,
     clients_addresses: Vec<String>,
 ) -> Result<Vec<ApplicationModel>, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
-    let result = Application::find()
-        .from_raw_sql(Statement::from_sql_and_values(
-            DbBackend::Postgres,
-            "SELECT DISTINCT ON (id) * FROM applications 
-         WHERE id = ANY($1)",
-            [clients_addresses.into()],
-        ))
-        .all(&conn)
+    let applications = Application::find()
+        .filter(Column::Id.is_in(clients_addresses))
+        .order_by(Column::Id, Order::Asc)
+        .order_by(Column::PrNumber, Order::Desc)
+        .all(db)
         .await?;
-
+    let mut result: Vec<ApplicationModel> = Vec::new();
+    for application in applications {
+        if result.last().map(|last| &last.id) != Some(&application.id) {
+            result.push(application);
+        }
+    }
     Ok(result)
 }
 
-pub async fn get_closed_applications() -> Result<Vec<ApplicationModel>, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
+pub async fn get_closed_applications(
+    db: &DatabaseConnection,
+) -> Result<Vec<ApplicationModel>, sea_orm::DbErr> {
+    let backend = db.get_database_backend();
     let result = Application::find()
-        .from_raw_sql(Statement::from_sql_and_values(
-            DbBackend::Postgres,
-            "SELECT * 
-            FROM applications 
-            WHERE (application::json->'Lifecycle'->>'Active')::boolean IS NOT TRUE",
-            [],
-        ))
-        .all(&conn)
+        .filter(Expr::cust(format!(
+            "NOT ({})",
+            application_is_active(backend)
+        )))
+        .all(db)
         .await?;
     Ok(result)
 }
 
 pub async fn get_allocator_closed_applications(
+    db: &DatabaseConnection,
     owner: &str,
     repo: &str,
 ) -> Result<Vec<ApplicationModel>, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
+    let backend = db.get_database_backend();
     let result = Application::find()
         .from_raw_sql(Statement::from_sql_and_values(
-            DbBackend::Postgres,
-            "SELECT DISTINCT ON (id) * 
-            FROM applications 
-            WHERE (application::json->'Lifecycle'->>'Active')::boolean IS NOT TRUE
-            AND owner = $1
-            AND repo = $2
-            ORDER BY id, pr_number DESC",
+            backend,
+            format!(
+                "SELECT * FROM (
+                    SELECT *, ROW_NUMBER() OVER (PARTITION BY id ORDER BY pr_number DESC) AS latest_rank
+                    FROM applications
+                    WHERE NOT ({})
+                    AND owner = $1
+                    AND repo = $2
+                ) latest
+                WHERE latest_rank = 1
+                ORDER BY id",
+                application_is_active(backend)
+            ),
             [owner.into(), repo.into()],
         ))
-        .all(&conn)
+        .all(db)
         .await?;
     Ok(result)
 }
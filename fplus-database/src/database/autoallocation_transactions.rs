@@ -0,0 +1,362 @@
+use std::future::Future;
+
+use chrono::Utc;
+use sea_orm::{entity::*, query::*, ConnectionTrait, DatabaseConnection, DbErr};
+
+use crate::models::autoallocation_transactions::{
+    ActiveModel, Column, Entity as AutoallocationTransaction,
+    Model as AutoallocationTransactionModel,
+};
+
+pub const TX_STATUS_PENDING: &str = "pending";
+pub const TX_STATUS_CONFIRMED: &str = "confirmed";
+pub const TX_STATUS_FAILED: &str = "failed";
+pub const TX_STATUS_REPLACED: &str = "replaced";
+/// Broadcasting failed in a way that does not tell whether the node got the
+/// transaction. The confirmer finds out from its hash and nonce.
+pub const TX_STATUS_BROADCAST_UNKNOWN: &str = "broadcast_unknown";
+
+/// Transactions that may still be mined and hold their nonce.
+const LIVE_TX_STATUSES: [&str; 2] = [TX_STATUS_PENDING, TX_STATUS_BROADCAST_UNKNOWN];
+
+/// A signed transaction to record before it is broadcast.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewAutoallocationTransaction {
+    pub grant_id: i64,
+    /// The network of the grant, whose profile signs and follows the transaction.
+    pub network: String,
+    pub tx_hash: String,
+    pub nonce: i64,
+    /// The signed transaction, hex encoded, to broadcast it again.
+    pub raw_transaction: String,
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+}
+
+/**
+ * Record a pending transaction
+ *
+ * # Arguments
+ * @param transaction: &NewAutoallocationTransaction - The signed transaction
+ *
+ * # Returns
+ * @return Result<AutoallocationTransactionModel, sea_orm::DbErr> - The recorded transaction
+ */
+pub async fn create_autoallocation_transaction<C: ConnectionTrait>(
+    db: &C,
+    transaction: &NewAutoallocationTransaction,
+) -> Result<AutoallocationTransactionModel, DbErr> {
+    ActiveModel {
+        grant_id: Set(transaction.grant_id),
+        network: Set(transaction.network.clone()),
+        tx_hash: Set(transaction.tx_hash.clone()),
+        nonce: Set(transaction.nonce),
+        raw_transaction: Set(transaction.raw_transaction.clone()),
+        max_fee_per_gas: Set(transaction.max_fee_per_gas.clone()),
+        max_priority_fee_per_gas: Set(transaction.max_priority_fee_per_gas.clone()),
+        status: Set(TX_STATUS_PENDING.to_string()),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
+
+/**
+ * Record a new transaction with the next nonce of the autoallocator wallet
+ *
+ * The wallet's lock row is held until the signed transaction is recorded, so
+ * concurrent callers, on any instance, never pick the same nonce.
+ *
+ * # Arguments
+ * @param network: &str - The network the transaction is sent on
+ * @param wallet_address: &str - The autoallocator wallet signing the transaction
+ * @param account_nonce: i64 - The pending nonce of the wallet on chain
+ * @param sign: F - Signs the transaction with the nonce it is given, or tells why it can not
+ *
+ * # Returns
+ * @return Result<AutoallocationTransactionModel, sea_orm::DbErr> - The recorded transaction
+ */
+pub async fn create_autoallocation_transaction_with_next_nonce<F, Fut>(
+    db: &DatabaseConnection,
+    network: &str,
+    wallet_address: &str,
+    account_nonce: i64,
+    sign: F,
+) -> Result<AutoallocationTransactionModel, DbErr>
+where
+    F: FnOnce(i64) -> Fut,
+    Fut: Future<Output = Result<NewAutoallocationTransaction, String>>,
+{
+    let txn = db.begin().await?;
+    // The upsert takes the row lock on Postgres and the write lock on SQLite.
+    txn.execute(Statement::from_sql_and_values(
+        txn.get_database_backend(),
+        "INSERT INTO autoallocator_nonce_locks (network, wallet_address)
+            VALUES ($1, $2)
+            ON CONFLICT (network, wallet_address)
+            DO UPDATE SET wallet_address = excluded.wallet_address",
+        [network.into(), wallet_address.into()],
+    ))
+    .await?;
+    let next_tracked_nonce = get_max_pending_autoallocation_nonce(&txn, network)
+        .await?
+        .map(|nonce| nonce + 1)
+        .unwrap_or_default();
+    let transaction = sign(account_nonce.max(next_tracked_nonce))
+        .await
+        .map_err(DbErr::Custom)?;
+    let transaction = create_autoallocation_transaction(&txn, &transaction).await?;
+    txn.commit().await?;
+    Ok(transaction)
+}
+
+/**
+ * Get the transactions the confirmer follows, pending or with an unknown
+ * broadcast, oldest first
+ *
+ * # Returns
+ * @return Result<Vec<AutoallocationTransactionModel>, sea_orm::DbErr> - The pending transactions
+ */
+pub async fn get_pending_autoallocation_transactions(
+    db: &DatabaseConnection,
+) -> Result<Vec<AutoallocationTransactionModel>, DbErr> {
+    AutoallocationTransaction::find()
+        .filter(Column::Status.is_in(LIVE_TX_STATUSES))
+        .order_by(Column::Id, Order::Asc)
+        .all(db)
+        .await
+}
+
+/**
+ * Get the transactions sent for a grant, oldest first
+ *
+ * # Arguments
+ * @param grant_id: i64 - The ID of the autoallocation grant
+ *
+ * # Returns
+ * @return Result<Vec<AutoallocationTransactionModel>, sea_orm::DbErr> - The transactions
+ */
+pub async fn get_autoallocation_transactions_by_grant(
+    db: &DatabaseConnection,
+    grant_id: i64,
+) -> Result<Vec<AutoallocationTransactionModel>, DbErr> {
+    AutoallocationTransaction::find()
+        .filter(Column::GrantId.eq(grant_id))
+        .order_by(Column::Id, Order::Asc)
+        .all(db)
+        .await
+}
+
+/**
+ * Get the highest nonce of the transactions on a network that may still be mined
+ *
+ * # Arguments
+ * @param network: &str - The network the transactions are sent on
+ *
+ * # Returns
+ * @return Result<Option<i64>, sea_orm::DbErr> - The nonce, `None` without pending transactions
+ */
+pub async fn get_max_pending_autoallocation_nonce<C: ConnectionTrait>(
+    db: &C,
+    network: &str,
+) -> Result<Option<i64>, DbErr> {
+    let nonce: Option<Option<i64>> = AutoallocationTransaction::find()
+        .select_only()
+        .column_as(Column::Nonce.max(), "max_nonce")
+        .filter(Column::Status.is_in(LIVE_TX_STATUSES))
+        .filter(Column::Network.eq(network))
+        .into_tuple()
+        .one(db)
+        .await?;
+    Ok(nonce.flatten())
+}
+
+/**
+ * Change the status of a transaction
+ *
+ * # Arguments
+ * @param id: i64 - The ID of the transaction
+ * @param status: &str - The new status
+ * @param last_error: Option<&str> - Why the transaction failed, if it did
+ */
+pub async fn set_autoallocation_transaction_status(
+    db: &DatabaseConnection,
+    id: i64,
+    status: &str,
+    last_error: Option<&str>,
+) -> Result<(), DbErr> {
+    AutoallocationTransaction::update_many()
+        .col_expr(Column::Status, status.into())
+        .col_expr(Column::LastError, last_error.map(str::to_string).into())
+        .col_expr(Column::UpdatedAt, Utc::now().into())
+        .filter(Column::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/**
+ * Record the receipt of a mined transaction
+ *
+ * # Arguments
+ * @param id: i64 - The ID of the transaction
+ * @param succeeded: bool - Whether the transaction succeeded
+ * @param gas_used: i64 - The gas used by the transaction
+ * @param block_number: Option<i64> - The block including the transaction
+ */
+pub async fn record_autoallocation_transaction_receipt(
+    db: &DatabaseConnection,
+    id: i64,
+    succeeded: bool,
+    gas_used: i64,
+    block_number: Option<i64>,
+) -> Result<(), DbErr> {
+    let (status, last_error) = if succeeded {
+        (TX_STATUS_CONFIRMED, None)
+    } else {
+        (TX_STATUS_FAILED, Some("Transaction reverted".to_string()))
+    };
+    AutoallocationTransaction::update_many()
+        .col_expr(Column::Status, status.into())
+        .col_expr(Column::GasUsed, Some(gas_used).into())
+        .col_expr(Column::BlockNumber, block_number.into())
+        .col_expr(Column::LastError, last_error.into())
+        .col_expr(Column::UpdatedAt, Utc::now().into())
+        .filter(Column::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::setup_test_environment;
+
+    fn signed(grant_id: i64, nonce: i64) -> NewAutoallocationTransaction {
+        NewAutoallocationTransaction {
+            grant_id,
+            network: "mainnet".to_string(),
+            tx_hash: format!("0x{grant_id}{nonce}"),
+            nonce,
+            raw_transaction: "0x".to_string(),
+            max_fee_per_gas: "1".to_string(),
+            max_priority_fee_per_gas: "1".to_string(),
+        }
+    }
+
+    async fn send(db: &DatabaseConnection, grant_id: i64, account_nonce: i64) -> i64 {
+        create_autoallocation_transaction_with_next_nonce(
+            db,
+            "mainnet",
+            "0x640bD4be149f40714D95aBcD414338bc7CfF39a3",
+            account_nonce,
+            |nonce| async move {
+                tokio::task::yield_now().await;
+                Ok(signed(grant_id, nonce))
+            },
+        )
+        .await
+        .unwrap()
+        .nonce
+    }
+
+    #[tokio::test]
+    async fn concurrent_transactions_get_distinct_nonces() {
+        let db = setup_test_environment().await;
+        let (first, second, third) =
+            futures::join!(send(&db, 1, 7), send(&db, 2, 7), send(&db, 3, 7));
+        let mut nonces = vec![first, second, third];
+        nonces.sort_unstable();
+        assert_eq!(nonces, [7, 8, 9]);
+
+        // The chain moving ahead of the tracked transactions wins.
+        assert_eq!(send(&db, 4, 20).await, 20);
+    }
+
+    #[tokio::test]
+    async fn nonces_of_failed_transactions_are_reused() {
+        let db = setup_test_environment().await;
+        let sent = send(&db, 1, 3).await;
+        let unknown = send(&db, 2, 3).await;
+        let transactions = get_pending_autoallocation_transactions(&db).await.unwrap();
+        set_autoallocation_transaction_status(
+            &db,
+            transactions[1].id,
+            TX_STATUS_BROADCAST_UNKNOWN,
+            Some("connection reset"),
+        )
+        .await
+        .unwrap();
+        assert_eq!((sent, unknown), (3, 4));
+
+        // A transaction with an unknown broadcast may still be mined, it keeps
+        // its nonce and stays followed by the confirmer.
+        assert_eq!(
+            get_pending_autoallocation_transactions(&db)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(send(&db, 3, 3).await, 5);
+
+        let transactions = get_pending_autoallocation_transactions(&db).await.unwrap();
+        set_autoallocation_transaction_status(&db, transactions[2].id, TX_STATUS_FAILED, None)
+            .await
+            .unwrap();
+        assert_eq!(send(&db, 4, 3).await, 5);
+
+        let failed = create_autoallocation_transaction_with_next_nonce(
+            &db,
+            "mainnet",
+            "0x640bD4be149f40714D95aBcD414338bc7CfF39a3",
+            3,
+            |_| async { Err("Signing failed".to_string()) },
+        )
+        .await;
+        assert!(failed.is_err());
+        assert_eq!(send(&db, 5, 3).await, 6);
+    }
+
+    #[tokio::test]
+    async fn replacements_share_the_nonce_and_receipts_settle_them() {
+        let db = setup_test_environment().await;
+        send(&db, 1, 0).await;
+        let original = get_pending_autoallocation_transactions(&db).await.unwrap()[0].clone();
+        let replacement = create_autoallocation_transaction(&db, &signed(1, original.nonce))
+            .await
+            .unwrap();
+        set_autoallocation_transaction_status(&db, original.id, TX_STATUS_REPLACED, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            get_max_pending_autoallocation_nonce(&db, "mainnet")
+                .await
+                .unwrap(),
+            Some(0)
+        );
+
+        record_autoallocation_transaction_receipt(&db, replacement.id, true, 21000, Some(9))
+            .await
+            .unwrap();
+        let transactions = get_autoallocation_transactions_by_grant(&db, 1)
+            .await
+            .unwrap();
+        assert_eq!(
+            transactions
+                .iter()
+                .map(|transaction| transaction.status.as_str())
+                .collect::<Vec<_>>(),
+            [TX_STATUS_REPLACED, TX_STATUS_CONFIRMED]
+        );
+        assert_eq!(transactions[1].block_number, Some(9));
+        assert!(get_pending_autoallocation_transactions(&db)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(send(&db, 2, 1).await, 1);
+    }
+}
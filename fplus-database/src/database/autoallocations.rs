@@ -1,57 +1,527 @@
-use crate::get_database_connection;
+use crate::database::autoallocation_transactions::TX_STATUS_CONFIRMED;
+use crate::dialect::utc_day;
+use crate::models::autoallocation_grants::{
+    ActiveModel as AutoallocationGrantActiveModel, Column as AutoallocationGrantColumn,
+    Entity as AutoallocationGrants, Model as AutoallocationGrantModel,
+};
+use crate::models::autoallocation_transactions::Entity as AutoallocationTransactions;
 use crate::models::autoallocations::{
     Column, Entity as Autoallocations, Model as AutoallocationModel,
 };
 use crate::types::AddressWrapper;
 use alloy::primitives::Address;
-use chrono::{DateTime, FixedOffset};
-use sea_orm::{entity::*, query::*, DbBackend, DbErr};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use sea_orm::{
+    entity::*, prelude::Expr, query::*, ConnectionTrait, DatabaseConnection, DbErr, FromQueryResult,
+};
+use serde::Serialize;
 
 pub async fn get_last_client_autoallocation(
+    db: &DatabaseConnection,
     client_evm_address: impl Into<AddressWrapper>,
+    network: &str,
 ) -> Result<Option<DateTime<FixedOffset>>, DbErr> {
-    let response = get_autoallocation(client_evm_address.into()).await?;
+    let response = get_autoallocation(db, client_evm_address.into(), network).await?;
     Ok(response.map(|allocation| allocation.last_allocation))
 }
 
 pub async fn create_or_update_autoallocation(
+    db: &DatabaseConnection,
     client_evm_address: &Address,
+    network: &str,
     days_to_next_autoallocation: &i64,
 ) -> Result<u64, sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
     let client_address = client_evm_address.to_checksum(None);
+    // Times are bound rather than computed in SQL so the statement runs on every backend.
+    let now = Utc::now();
+    let next_allowed_after = now - Duration::days(*days_to_next_autoallocation);
 
-    let exec_res = conn
+    let exec_res = db
         .execute(Statement::from_sql_and_values(
-            DbBackend::Postgres,
-            "INSERT INTO autoallocations (evm_wallet_address, last_allocation)
-                VALUES ($1, NOW())
-                ON CONFLICT (evm_wallet_address)
-                DO UPDATE SET last_allocation = NOW()
-                WHERE autoallocations.last_allocation <= NOW() - (INTERVAL '1 day' * $2::int);",
-            [client_address.into(), (*days_to_next_autoallocation).into()],
+            db.get_database_backend(),
+            "INSERT INTO autoallocations (evm_wallet_address, network, last_allocation)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (evm_wallet_address, network)
+                DO UPDATE SET last_allocation = $3
+                WHERE autoallocations.last_allocation <= $4;",
+            [
+                client_address.into(),
+                network.into(),
+                now.into(),
+                next_allowed_after.into(),
+            ],
         ))
         .await?;
     Ok(exec_res.rows_affected())
 }
 
 pub async fn get_autoallocation(
+    db: &DatabaseConnection,
     client_evm_address: impl Into<AddressWrapper>,
+    network: &str,
 ) -> Result<Option<AutoallocationModel>, DbErr> {
-    let conn = get_database_connection().await?;
     let response = Autoallocations::find()
         .filter(Column::EvmWalletAddress.contains(client_evm_address.into()))
-        .one(&conn)
+        .filter(Column::Network.eq(network))
+        .one(db)
         .await?;
     Ok(response)
 }
 
 pub async fn delete_autoallocation(
+    db: &DatabaseConnection,
     client_evm_address: impl Into<AddressWrapper>,
+    network: &str,
 ) -> Result<(), sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
-    Autoallocations::delete_by_id(client_evm_address.into())
-        .exec(&conn)
+    Autoallocations::delete_by_id((client_evm_address.into(), network.to_string()))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub const GRANT_STATUS_PENDING: &str = "pending";
+pub const GRANT_STATUS_GRANTED: &str = "granted";
+pub const GRANT_STATUS_FAILED: &str = "failed";
+/// Backfilled from `autoallocations`, which only kept the last allocation of a
+/// wallet without its client address.
+pub const GRANT_STATUS_LEGACY: &str = "legacy";
+
+pub async fn create_autoallocation_grant<C: ConnectionTrait>(
+    db: &C,
+    client_evm_address: impl Into<AddressWrapper>,
+    client_fil_address: &str,
+    network: &str,
+    amount: i64,
+    score: f64,
+) -> Result<AutoallocationGrantModel, DbErr> {
+    AutoallocationGrantActiveModel {
+        evm_wallet_address: Set(client_evm_address.into()),
+        client_fil_address: Set(client_fil_address.to_string()),
+        amount: Set(amount),
+        score: Set(score),
+        status: Set(GRANT_STATUS_PENDING.to_string()),
+        network: Set(network.to_string()),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
+
+pub async fn get_autoallocation_grant(
+    db: &DatabaseConnection,
+    id: i64,
+) -> Result<Option<AutoallocationGrantModel>, DbErr> {
+    AutoallocationGrants::find_by_id(id).one(db).await
+}
+
+pub async fn set_autoallocation_grant_status(
+    db: &DatabaseConnection,
+    id: i64,
+    status: &str,
+) -> Result<(), DbErr> {
+    AutoallocationGrants::update_many()
+        .col_expr(AutoallocationGrantColumn::Status, status.into())
+        .filter(AutoallocationGrantColumn::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+async fn sum_autoallocation_grants<C: ConnectionTrait>(
+    db: &C,
+    condition: Condition,
+) -> Result<i64, DbErr> {
+    // Failed grants do not count. SUM(bigint) is numeric on Postgres, cast it
+    // back to fit an i64.
+    let total: Option<i64> = AutoallocationGrants::find()
+        .select_only()
+        .column_as(
+            Expr::cust("CAST(COALESCE(SUM(amount), 0) AS bigint)"),
+            "total",
+        )
+        .filter(condition)
+        .filter(AutoallocationGrantColumn::Status.ne(GRANT_STATUS_FAILED))
+        .into_tuple()
+        .one(db)
         .await?;
+    Ok(total.unwrap_or(0))
+}
+
+pub async fn get_autoallocated_amount_for_wallet<C: ConnectionTrait>(
+    db: &C,
+    client_evm_address: impl Into<AddressWrapper>,
+    network: &str,
+) -> Result<i64, DbErr> {
+    sum_autoallocation_grants(
+        db,
+        Condition::all()
+            .add(AutoallocationGrantColumn::EvmWalletAddress.eq(client_evm_address.into()))
+            .add(AutoallocationGrantColumn::Network.eq(network)),
+    )
+    .await
+}
+
+pub async fn get_autoallocated_amount_for_client<C: ConnectionTrait>(
+    db: &C,
+    client_fil_address: &str,
+    network: &str,
+) -> Result<i64, DbErr> {
+    sum_autoallocation_grants(
+        db,
+        Condition::all()
+            .add(AutoallocationGrantColumn::ClientFilAddress.eq(client_fil_address))
+            .add(AutoallocationGrantColumn::Network.eq(network)),
+    )
+    .await
+}
+
+/// Lifetime limits in bytes of the DataCap autoallocated to one EVM wallet and
+/// to one client address, `None` when unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AutoallocationGrantCaps {
+    pub per_wallet: Option<i64>,
+    pub per_client: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AutoallocationGrantOutcome {
+    Granted(AutoallocationGrantModel),
+    /// Granting would take `holder` over `cap`, `granted` were already granted.
+    CapExceeded {
+        holder: String,
+        cap: i64,
+        granted: i64,
+    },
+}
+
+/**
+ * Lock the cap row of a wallet or client address until the transaction ends
+ *
+ * # Arguments
+ * @param db: &C - The transaction of the grant
+ * @param network: &str - The network of the grant
+ * @param holder: &str - The capped wallet or client address, with its kind
+ */
+async fn lock_autoallocation_cap<C: ConnectionTrait>(
+    db: &C,
+    network: &str,
+    holder: &str,
+) -> Result<(), DbErr> {
+    // The upsert takes the row lock on Postgres and the write lock on SQLite.
+    db.execute(Statement::from_sql_and_values(
+        db.get_database_backend(),
+        "INSERT INTO autoallocation_cap_locks (network, holder)
+            VALUES ($1, $2)
+            ON CONFLICT (network, holder)
+            DO UPDATE SET holder = excluded.holder",
+        [network.into(), holder.into()],
+    ))
+    .await?;
     Ok(())
 }
+
+/**
+ * Create a pending autoallocation grant unless it would exceed a lifetime cap
+ *
+ * # Arguments
+ * @param db: &DatabaseConnection - The database connection
+ * @param client_evm_address: Address - The EVM wallet of the client
+ * @param client_fil_address: &str - The client address the DataCap goes to
+ * @param network: &str - The network of the grant
+ * @param amount: i64 - Bytes of DataCap to grant
+ * @param score: f64 - The Gitcoin Passport score the grant is for
+ * @param caps: AutoallocationGrantCaps - The lifetime caps to respect
+ *
+ * # Returns
+ * @return Result<AutoallocationGrantOutcome, DbErr> - The grant, or the cap it would exceed
+ */
+pub async fn create_autoallocation_grant_within_caps(
+    db: &DatabaseConnection,
+    client_evm_address: impl Into<AddressWrapper>,
+    client_fil_address: &str,
+    network: &str,
+    amount: i64,
+    score: f64,
+    caps: AutoallocationGrantCaps,
+) -> Result<AutoallocationGrantOutcome, DbErr> {
+    let client_evm_address: AddressWrapper = client_evm_address.into();
+    let txn = db.begin().await?;
+    // Holders are always locked wallet first, so two grants never wait on
+    // each other's locks.
+    if let Some(cap) = caps.per_wallet {
+        let holder = format!("wallet {}", client_evm_address.0.to_checksum(None));
+        lock_autoallocation_cap(&txn, network, &holder).await?;
+        let granted =
+            get_autoallocated_amount_for_wallet(&txn, client_evm_address.clone(), network).await?;
+        if granted.saturating_add(amount) > cap {
+            txn.rollback().await?;
+            return Ok(AutoallocationGrantOutcome::CapExceeded {
+                holder,
+                cap,
+                granted,
+            });
+        }
+    }
+    if let Some(cap) = caps.per_client {
+        let holder = format!("client {client_fil_address}");
+        lock_autoallocation_cap(&txn, network, &holder).await?;
+        let granted =
+            get_autoallocated_amount_for_client(&txn, client_fil_address, network).await?;
+        if granted.saturating_add(amount) > cap {
+            txn.rollback().await?;
+            return Ok(AutoallocationGrantOutcome::CapExceeded {
+                holder,
+                cap,
+                granted,
+            });
+        }
+    }
+    let grant = create_autoallocation_grant(
+        &txn,
+        client_evm_address,
+        client_fil_address,
+        network,
+        amount,
+        score,
+    )
+    .await?;
+    txn.commit().await?;
+    Ok(AutoallocationGrantOutcome::Granted(grant))
+}
+
+/// Which autoallocation grants to list, every field narrows the selection.
+#[derive(Debug, Clone, Default)]
+pub struct AutoallocationFilter {
+    pub evm_wallet_address: Option<AddressWrapper>,
+    pub client_fil_address: Option<String>,
+    /// Granted at or after, inclusive.
+    pub since: Option<DateTime<Utc>>,
+    /// Granted before, exclusive.
+    pub until: Option<DateTime<Utc>>,
+    pub status: Option<String>,
+    pub network: Option<String>,
+}
+
+impl AutoallocationFilter {
+    fn condition(&self) -> Condition {
+        let mut condition = Condition::all();
+        if let Some(evm_wallet_address) = &self.evm_wallet_address {
+            condition = condition
+                .add(AutoallocationGrantColumn::EvmWalletAddress.eq(evm_wallet_address.clone()));
+        }
+        if let Some(client_fil_address) = &self.client_fil_address {
+            condition = condition
+                .add(AutoallocationGrantColumn::ClientFilAddress.eq(client_fil_address.clone()));
+        }
+        if let Some(since) = self.since {
+            condition = condition.add(AutoallocationGrantColumn::CreatedAt.gte(since));
+        }
+        if let Some(until) = self.until {
+            condition = condition.add(AutoallocationGrantColumn::CreatedAt.lt(until));
+        }
+        if let Some(status) = &self.status {
+            condition = condition.add(AutoallocationGrantColumn::Status.eq(status.clone()));
+        }
+        if let Some(network) = &self.network {
+            condition = condition.add(AutoallocationGrantColumn::Network.eq(network.clone()));
+        }
+        condition
+    }
+}
+
+/// A grant with the transaction that carried it out.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AutoallocationHistoryEntry {
+    #[serde(flatten)]
+    pub grant: AutoallocationGrantModel,
+    pub tx_hash: Option<String>,
+    pub block_number: Option<i64>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, FromQueryResult)]
+pub struct AutoallocationDailyStats {
+    /// UTC day, `YYYY-MM-DD`.
+    pub day: String,
+    pub status: String,
+    pub count: i64,
+    /// Bytes of DataCap.
+    pub volume: i64,
+}
+
+/// The most grants returned by one page of history.
+pub const HISTORY_PAGE_MAX: u64 = 1000;
+
+/// A page of history, `next_cursor` is passed back to get the following one.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AutoallocationHistoryPage {
+    pub autoallocations: Vec<AutoallocationHistoryEntry>,
+    pub next_cursor: Option<i64>,
+}
+
+/**
+ * Get the grants matching the filter, newest first, each with its confirmed
+ * transaction, else its most recent one
+ *
+ * # Arguments
+ * @param filter: &AutoallocationFilter - Which grants to list
+ * @param limit: u64 - The size of the page, at most `HISTORY_PAGE_MAX`
+ * @param cursor: Option<i64> - The `next_cursor` of the previous page
+ *
+ * # Returns
+ * @return Result<AutoallocationHistoryPage, sea_orm::DbErr> - The page of grants
+ */
+pub async fn get_autoallocation_history(
+    db: &DatabaseConnection,
+    filter: &AutoallocationFilter,
+    limit: u64,
+    cursor: Option<i64>,
+) -> Result<AutoallocationHistoryPage, DbErr> {
+    let limit = limit.clamp(1, HISTORY_PAGE_MAX);
+    let mut condition = filter.condition();
+    if let Some(cursor) = cursor {
+        condition = condition.add(AutoallocationGrantColumn::Id.lt(cursor));
+    }
+    let mut query = AutoallocationGrants::find()
+        .select_also(AutoallocationTransactions)
+        .filter(condition)
+        .order_by(AutoallocationGrantColumn::Id, Order::Desc)
+        .limit(limit + 1);
+    QueryTrait::query(&mut query).join(
+        JoinType::LeftJoin,
+        AutoallocationTransactions,
+        Expr::cust(format!(
+            "autoallocation_transactions.id = (
+                SELECT t.id FROM autoallocation_transactions t
+                WHERE t.grant_id = autoallocation_grants.id
+                ORDER BY CASE WHEN t.status = '{TX_STATUS_CONFIRMED}' THEN 0 ELSE 1 END, t.id DESC
+                LIMIT 1
+            )"
+        )),
+    );
+    let mut rows = query.all(db).await?;
+    let next_cursor = if rows.len() as u64 > limit {
+        rows.truncate(limit as usize);
+        rows.last().map(|(grant, _)| grant.id)
+    } else {
+        None
+    };
+    Ok(AutoallocationHistoryPage {
+        autoallocations: rows
+            .into_iter()
+            .map(|(grant, transaction)| AutoallocationHistoryEntry {
+                tx_hash: transaction
+                    .as_ref()
+                    .map(|transaction| transaction.tx_hash.clone()),
+                block_number: transaction.and_then(|transaction| transaction.block_number),
+                grant,
+            })
+            .collect(),
+        next_cursor,
+    })
+}
+
+/**
+ * Get the client addresses autoallocated to by the grants matching the filter,
+ * failed and legacy grants aside
+ *
+ * # Arguments
+ * @param filter: &AutoallocationFilter - Which grants to look at
+ *
+ * # Returns
+ * @return Result<Vec<String>, sea_orm::DbErr> - The distinct client addresses
+ */
+pub async fn get_autoallocated_client_addresses(
+    db: &DatabaseConnection,
+    filter: &AutoallocationFilter,
+) -> Result<Vec<String>, DbErr> {
+    AutoallocationGrants::find()
+        .select_only()
+        .column(AutoallocationGrantColumn::ClientFilAddress)
+        .distinct()
+        .filter(filter.condition())
+        .filter(
+            AutoallocationGrantColumn::Status.is_not_in([GRANT_STATUS_FAILED, GRANT_STATUS_LEGACY]),
+        )
+        .into_tuple()
+        .all(db)
+        .await
+}
+
+/**
+ * Record a legacy grant for every autoallocation made before grants were
+ * recorded. Only the last allocation of a wallet was kept, it is backfilled
+ * with the fixed amount autoallocated at the time. Wallets with a grant on the
+ * network are skipped, so running it again does nothing.
+ *
+ * # Arguments
+ * @param amount: i64 - The amount autoallocated before grants were recorded
+ *
+ * # Returns
+ * @return Result<u64, sea_orm::DbErr> - The number of grants backfilled
+ */
+pub async fn backfill_legacy_autoallocation_grants(
+    db: &DatabaseConnection,
+    amount: i64,
+) -> Result<u64, DbErr> {
+    let legacy = Autoallocations::find()
+        .filter(Expr::cust(
+            "NOT EXISTS (
+                SELECT 1 FROM autoallocation_grants g
+                WHERE g.evm_wallet_address = autoallocations.evm_wallet_address
+                AND g.network = autoallocations.network
+            )",
+        ))
+        .all(db)
+        .await?;
+    let backfilled = legacy.len() as u64;
+    for autoallocation in legacy {
+        AutoallocationGrantActiveModel {
+            evm_wallet_address: Set(autoallocation.evm_wallet_address),
+            client_fil_address: Set(String::new()),
+            amount: Set(amount),
+            score: Set(0.0),
+            status: Set(GRANT_STATUS_LEGACY.to_string()),
+            network: Set(autoallocation.network),
+            created_at: Set(autoallocation.last_allocation.with_timezone(&Utc)),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+    Ok(backfilled)
+}
+
+/**
+ * Get the number and volume of the grants matching the filter, per UTC day and
+ * status
+ *
+ * # Arguments
+ * @param filter: &AutoallocationFilter - Which grants to count
+ *
+ * # Returns
+ * @return Result<Vec<AutoallocationDailyStats>, sea_orm::DbErr> - The stats, oldest day first
+ */
+pub async fn get_autoallocation_daily_stats(
+    db: &DatabaseConnection,
+    filter: &AutoallocationFilter,
+) -> Result<Vec<AutoallocationDailyStats>, DbErr> {
+    let day = utc_day(db.get_database_backend(), "created_at");
+    AutoallocationGrants::find()
+        .select_only()
+        .column_as(Expr::cust(day.clone()), "day")
+        .column(AutoallocationGrantColumn::Status)
+        .column_as(Expr::cust("COUNT(*)"), "count")
+        .column_as(
+            Expr::cust("CAST(COALESCE(SUM(amount), 0) AS bigint)"),
+            "volume",
+        )
+        .filter(filter.condition())
+        .group_by(Expr::cust(day.clone()))
+        .group_by(AutoallocationGrantColumn::Status)
+        .order_by(Expr::cust(day), Order::Asc)
+        .order_by(AutoallocationGrantColumn::Status, Order::Asc)
+        .into_model::<AutoallocationDailyStats>()
+        .all(db)
+        .await
+}
@@ -1,34 +1,40 @@
-use crate::get_database_connection;
+use crate::dialect::json_text;
 use crate::models::comparable_applications::{
     ActiveModel, ApplicationComparableData, Entity as ComparableApplication,
     Model as ComparableApplicationModel,
 };
 use sea_orm::prelude::Expr;
-use sea_orm::{entity::*, Condition, DbErr, QueryFilter};
+use sea_orm::{entity::*, Condition, ConnectionTrait, DatabaseConnection, DbErr, QueryFilter};
 
 pub async fn create_comparable_application(
+    db: &DatabaseConnection,
     client_address: &str,
     comparable_data: &ApplicationComparableData,
 ) -> Result<(), sea_orm::DbErr> {
-    let conn = get_database_connection().await?;
     let new_comparable_data = ActiveModel {
         client_address: Set(client_address.to_string()),
         application: Set(comparable_data.clone()),
     };
-    new_comparable_data.insert(&conn).await?;
+    new_comparable_data.insert(db).await?;
     Ok(())
 }
 
-pub async fn get_comparable_applications() -> Result<Vec<ComparableApplicationModel>, DbErr> {
-    let conn = get_database_connection().await?;
+pub async fn get_comparable_applications(
+    db: &DatabaseConnection,
+) -> Result<Vec<ComparableApplicationModel>, DbErr> {
+    let backend = db.get_database_backend();
     let condition = Condition::any()
-        .add(Expr::cust("char_length(application->>'project_desc') > 40"))
-        .add(Expr::cust(
-            "char_length(application->>'stored_data_desc') > 40",
-        ));
+        .add(Expr::cust(format!(
+            "length({}) > 40",
+            json_text(backend, "application", &["project_desc"])
+        )))
+        .add(Expr::cust(format!(
+            "length({}) > 40",
+            json_text(backend, "application", &["stored_data_desc"])
+        )));
     let response = ComparableApplication::find()
         .filter(condition)
-        .all(&conn)
+        .all(db)
         .await?;
     Ok(response)
 }
@@ -0,0 +1,59 @@
+use chrono::{Duration, Utc};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, Statement};
+
+/**
+ * Take or renew the lease of a job that must run on a single instance
+ *
+ * # Arguments
+ * @param name: &str - The job
+ * @param holder: &str - The instance asking, unique per process
+ * @param lease_secs: i64 - How long the lease lasts unless it is renewed
+ *
+ * # Returns
+ * @return Result<bool, sea_orm::DbErr> - Whether `holder` holds the lease
+ */
+pub async fn acquire_leader_lease(
+    db: &DatabaseConnection,
+    name: &str,
+    holder: &str,
+    lease_secs: i64,
+) -> Result<bool, DbErr> {
+    // Times are bound rather than computed in SQL so the statement runs on every backend.
+    let now = Utc::now();
+    let exec_res = db
+        .execute(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "INSERT INTO leader_leases (name, holder, expires_at)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (name)
+                DO UPDATE SET holder = $2, expires_at = $3
+                WHERE leader_leases.holder = $2 OR leader_leases.expires_at <= $4",
+            [
+                name.into(),
+                holder.into(),
+                (now + Duration::seconds(lease_secs)).into(),
+                now.into(),
+            ],
+        ))
+        .await?;
+    Ok(exec_res.rows_affected() == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::setup_test_environment;
+
+    #[tokio::test]
+    async fn one_holder_leads_until_its_lease_expires() {
+        let db = setup_test_environment().await;
+        assert!(acquire_leader_lease(&db, "job", "a", 60).await.unwrap());
+        assert!(!acquire_leader_lease(&db, "job", "b", 60).await.unwrap());
+        assert!(acquire_leader_lease(&db, "job", "a", 0).await.unwrap());
+        assert!(acquire_leader_lease(&db, "other", "b", 60).await.unwrap());
+
+        // The lease of `a` ran out.
+        assert!(acquire_leader_lease(&db, "job", "b", 60).await.unwrap());
+        assert!(!acquire_leader_lease(&db, "job", "a", 60).await.unwrap());
+    }
+}
@@ -1,5 +1,12 @@
 pub mod allocation_amounts;
 pub mod allocators;
+pub mod allowance_alerts;
+pub mod application_details;
+pub mod application_versions;
 pub mod applications;
+pub mod autoallocation_transactions;
 pub mod autoallocations;
 pub mod comparable_applications;
+pub mod leader_leases;
+pub mod outbox;
+pub mod used_signatures;
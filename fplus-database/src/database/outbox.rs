@@ -0,0 +1,391 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::*, query::*, ConnectionTrait, DatabaseConnection, DbBackend, DbErr};
+
+use crate::models::outbox_messages::{
+    ActiveModel, Column, Entity as OutboxMessage, Model as OutboxMessageModel,
+};
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_DONE: &str = "done";
+pub const STATUS_DEAD: &str = "dead";
+
+/// A side effect to record in the outbox.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewOutboxMessage {
+    /// Messages of the same aggregate are applied in the order they were enqueued.
+    pub aggregate: String,
+    pub kind: String,
+    pub payload: String,
+}
+
+/**
+ * Record side effects in the outbox
+ *
+ * Pass the transaction of the change that causes the side effects, so both
+ * are committed or rolled back together.
+ *
+ * # Arguments
+ * @param db: &C - The connection or transaction to use
+ * @param messages: &[NewOutboxMessage] - The side effects to record
+ */
+pub async fn enqueue_outbox_messages<C: ConnectionTrait>(
+    db: &C,
+    messages: &[NewOutboxMessage],
+) -> Result<(), DbErr> {
+    if messages.is_empty() {
+        return Ok(());
+    }
+    OutboxMessage::insert_many(messages.iter().map(|message| ActiveModel {
+        aggregate: Set(message.aggregate.clone()),
+        kind: Set(message.kind.clone()),
+        payload: Set(message.payload.clone()),
+        ..Default::default()
+    }))
+    .exec(db)
+    .await?;
+    Ok(())
+}
+
+/**
+ * Claim the pending messages that are due
+ *
+ * A message is skipped while an older message of its aggregate is still
+ * pending or dead, so side effects are never applied out of order. Claimed messages have their attempt counted and are hidden from
+ * other workers for `lease_secs`, after which they are picked up again unless
+ * they were marked done or failed.
+ *
+ * # Arguments
+ * @param limit: u64 - The maximum number of messages to claim
+ * @param lease_secs: i64 - How long the claimed messages stay hidden
+ *
+ * # Returns
+ * @return Result<Vec<OutboxMessageModel>, sea_orm::DbErr> - The claimed messages, oldest first
+ */
+pub async fn claim_due_outbox_messages(
+    db: &DatabaseConnection,
+    limit: u64,
+    lease_secs: i64,
+) -> Result<Vec<OutboxMessageModel>, DbErr> {
+    let backend = db.get_database_backend();
+    // SQLite serializes writers, only Postgres needs to skip rows claimed concurrently.
+    let lock = match backend {
+        DbBackend::Postgres => "FOR UPDATE SKIP LOCKED",
+        _ => "",
+    };
+    let now = Utc::now();
+    let mut messages = OutboxMessage::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            backend,
+            format!(
+                "UPDATE outbox_messages
+            SET attempts = attempts + 1,
+                next_attempt_at = $2,
+                updated_at = $3
+            WHERE id IN (
+                SELECT o.id FROM outbox_messages o
+                WHERE o.status = 'pending'
+                AND o.next_attempt_at <= $3
+                AND NOT EXISTS (
+                    SELECT 1 FROM outbox_messages earlier
+                    WHERE earlier.aggregate = o.aggregate
+                    AND earlier.id < o.id
+                    AND earlier.status <> 'done'
+                )
+                ORDER BY o.id
+                LIMIT $1
+                {lock}
+            )
+            RETURNING *"
+            ),
+            [
+                (limit as i64).into(),
+                (now + chrono::Duration::seconds(lease_secs)).into(),
+                now.into(),
+            ],
+        ))
+        .all(db)
+        .await?;
+    messages.sort_by_key(|message| message.id);
+    Ok(messages)
+}
+
+/**
+ * Mark a message as applied
+ *
+ * # Arguments
+ * @param id: i64 - The ID of the message
+ */
+pub async fn mark_outbox_message_done(db: &DatabaseConnection, id: i64) -> Result<(), DbErr> {
+    OutboxMessage::update_many()
+        .col_expr(Column::Status, STATUS_DONE.into())
+        .col_expr(Column::LastError, Option::<String>::None.into())
+        .col_expr(Column::UpdatedAt, Utc::now().into())
+        .filter(Column::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/**
+ * Record a failed attempt of a message
+ *
+ * # Arguments
+ * @param id: i64 - The ID of the message
+ * @param error: &str - Why the attempt failed
+ * @param retry_at: Option<DateTime<Utc>> - When to try again, `None` moves the message to the dead letters
+ */
+pub async fn mark_outbox_message_failed(
+    db: &DatabaseConnection,
+    id: i64,
+    error: &str,
+    retry_at: Option<DateTime<Utc>>,
+) -> Result<(), DbErr> {
+    let mut update = OutboxMessage::update_many()
+        .col_expr(Column::LastError, Some(error.to_string()).into())
+        .col_expr(Column::UpdatedAt, Utc::now().into());
+    update = match retry_at {
+        Some(retry_at) => update.col_expr(Column::NextAttemptAt, retry_at.into()),
+        None => update.col_expr(Column::Status, STATUS_DEAD.into()),
+    };
+    update.filter(Column::Id.eq(id)).exec(db).await?;
+    Ok(())
+}
+
+/**
+ * Get the messages which ran out of attempts
+ *
+ * # Arguments
+ * @param aggregate_prefix: &str - Start of the aggregates to include
+ *
+ * # Returns
+ * @return Result<Vec<OutboxMessageModel>, sea_orm::DbErr> - The dead letters, oldest first
+ */
+pub async fn get_dead_outbox_messages(
+    db: &DatabaseConnection,
+    aggregate_prefix: &str,
+) -> Result<Vec<OutboxMessageModel>, DbErr> {
+    // LIKE treats `_` in names as a wildcard, so the prefix is checked again.
+    let messages = OutboxMessage::find()
+        .filter(Column::Status.eq(STATUS_DEAD))
+        .filter(Column::Aggregate.starts_with(aggregate_prefix))
+        .order_by(Column::Id, Order::Asc)
+        .all(db)
+        .await?;
+    Ok(messages
+        .into_iter()
+        .filter(|message| message.aggregate.starts_with(aggregate_prefix))
+        .collect())
+}
+
+/**
+ * Queue a dead message again with a fresh set of attempts
+ *
+ * # Arguments
+ * @param id: i64 - The ID of the message
+ * @param aggregate_prefix: &str - Start of the aggregates the message may belong to
+ *
+ * # Returns
+ * @return Result<Option<OutboxMessageModel>, sea_orm::DbErr> - The message, `None` if there is no such dead message
+ */
+pub async fn replay_outbox_message(
+    db: &DatabaseConnection,
+    id: i64,
+    aggregate_prefix: &str,
+) -> Result<Option<OutboxMessageModel>, DbErr> {
+    let Some(message) = OutboxMessage::find_by_id(id)
+        .filter(Column::Status.eq(STATUS_DEAD))
+        .one(db)
+        .await?
+        .filter(|message| message.aggregate.starts_with(aggregate_prefix))
+    else {
+        return Ok(None);
+    };
+    let mut message = message.into_active_model();
+    message.status = Set(STATUS_PENDING.to_string());
+    message.attempts = Set(0);
+    message.next_attempt_at = Set(Utc::now());
+    message.updated_at = Set(Utc::now());
+    message.update(db).await.map(Some)
+}
+
+/**
+ * Delete applied messages, which nothing reads anymore once they are done
+ *
+ * # Arguments
+ * @param before: DateTime<Utc> - Delete messages applied before
+ *
+ * # Returns
+ * @return Result<u64, sea_orm::DbErr> - The number of deleted messages
+ */
+pub async fn delete_done_outbox_messages(
+    db: &DatabaseConnection,
+    before: DateTime<Utc>,
+) -> Result<u64, DbErr> {
+    let result = OutboxMessage::delete_many()
+        .filter(Column::Status.eq(STATUS_DONE))
+        .filter(Column::UpdatedAt.lt(before))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::setup_test_environment;
+
+    fn message(aggregate: &str, kind: &str) -> NewOutboxMessage {
+        NewOutboxMessage {
+            aggregate: aggregate.to_string(),
+            kind: kind.to_string(),
+            payload: "{}".to_string(),
+        }
+    }
+
+    fn kinds(messages: &[OutboxMessageModel]) -> Vec<&str> {
+        messages
+            .iter()
+            .map(|message| message.kind.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn claimed_messages_are_leased_to_one_worker() {
+        let db = setup_test_environment().await;
+        let messages: Vec<_> = (0..6)
+            .map(|i| message(&format!("owner/repo/f1client{i}"), &format!("kind{i}")))
+            .collect();
+        enqueue_outbox_messages(&db, &messages).await.unwrap();
+
+        let (first, second) = futures::join!(
+            claim_due_outbox_messages(&db, 4, 300),
+            claim_due_outbox_messages(&db, 4, 300),
+        );
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_eq!(first.len() + second.len(), 6);
+        assert!(first.iter().all(|message| !second.contains(message)));
+        assert!(first.iter().all(|message| message.attempts == 1));
+
+        assert!(claim_due_outbox_messages(&db, 10, 300)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn messages_of_an_aggregate_are_applied_in_order() {
+        let db = setup_test_environment().await;
+        enqueue_outbox_messages(
+            &db,
+            &[
+                message("owner/repo/f1client", "commit"),
+                message("owner/repo/f1client", "comment"),
+                message("owner/repo/f1other", "labels"),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let claimed = claim_due_outbox_messages(&db, 10, 0).await.unwrap();
+        assert_eq!(kinds(&claimed), ["commit", "labels"]);
+        mark_outbox_message_failed(&db, claimed[0].id, "failed", None)
+            .await
+            .unwrap();
+        mark_outbox_message_done(&db, claimed[1].id).await.unwrap();
+
+        // The dead commit holds back the comment that follows it.
+        assert!(claim_due_outbox_messages(&db, 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let replayed = replay_outbox_message(&db, claimed[0].id, "owner/repo/")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(replayed.attempts, 0);
+        let claimed = claim_due_outbox_messages(&db, 10, 0).await.unwrap();
+        assert_eq!(kinds(&claimed), ["commit"]);
+        mark_outbox_message_done(&db, claimed[0].id).await.unwrap();
+        assert_eq!(
+            kinds(&claim_due_outbox_messages(&db, 10, 0).await.unwrap()),
+            ["comment"]
+        );
+    }
+
+    #[tokio::test]
+    async fn dead_messages_are_listed_and_replayed_per_allocator() {
+        let db = setup_test_environment().await;
+        enqueue_outbox_messages(
+            &db,
+            &[
+                message("owner/repo/f1client", "commit"),
+                message("owner/repository/f1client", "commit"),
+            ],
+        )
+        .await
+        .unwrap();
+        for message in claim_due_outbox_messages(&db, 10, 0).await.unwrap() {
+            mark_outbox_message_failed(&db, message.id, "failed", None)
+                .await
+                .unwrap();
+        }
+
+        let dead = get_dead_outbox_messages(&db, "owner/repo/").await.unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].aggregate, "owner/repo/f1client");
+        assert_eq!(dead[0].last_error.as_deref(), Some("failed"));
+
+        let other = get_dead_outbox_messages(&db, "owner/repository/")
+            .await
+            .unwrap();
+        assert!(replay_outbox_message(&db, other[0].id, "owner/repo/")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(replay_outbox_message(&db, dead[0].id, "owner/repo/")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(get_dead_outbox_messages(&db, "owner/repo/")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_done_messages_past_retention_are_deleted() {
+        let db = setup_test_environment().await;
+        let messages: Vec<_> = (0..3)
+            .map(|i| message("owner/repo/f1client", &format!("kind{i}")))
+            .collect();
+        enqueue_outbox_messages(&db, &messages).await.unwrap();
+        let claimed = claim_due_outbox_messages(&db, 10, 300).await.unwrap();
+        assert_eq!(kinds(&claimed), vec!["kind0"]);
+        mark_outbox_message_done(&db, claimed[0].id).await.unwrap();
+        let claimed = claim_due_outbox_messages(&db, 10, 300).await.unwrap();
+        mark_outbox_message_failed(&db, claimed[0].id, "boom", None)
+            .await
+            .unwrap();
+
+        let retention_start = Utc::now() - chrono::Duration::days(7);
+        assert_eq!(
+            delete_done_outbox_messages(&db, retention_start)
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            delete_done_outbox_messages(&db, Utc::now() + chrono::Duration::seconds(1))
+                .await
+                .unwrap(),
+            1
+        );
+        let remaining = OutboxMessage::find()
+            .order_by(Column::Id, Order::Asc)
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(kinds(&remaining), vec!["kind1", "kind2"]);
+    }
+}
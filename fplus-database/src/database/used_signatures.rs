@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::*, query::*, ConnectionTrait, DatabaseConnection, DbErr, Statement};
+
+use crate::models::used_signatures::{Column, Entity as UsedSignature};
+
+/**
+ * Record a signature as used, unless it already is. The check and the insert
+ * are one statement, so of concurrent requests with the same signature only
+ * one consumes it.
+ *
+ * # Arguments
+ * @param signature_hash: &str - The hash identifying the signature
+ * @param kind: &str - The kind of the signed message
+ * @param signer: &str - The address recovered from the signature
+ * @param expires_at: DateTime<Utc> - When the signed message expires
+ *
+ * # Returns
+ * @return Result<bool, sea_orm::DbErr> - `false` when the signature was already used
+ */
+pub async fn consume_signature(
+    db: &DatabaseConnection,
+    signature_hash: &str,
+    kind: &str,
+    signer: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<bool, DbErr> {
+    let result = db
+        .execute(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            "INSERT INTO used_signatures (signature_hash, kind, signer, expires_at, used_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (signature_hash) DO NOTHING",
+            [
+                signature_hash.into(),
+                kind.into(),
+                signer.into(),
+                expires_at.into(),
+                Utc::now().into(),
+            ],
+        ))
+        .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/**
+ * Release a consumed signature, when the request it was consumed for failed
+ * before changing anything
+ *
+ * # Arguments
+ * @param signature_hash: &str - The hash identifying the signature
+ *
+ * # Returns
+ * @return Result<(), sea_orm::DbErr> - The result of the operation
+ */
+pub async fn release_signature(db: &DatabaseConnection, signature_hash: &str) -> Result<(), DbErr> {
+    UsedSignature::delete_many()
+        .filter(Column::SignatureHash.eq(signature_hash))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/**
+ * Delete the signatures of messages that expired, which are rejected without
+ * looking them up
+ *
+ * # Arguments
+ * @param before: DateTime<Utc> - Delete signatures of messages expired before
+ *
+ * # Returns
+ * @return Result<u64, sea_orm::DbErr> - The number of deleted signatures
+ */
+pub async fn delete_expired_used_signatures(
+    db: &DatabaseConnection,
+    before: DateTime<Utc>,
+) -> Result<u64, DbErr> {
+    let result = UsedSignature::delete_many()
+        .filter(Column::ExpiresAt.lt(before))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
@@ -0,0 +1,83 @@
+use sea_orm::DbBackend;
+
+/**
+ * SQL extracting a text value from a JSON column
+ *
+ * # Arguments
+ * @param backend: DbBackend - The database backend
+ * @param column: &str - The JSON (or JSON text) column
+ * @param path: &[&str] - The keys leading to the value
+ *
+ * # Returns
+ * @return String - `column::json->'a'->>'b'` on Postgres, `json_extract(column, '$."a"."b"')` on SQLite
+ */
+pub(crate) fn json_text(backend: DbBackend, column: &str, path: &[&str]) -> String {
+    match backend {
+        DbBackend::Sqlite => {
+            let path: String = path.iter().map(|key| format!(".\"{key}\"")).collect();
+            format!("json_extract({column}, '${path}')")
+        }
+        _ => {
+            let mut sql = format!("{column}::json");
+            for (i, key) in path.iter().enumerate() {
+                let arrow = if i + 1 == path.len() { "->>" } else { "->" };
+                sql.push_str(&format!("{arrow}'{key}'"));
+            }
+            format!("({sql})")
+        }
+    }
+}
+
+/**
+ * SQL condition that is true for applications whose JSON is marked active
+ *
+ * # Arguments
+ * @param backend: DbBackend - The database backend
+ */
+pub(crate) fn application_is_active(backend: DbBackend) -> String {
+    let active = json_text(backend, "application", &["Lifecycle", "Active"]);
+    match backend {
+        // json_extract returns JSON booleans as 1 and 0.
+        DbBackend::Sqlite => format!("{active} IS TRUE"),
+        _ => format!("{active}::boolean IS TRUE"),
+    }
+}
+
+/**
+ * SQL formatting a timestamp column as its UTC day, `YYYY-MM-DD`
+ *
+ * # Arguments
+ * @param backend: DbBackend - The database backend
+ * @param column: &str - The timestamp column, stored as RFC 3339 text on SQLite
+ */
+pub(crate) fn utc_day(backend: DbBackend, column: &str) -> String {
+    match backend {
+        DbBackend::Sqlite => format!("substr({column}, 1, 10)"),
+        _ => format!("to_char({column} AT TIME ZONE 'UTC', 'YYYY-MM-DD')"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_paths_are_rendered_per_backend() {
+        assert_eq!(
+            json_text(DbBackend::Postgres, "application", &["Lifecycle", "Active"]),
+            "(application::json->'Lifecycle'->>'Active')"
+        );
+        assert_eq!(
+            json_text(DbBackend::Sqlite, "application", &["Lifecycle", "Active"]),
+            "json_extract(application, '$.\"Lifecycle\".\"Active\"')"
+        );
+        assert_eq!(
+            application_is_active(DbBackend::Postgres),
+            "(application::json->'Lifecycle'->>'Active')::boolean IS TRUE"
+        );
+        assert_eq!(
+            utc_day(DbBackend::Sqlite, "created_at"),
+            "substr(created_at, 1, 10)"
+        );
+    }
+}
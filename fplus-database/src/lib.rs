@@ -1,18 +1,14 @@
 pub mod config;
 pub mod database;
+mod dialect;
+pub mod migrations;
 pub mod models;
+pub mod pool;
 mod types;
 
-use crate::config::get_env_or_throw;
-use once_cell::sync::Lazy;
-use sea_orm::{Database, DatabaseConnection, DbErr};
-use std::sync::Mutex;
-use types::DbConnectParams;
-
-/**
- * The global database connection
- */
-static DB_CONN: Lazy<Mutex<Option<DatabaseConnection>>> = Lazy::new(|| Mutex::new(None));
+pub use pool::{DbPool, PoolConfig};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /**
  * Initialize the database (Just for testing purposes, not used in the actual application, as dotenv is called in the main function of the application)
@@ -25,58 +21,88 @@ pub fn init() {
 }
 
 /**
- * Establish a connection to the database
- *  
+ * Open the connection pool configured in the environment. Library code does
+ * not keep a pool of its own, callers hand this one down.
+ *
  * # Returns
- * @return Result<DatabaseConnection, sea_orm::DbErr> - The result of the operation
+ * @return Result<DatabaseConnection, sea_orm::DbErr> - The connection pool
  */
-pub async fn setup() -> Result<(), DbErr> {
-    let database_url = std::env::var("DB_URL").unwrap_or_else(|_| {
-        let params: DbConnectParams =
-            serde_json::from_str(&get_env_or_throw("DB_CONNECT_PARAMS_JSON"))
-                .expect("Invalid JSON in DB_CONNECT_PARAMS_JSON");
-        params.to_url()
-    });
-    let db_conn = Database::connect(&database_url).await?;
-    let mut db_conn_global = DB_CONN
-        .lock()
-        .map_err(|e| DbErr::Custom(format!("Failed to lock database connection: {e}")))?;
-    *db_conn_global = Some(db_conn);
-    Ok(())
+pub async fn setup() -> Result<DatabaseConnection, DbErr> {
+    pool::connect(&PoolConfig::from_env()).await
 }
 
 /**
- * Get a reference to the established database connection
- *
- * # Returns
- * @return Result<DatabaseConnection, &'static str> - The database connection or an error message
- */
-pub async fn get_database_connection() -> Result<DatabaseConnection, DbErr> {
-    let db_conn = DB_CONN
-        .lock()
-        .map_err(|e| DbErr::Custom(format!("Failed to lock database connection: {e}")))?;
-    if let Some(ref conn) = *db_conn {
-        Ok(conn.clone())
-    } else {
-        Err(DbErr::Custom(
-            "Database connection is not established".into(),
-        ))
+* Sets up the initial test environment (env variables) and opens a private, migrated
+* database, so tests can run in parallel without sharing state. The database is an
+* in-memory SQLite one, or a fresh schema of the Postgres database in `TEST_DB_URL`.
+* Panics when `TEST_DB_URL` does not look like a test database.
+*/
+pub async fn setup_test_environment() -> DatabaseConnection {
+    init();
+    let database_url =
+        std::env::var("TEST_DB_URL").unwrap_or_else(|_| "sqlite::memory:".to_string());
+    if let Err(e) = check_test_database_url(&database_url, std::env::var("DB_URL").ok()) {
+        panic!("{e}");
+    }
+    let mut config = PoolConfig::from_settings(database_url, |_| None);
+    if !config.is_sqlite_memory() {
+        config.schema_search_path = Some(create_test_schema(&config).await);
     }
+    let db = pool::connect(&config)
+        .await
+        .expect("Failed to setup database connection.");
+    migrations::run_pending(&db)
+        .await
+        .expect("Failed to apply migrations.");
+    db
 }
 
 /**
-* Sets up the initial test environment (database connection and env variables)
-*/
-pub async fn setup_test_environment() {
-    init();
-    setup().await.expect("Failed to setup database connection.");
+ * Tests create schemas and write rows, so they only run against in-memory
+ * SQLite or a database whose name mentions `test` and which is not `DB_URL`.
+ */
+fn check_test_database_url(database_url: &str, db_url: Option<String>) -> Result<(), String> {
+    if PoolConfig::from_settings(database_url.to_string(), |_| None).is_sqlite_memory() {
+        return Ok(());
+    }
+    if db_url.as_deref() == Some(database_url) {
+        return Err("TEST_DB_URL must not be the DB_URL database".to_string());
+    }
+    let database_name = database_url
+        .split('?')
+        .next()
+        .and_then(|url| url.rsplit('/').next())
+        .unwrap_or_default();
+    if !database_name.to_lowercase().contains("test") {
+        return Err(format!(
+            "TEST_DB_URL must be in-memory SQLite or name a test database, got `{database_name}`"
+        ));
+    }
+    Ok(())
+}
+
+async fn create_test_schema(config: &PoolConfig) -> String {
+    static NEXT_SCHEMA: AtomicUsize = AtomicUsize::new(0);
+    let schema = format!(
+        "test_{}_{}_{}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_micros(),
+        NEXT_SCHEMA.fetch_add(1, Ordering::SeqCst)
+    );
+    let db = pool::connect(config)
+        .await
+        .expect("Failed to setup database connection.");
+    db.execute_unprepared(&format!("CREATE SCHEMA {schema}"))
+        .await
+        .expect("Failed to create the test schema.");
+    db.close().await.ok();
+    schema
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
-    use serial_test::serial;
 
     /**
      * Test the establish_connection function
@@ -85,13 +111,52 @@ mod tests {
      * @return () - The result of the test
      */
     #[tokio::test]
-    #[serial]
-    async fn test_establish_connection_with_env_url() {
-        init();
-        let connection_result = setup().await;
+    async fn test_establish_connection() {
+        let config = PoolConfig::from_settings("sqlite::memory:".to_string(), |_| None);
+        let connection_result = pool::connect(&config).await;
         assert!(connection_result.is_ok());
     }
 
+    /**
+     * Test that tests refuse to run against databases that are not test ones
+     *
+     * # Returns
+     * @return () - The result of the test
+     */
+    #[test]
+    fn test_only_test_databases_are_accepted() {
+        assert!(check_test_database_url("sqlite::memory:", None).is_ok());
+        assert!(check_test_database_url("postgres://u:p@localhost/fplus_test", None).is_ok());
+        assert!(check_test_database_url("postgres://u:p@localhost/fplus", None).is_err());
+        assert!(check_test_database_url("sqlite://fplus.db?mode=rwc", None).is_err());
+
+        let production = "postgres://u:p@localhost/test".to_string();
+        assert!(check_test_database_url(&production, Some(production.clone())).is_err());
+    }
+
+    /**
+     * Test that pending migrations are applied once and can be reverted
+     *
+     * # Returns
+     * @return () - The result of the test
+     */
+    #[tokio::test]
+    async fn test_run_and_rollback_migrations() {
+        let conn = setup_test_environment().await;
+
+        migrations::run_pending(&conn).await.unwrap();
+        assert!(migrations::run_pending(&conn).await.unwrap().is_empty());
+
+        let last = migrations::MIGRATIONS.last().unwrap().version;
+        assert_eq!(migrations::rollback(&conn, 1).await.unwrap(), vec![last]);
+        let status = migrations::status(&conn).await.unwrap();
+        assert!(!status.last().unwrap().applied);
+
+        assert_eq!(migrations::run_pending(&conn).await.unwrap(), vec![last]);
+        let status = migrations::status(&conn).await.unwrap();
+        assert!(status.iter().all(|m| m.applied && !m.modified));
+    }
+
     /**
      * Test the create_allocator function
      *
@@ -99,18 +164,17 @@ mod tests {
      * @return () - The result of the test
      */
     #[tokio::test]
-    #[serial]
     async fn test_create_allocator() {
-        setup_test_environment().await;
+        let db = setup_test_environment().await;
 
         let owner = "test_owner".to_string();
         let repo = "test_repo".to_string();
 
-        let existing_allocator = database::allocators::get_allocator(&owner, &repo)
+        let existing_allocator = database::allocators::get_allocator(&db, &owner, &repo)
             .await
             .unwrap();
         if existing_allocator.is_some() {
-            let result = database::allocators::delete_allocator(&owner, &repo).await;
+            let result = database::allocators::delete_allocator(&db, &owner, &repo).await;
             return assert!(result.is_ok());
         }
 
@@ -127,6 +191,7 @@ mod tests {
         let client_contract_address = Some("f1owcbryeqlq3vl7kydzax7r75sbtyvgpnny7fswy".to_string());
         let ma_address = Some("f11234567890".to_string());
         let result = database::allocators::create_or_update_allocator(
+            &db,
             owner,
             repo,
             installation_id,
@@ -153,11 +218,10 @@ mod tests {
      * @return () - The result of the test
      */
     #[tokio::test]
-    #[serial]
     async fn test_get_allocators() {
-        setup_test_environment().await;
+        let db = setup_test_environment().await;
 
-        let result = database::allocators::get_allocators().await;
+        let result = database::allocators::get_allocators(&db).await;
         assert!(result.is_ok());
     }
 
@@ -168,17 +232,43 @@ mod tests {
      * @return () - The result of the test
      */
     #[tokio::test]
-    #[serial]
     async fn test_get_allocator() {
-        setup_test_environment().await;
+        let db = setup_test_environment().await;
+
+        if database::allocators::get_allocators(&db)
+            .await
+            .expect("Failed to get allocators")
+            .is_empty()
+        {
+            database::allocators::create_or_update_allocator(
+                &db,
+                "test_owner".to_string(),
+                "test_repo".to_string(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .expect("Failed to create allocator");
+        }
 
-        let allocator = database::allocators::get_allocators()
+        let allocator = database::allocators::get_allocators(&db)
             .await
             .expect("Failed to get allocators")
             .pop()
             .expect("No allocators found");
 
-        let result = database::allocators::get_allocator(&allocator.owner, &allocator.repo).await;
+        let result =
+            database::allocators::get_allocator(&db, &allocator.owner, &allocator.repo).await;
         assert!(result.is_ok());
     }
 
@@ -189,18 +279,17 @@ mod tests {
      * @return () - The result of the test
      */
     #[tokio::test]
-    #[serial]
     async fn test_delete_allocator() {
-        setup_test_environment().await;
+        let db = setup_test_environment().await;
 
         let owner = "test_owner".to_string();
         let repo = "test_repo".to_string();
 
-        let existing_allocator = database::allocators::get_allocator(&owner, &repo)
+        let existing_allocator = database::allocators::get_allocator(&db, &owner, &repo)
             .await
             .unwrap();
         if existing_allocator.is_some() {
-            let result = database::allocators::delete_allocator(&owner, &repo).await;
+            let result = database::allocators::delete_allocator(&db, &owner, &repo).await;
             return assert!(result.is_ok());
         }
 
@@ -218,6 +307,7 @@ mod tests {
         let ma_address = Some("f11234567890".to_string());
 
         let result = database::allocators::create_or_update_allocator(
+            &db,
             owner.clone(),
             repo.clone(),
             installation_id,
@@ -237,7 +327,419 @@ mod tests {
 
         assert!(result.is_ok());
 
-        let result = database::allocators::delete_allocator(&owner, &repo).await;
+        let result = database::allocators::delete_allocator(&db, &owner, &repo).await;
         assert!(result.is_ok());
     }
+
+    /**
+     * Test that the latest row of each application is returned by the
+     * queries which pick one row per application
+     *
+     * # Returns
+     * @return () - The result of the test
+     */
+    #[tokio::test]
+    async fn test_latest_application_rows() {
+        let db = setup_test_environment().await;
+
+        let owner = "test_owner".to_string();
+        let repo = "test_repo".to_string();
+        let id = "test_latest_application".to_string();
+        let active = r#"{"Lifecycle": {"Active": true, "State": "Submitted"}}"#;
+        let closed = r#"{"Lifecycle": {"Active": false, "State": "Declined"}}"#;
+        for (pr_number, app_file) in [(1, active), (2, active), (3, closed)] {
+            database::applications::create_application(
+                &db,
+                id.clone(),
+                owner.clone(),
+                repo.clone(),
+                pr_number,
+                pr_number as i64,
+                app_file.to_string(),
+                "applications/test.json".to_string(),
+                None,
+                "sha".to_string(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let active_applications = database::applications::get_active_applications(&db)
+            .await
+            .unwrap();
+        let latest = active_applications
+            .iter()
+            .find(|application| application.id == id)
+            .unwrap();
+        assert_eq!(latest.pr_number, 2);
+
+        let closed_applications =
+            database::applications::get_allocator_closed_applications(&db, &owner, &repo)
+                .await
+                .unwrap();
+        assert!(closed_applications
+            .iter()
+            .any(|application| application.id == id && application.pr_number == 3));
+
+        let distinct = database::applications::get_distinct_applications_by_clients_addresses(
+            &db,
+            vec![id.clone()],
+        )
+        .await
+        .unwrap();
+        assert_eq!(distinct.len(), 1);
+        assert_eq!(distinct[0].pr_number, 3);
+
+        for pr_number in 1..=3 {
+            database::applications::delete_application(
+                &db,
+                id.clone(),
+                owner.clone(),
+                repo.clone(),
+                pr_number,
+                &database::application_versions::WriteContext::new("Test cleanup"),
+            )
+            .await
+            .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_autoallocation_history() {
+        use database::autoallocation_transactions::{
+            create_autoallocation_transaction, record_autoallocation_transaction_receipt,
+            NewAutoallocationTransaction,
+        };
+        use database::autoallocations::{
+            create_autoallocation_grant, get_autoallocation_daily_stats,
+            get_autoallocation_history, AutoallocationFilter, GRANT_STATUS_FAILED,
+        };
+        let db = setup_test_environment().await;
+
+        let client = format!("f1history{}", chrono::Utc::now().timestamp_micros());
+        let wallet = alloy::primitives::Address::repeat_byte(0x45);
+        let transaction = |grant_id, tx_hash: &str| NewAutoallocationTransaction {
+            grant_id,
+            network: "mainnet".to_string(),
+            tx_hash: tx_hash.to_string(),
+            nonce: 0,
+            raw_transaction: "0x".to_string(),
+            max_fee_per_gas: "1".to_string(),
+            max_priority_fee_per_gas: "1".to_string(),
+        };
+        let mut grants = vec![];
+        for _ in 0..3 {
+            grants.push(
+                create_autoallocation_grant(&db, wallet, &client, "mainnet", 1024, 42.0)
+                    .await
+                    .unwrap(),
+            );
+        }
+        // The confirmed transaction is shown even if a later one was sent.
+        let mined = create_autoallocation_transaction(&db, &transaction(grants[2].id, "0xmined"))
+            .await
+            .unwrap();
+        create_autoallocation_transaction(&db, &transaction(grants[2].id, "0xlater"))
+            .await
+            .unwrap();
+        record_autoallocation_transaction_receipt(&db, mined.id, true, 21000, Some(7))
+            .await
+            .unwrap();
+        create_autoallocation_transaction(&db, &transaction(grants[1].id, "0xpending"))
+            .await
+            .unwrap();
+
+        let filter = AutoallocationFilter {
+            client_fil_address: Some(client.clone()),
+            ..Default::default()
+        };
+        let page = get_autoallocation_history(&db, &filter, 2, None)
+            .await
+            .unwrap();
+        let hashes: Vec<_> = page
+            .autoallocations
+            .iter()
+            .map(|entry| entry.tx_hash.as_deref())
+            .collect();
+        assert_eq!(hashes, [Some("0xmined"), Some("0xpending")]);
+        assert_eq!(page.autoallocations[0].block_number, Some(7));
+        assert_eq!(page.next_cursor, Some(grants[1].id));
+        let page = get_autoallocation_history(&db, &filter, 2, page.next_cursor)
+            .await
+            .unwrap();
+        assert_eq!(page.autoallocations.len(), 1);
+        assert_eq!(page.autoallocations[0].grant.id, grants[0].id);
+        assert_eq!(page.autoallocations[0].tx_hash, None);
+        assert_eq!(page.next_cursor, None);
+
+        let stats = get_autoallocation_daily_stats(&db, &filter).await.unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(
+            stats[0].day,
+            grants[0].created_at.format("%Y-%m-%d").to_string()
+        );
+        assert_eq!((stats[0].count, stats[0].volume), (3, 3072));
+
+        let filter = AutoallocationFilter {
+            client_fil_address: Some(client),
+            status: Some(GRANT_STATUS_FAILED.to_string()),
+            ..Default::default()
+        };
+        assert!(get_autoallocation_history(&db, &filter, 10, None)
+            .await
+            .unwrap()
+            .autoallocations
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_autoallocations_are_backfilled_once() {
+        use database::autoallocations::{
+            backfill_legacy_autoallocation_grants, create_autoallocation_grant,
+            create_or_update_autoallocation, get_autoallocated_amount_for_wallet,
+            get_autoallocated_client_addresses, get_autoallocation_history, AutoallocationFilter,
+            GRANT_STATUS_LEGACY,
+        };
+        let db = setup_test_environment().await;
+        let legacy_wallet = alloy::primitives::Address::repeat_byte(0x47);
+        let wallet = alloy::primitives::Address::repeat_byte(0x48);
+        create_or_update_autoallocation(&db, &legacy_wallet, "mainnet", &30)
+            .await
+            .unwrap();
+        create_or_update_autoallocation(&db, &wallet, "mainnet", &30)
+            .await
+            .unwrap();
+        create_autoallocation_grant(&db, wallet, "f1recorded", "mainnet", 1024, 42.0)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            backfill_legacy_autoallocation_grants(&db, 2048)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            backfill_legacy_autoallocation_grants(&db, 2048)
+                .await
+                .unwrap(),
+            0
+        );
+
+        let filter = AutoallocationFilter {
+            evm_wallet_address: Some(legacy_wallet.into()),
+            ..Default::default()
+        };
+        let history = get_autoallocation_history(&db, &filter, 10, None)
+            .await
+            .unwrap()
+            .autoallocations;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].grant.status, GRANT_STATUS_LEGACY);
+        assert_eq!(history[0].grant.amount, 2048);
+        // Legacy grants count against the caps but name no client.
+        assert_eq!(
+            get_autoallocated_amount_for_wallet(&db, legacy_wallet, "mainnet")
+                .await
+                .unwrap(),
+            2048
+        );
+        assert!(get_autoallocated_client_addresses(&db, &filter)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_autoallocation_grants_respect_caps() {
+        use database::autoallocations::{
+            create_autoallocation_grant_within_caps, set_autoallocation_grant_status,
+            AutoallocationGrantCaps, AutoallocationGrantOutcome, GRANT_STATUS_FAILED,
+        };
+        let db = setup_test_environment().await;
+        let wallet = alloy::primitives::Address::repeat_byte(0x46);
+        let caps = AutoallocationGrantCaps {
+            per_wallet: Some(2048),
+            per_client: Some(1024),
+        };
+
+        let granted = |outcome| match outcome {
+            AutoallocationGrantOutcome::Granted(grant) => Some(grant),
+            AutoallocationGrantOutcome::CapExceeded { .. } => None,
+        };
+        let first = create_autoallocation_grant_within_caps(
+            &db, wallet, "f1capped", "mainnet", 1024, 42.0, caps,
+        )
+        .await
+        .unwrap();
+        let first = granted(first).unwrap();
+        let second = create_autoallocation_grant_within_caps(
+            &db, wallet, "f1capped", "mainnet", 1024, 42.0, caps,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            second,
+            AutoallocationGrantOutcome::CapExceeded {
+                cap: 1024,
+                granted: 1024,
+                ..
+            }
+        ));
+
+        // Another client of the wallet is held by the wallet cap only.
+        let other_client = create_autoallocation_grant_within_caps(
+            &db, wallet, "f1other", "mainnet", 1024, 42.0, caps,
+        )
+        .await
+        .unwrap();
+        assert!(granted(other_client).is_some());
+        let wallet_full = create_autoallocation_grant_within_caps(
+            &db, wallet, "f1third", "mainnet", 1, 42.0, caps,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            wallet_full,
+            AutoallocationGrantOutcome::CapExceeded {
+                cap: 2048,
+                granted: 2048,
+                ..
+            }
+        ));
+
+        // Failed grants free their amount.
+        set_autoallocation_grant_status(&db, first.id, GRANT_STATUS_FAILED)
+            .await
+            .unwrap();
+        let retried = create_autoallocation_grant_within_caps(
+            &db, wallet, "f1capped", "mainnet", 1024, 42.0, caps,
+        )
+        .await
+        .unwrap();
+        assert!(granted(retried).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_used_signatures() {
+        let db = setup_test_environment().await;
+
+        let signature_hash = format!("0xused{}", chrono::Utc::now().timestamp_micros());
+        let expires_at = chrono::Utc::now() - chrono::Duration::minutes(1);
+        for expected in [true, false] {
+            let consumed = database::used_signatures::consume_signature(
+                &db,
+                &signature_hash,
+                "KycApproval",
+                "0x0000000000000000000000000000000000000000",
+                expires_at,
+            )
+            .await
+            .unwrap();
+            assert_eq!(consumed, expected);
+        }
+
+        let pruned =
+            database::used_signatures::delete_expired_used_signatures(&db, chrono::Utc::now())
+                .await
+                .unwrap();
+        assert!(pruned >= 1);
+        assert!(database::used_signatures::consume_signature(
+            &db,
+            &signature_hash,
+            "KycApproval",
+            "0x0000000000000000000000000000000000000000",
+            expires_at,
+        )
+        .await
+        .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_pr_numbers_are_never_reused() {
+        let db = setup_test_environment().await;
+        let next =
+            || database::applications::get_next_pr_number(&db, "owner".into(), "repo".into());
+
+        let mut numbers = futures::future::try_join_all((0..10).map(|_| next()))
+            .await
+            .unwrap();
+        numbers.sort();
+        assert_eq!(numbers, (1..=10).collect::<Vec<u64>>());
+        assert_eq!(next().await.unwrap(), 11);
+        assert_eq!(
+            database::applications::get_next_pr_number(&db, "owner".into(), "other".into())
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stale_application_writes_are_rejected() {
+        let db = setup_test_environment().await;
+        let app_file =
+            |state: &str| format!(r#"{{"Lifecycle": {{"Active": true, "State": "{state}"}}}}"#);
+        let read = database::applications::create_application(
+            &db,
+            "test_stale_write".into(),
+            "owner".into(),
+            "repo".into(),
+            1,
+            1,
+            app_file("Submitted"),
+            "applications/test_stale_write.json".into(),
+            None,
+            "sha0".into(),
+        )
+        .await
+        .unwrap();
+        let context = database::application_versions::WriteContext::new("Test");
+
+        let (first, second) = futures::join!(
+            database::applications::update_application_file_if_unchanged(
+                &db,
+                &read,
+                app_file("ReadyToSign"),
+                "sha1".into(),
+                &context,
+            ),
+            database::applications::update_application_file_if_unchanged(
+                &db,
+                &read,
+                app_file("Declined"),
+                "sha2".into(),
+                &context,
+            ),
+        );
+        assert_eq!(
+            [first.unwrap(), second.unwrap()]
+                .iter()
+                .filter(|written| **written)
+                .count(),
+            1
+        );
+
+        let stored = database::applications::get_application(
+            &db,
+            "test_stale_write".into(),
+            "owner".into(),
+            "repo".into(),
+            Some(1),
+        )
+        .await
+        .unwrap();
+        assert_eq!(stored.version, 1);
+        assert!(
+            database::applications::update_application_file_if_unchanged(
+                &db,
+                &stored,
+                app_file("Granted"),
+                "sha3".into(),
+                &context,
+            )
+            .await
+            .unwrap()
+        );
+    }
 }
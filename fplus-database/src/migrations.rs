@@ -0,0 +1,425 @@
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DatabaseTransaction, DbBackend, DbErr, Statement,
+    TransactionTrait, Value,
+};
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+
+/**
+ * A schema change embedded in the binary, with the SQL reverting it. The
+ * SQLite variants live in `migrations/sqlite` under the same file names.
+ */
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+    pub sqlite_up: &'static str,
+    pub sqlite_down: &'static str,
+    /// Reverting would drop data that no later migration can restore.
+    pub irreversible: bool,
+}
+
+impl Migration {
+    /**
+     * The SQL applying the migration on the given backend
+     */
+    pub fn up_sql(&self, backend: DbBackend) -> &'static str {
+        match backend {
+            DbBackend::Sqlite => self.sqlite_up,
+            _ => self.up,
+        }
+    }
+
+    /**
+     * The SQL reverting the migration on the given backend
+     */
+    pub fn down_sql(&self, backend: DbBackend) -> &'static str {
+        match backend {
+            DbBackend::Sqlite => self.sqlite_down,
+            _ => self.down,
+        }
+    }
+}
+
+macro_rules! migration_sql {
+    ($dir:literal, $version:literal, $name:literal, $direction:literal) => {
+        include_str!(concat!(
+            $dir,
+            stringify!($version),
+            "_",
+            $name,
+            ".",
+            $direction,
+            ".sql"
+        ))
+    };
+}
+
+macro_rules! migration {
+    ($version:literal, $name:literal, irreversible) => {
+        Migration {
+            irreversible: true,
+            ..migration!($version, $name)
+        }
+    };
+    ($version:literal, $name:literal) => {
+        Migration {
+            irreversible: false,
+            version: $version,
+            name: $name,
+            up: migration_sql!("../migrations/", $version, $name, "up"),
+            down: migration_sql!("../migrations/", $version, $name, "down"),
+            sqlite_up: migration_sql!("../migrations/sqlite/", $version, $name, "up"),
+            sqlite_down: migration_sql!("../migrations/sqlite/", $version, $name, "down"),
+        }
+    };
+}
+
+/**
+ * All migrations, ordered by version. The ones up to `allocator_comment_templates`
+ * are the former manual scripts; they are idempotent so that databases where
+ * they were applied by hand adopt them without changes.
+ */
+pub static MIGRATIONS: &[Migration] = &[
+    migration!(20240101000000, "initial_schema", irreversible),
+    migration!(20240712000000, "allocator_requirements"),
+    migration!(20240723000000, "application_issue_number"),
+    migration!(20240830000000, "autoallocations"),
+    migration!(20241016000000, "allocator_client_contract_address"),
+    migration!(20241018000000, "application_client_contract_address"),
+    migration!(20250122000000, "application_issue_reporter_handle"),
+    migration!(20250131000000, "comparable_applications"),
+    migration!(20250422000000, "allocator_ma_address"),
+    migration!(20261018000000, "allocator_application_store"),
+    migration!(20261018000001, "allocator_comment_templates"),
+    migration!(20261019000000, "application_details"),
+    migration!(20261019000001, "outbox_messages"),
+    migration!(20261019000002, "application_versions"),
+    migration!(20261019000003, "autoallocation_grants"),
+    migration!(20261019000004, "autoallocation_transactions"),
+    migration!(20261019000005, "autoallocator_networks"),
+    migration!(20261019000006, "used_signatures"),
+    migration!(20261019000007, "allocator_kyc_providers"),
+    migration!(20261019000008, "pr_number_counters"),
+    migration!(20261019000009, "application_version"),
+    migration!(20261019000010, "application_details_skipped"),
+    migration!(20261019000011, "application_version_counters"),
+    migration!(20261019000012, "autoallocation_cap_locks"),
+    migration!(20261019000013, "autoallocator_locks"),
+    migration!(20261019000014, "allowance_alerts"),
+];
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: String,
+    pub applied: bool,
+    /// The embedded SQL differs from the SQL that was applied.
+    pub modified: bool,
+}
+
+/// Key of the Postgres advisory lock serializing migrations across instances.
+const MIGRATIONS_LOCK_KEY: i64 = 0x6670_6c75_735f_6d67;
+
+fn checksum(sql: &str) -> String {
+    format!("{:x}", Sha1::digest(sql.as_bytes()))
+}
+
+async fn ensure_migrations_table(conn: &DatabaseConnection) -> Result<(), DbErr> {
+    let applied_at = match conn.get_database_backend() {
+        DbBackend::Sqlite => "text NOT NULL DEFAULT CURRENT_TIMESTAMP",
+        _ => "timestamp with time zone NOT NULL DEFAULT now()",
+    };
+    conn.execute_unprepared(&format!(
+        "CREATE TABLE IF NOT EXISTS schema_migrations
+        (
+            version bigint NOT NULL,
+            name text NOT NULL,
+            checksum text NOT NULL,
+            applied_at {applied_at},
+            PRIMARY KEY (version)
+        )"
+    ))
+    .await?;
+    Ok(())
+}
+
+/**
+ * Versions and checksums of the applied migrations, ordered by version
+ */
+async fn applied_migrations(conn: &DatabaseConnection) -> Result<Vec<(i64, String)>, DbErr> {
+    ensure_migrations_table(conn).await?;
+    let rows = conn
+        .query_all(Statement::from_string(
+            conn.get_database_backend(),
+            "SELECT version, checksum FROM schema_migrations ORDER BY version",
+        ))
+        .await?;
+    rows.iter()
+        .map(|row| {
+            Ok((
+                row.try_get::<i64>("", "version")?,
+                row.try_get::<String>("", "checksum")?,
+            ))
+        })
+        .collect()
+}
+
+fn find_migration(version: i64) -> Result<&'static Migration, DbErr> {
+    MIGRATIONS
+        .iter()
+        .find(|migration| migration.version == version)
+        .ok_or_else(|| DbErr::Custom(format!("Unknown migration {version} was applied")))
+}
+
+/**
+ * Take the lock serializing migrations for the rest of the transaction. On
+ * SQLite writers are serialized by the database itself.
+ */
+async fn lock_migrations(txn: &DatabaseTransaction) -> Result<(), DbErr> {
+    if txn.get_database_backend() == DbBackend::Postgres {
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT pg_advisory_xact_lock($1)",
+            [Value::from(MIGRATIONS_LOCK_KEY)],
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+async fn is_applied(txn: &DatabaseTransaction, version: i64) -> Result<bool, DbErr> {
+    Ok(txn
+        .query_one(Statement::from_sql_and_values(
+            txn.get_database_backend(),
+            "SELECT version FROM schema_migrations WHERE version = $1",
+            [Value::from(version)],
+        ))
+        .await?
+        .is_some())
+}
+
+/**
+ * Apply every pending migration, each one in its own transaction holding the
+ * migrations lock, so instances starting together apply each migration once.
+ * Fails without applying anything when an applied migration was changed.
+ *
+ * # Returns
+ * @return Result<Vec<i64>, sea_orm::DbErr> - The versions that were applied
+ */
+pub async fn run_pending(conn: &DatabaseConnection) -> Result<Vec<i64>, DbErr> {
+    let backend = conn.get_database_backend();
+    let applied = applied_migrations(conn).await?;
+    for (version, applied_checksum) in &applied {
+        let migration = find_migration(*version)?;
+        if *applied_checksum != checksum(migration.up_sql(backend)) {
+            return Err(DbErr::Custom(format!(
+                "Migration {}_{} changed after it was applied, add a new migration instead",
+                migration.version, migration.name
+            )));
+        }
+    }
+
+    let mut newly_applied = Vec::new();
+    for migration in MIGRATIONS {
+        if applied.iter().any(|(v, _)| *v == migration.version) {
+            continue;
+        }
+
+        let txn = conn.begin().await?;
+        lock_migrations(&txn).await?;
+        if is_applied(&txn, migration.version).await? {
+            txn.commit().await?;
+            continue;
+        }
+        log::info!(
+            "Applying migration {}_{}",
+            migration.version,
+            migration.name
+        );
+        txn.execute_unprepared(migration.up_sql(backend)).await?;
+        txn.execute(Statement::from_sql_and_values(
+            backend,
+            "INSERT INTO schema_migrations (version, name, checksum) VALUES ($1, $2, $3)",
+            [
+                Value::from(migration.version),
+                Value::from(migration.name),
+                Value::from(checksum(migration.up_sql(backend))),
+            ],
+        ))
+        .await?;
+        txn.commit().await?;
+        newly_applied.push(migration.version);
+    }
+    Ok(newly_applied)
+}
+
+/**
+ * Revert the most recently applied migrations. Nothing is reverted when one of
+ * them is irreversible.
+ *
+ * # Arguments
+ * @param steps: usize - The number of migrations to revert
+ *
+ * # Returns
+ * @return Result<Vec<i64>, sea_orm::DbErr> - The versions that were reverted
+ */
+pub async fn rollback(conn: &DatabaseConnection, steps: usize) -> Result<Vec<i64>, DbErr> {
+    let backend = conn.get_database_backend();
+    let applied = applied_migrations(conn).await?;
+    let to_revert = applied
+        .iter()
+        .rev()
+        .take(steps)
+        .map(|(version, _)| find_migration(*version))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(migration) = to_revert.iter().find(|migration| migration.irreversible) {
+        return Err(DbErr::Custom(format!(
+            "Migration {}_{} is irreversible, restore a backup instead",
+            migration.version, migration.name
+        )));
+    }
+
+    let mut reverted = Vec::new();
+    for migration in to_revert {
+        let txn = conn.begin().await?;
+        lock_migrations(&txn).await?;
+        if !is_applied(&txn, migration.version).await? {
+            txn.commit().await?;
+            continue;
+        }
+        log::info!(
+            "Reverting migration {}_{}",
+            migration.version,
+            migration.name
+        );
+        txn.execute_unprepared(migration.down_sql(backend)).await?;
+        txn.execute(Statement::from_sql_and_values(
+            backend,
+            "DELETE FROM schema_migrations WHERE version = $1",
+            [Value::from(migration.version)],
+        ))
+        .await?;
+        txn.commit().await?;
+        reverted.push(migration.version);
+    }
+    Ok(reverted)
+}
+
+/**
+ * List the embedded migrations with whether they are applied
+ */
+pub async fn status(conn: &DatabaseConnection) -> Result<Vec<MigrationStatus>, DbErr> {
+    let backend = conn.get_database_backend();
+    let applied = applied_migrations(conn).await?;
+    Ok(MIGRATIONS
+        .iter()
+        .map(|migration| {
+            let applied_checksum = applied
+                .iter()
+                .find(|(version, _)| *version == migration.version)
+                .map(|(_, checksum)| checksum);
+            MigrationStatus {
+                version: migration.version,
+                name: migration.name.to_string(),
+                applied: applied_checksum.is_some(),
+                modified: applied_checksum
+                    .is_some_and(|c| *c != checksum(migration.up_sql(backend))),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrations_are_ordered_and_reversible() {
+        assert!(MIGRATIONS
+            .windows(2)
+            .all(|pair| pair[0].version < pair[1].version));
+        for migration in MIGRATIONS {
+            assert!(!migration.up.trim().is_empty(), "{}", migration.name);
+            assert!(!migration.down.trim().is_empty(), "{}", migration.name);
+            assert!(!migration.sqlite_up.trim().is_empty(), "{}", migration.name);
+            assert!(
+                !migration.sqlite_down.trim().is_empty(),
+                "{}",
+                migration.name
+            );
+        }
+    }
+
+    fn migration_files(dir: &str) -> Vec<String> {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+        let mut files: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name.ends_with(".sql"))
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn postgres_and_sqlite_migrations_match_the_registered_ones() {
+        let registered: Vec<String> = MIGRATIONS
+            .iter()
+            .flat_map(|m| {
+                [
+                    format!("{}_{}.down.sql", m.version, m.name),
+                    format!("{}_{}.up.sql", m.version, m.name),
+                ]
+            })
+            .collect();
+        assert_eq!(migration_files("migrations"), registered);
+        assert_eq!(migration_files("migrations/sqlite"), registered);
+    }
+
+    #[tokio::test]
+    async fn every_migration_applies_and_reverts_cleanly() {
+        let conn = crate::setup_test_environment().await;
+        let versions: Vec<i64> = MIGRATIONS.iter().map(|m| m.version).collect();
+
+        let reverted = rollback(&conn, MIGRATIONS.len() - 1).await.unwrap();
+        assert_eq!(
+            reverted,
+            versions[1..].iter().rev().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(run_pending(&conn).await.unwrap(), versions[1..].to_vec());
+        assert!(status(&conn)
+            .await
+            .unwrap()
+            .iter()
+            .all(|m| m.applied && !m.modified));
+    }
+
+    #[tokio::test]
+    async fn initial_schema_is_not_reverted() {
+        let conn = crate::setup_test_environment().await;
+
+        assert!(rollback(&conn, MIGRATIONS.len()).await.is_err());
+        assert!(status(&conn).await.unwrap().iter().all(|m| m.applied));
+    }
+
+    #[tokio::test]
+    async fn changed_migrations_stop_the_run() {
+        let conn = crate::setup_test_environment().await;
+        let last = MIGRATIONS.last().unwrap().version;
+        rollback(&conn, 1).await.unwrap();
+        conn.execute(Statement::from_sql_and_values(
+            conn.get_database_backend(),
+            "UPDATE schema_migrations SET checksum = 'changed' WHERE version = $1",
+            [Value::from(MIGRATIONS[0].version)],
+        ))
+        .await
+        .unwrap();
+
+        assert!(run_pending(&conn).await.is_err());
+        let status = status(&conn).await.unwrap();
+        assert!(status[0].modified);
+        assert!(!status.iter().find(|m| m.version == last).unwrap().applied);
+    }
+}
@@ -23,6 +23,11 @@ pub struct Model {
     pub required_replicas: Option<String>,
     pub registry_file_path: Option<String>,
     pub client_contract_address: Option<String>,
+    pub application_store: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub comment_templates: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub kyc_providers: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter)]
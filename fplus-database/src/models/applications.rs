@@ -29,6 +29,7 @@ pub struct Model {
     pub client_contract_address: Option<String>,
     #[sea_orm(nullable)]
     pub issue_reporter_handle: Option<String>,
+    pub version: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -9,6 +9,8 @@ use crate::types::AddressWrapper;
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub evm_wallet_address: AddressWrapper,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub network: String,
     pub last_allocation: DateTime<FixedOffset>,
 }
 
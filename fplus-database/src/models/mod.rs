@@ -4,6 +4,17 @@ pub mod prelude;
 
 pub mod allocation_amounts;
 pub mod allocators;
+pub mod application_allocation_signers;
+pub mod application_allocations;
+pub mod application_details_skipped;
+pub mod application_lifecycles;
+pub mod application_sps_change_requests;
+pub mod application_sps_change_signers;
+pub mod application_versions;
 pub mod applications;
+pub mod autoallocation_grants;
+pub mod autoallocation_transactions;
 pub mod autoallocations;
 pub mod comparable_applications;
+pub mod outbox_messages;
+pub mod used_signatures;
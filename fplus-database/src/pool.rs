@@ -0,0 +1,177 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use log::LevelFilter;
+use sea_orm::{ConnectOptions, Database, DatabaseConnection, DbErr};
+
+use crate::config::get_env_or_throw;
+use crate::types::DbConnectParams;
+
+/**
+ * A handle on the connection pool, cheap to clone. Repository functions in
+ * `database` take it as their first argument.
+ */
+pub type DbPool = DatabaseConnection;
+
+/**
+ * Settings of the database connection pool, read from the environment. The
+ * URL selects the backend, `postgres://…` or `sqlite://…` (`sqlite::memory:`
+ * for a database that lives as long as the pool).
+ *
+ * | Variable                   | Default |
+ * |----------------------------|---------|
+ * | `DB_MAX_CONNECTIONS`       | 10      |
+ * | `DB_MIN_CONNECTIONS`       | 1       |
+ * | `DB_CONNECT_TIMEOUT_SECS`  | 10      |
+ * | `DB_ACQUIRE_TIMEOUT_SECS`  | 10      |
+ * | `DB_IDLE_TIMEOUT_SECS`     | 300     |
+ * | `DB_SQL_LOGGING`           | false   |
+ * | `DB_SQL_LOG_LEVEL`         | debug   |
+ */
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    pub database_url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub connect_timeout: Duration,
+    pub acquire_timeout: Duration,
+    pub idle_timeout: Duration,
+    pub sql_logging: bool,
+    pub sql_log_level: LevelFilter,
+    /// Postgres schema the connections work in, the user's default when unset.
+    pub schema_search_path: Option<String>,
+}
+
+fn setting_or<T: FromStr>(setting: &impl Fn(&str) -> Option<String>, key: &str, default: T) -> T {
+    match setting(key) {
+        Some(value) => value.parse().unwrap_or_else(|_| {
+            log::warn!("Invalid value '{value}' for {key}, using the default");
+            default
+        }),
+        None => default,
+    }
+}
+
+impl PoolConfig {
+    /**
+     * Read the pool settings for the given database URL from the environment
+     */
+    pub fn with_url(database_url: String) -> Self {
+        Self::from_settings(database_url, |key| std::env::var(key).ok())
+    }
+
+    /**
+     * Build the pool settings for the given database URL, looking up each
+     * setting by its environment variable name
+     *
+     * # Arguments
+     * @param database_url: String - The database URL
+     * @param setting: impl Fn(&str) -> Option<String> - The value of a setting, `None` for the default
+     */
+    pub fn from_settings(database_url: String, setting: impl Fn(&str) -> Option<String>) -> Self {
+        Self {
+            database_url,
+            max_connections: setting_or(&setting, "DB_MAX_CONNECTIONS", 10),
+            min_connections: setting_or(&setting, "DB_MIN_CONNECTIONS", 1),
+            connect_timeout: Duration::from_secs(setting_or(
+                &setting,
+                "DB_CONNECT_TIMEOUT_SECS",
+                10,
+            )),
+            acquire_timeout: Duration::from_secs(setting_or(
+                &setting,
+                "DB_ACQUIRE_TIMEOUT_SECS",
+                10,
+            )),
+            idle_timeout: Duration::from_secs(setting_or(&setting, "DB_IDLE_TIMEOUT_SECS", 300)),
+            sql_logging: setting_or(&setting, "DB_SQL_LOGGING", false),
+            sql_log_level: setting_or(&setting, "DB_SQL_LOG_LEVEL", LevelFilter::Debug),
+            schema_search_path: None,
+        }
+    }
+
+    /**
+     * Read the pool settings from the environment, the database URL comes from
+     * `DB_URL` or else from `DB_CONNECT_PARAMS_JSON`
+     */
+    pub fn from_env() -> Self {
+        let database_url = std::env::var("DB_URL").unwrap_or_else(|_| {
+            let params: DbConnectParams =
+                serde_json::from_str(&get_env_or_throw("DB_CONNECT_PARAMS_JSON"))
+                    .expect("Invalid JSON in DB_CONNECT_PARAMS_JSON");
+            params.to_url()
+        });
+        Self::with_url(database_url)
+    }
+
+    /**
+     * Whether the URL points to an in-memory SQLite database, which lives only
+     * as long as its single connection
+     */
+    pub fn is_sqlite_memory(&self) -> bool {
+        self.database_url.starts_with("sqlite::memory:")
+            || (self.database_url.starts_with("sqlite:")
+                && self.database_url.contains("mode=memory"))
+    }
+
+    fn connect_options(&self) -> ConnectOptions {
+        let mut options = ConnectOptions::new(self.database_url.clone());
+        options
+            .connect_timeout(self.connect_timeout)
+            .acquire_timeout(self.acquire_timeout)
+            .sqlx_logging(self.sql_logging)
+            .sqlx_logging_level(self.sql_log_level);
+        if let Some(schema) = &self.schema_search_path {
+            options.set_schema_search_path(schema.clone());
+        }
+        if self.is_sqlite_memory() {
+            options.max_connections(1).min_connections(1);
+        } else {
+            options
+                .max_connections(self.max_connections)
+                .min_connections(self.min_connections)
+                .idle_timeout(self.idle_timeout);
+        }
+        options
+    }
+}
+
+/**
+ * Open a connection pool. The returned handle is cheap to clone and every
+ * clone shares the same pool.
+ *
+ * # Arguments
+ * @param config: &PoolConfig - The pool settings
+ *
+ * # Returns
+ * @return Result<DatabaseConnection, sea_orm::DbErr> - The connection pool
+ */
+pub async fn connect(config: &PoolConfig) -> Result<DatabaseConnection, DbErr> {
+    Database::connect(config.connect_options()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn pool_settings_fall_back_to_defaults() {
+        let settings = HashMap::from([
+            ("DB_MAX_CONNECTIONS", "25"),
+            ("DB_ACQUIRE_TIMEOUT_SECS", "not a number"),
+        ]);
+        let config = PoolConfig::from_settings("postgres://localhost/test".into(), |key| {
+            settings.get(key).map(|value| value.to_string())
+        });
+
+        assert_eq!(config.max_connections, 25);
+        assert_eq!(config.acquire_timeout, Duration::from_secs(10));
+        assert!(!config.sql_logging);
+        assert_eq!(config.sql_log_level, LevelFilter::Debug);
+        assert!(!config.is_sqlite_memory());
+        assert!(PoolConfig::with_url("sqlite::memory:".into()).is_sqlite_memory());
+        assert!(!PoolConfig::with_url("sqlite://fplus.db?mode=rwc".into()).is_sqlite_memory());
+    }
+}
@@ -2,15 +2,17 @@ use actix_web::{
     error::{ErrorInternalServerError, ErrorUnauthorized},
     Error,
 };
+use fplus_database::DbPool;
 use fplus_lib::{config::get_env_var_or_default, external_services::github::github_async_new};
 
 pub async fn check_if_pull_request_opened_by_bot(
+    db: &DbPool,
     owner: &str,
     repo: &str,
     pr_number: &u64,
 ) -> Result<(), Error> {
     let gh_bot = get_env_var_or_default("BOT_USER");
-    let gh = github_async_new(owner.to_string(), repo.to_string())
+    let gh = github_async_new(db, owner.to_string(), repo.to_string())
         .await
         .map_err(|e| ErrorInternalServerError(format!("Failed to get GitHub client: {e}")))?;
     let pr = gh
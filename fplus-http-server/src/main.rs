@@ -1,4 +1,15 @@
-use fplus_lib::core::allocator::update_installation_ids_logic;
+use fplus_database::database::application_details::backfill_application_details;
+use fplus_database::database::used_signatures::delete_expired_used_signatures;
+use fplus_database::migrations;
+use fplus_database::DbPool;
+use fplus_lib::config::get_env_var_or_default;
+use fplus_lib::core::allocator::{
+    labels::sync_all_allocator_labels, update_installation_ids_logic,
+};
+use fplus_lib::core::allowance_monitor::check_allowances;
+use fplus_lib::core::autoallocator::backfill_legacy_autoallocation_grants;
+use fplus_lib::core::autoallocator::transactions::run_autoallocation_confirmer;
+use fplus_lib::core::outbox::{prune_outbox, run_outbox_worker};
 mod middleware;
 use middleware::verifier_auth::VerifierAuth;
 mod auth;
@@ -43,18 +54,85 @@ where
     }
 }
 
+/// Handles `--migrate`, `--migrate-down[=N]`, `--migrations-status`,
+/// `--backfill-application-details` and `--backfill-autoallocation-grants`.
+/// Returns `None` when no migration flag was given and the server should start.
+async fn run_migration_command(conn: &DbPool, args: &[String]) -> Option<Result<(), String>> {
+    let result = match args.first().map(String::as_str) {
+        Some("--migrate") => migrations::run_pending(conn)
+            .await
+            .map(|applied| println!("Applied migrations: {applied:?}"))
+            .map_err(|e| e.to_string()),
+        Some(flag) if flag.starts_with("--migrate-down") => {
+            let steps = flag
+                .strip_prefix("--migrate-down=")
+                .map_or(Ok(1), str::parse)
+                .map_err(|e| format!("Invalid number of migrations: {e}"));
+            match steps {
+                Ok(steps) => migrations::rollback(conn, steps)
+                    .await
+                    .map(|reverted| println!("Reverted migrations: {reverted:?}"))
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            }
+        }
+        Some("--migrations-status") => migrations::status(conn)
+            .await
+            .map(|status| {
+                for migration in status {
+                    println!(
+                        "{} {} applied: {} modified: {}",
+                        migration.version, migration.name, migration.applied, migration.modified
+                    );
+                }
+            })
+            .map_err(|e| e.to_string()),
+        Some("--backfill-application-details") => backfill_application_details(conn)
+            .await
+            .map(|summary| {
+                println!(
+                    "Normalized {} applications, skipped {}",
+                    summary.normalized, summary.skipped
+                )
+            })
+            .map_err(|e| e.to_string()),
+        Some("--backfill-autoallocation-grants") => backfill_legacy_autoallocation_grants(conn)
+            .await
+            .map(|backfilled| println!("Backfilled {backfilled} legacy autoallocation grants"))
+            .map_err(|e| e.to_string()),
+        _ => return None,
+    };
+    Some(result)
+}
+
 #[tokio::main]
 async fn main() -> std::io::Result<()> {
     dotenv::dotenv().ok();
     env_logger::init();
-    if let Err(e) = fplus_database::setup().await {
-        panic!("Failed to setup database connection: {e}");
+    let db = match fplus_database::setup().await {
+        Ok(db) => db,
+        Err(e) => panic!("Failed to setup database connection: {e}"),
+    };
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = run_migration_command(&db, &args).await {
+        return result.map_err(std::io::Error::other);
+    }
+    if get_env_var_or_default("RUN_MIGRATIONS") == "true" {
+        if let Err(e) = migrations::run_pending(&db).await {
+            panic!("Failed to run database migrations: {e}");
+        }
     }
 
-    tokio::spawn(async {
-        run_cron("0 0 0,4,8,12,16,20 * * * *", || {
-            tokio::spawn(async {
-                if let Err(e) = update_installation_ids_logic().await {
+    tokio::spawn(run_outbox_worker(db.clone()));
+    tokio::spawn(run_autoallocation_confirmer(db.clone()));
+
+    let installations_db = db.clone();
+    tokio::spawn(async move {
+        run_cron("0 0 0,4,8,12,16,20 * * * *", move || {
+            let db = installations_db.clone();
+            tokio::spawn(async move {
+                if let Err(e) = update_installation_ids_logic(&db).await {
                     eprintln!("Error: {e:?}");
                 }
             })
@@ -62,17 +140,78 @@ async fn main() -> std::io::Result<()> {
         .await;
     });
 
+    let labels_db = db.clone();
+    tokio::spawn(async move {
+        run_cron("0 30 3 * * * *", move || {
+            let db = labels_db.clone();
+            tokio::spawn(async move {
+                if let Err(e) = sync_all_allocator_labels(&db).await {
+                    eprintln!("Error: {e:?}");
+                }
+            })
+        })
+        .await;
+    });
+
+    let signatures_db = db.clone();
+    tokio::spawn(async move {
+        run_cron("0 15 * * * * *", move || {
+            let db = signatures_db.clone();
+            tokio::spawn(async move {
+                match delete_expired_used_signatures(&db, Utc::now()).await {
+                    Ok(0) => {}
+                    Ok(count) => log::info!("Pruned {count} expired signatures"),
+                    Err(e) => log::error!("Pruning used signatures failed: {e}"),
+                }
+            })
+        })
+        .await;
+    });
+
+    let outbox_db = db.clone();
+    tokio::spawn(async move {
+        run_cron("0 45 * * * * *", move || {
+            let db = outbox_db.clone();
+            tokio::spawn(async move {
+                match prune_outbox(&db).await {
+                    Ok(0) => {}
+                    Ok(count) => log::info!("Pruned {count} applied outbox messages"),
+                    Err(e) => log::error!("{e}"),
+                }
+            })
+        })
+        .await;
+    });
+
+    let monitor_db = db.clone();
+    tokio::spawn(async move {
+        run_cron(
+            &get_env_var_or_default("ALLOWANCE_MONITOR_CRON"),
+            move || {
+                let db = monitor_db.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = check_allowances(&db).await {
+                        log::error!("Allowance check failed: {e}");
+                    }
+                })
+            },
+        )
+        .await;
+    });
+
     HttpServer::new(move || {
         let cors = actix_cors::Cors::default()
             .allow_any_origin()
             .allow_any_method()
             .allow_any_header();
         App::new()
+            .app_data(web::Data::new(db.clone()))
             .wrap(Compress::default())
             .wrap(Logger::default())
             .wrap(cors)
             .service(router::health)
             .service(router::application::create)
+            .service(router::application::validate_issue)
             .service(
                 web::scope("/verifier")
                     .wrap(VerifierAuth)
@@ -90,7 +229,11 @@ async fn main() -> std::io::Result<()> {
                     .service(router::application::decline)
                     .service(router::application::reopen_declined_application)
                     .service(router::application::propose_decrease_allowance)
-                    .service(router::application::approve_decrease_allowance),
+                    .service(router::application::approve_decrease_allowance)
+                    .service(router::outbox::failed)
+                    .service(router::outbox::replay)
+                    .service(router::allocator::sync_labels)
+                    .service(router::allowance::refresh),
             )
             .service(router::application::merged)
             .service(router::application::active)
@@ -100,6 +243,9 @@ async fn main() -> std::io::Result<()> {
             .service(router::application::closed_allocator_applications)
             .service(router::application::total_dc_reached)
             .service(router::application::single)
+            .service(router::application::list_versions)
+            .service(router::application::single_version)
+            .service(router::application::diff_versions)
             .service(router::application::get_applications_by_contract_address)
             .service(router::application::application_with_allocation_amount_handler)
             .service(router::application::validate_application_flow)
@@ -114,6 +260,9 @@ async fn main() -> std::io::Result<()> {
             .service(router::blockchain::address_allowance)
             .service(router::blockchain::verified_clients)
             .service(router::verifier::verifiers)
+            .service(router::verifier::signed_allocations)
+            .service(router::github::rate_limit)
+            .service(router::allowance::status)
             .service(router::allocator::allocators)
             .service(router::allocator::allocator)
             .service(router::allocator::create_allocator_from_json)
@@ -121,6 +270,9 @@ async fn main() -> std::io::Result<()> {
             .service(router::allocator::check_if_repository_application_is_installed)
             .service(router::autoallocator::last_client_allocation)
             .service(router::autoallocator::trigger_autoallocation)
+            .service(router::autoallocator::autoallocation_history)
+            .service(router::autoallocator::autoallocation_stats)
+            .service(router::autoallocator::autoallocation_status)
             .service(router::autoallocator::check_if_allowance_is_sufficient)
         // .service(router::allocator::get_installation_ids)
     })
@@ -8,6 +8,7 @@ use serde::Deserialize;
 
 // Import any other modules that you reference in this file
 use fplus_database::database::allocators::get_allocator;
+use fplus_database::DbPool;
 #[derive(Deserialize, Debug)]
 struct RepoQuery {
     owner: String,
@@ -55,6 +56,11 @@ where
         let method = req.method();
         let path = req.path();
         let request_info = format!("{method} {path}?{query_string}");
+        let Some(db) = req.app_data::<web::Data<DbPool>>().cloned() else {
+            let err = actix_web::error::ErrorInternalServerError("Database pool is not configured");
+            log::error!("{err}");
+            return Box::pin(async { Err(err) });
+        };
         let query: Result<web::Query<RepoQuery>, _> = web::Query::from_query(query_string);
         let RepoQuery {
             owner,
@@ -162,7 +168,7 @@ where
                 return Err(err);
             }
 
-            match get_allocator(&owner, &repo).await {
+            match get_allocator(&db, &owner, &repo).await {
                 Ok(allocator) => {
                     if let Some(allocator) = &allocator {
                         if let Some(verifiers) = &allocator.verifiers_gh_handles {
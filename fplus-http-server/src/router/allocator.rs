@@ -3,10 +3,11 @@ use actix_web::{
     get, post, web, HttpResponse, Responder,
 };
 use fplus_database::database::allocators as allocators_db;
+use fplus_database::DbPool;
 use fplus_lib::core::{
     allocator::{
         check_if_repo_app_installed, create_allocator_from_file, fetch_installation_ids,
-        force_update_allocators, generate_github_app_jwt,
+        force_update_allocators, generate_github_app_jwt, labels::sync_allocator_labels,
     },
     AllocatorUpdateForceInfo, ChangedAllocators, GithubQueryParams,
 };
@@ -18,8 +19,8 @@ use reqwest::Client;
  * @return HttpResponse - The result of the operation
  */
 #[get("/allocators")]
-pub async fn allocators() -> actix_web::Result<impl Responder> {
-    let allocators = allocators_db::get_allocators()
+pub async fn allocators(db: web::Data<DbPool>) -> actix_web::Result<impl Responder> {
+    let allocators = allocators_db::get_allocators(&db)
         .await
         .map_err(ErrorNotFound)?;
     Ok(HttpResponse::Ok().json(allocators))
@@ -35,10 +36,11 @@ pub async fn allocators() -> actix_web::Result<impl Responder> {
  */
 #[post("/allocator/create")]
 pub async fn create_allocator_from_json(
+    db: web::Data<DbPool>,
     files: web::Json<ChangedAllocators>,
 ) -> actix_web::Result<impl Responder> {
     let ChangedAllocators { files_changed } = files.into_inner();
-    create_allocator_from_file(files_changed)
+    create_allocator_from_file(&db, files_changed)
         .await
         .map_err(ErrorInternalServerError)?;
     Ok(HttpResponse::Ok().body(
@@ -57,9 +59,12 @@ pub async fn create_allocator_from_json(
  * @return HttpResponse - The result of the operation
  */
 #[get("/allocator/{owner}/{repo}")]
-pub async fn allocator(path: web::Path<(String, String)>) -> actix_web::Result<impl Responder> {
+pub async fn allocator(
+    db: web::Data<DbPool>,
+    path: web::Path<(String, String)>,
+) -> actix_web::Result<impl Responder> {
     let (owner, repo) = path.into_inner();
-    let allocator = allocators_db::get_allocator(&owner, &repo)
+    let allocator = allocators_db::get_allocator(&db, &owner, &repo)
         .await
         .map_err(ErrorInternalServerError)?;
     if let Some(allocator) = allocator {
@@ -69,6 +74,27 @@ pub async fn allocator(path: web::Path<(String, String)>) -> actix_web::Result<i
     }
 }
 
+/**
+ * Create missing labels, fix drifted ones and align issue labels with application states
+ *
+ * # Arguments
+ * @param query: web::Query<GithubQueryParams> - The allocator repository
+ *
+ * # Returns
+ * @return HttpResponse - The labels and issues that were changed
+ */
+#[post("/allocator/labels/sync")]
+pub async fn sync_labels(
+    db: web::Data<DbPool>,
+    query: web::Query<GithubQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let GithubQueryParams { owner, repo } = query.into_inner();
+    let report = sync_allocator_labels(&db, &owner, &repo)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
 /**
  * Force updating allocator files from template.
  * It receives a list of changed files and allocators to update.
@@ -79,6 +105,7 @@ pub async fn allocator(path: web::Path<(String, String)>) -> actix_web::Result<i
  */
 #[post("/allocator/update/force")]
 pub async fn update_allocator_force(
+    db: web::Data<DbPool>,
     body: web::Json<AllocatorUpdateForceInfo>,
 ) -> actix_web::Result<impl Responder> {
     // First we need to deconstruct the body
@@ -88,7 +115,7 @@ pub async fn update_allocator_force(
     } = body.into_inner();
 
     // Logic will be implemented in allocator::update_allocator_force
-    force_update_allocators(files, affected_allocators)
+    force_update_allocators(&db, files, affected_allocators)
         .await
         .map_err(ErrorInternalServerError)?;
     Ok(HttpResponse::Ok().json(()))
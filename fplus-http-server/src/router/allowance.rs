@@ -0,0 +1,36 @@
+use actix_web::{
+    error::{ErrorInternalServerError, ErrorNotFound},
+    get, post, web, HttpResponse, Responder,
+};
+use fplus_database::DbPool;
+use fplus_lib::core::allowance_monitor::{check_allowances, latest_allowance_report};
+
+/**
+ * Get the allowance of the autoallocators and allocators with the forecast of
+ * when they run out, as of the last scheduled check
+ *
+ * # Returns
+ * @return HttpResponse - The allowance report
+ */
+#[get("/allowances/status")]
+pub async fn status(db: web::Data<DbPool>) -> actix_web::Result<impl Responder> {
+    let report = latest_allowance_report(&db)
+        .await
+        .map_err(ErrorInternalServerError)?
+        .ok_or_else(|| ErrorNotFound("No allowance check ran yet"))?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/**
+ * Check the allowances now instead of waiting for the scheduled check
+ *
+ * # Returns
+ * @return HttpResponse - The allowance report
+ */
+#[post("/allowances/refresh")]
+pub async fn refresh(db: web::Data<DbPool>) -> actix_web::Result<impl Responder> {
+    let report = check_allowances(&db)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(report))
+}
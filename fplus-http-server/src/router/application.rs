@@ -1,26 +1,85 @@
 use actix_web::{
-    error::{ErrorBadRequest, ErrorInternalServerError, ErrorNotFound},
-    get, post, web, HttpResponse, Responder,
+    error::{
+        ErrorBadRequest, ErrorConflict, ErrorInternalServerError, ErrorNotFound,
+        ErrorPreconditionFailed,
+    },
+    get,
+    http::header::{ETAG, IF_MATCH},
+    post, web, HttpRequest, HttpResponse, Responder,
 };
 
+use fplus_database::DbPool;
 use fplus_lib::core::{
     application::file::{
         DecreaseClientAllowanceVerifier, StorageProviderChangeVerifier, VerifierInput,
     },
+    application::versions::{
+        self, ApplicationVersionDiffQueryParams, ApplicationVersionQueryParams,
+    },
     ApplicationQueryParams, CompleteGovernanceReviewInfo, CompleteNewApplicationApprovalInfo,
     CompleteNewApplicationProposalInfo, CreateApplicationInfo, DcReachedInfo,
     DecreaseAllowanceApprovalInfo, DecreaseAllowanceProposalInfo,
     GetApplicationsByClientContractAddressQueryParams, GithubQueryParams, LDNApplication,
     MoreInfoNeeded, NotifyRefillInfo, StorageProvidersChangeApprovalInfo,
-    StorageProvidersChangeProposalInfo, SubmitKYCInfo, TriggerSSAInfo, ValidationPullRequestData,
-    VerifierActionsQueryParams,
+    StorageProvidersChangeProposalInfo, SubmitKYCInfo, TriggerSSAInfo, ValidateIssueInfo,
+    ValidationPullRequestData, VerifierActionsQueryParams,
 };
 
+use fplus_lib::error::LDNError;
+
 use crate::auth::gh_handle_auth::check_if_pull_request_opened_by_bot;
 
+/// Loads the application a verifier acts on. When the request carries an
+/// `If-Match` header, the action is rejected with `412 Precondition Failed`
+/// unless the application is still at that version.
+async fn load_application(
+    db: &DbPool,
+    query: &VerifierActionsQueryParams,
+    req: &HttpRequest,
+) -> actix_web::Result<LDNApplication> {
+    let mut ldn_application = LDNApplication::load(
+        db,
+        query.id.clone(),
+        query.owner.clone(),
+        query.repo.clone(),
+    )
+    .await
+    .map_err(ErrorNotFound)?;
+    if let Some(if_match) = req.headers().get(IF_MATCH) {
+        let expected = if_match
+            .to_str()
+            .map_err(ErrorBadRequest)?
+            .trim()
+            .trim_start_matches("W/")
+            .trim_matches('"');
+        if expected != "*" {
+            ldn_application
+                .expect_version(expected)
+                .map_err(ErrorPreconditionFailed)?;
+        }
+    }
+    Ok(ldn_application)
+}
+
+/// Reports conflicts the backend could not resolve as `409 Conflict` and
+/// changes since the `If-Match` version as `412 Precondition Failed`, other
+/// errors with the status the handler chose.
+fn conflict_or(
+    otherwise: fn(LDNError) -> actix_web::Error,
+) -> impl Fn(LDNError) -> actix_web::Error {
+    move |e| match e {
+        LDNError::Conflict(_) => ErrorConflict(e),
+        LDNError::PreconditionFailed(_) => ErrorPreconditionFailed(e),
+        e => otherwise(e),
+    }
+}
+
 #[post("/application")]
-pub async fn create(info: web::Json<CreateApplicationInfo>) -> actix_web::Result<impl Responder> {
-    let app = LDNApplication::new_from_issue(info.into_inner())
+pub async fn create(
+    db: web::Data<DbPool>,
+    info: web::Json<CreateApplicationInfo>,
+) -> actix_web::Result<impl Responder> {
+    let app = LDNApplication::new_from_issue(&db, info.into_inner())
         .await
         .map_err(ErrorBadRequest)?;
     Ok(HttpResponse::Ok().body(format!(
@@ -29,21 +88,95 @@ pub async fn create(info: web::Json<CreateApplicationInfo>) -> actix_web::Result
     )))
 }
 
+#[post("/application/validate_issue")]
+pub async fn validate_issue(
+    db: web::Data<DbPool>,
+    info: web::Json<ValidateIssueInfo>,
+) -> actix_web::Result<impl Responder> {
+    let ValidateIssueInfo {
+        issue_number,
+        owner,
+        repo,
+        post_comment,
+    } = info.into_inner();
+    let report = LDNApplication::validate_application_issue(
+        &db,
+        issue_number,
+        owner,
+        repo,
+        post_comment.unwrap_or(false),
+    )
+    .await
+    .map_err(ErrorBadRequest)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
 #[get("/application")]
 pub async fn single(
+    db: web::Data<DbPool>,
     query: web::Query<ApplicationQueryParams>,
 ) -> actix_web::Result<impl Responder> {
     let ApplicationQueryParams { id, owner, repo } = query.into_inner();
-    let app_file = LDNApplication::load_from_db(id, owner, repo)
+    let (app_file, sha) = LDNApplication::load_versioned_from_db(&db, id, owner, repo)
         .await
         .map_err(ErrorNotFound)?;
     let body = serde_json::to_string_pretty(&app_file).map_err(ErrorInternalServerError)?;
-    Ok(HttpResponse::Ok().body(body))
+    let mut response = HttpResponse::Ok();
+    if let Some(sha) = sha {
+        response.insert_header((ETAG, format!("\"{sha}\"")));
+    }
+    Ok(response.body(body))
+}
+
+#[get("/application/versions")]
+pub async fn list_versions(
+    db: web::Data<DbPool>,
+    query: web::Query<ApplicationQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let ApplicationQueryParams { id, owner, repo } = query.into_inner();
+    let versions = versions::application_versions(&db, &id, &owner, &repo)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(versions))
+}
+
+#[get("/application/version")]
+pub async fn single_version(
+    db: web::Data<DbPool>,
+    query: web::Query<ApplicationVersionQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let ApplicationVersionQueryParams {
+        id,
+        owner,
+        repo,
+        version,
+    } = query.into_inner();
+    let version = versions::application_version(&db, &id, &owner, &repo, version)
+        .await
+        .map_err(|e| match e {
+            LDNError::New(_) => ErrorNotFound(e),
+            e => ErrorInternalServerError(e),
+        })?;
+    Ok(HttpResponse::Ok().json(version))
+}
+
+#[get("/application/versions/diff")]
+pub async fn diff_versions(
+    db: web::Data<DbPool>,
+    query: web::Query<ApplicationVersionDiffQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let changes = versions::application_version_diff(&db, &query)
+        .await
+        .map_err(|e| match e {
+            LDNError::New(_) => ErrorNotFound(e),
+            e => ErrorInternalServerError(e),
+        })?;
+    Ok(HttpResponse::Ok().json(changes))
 }
 
 #[get("/applications/closed")]
-pub async fn closed_applications() -> actix_web::Result<impl Responder> {
-    let apps = LDNApplication::get_closed_applications()
+pub async fn closed_applications(db: web::Data<DbPool>) -> actix_web::Result<impl Responder> {
+    let apps = LDNApplication::get_closed_applications(&db)
         .await
         .map_err(ErrorInternalServerError)?;
 
@@ -55,12 +188,15 @@ pub async fn closed_applications() -> actix_web::Result<impl Responder> {
 
 #[get("/applications/by_contract_address")]
 pub async fn get_applications_by_contract_address(
+    db: web::Data<DbPool>,
     query: web::Query<GetApplicationsByClientContractAddressQueryParams>,
 ) -> actix_web::Result<impl Responder> {
-    let applications =
-        LDNApplication::get_applications_by_client_contract_address(&query.client_contract_address)
-            .await
-            .map_err(ErrorNotFound)?;
+    let applications = LDNApplication::get_applications_by_client_contract_address(
+        &db,
+        &query.client_contract_address,
+    )
+    .await
+    .map_err(ErrorNotFound)?;
     let parsed = serde_json::to_string_pretty(&applications).map_err(ErrorInternalServerError)?;
     Ok(HttpResponse::Ok()
         .content_type("application/json")
@@ -69,10 +205,11 @@ pub async fn get_applications_by_contract_address(
 
 #[get("/applications/closed/allocator")]
 pub async fn closed_allocator_applications(
+    db: web::Data<DbPool>,
     query: web::Query<GithubQueryParams>,
 ) -> actix_web::Result<impl Responder> {
     let GithubQueryParams { owner, repo } = query.into_inner();
-    let apps = LDNApplication::get_allocator_closed_applications(&owner, &repo)
+    let apps = LDNApplication::get_allocator_closed_applications(&db, &owner, &repo)
         .await
         .map_err(ErrorInternalServerError)?;
 
@@ -84,10 +221,11 @@ pub async fn closed_allocator_applications(
 
 #[get("/application/with-allocation-amount")]
 pub async fn application_with_allocation_amount_handler(
+    db: web::Data<DbPool>,
     query: web::Query<ApplicationQueryParams>,
 ) -> actix_web::Result<impl Responder> {
     let ApplicationQueryParams { id, owner, repo } = query.into_inner();
-    let application = LDNApplication::application_with_allocation_amount(id, owner, repo)
+    let application = LDNApplication::application_with_allocation_amount(&db, id, owner, repo)
         .await
         .map_err(ErrorNotFound)?;
     Ok(HttpResponse::Ok().json(application))
@@ -95,13 +233,12 @@ pub async fn application_with_allocation_amount_handler(
 
 #[post("/application/trigger")]
 pub async fn trigger(
+    db: web::Data<DbPool>,
+    req: HttpRequest,
     query: web::Query<VerifierActionsQueryParams>,
     info: web::Json<CompleteGovernanceReviewInfo>,
 ) -> actix_web::Result<impl Responder> {
-    let ldn_application =
-        LDNApplication::load(query.id.clone(), query.owner.clone(), query.repo.clone())
-            .await
-            .map_err(ErrorNotFound)?;
+    let ldn_application = load_application(&db, &query, &req).await?;
 
     dbg!(&ldn_application);
     let CompleteGovernanceReviewInfo {
@@ -119,7 +256,7 @@ pub async fn trigger(
             reason_for_not_using_client_smart_contract,
         )
         .await
-        .map_err(ErrorBadRequest)?;
+        .map_err(conflict_or(ErrorBadRequest))?;
 
     let serialized_app = serde_json::to_string_pretty(&response)
         .map_err(|_| ErrorInternalServerError("Failed to serialize success message".to_string()))?;
@@ -129,17 +266,16 @@ pub async fn trigger(
 
 #[post("/application/approve_changes")]
 pub async fn approve_changes(
+    db: web::Data<DbPool>,
+    req: HttpRequest,
     query: web::Query<VerifierActionsQueryParams>,
 ) -> actix_web::Result<impl Responder> {
-    let ldn_application =
-        LDNApplication::load(query.id.clone(), query.owner.clone(), query.repo.clone())
-            .await
-            .map_err(ErrorNotFound)?;
+    let ldn_application = load_application(&db, &query, &req).await?;
 
     let response = ldn_application
         .approve_changes(query.owner.clone(), query.repo.clone())
         .await
-        .map_err(ErrorNotFound)?;
+        .map_err(conflict_or(ErrorNotFound))?;
 
     let serialized_app = serde_json::to_string_pretty(&response)
         .map_err(|_| ErrorInternalServerError("Failed to serialize success message".to_string()))?;
@@ -148,6 +284,8 @@ pub async fn approve_changes(
 
 #[post("/application/propose")]
 pub async fn propose(
+    db: web::Data<DbPool>,
+    req: HttpRequest,
     info: web::Json<CompleteNewApplicationProposalInfo>,
     query: web::Query<VerifierActionsQueryParams>,
 ) -> actix_web::Result<impl Responder> {
@@ -157,10 +295,7 @@ pub async fn propose(
         new_allocation_amount,
         amount_of_datacap_sent_to_contract,
     } = info.into_inner();
-    let ldn_application =
-        LDNApplication::load(query.id.clone(), query.owner.clone(), query.repo.clone())
-            .await
-            .map_err(ErrorNotFound)?;
+    let ldn_application = load_application(&db, &query, &req).await?;
     let updated_signer = VerifierInput {
         github_username: query.github_username.clone(), // Use the provided `github_username` parameter
         signing_address: signer.signing_address,
@@ -178,7 +313,7 @@ pub async fn propose(
             amount_of_datacap_sent_to_contract,
         )
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(conflict_or(ErrorInternalServerError))?;
     let serialized_app = serde_json::to_string_pretty(&response)
         .map_err(|_| ErrorInternalServerError("Failed to serialize success message".to_string()))?;
     Ok(HttpResponse::Ok().body(serialized_app))
@@ -186,6 +321,8 @@ pub async fn propose(
 
 #[post("/application/propose_storage_providers")]
 pub async fn propose_storage_providers(
+    db: web::Data<DbPool>,
+    req: HttpRequest,
     info: web::Json<StorageProvidersChangeProposalInfo>,
     query: web::Query<VerifierActionsQueryParams>,
 ) -> actix_web::Result<impl Responder> {
@@ -194,10 +331,7 @@ pub async fn propose_storage_providers(
         allowed_sps,
         max_deviation,
     } = info.into_inner();
-    let ldn_application =
-        LDNApplication::load(query.id.clone(), query.owner.clone(), query.repo.clone())
-            .await
-            .map_err(ErrorNotFound)?;
+    let ldn_application = load_application(&db, &query, &req).await?;
     let verifier = StorageProviderChangeVerifier {
         github_username: query.github_username.clone(),
         signing_address: signer.signing_address.clone(),
@@ -215,7 +349,7 @@ pub async fn propose_storage_providers(
             max_deviation,
         )
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(conflict_or(ErrorInternalServerError))?;
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
             .expect("Serialization of static string should succeed"),
@@ -224,14 +358,13 @@ pub async fn propose_storage_providers(
 
 #[post("/application/approve_storage_providers")]
 pub async fn approve_storage_providers(
+    db: web::Data<DbPool>,
+    req: HttpRequest,
     info: web::Json<StorageProvidersChangeApprovalInfo>,
     query: web::Query<VerifierActionsQueryParams>,
 ) -> actix_web::Result<impl Responder> {
     let StorageProvidersChangeApprovalInfo { signer, request_id } = info.into_inner();
-    let ldn_application =
-        LDNApplication::load(query.id.clone(), query.owner.clone(), query.repo.clone())
-            .await
-            .map_err(ErrorNotFound)?;
+    let ldn_application = load_application(&db, &query, &req).await?;
     let verifier = StorageProviderChangeVerifier {
         github_username: query.github_username.clone(),
         signing_address: signer.signing_address.clone(),
@@ -247,7 +380,7 @@ pub async fn approve_storage_providers(
             request_id,
         )
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(conflict_or(ErrorInternalServerError))?;
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
             .expect("Serialization of static string should succeed"),
@@ -256,13 +389,12 @@ pub async fn approve_storage_providers(
 
 #[post("/application/propose_decrease_allowance")]
 pub async fn propose_decrease_allowance(
+    db: web::Data<DbPool>,
+    req: HttpRequest,
     info: web::Json<DecreaseAllowanceProposalInfo>,
     query: web::Query<VerifierActionsQueryParams>,
 ) -> actix_web::Result<impl Responder> {
-    let ldn_application =
-        LDNApplication::load(query.id.clone(), query.owner.clone(), query.repo.clone())
-            .await
-            .map_err(ErrorNotFound)?;
+    let ldn_application = load_application(&db, &query, &req).await?;
     let verifier = DecreaseClientAllowanceVerifier {
         github_username: query.github_username.clone(),
         signing_address: info.signer.signing_address.clone(),
@@ -278,7 +410,7 @@ pub async fn propose_decrease_allowance(
             &info.reason_for_decrease,
         )
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(conflict_or(ErrorInternalServerError))?;
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
             .expect("Serialization of static string should succeed"),
@@ -287,13 +419,12 @@ pub async fn propose_decrease_allowance(
 
 #[post("/application/approve_decrease_allowance")]
 pub async fn approve_decrease_allowance(
+    db: web::Data<DbPool>,
+    req: HttpRequest,
     info: web::Json<DecreaseAllowanceApprovalInfo>,
     query: web::Query<VerifierActionsQueryParams>,
 ) -> actix_web::Result<impl Responder> {
-    let ldn_application =
-        LDNApplication::load(query.id.clone(), query.owner.clone(), query.repo.clone())
-            .await
-            .map_err(ErrorNotFound)?;
+    let ldn_application = load_application(&db, &query, &req).await?;
     let verifier = DecreaseClientAllowanceVerifier {
         github_username: query.github_username.clone(),
         signing_address: info.signer.signing_address.clone(),
@@ -303,7 +434,7 @@ pub async fn approve_decrease_allowance(
     ldn_application
         .approve_decrease_allowance(&verifier, &query.owner, &query.repo, &info.request_id)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(conflict_or(ErrorInternalServerError))?;
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
             .expect("Serialization of static string should succeed"),
@@ -312,14 +443,13 @@ pub async fn approve_decrease_allowance(
 
 #[post("/application/approve")]
 pub async fn approve(
+    db: web::Data<DbPool>,
+    req: HttpRequest,
     query: web::Query<VerifierActionsQueryParams>,
     info: web::Json<CompleteNewApplicationApprovalInfo>,
 ) -> actix_web::Result<impl Responder> {
     let CompleteNewApplicationApprovalInfo { signer, request_id } = info.into_inner();
-    let ldn_application =
-        LDNApplication::load(query.id.clone(), query.owner.clone(), query.repo.clone())
-            .await
-            .map_err(ErrorNotFound)?;
+    let ldn_application = load_application(&db, &query, &req).await?;
     let updated_signer = VerifierInput {
         github_username: query.github_username.clone(), // Use the provided `github_username` parameter
         signing_address: signer.signing_address,
@@ -337,7 +467,7 @@ pub async fn approve(
             None,
         )
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(conflict_or(ErrorInternalServerError))?;
     let serialized_app = serde_json::to_string_pretty(&app).map_err(ErrorInternalServerError)?;
 
     Ok(HttpResponse::Ok().body(serialized_app))
@@ -345,25 +475,26 @@ pub async fn approve(
 
 #[post("/application/decline")]
 pub async fn decline(
+    db: web::Data<DbPool>,
+    req: HttpRequest,
     query: web::Query<VerifierActionsQueryParams>,
 ) -> actix_web::Result<impl Responder> {
-    let ldn_application =
-        LDNApplication::load(query.id.clone(), query.owner.clone(), query.repo.clone())
-            .await
-            .map_err(ErrorNotFound)?;
+    let ldn_application = load_application(&db, &query, &req).await?;
     ldn_application
         .decline_application(query.owner.clone(), query.repo.clone())
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(conflict_or(ErrorInternalServerError))?;
 
     Ok(HttpResponse::Ok().body(()))
 }
 
 #[post("/application/reopen_declined_application")]
 pub async fn reopen_declined_application(
+    db: web::Data<DbPool>,
     query: web::Query<VerifierActionsQueryParams>,
 ) -> actix_web::Result<impl Responder> {
     LDNApplication::reopen_declined_application(
+        &db,
         &query.owner,
         &query.repo,
         &query.github_username,
@@ -377,26 +508,25 @@ pub async fn reopen_declined_application(
 
 #[post("/application/additional_info_required")]
 pub async fn additional_info_required(
+    db: web::Data<DbPool>,
+    req: HttpRequest,
     query: web::Query<VerifierActionsQueryParams>,
     info: web::Json<MoreInfoNeeded>,
 ) -> actix_web::Result<impl Responder> {
     let MoreInfoNeeded { verifier_message } = info.into_inner();
-    let ldn_application =
-        LDNApplication::load(query.id.clone(), query.owner.clone(), query.repo.clone())
-            .await
-            .map_err(ErrorNotFound)?;
+    let ldn_application = load_application(&db, &query, &req).await?;
     let app = ldn_application
         .additional_info_required(query.owner.clone(), query.repo.clone(), verifier_message)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(conflict_or(ErrorInternalServerError))?;
     let serialized_app = serde_json::to_string_pretty(&app).map_err(ErrorInternalServerError)?;
 
     Ok(HttpResponse::Ok().body(serialized_app))
 }
 
 #[get("/applications/active")]
-pub async fn all_applications() -> actix_web::Result<impl Responder> {
-    let apps = LDNApplication::all_applications()
+pub async fn all_applications(db: web::Data<DbPool>) -> actix_web::Result<impl Responder> {
+    let apps = LDNApplication::all_applications(&db)
         .await
         .map_err(ErrorNotFound)?;
 
@@ -407,9 +537,12 @@ pub async fn all_applications() -> actix_web::Result<impl Responder> {
 }
 
 #[get("/applications/open_pull_request")]
-pub async fn active(query: web::Query<GithubQueryParams>) -> actix_web::Result<impl Responder> {
+pub async fn active(
+    db: web::Data<DbPool>,
+    query: web::Query<GithubQueryParams>,
+) -> actix_web::Result<impl Responder> {
     let GithubQueryParams { owner, repo } = query.into_inner();
-    let app = LDNApplication::active(owner, repo, None)
+    let app = LDNApplication::active(&db, owner, repo, None)
         .await
         .map_err(ErrorInternalServerError)?;
     let serialized_app = serde_json::to_string_pretty(&app).map_err(ErrorInternalServerError)?;
@@ -418,9 +551,12 @@ pub async fn active(query: web::Query<GithubQueryParams>) -> actix_web::Result<i
 }
 
 #[get("/application/merged")]
-pub async fn merged(query: web::Query<GithubQueryParams>) -> actix_web::Result<impl Responder> {
+pub async fn merged(
+    db: web::Data<DbPool>,
+    query: web::Query<GithubQueryParams>,
+) -> actix_web::Result<impl Responder> {
     let GithubQueryParams { owner, repo } = query.into_inner();
-    let apps = LDNApplication::merged(owner, repo)
+    let apps = LDNApplication::merged(&db, owner, repo)
         .await
         .map_err(ErrorInternalServerError)?;
     let serialized_apps = serde_json::to_string_pretty(&apps).map_err(ErrorInternalServerError)?;
@@ -429,8 +565,11 @@ pub async fn merged(query: web::Query<GithubQueryParams>) -> actix_web::Result<i
 }
 
 #[post("/application/notify_refill")]
-pub async fn notify_refill(info: web::Json<NotifyRefillInfo>) -> actix_web::Result<impl Responder> {
-    LDNApplication::notify_refill(info.into_inner())
+pub async fn notify_refill(
+    db: web::Data<DbPool>,
+    info: web::Json<NotifyRefillInfo>,
+) -> actix_web::Result<impl Responder> {
+    LDNApplication::notify_refill(&db, info.into_inner())
         .await
         .map_err(ErrorInternalServerError)?;
     Ok(HttpResponse::Ok().body(
@@ -440,9 +579,12 @@ pub async fn notify_refill(info: web::Json<NotifyRefillInfo>) -> actix_web::Resu
 }
 
 #[post("/application/totaldcreached")]
-pub async fn total_dc_reached(data: web::Json<DcReachedInfo>) -> actix_web::Result<impl Responder> {
+pub async fn total_dc_reached(
+    db: web::Data<DbPool>,
+    data: web::Json<DcReachedInfo>,
+) -> actix_web::Result<impl Responder> {
     let DcReachedInfo { id, owner, repo } = data.into_inner();
-    let ldn_application = LDNApplication::load(id.clone(), owner.clone(), repo.clone())
+    let ldn_application = LDNApplication::load(&db, id.clone(), owner.clone(), repo.clone())
         .await
         .map_err(ErrorNotFound)?;
     let applications = ldn_application
@@ -454,6 +596,7 @@ pub async fn total_dc_reached(data: web::Json<DcReachedInfo>) -> actix_web::Resu
 
 #[post("application/flow/validate")]
 pub async fn validate_application_flow(
+    db: web::Data<DbPool>,
     info: web::Json<ValidationPullRequestData>,
 ) -> actix_web::Result<impl Responder> {
     let ValidationPullRequestData {
@@ -463,7 +606,7 @@ pub async fn validate_application_flow(
         repo,
     } = info.into_inner();
     if let Ok(pr_number) = pr_number.trim_matches('"').parse::<u64>() {
-        let result = LDNApplication::validate_flow(pr_number, &user_handle, owner, repo)
+        let result = LDNApplication::validate_flow(&db, pr_number, &user_handle, owner, repo)
             .await
             .map_err(ErrorInternalServerError)?;
         Ok(HttpResponse::Ok().json(result))
@@ -474,6 +617,7 @@ pub async fn validate_application_flow(
 
 #[post("application/trigger/validate")]
 pub async fn validate_application_trigger(
+    db: web::Data<DbPool>,
     info: web::Json<ValidationPullRequestData>,
 ) -> actix_web::Result<impl Responder> {
     let ValidationPullRequestData {
@@ -484,7 +628,7 @@ pub async fn validate_application_trigger(
     } = info.into_inner();
 
     if let Ok(pr_number) = pr_number.trim_matches('"').parse::<u64>() {
-        let result = LDNApplication::validate_trigger(pr_number, &user_handle, owner, repo)
+        let result = LDNApplication::validate_trigger(&db, pr_number, &user_handle, owner, repo)
             .await
             .map_err(ErrorInternalServerError)?;
         Ok(HttpResponse::Ok().json(result))
@@ -495,6 +639,7 @@ pub async fn validate_application_trigger(
 
 #[post("application/proposal/validate")]
 pub async fn validate_application_proposal(
+    db: web::Data<DbPool>,
     info: web::Json<ValidationPullRequestData>,
 ) -> actix_web::Result<impl Responder> {
     let ValidationPullRequestData {
@@ -505,7 +650,7 @@ pub async fn validate_application_proposal(
     } = info.into_inner();
 
     if let Ok(pr_number) = pr_number.trim_matches('"').parse::<u64>() {
-        let result = LDNApplication::validate_proposal(pr_number, owner, repo)
+        let result = LDNApplication::validate_proposal(&db, pr_number, owner, repo)
             .await
             .map_err(ErrorInternalServerError)?;
         Ok(HttpResponse::Ok().json(result))
@@ -516,6 +661,7 @@ pub async fn validate_application_proposal(
 
 #[post("application/approval/validate")]
 pub async fn validate_application_approval(
+    db: web::Data<DbPool>,
     info: web::Json<ValidationPullRequestData>,
 ) -> actix_web::Result<impl Responder> {
     let ValidationPullRequestData {
@@ -526,7 +672,7 @@ pub async fn validate_application_approval(
     } = info.into_inner();
 
     if let Ok(pr_number) = pr_number.trim_matches('"').parse::<u64>() {
-        let result = LDNApplication::validate_approval(pr_number, owner, repo)
+        let result = LDNApplication::validate_approval(&db, pr_number, owner, repo)
             .await
             .map_err(ErrorInternalServerError)?;
         Ok(HttpResponse::Ok().json(result))
@@ -537,6 +683,7 @@ pub async fn validate_application_approval(
 
 #[post("application/merge/validate")]
 pub async fn validate_application_merge(
+    db: web::Data<DbPool>,
     info: web::Json<ValidationPullRequestData>,
 ) -> actix_web::Result<impl Responder> {
     let ValidationPullRequestData {
@@ -546,8 +693,8 @@ pub async fn validate_application_merge(
         repo,
     } = info.into_inner();
     if let Ok(pr_number) = pr_number.trim_matches('"').parse::<u64>() {
-        check_if_pull_request_opened_by_bot(&owner, &repo, &pr_number).await?;
-        let result = LDNApplication::validate_merge_application(pr_number, owner, repo)
+        check_if_pull_request_opened_by_bot(&db, &owner, &repo, &pr_number).await?;
+        let result = LDNApplication::validate_merge_application(&db, pr_number, owner, repo)
             .await
             .map_err(ErrorInternalServerError)?;
         Ok(HttpResponse::Ok().json(result))
@@ -558,14 +705,15 @@ pub async fn validate_application_merge(
 
 #[post("application/cache/renewal")]
 pub async fn cache_renewal(
+    db: web::Data<DbPool>,
     info: web::Json<GithubQueryParams>,
 ) -> actix_web::Result<impl Responder> {
     let GithubQueryParams { owner, repo } = info.into_inner();
-    LDNApplication::cache_renewal_active(owner.clone(), repo.clone())
+    LDNApplication::cache_renewal_active(&db, owner.clone(), repo.clone())
         .await
         .map_err(ErrorInternalServerError)?;
 
-    LDNApplication::cache_renewal_merged(owner, repo)
+    LDNApplication::cache_renewal_merged(&db, owner, repo)
         .await
         .map_err(ErrorInternalServerError)?;
     Ok(HttpResponse::Ok().json("Cache renewal for active and merged applications succeeded"))
@@ -573,9 +721,10 @@ pub async fn cache_renewal(
 
 #[post("application/update-from-issue")]
 pub async fn update_from_issue(
+    db: web::Data<DbPool>,
     info: web::Json<CreateApplicationInfo>,
 ) -> actix_web::Result<impl Responder> {
-    let app = LDNApplication::update_from_issue(info.into_inner())
+    let app = LDNApplication::update_from_issue(&db, info.into_inner())
         .await
         .map_err(ErrorInternalServerError)?;
     Ok(HttpResponse::Ok().body(format!(
@@ -586,6 +735,7 @@ pub async fn update_from_issue(
 
 #[post("application/check_for_changes")]
 pub async fn check_for_changes(
+    db: web::Data<DbPool>,
     info: web::Json<ValidationPullRequestData>,
 ) -> actix_web::Result<impl Responder> {
     let ValidationPullRequestData {
@@ -596,8 +746,8 @@ pub async fn check_for_changes(
     } = info.into_inner();
 
     if let Ok(pr_number) = pr_number.trim_matches('"').parse::<u64>() {
-        check_if_pull_request_opened_by_bot(&owner, &repo, &pr_number).await?;
-        let result = LDNApplication::check_for_changes(pr_number, owner, repo)
+        check_if_pull_request_opened_by_bot(&db, &owner, &repo, &pr_number).await?;
+        let result = LDNApplication::check_for_changes(&db, pr_number, owner, repo)
             .await
             .map_err(ErrorInternalServerError)?;
         Ok(HttpResponse::Ok().json(result))
@@ -607,8 +757,12 @@ pub async fn check_for_changes(
 }
 
 #[post("application/submit_kyc")]
-pub async fn submit_kyc(info: web::Json<SubmitKYCInfo>) -> actix_web::Result<impl Responder> {
+pub async fn submit_kyc(
+    db: web::Data<DbPool>,
+    info: web::Json<SubmitKYCInfo>,
+) -> actix_web::Result<impl Responder> {
     let ldn_application = LDNApplication::load(
+        &db,
         info.message.client_id.clone(),
         info.message.allocator_repo_owner.clone(),
         info.message.allocator_repo_name.clone(),
@@ -633,16 +787,15 @@ pub async fn health() -> actix_web::Result<impl Responder> {
 
 #[post("application/request_kyc")]
 pub async fn request_kyc(
+    db: web::Data<DbPool>,
+    req: HttpRequest,
     query: web::Query<VerifierActionsQueryParams>,
 ) -> actix_web::Result<impl Responder> {
-    let ldn_application =
-        LDNApplication::load(query.id.clone(), query.owner.clone(), query.repo.clone())
-            .await
-            .map_err(ErrorNotFound)?;
+    let ldn_application = load_application(&db, &query, &req).await?;
     ldn_application
         .request_kyc(&query.id, &query.owner, &query.repo)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(conflict_or(ErrorInternalServerError))?;
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
             .expect("Serialization of static string should succeed"),
@@ -651,10 +804,12 @@ pub async fn request_kyc(
 
 #[post("application/trigger_ssa")]
 pub async fn trigger_ssa(
+    db: web::Data<DbPool>,
     query: web::Query<VerifierActionsQueryParams>,
     info: web::Json<TriggerSSAInfo>,
 ) -> actix_web::Result<impl Responder> {
     LDNApplication::trigger_ssa(
+        &db,
         &query.id,
         &query.owner,
         &query.repo,
@@ -671,16 +826,15 @@ pub async fn trigger_ssa(
 
 #[post("application/remove_pending_allocation")]
 pub async fn remove_pending_allocation(
+    db: web::Data<DbPool>,
+    req: HttpRequest,
     query: web::Query<VerifierActionsQueryParams>,
 ) -> actix_web::Result<impl Responder> {
-    let ldn_application =
-        LDNApplication::load(query.id.clone(), query.owner.clone(), query.repo.clone())
-            .await
-            .map_err(ErrorNotFound)?;
+    let ldn_application = load_application(&db, &query, &req).await?;
     ldn_application
         .remove_pending_allocation(&query.id, &query.owner, &query.repo)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(conflict_or(ErrorInternalServerError))?;
 
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
@@ -690,16 +844,15 @@ pub async fn remove_pending_allocation(
 
 #[post("application/allocation_failed")]
 pub async fn allocation_failed(
+    db: web::Data<DbPool>,
+    req: HttpRequest,
     query: web::Query<VerifierActionsQueryParams>,
 ) -> actix_web::Result<impl Responder> {
-    let ldn_application =
-        LDNApplication::load(query.id.clone(), query.owner.clone(), query.repo.clone())
-            .await
-            .map_err(ErrorNotFound)?;
+    let ldn_application = load_application(&db, &query, &req).await?;
     ldn_application
         .revert_to_ready_to_sign(&query.id, &query.owner, &query.repo)
         .await
-        .map_err(ErrorInternalServerError)?;
+        .map_err(conflict_or(ErrorInternalServerError))?;
 
     Ok(HttpResponse::Ok().body(
         serde_json::to_string_pretty("Success")
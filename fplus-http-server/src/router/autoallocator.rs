@@ -1,38 +1,106 @@
 use actix_web::error::ErrorInternalServerError;
 use actix_web::{get, post, web, HttpResponse, Responder};
 use fplus_database::database::autoallocations as autoallocations_db;
-use fplus_lib::core::autoallocator;
-use fplus_lib::core::{LastAutoallocationQueryParams, TriggerAutoallocationInfo};
+use fplus_database::DbPool;
+use fplus_lib::core::autoallocator::profiles::AutoallocatorNetwork;
+use fplus_lib::core::autoallocator::{self, transactions};
+use fplus_lib::core::{
+    AutoallocationHistoryQueryParams, AutoallocatorNetworkQueryParams,
+    LastAutoallocationQueryParams, TriggerAutoallocationInfo,
+};
+use fplus_lib::error::LDNError;
+use serde_json::json;
+
+/// Grants per history page when the query sets no `limit`.
+const HISTORY_PAGE_DEFAULT: u64 = 100;
+
 #[get("/autoallocator/last_client_allocation")]
 pub async fn last_client_allocation(
+    db: web::Data<DbPool>,
     query: web::Query<LastAutoallocationQueryParams>,
 ) -> actix_web::Result<impl Responder> {
-    let last_client_allocation =
-        autoallocations_db::get_last_client_autoallocation(query.evm_wallet_address)
-            .await
-            .map_err(ErrorInternalServerError)?;
+    let network = query.network.unwrap_or(AutoallocatorNetwork::Mainnet);
+    let last_client_allocation = autoallocations_db::get_last_client_autoallocation(
+        &db,
+        query.evm_wallet_address,
+        network.as_str(),
+    )
+    .await
+    .map_err(ErrorInternalServerError)?;
 
     let serialized_last_client_allocation =
         serde_json::to_string_pretty(&last_client_allocation).map_err(ErrorInternalServerError)?;
     Ok(HttpResponse::Ok().body(serialized_last_client_allocation))
 }
 
+/// Returns as soon as the transaction is sent, with the URL where its status
+/// can be followed. A client refused by a sybil check gets `403 Forbidden`
+/// with the reason code.
 #[post("autoallocator/trigger_autoallocation")]
 pub async fn trigger_autoallocation(
+    db: web::Data<DbPool>,
     info: web::Json<TriggerAutoallocationInfo>,
 ) -> actix_web::Result<impl Responder> {
-    autoallocator::trigger_autoallocation(&info.into_inner())
+    let grant = match autoallocator::trigger_autoallocation(&db, &info.into_inner()).await {
+        Ok(grant) => grant,
+        Err(LDNError::Rejected(rejection)) => return Ok(HttpResponse::Forbidden().json(rejection)),
+        Err(e) => return Err(ErrorInternalServerError(e)),
+    };
+    Ok(HttpResponse::Accepted().json(json!({
+        "grant_id": grant.id,
+        "status": grant.status,
+        "status_url": format!("/autoallocator/autoallocations/{}", grant.id),
+    })))
+}
+
+/// Lists a page of the grants matching the query, newest first, with their
+/// amount and transaction hash. Pass `next_cursor` back as `cursor` for the
+/// next page.
+#[get("/autoallocator/autoallocations")]
+pub async fn autoallocation_history(
+    db: web::Data<DbPool>,
+    query: web::Query<AutoallocationHistoryQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let query = query.into_inner();
+    let limit = query.limit.unwrap_or(HISTORY_PAGE_DEFAULT);
+    let cursor = query.cursor;
+    let history = autoallocations_db::get_autoallocation_history(&db, &query.into(), limit, cursor)
+        .await
+        .map_err(ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(history))
+}
+
+/// Counts the grants matching the query and sums their amounts per UTC day
+/// and status.
+#[get("/autoallocator/stats")]
+pub async fn autoallocation_stats(
+    db: web::Data<DbPool>,
+    query: web::Query<AutoallocationHistoryQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let stats = autoallocations_db::get_autoallocation_daily_stats(&db, &query.into_inner().into())
         .await
         .map_err(ErrorInternalServerError)?;
-    Ok(HttpResponse::Ok().body(
-        serde_json::to_string_pretty("Success")
-            .expect("Serialization of static string should succeed"),
-    ))
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+#[get("/autoallocator/autoallocations/{id}")]
+pub async fn autoallocation_status(
+    db: web::Data<DbPool>,
+    path: web::Path<i64>,
+) -> actix_web::Result<impl Responder> {
+    match transactions::autoallocation_status(&db, path.into_inner()).await {
+        Ok(status) => Ok(HttpResponse::Ok().json(status)),
+        Err(LDNError::New(e)) => Ok(HttpResponse::NotFound().body(e)),
+        Err(e) => Err(ErrorInternalServerError(e)),
+    }
 }
 
 #[get("/autoallocator/check_if_allowance_is_sufficient")]
-pub async fn check_if_allowance_is_sufficient() -> actix_web::Result<impl Responder> {
-    let allowance_sufficient = autoallocator::check_if_allowance_is_sufficient()
+pub async fn check_if_allowance_is_sufficient(
+    query: web::Query<AutoallocatorNetworkQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let network = query.network.unwrap_or(AutoallocatorNetwork::Mainnet);
+    let allowance_sufficient = autoallocator::check_if_allowance_is_sufficient(network)
         .await
         .map_err(ErrorInternalServerError)?;
     Ok(HttpResponse::Ok().json(allowance_sufficient))
@@ -0,0 +1,32 @@
+use actix_web::{get, HttpResponse, Responder};
+use fplus_lib::external_services::github_rate_limit::rate_limit_status;
+
+/// GitHub Rate Limit.
+///
+/// # Returns
+/// Returns the last rate limit reported by GitHub for every installation
+/// and resource used by the server since it started.
+///
+/// # Example
+/// ```plaintext
+/// curl http://localhost:8080/github/rate_limit
+/// ```
+///
+/// # Response
+/// ```
+/// [
+///   {
+///     "installation_id": 12345678,
+///     "resource": "core",
+///     "limit": 5000,
+///     "remaining": 4987,
+///     "used": 13,
+///     "reset": "2024-01-01T12:00:00Z",
+///     "updated_at": "2024-01-01T11:10:00Z"
+///   }
+/// ]
+/// ```
+#[get("/github/rate_limit")]
+pub async fn rate_limit() -> actix_web::Result<impl Responder> {
+    Ok(HttpResponse::Ok().json(rate_limit_status()))
+}
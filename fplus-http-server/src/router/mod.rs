@@ -1,9 +1,12 @@
 use actix_web::{get, HttpResponse, Responder};
 
 pub mod allocator;
+pub mod allowance;
 pub mod application;
 pub mod autoallocator;
 pub mod blockchain;
+pub mod github;
+pub mod outbox;
 pub mod verifier;
 
 /// Return server health status
@@ -0,0 +1,57 @@
+use actix_web::{
+    error::{ErrorInternalServerError, ErrorNotFound},
+    get, post, web, HttpResponse, Responder,
+};
+use fplus_database::database::outbox as outbox_db;
+use fplus_database::DbPool;
+use fplus_lib::core::outbox::allocator_aggregates;
+use fplus_lib::core::GithubQueryParams;
+
+/**
+ * List the side effects of the allocator's applications which ran out of attempts
+ *
+ * # Arguments
+ * @param query: web::Query<GithubQueryParams> - The allocator repository
+ *
+ * # Returns
+ * @return HttpResponse - The dead letters with their last error
+ */
+#[get("/outbox/failed")]
+pub async fn failed(
+    db: web::Data<DbPool>,
+    query: web::Query<GithubQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let GithubQueryParams { owner, repo } = query.into_inner();
+    let messages = outbox_db::get_dead_outbox_messages(&db, &allocator_aggregates(&owner, &repo))
+        .await
+        .map_err(ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(messages))
+}
+
+/**
+ * Queue a failed side effect again
+ *
+ * # Arguments
+ * @param path: web::Path<i64> - The ID of the outbox message
+ * @param query: web::Query<GithubQueryParams> - The allocator repository the message belongs to
+ *
+ * # Returns
+ * @return HttpResponse - The requeued message
+ */
+#[post("/outbox/{id}/replay")]
+pub async fn replay(
+    db: web::Data<DbPool>,
+    path: web::Path<i64>,
+    query: web::Query<GithubQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let GithubQueryParams { owner, repo } = query.into_inner();
+    let message = outbox_db::replay_outbox_message(
+        &db,
+        path.into_inner(),
+        &allocator_aggregates(&owner, &repo),
+    )
+    .await
+    .map_err(ErrorInternalServerError)?
+    .ok_or_else(|| ErrorNotFound("Failed outbox message not found"))?;
+    Ok(HttpResponse::Ok().json(message))
+}
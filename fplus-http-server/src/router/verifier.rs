@@ -1,12 +1,102 @@
 use actix_web::{error::ErrorInternalServerError, get, web, HttpResponse, Responder};
-use fplus_lib::core::{GithubQueryParams, LDNApplication};
+use chrono::Utc;
+use fplus_database::database::application_details;
+use fplus_database::DbPool;
+use fplus_lib::core::{GithubQueryParams, LDNApplication, SignedAllocationsQueryParams};
 
 #[get("/verifiers")]
-pub async fn verifiers(query: web::Query<GithubQueryParams>) -> actix_web::Result<impl Responder> {
+pub async fn verifiers(
+    db: web::Data<DbPool>,
+    query: web::Query<GithubQueryParams>,
+) -> actix_web::Result<impl Responder> {
     let GithubQueryParams { owner, repo } = query.into_inner();
 
-    let notaries = LDNApplication::fetch_verifiers(owner, repo)
+    let notaries = LDNApplication::fetch_verifiers(&db, owner, repo)
         .await
         .map_err(ErrorInternalServerError)?;
     Ok(HttpResponse::Ok().json(notaries))
 }
+
+#[get("/verifiers/{github_username}/allocations")]
+pub async fn signed_allocations(
+    db: web::Data<DbPool>,
+    path: web::Path<String>,
+    query: web::Query<SignedAllocationsQueryParams>,
+) -> actix_web::Result<impl Responder> {
+    let SignedAllocationsQueryParams { since, until } = query.into_inner();
+    let allocations = application_details::get_allocations_signed_by(
+        &db,
+        &path.into_inner(),
+        since,
+        until.unwrap_or_else(Utc::now),
+    )
+    .await
+    .map_err(ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(allocations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use fplus_database::database::applications::create_application;
+
+    #[actix_web::test]
+    async fn signed_allocations_are_listed_per_verifier() {
+        let db = fplus_database::setup_test_environment().await;
+        let application = serde_json::json!({
+            "Lifecycle": {"State": "Granted", "Active": true},
+            "Allocation Requests": [{
+                "ID": "request-1",
+                "Request Type": "First",
+                "Active": false,
+                "Allocation Amount": "1PiB",
+                "Amount of Datacap Sent to Contract": null,
+                "Signers": [{
+                    "Github Username": "alice",
+                    "Signing Address": "f1signer",
+                    "Created At": "2024-05-28 09:02:51.126 UTC",
+                    "Message CID": "bafy",
+                    "Increase allowance CID": null,
+                }],
+            }],
+        });
+        create_application(
+            &db,
+            "f1client".into(),
+            "owner".into(),
+            "repo".into(),
+            1,
+            1,
+            application.to_string(),
+            "applications/f1client.json".into(),
+            None,
+            "sha".into(),
+        )
+        .await
+        .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(db))
+                .service(signed_allocations),
+        )
+        .await;
+
+        let request = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+        let listed: serde_json::Value = test::call_and_read_body_json(
+            &app,
+            request("/verifiers/alice/allocations?since=2024-05-01T00:00:00Z"),
+        )
+        .await;
+        assert_eq!(listed.as_array().unwrap().len(), 1);
+        assert_eq!(listed[0]["allocation_id"], "request-1");
+        assert_eq!(listed[0]["amount"], "1PiB");
+
+        let outside: serde_json::Value = test::call_and_read_body_json(
+            &app,
+            request("/verifiers/alice/allocations?since=2024-05-01T00:00:00Z&until=2024-05-28T00:00:00Z"),
+        )
+        .await;
+        assert!(outside.as_array().unwrap().is_empty());
+    }
+}
@@ -43,10 +43,32 @@ pub fn default_env_vars() -> &'static HashMap<&'static str, &'static str> {
             "ALLOCATOR_CONTRACT_ADDRESS",
             "0x640bD4be149f40714D95aBcD414338bc7CfF39a3",
         );
+        m.insert("AUTOALLOCATOR_PROFILES", ""); // JSON list, empty uses ALLOCATOR_CONTRACT_ADDRESS on mainnet
         m.insert("AUTOALLOCATION_AMOUNT", "1099511627776"); // 1099511627776 B == 1 TiB
+        m.insert("AUTOALLOCATION_TIERS", ""); // e.g. "30:1TiB,50:5TiB", empty uses AUTOALLOCATION_AMOUNT
+        m.insert("AUTOALLOCATION_SYBIL_CHECKS", ""); // e.g. "address_type,wallet_pairing", empty runs none
+        m.insert("AUTOALLOCATION_ALLOWED_ADDRESS_TYPES", ""); // e.g. "f1,f3,f410", empty allows every type
+        m.insert("AUTOALLOCATION_WALLET_CAP", "");
+        m.insert("AUTOALLOCATION_CLIENT_CAP", "");
+        m.insert("AUTOALLOCATOR_CONFIRMER_INTERVAL_SECS", "15");
+        m.insert("AUTOALLOCATOR_CONFIRMER_LEASE_SECS", "60");
+        m.insert("AUTOALLOCATOR_STUCK_AFTER_SECS", "300");
+        m.insert("AUTOALLOCATOR_GAS_BUMP_PERCENT", "25");
+        m.insert("AUTOALLOCATOR_MAX_REPLACEMENTS", "5");
+        m.insert("ALLOWANCE_MONITOR_CRON", "0 0 * * * * *"); // hourly
+        m.insert("ALLOWANCE_RATE_WINDOW_DAYS", "7");
+        m.insert("ALLOWANCE_ALERT_MIN_DAYS", "7");
+        m.insert("ALLOWANCE_ALERT_MIN_GRANTS", "10");
+        m.insert("ALLOWANCE_ALERT_WEBHOOKS", ""); // comma separated URLs
         m.insert("TFIDF_THRESHOLD", "0.4");
         m.insert("LEVENSHTEIN_THRESHOLD", "8");
         m.insert("ALLOCATOR_TECH_URL", "https://allocator.tech");
+        m.insert("APPLICATION_STORE", "github");
+        m.insert("RUN_MIGRATIONS", "true");
+        m.insert("OUTBOX_POLL_INTERVAL_SECS", "5");
+        m.insert("OUTBOX_MAX_ATTEMPTS", "8");
+        m.insert("OUTBOX_RETENTION_DAYS", "7");
+        m.insert("KNOWN_REGIONS", ""); // ";"-separated, empty uses the issue template's countries
         m
     })
 }
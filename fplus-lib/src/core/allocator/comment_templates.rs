@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+
+use fplus_database::database::allocators::get_allocator;
+use fplus_database::DbPool;
+use serde::{Deserialize, Serialize};
+
+use crate::core::application::file::ApplicationFile;
+use crate::error::LDNError;
+use crate::external_services::github::GithubWrapper;
+
+/// Repository file holding the comment templates of an allocator, it sits
+/// next to `.filplusignore`.
+pub const COMMENT_TEMPLATES_FILE: &str = ".filplus-comments.json";
+
+/// Issue comments the bot posts whose wording allocators can change.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentKind {
+    DatacapRequestTrigger,
+    DatacapAllocationRequested,
+    DatacapRequestSignature,
+    Refill,
+    EarlyRefill,
+    AdditionalInfoRequired,
+    ApplicationDeclined,
+}
+
+impl CommentKind {
+    fn default_template(&self) -> &'static str {
+        match self {
+            CommentKind::DatacapRequestTrigger => {
+                "### Datacap Request Trigger
+**Total DataCap requested**
+> {{total_requested}}
+
+**Expected weekly DataCap usage rate**
+> {{weekly_allocation}}
+
+**DataCap Amount - First Tranche**
+> {{amount}}
+
+**Client address**
+> {{client_address}}"
+            }
+            CommentKind::DatacapAllocationRequested => {
+                "## DataCap Allocation requested
+
+#### Multisig Notary address
+> {{multisig_address}}
+
+#### Client address
+> {{client_address}}
+
+#### DataCap allocation requested
+> {{amount}}
+
+#### Id
+> {{allocation_id}}"
+            }
+            CommentKind::DatacapRequestSignature => {
+                "## Request {{signature_step}}
+Your Datacap Allocation Request has been {{signature_step_lowercase}} by the Notary
+#### Message sent to Filecoin Network
+> {{message_cid}} {{increase_allowance_cid}}
+#### Address
+> {{client_address}}
+#### Datacap Allocated
+> {{amount}}
+#### Signer Address
+> {{signer_address}}
+#### Id
+> {{allocation_id}}
+#### You can check the status here:
+{{#message_cid_filfox_link}}- {{message_cid_filfox_link}}{{/message_cid_filfox_link}}
+{{#increase_allowance_cid_filfox_link}}- {{increase_allowance_cid_filfox_link}}{{/increase_allowance_cid_filfox_link}}
+"
+            }
+            CommentKind::Refill => "Application is in Refill",
+            CommentKind::EarlyRefill => {
+                "## Reason for triggering a new allocation despite 75% of the previous one not being utilized\n {{reason}}"
+            }
+            CommentKind::AdditionalInfoRequired => {
+                "## Additional Information Requested
+#### A verifier has reviewed your application and has issued the following message:
+
+> {{verifier_message}}
+
+_The initial issue can be edited in order to solve the request of the verifier. The changes will be reflected in the application and an automatic comment will be posted in order to let the verifiers know the updated application can be reviewed._"
+            }
+            CommentKind::ApplicationDeclined => "### The application has been declined.",
+        }
+    }
+}
+
+/// Values a template can refer to as `{{name}}`. Empty values render as an
+/// empty string and skip `{{#name}}...{{/name}}` sections.
+#[derive(Debug, Clone, Default)]
+pub struct CommentVariables {
+    pub client_name: String,
+    pub client_address: String,
+    pub multisig_address: String,
+    pub issue_number: String,
+    pub total_requested: String,
+    pub weekly_allocation: String,
+    pub amount: String,
+    pub allocation_id: String,
+    pub message_cid: String,
+    pub increase_allowance_cid: String,
+    pub signer_address: String,
+    pub signature_step: String,
+    pub verifier_handle: String,
+    pub verifier_message: String,
+    pub reason: String,
+}
+
+impl CommentVariables {
+    pub const NAMES: [&'static str; 18] = [
+        "client_name",
+        "client_address",
+        "multisig_address",
+        "issue_number",
+        "total_requested",
+        "weekly_allocation",
+        "amount",
+        "allocation_id",
+        "message_cid",
+        "message_cid_filfox_link",
+        "increase_allowance_cid",
+        "increase_allowance_cid_filfox_link",
+        "signer_address",
+        "signature_step",
+        "signature_step_lowercase",
+        "verifier_handle",
+        "verifier_message",
+        "reason",
+    ];
+
+    fn get(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "client_name" => &self.client_name,
+            "client_address" => &self.client_address,
+            "multisig_address" => &self.multisig_address,
+            "issue_number" => &self.issue_number,
+            "total_requested" => &self.total_requested,
+            "weekly_allocation" => &self.weekly_allocation,
+            "amount" => &self.amount,
+            "allocation_id" => &self.allocation_id,
+            "message_cid" => &self.message_cid,
+            "message_cid_filfox_link" => return Some(filfox_link(&self.message_cid)),
+            "increase_allowance_cid" => &self.increase_allowance_cid,
+            "increase_allowance_cid_filfox_link" => {
+                return Some(filfox_link(&self.increase_allowance_cid))
+            }
+            "signer_address" => &self.signer_address,
+            "signature_step" => &self.signature_step,
+            "signature_step_lowercase" => return Some(self.signature_step.to_lowercase()),
+            "verifier_handle" => &self.verifier_handle,
+            "verifier_message" => &self.verifier_message,
+            "reason" => &self.reason,
+            _ => return None,
+        };
+        Some(value.clone())
+    }
+
+    /// Variables describing the client of an application.
+    pub fn from_application(application_file: &ApplicationFile) -> Self {
+        Self {
+            client_name: application_file.client.name.clone(),
+            client_address: application_file.lifecycle.client_on_chain_address.clone(),
+            multisig_address: application_file.datacap.identifier.clone(),
+            issue_number: application_file.issue_number.clone(),
+            total_requested: application_file.datacap.total_requested_amount.clone(),
+            weekly_allocation: application_file.datacap.weekly_allocation.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+fn filfox_link(cid: &str) -> String {
+    if cid.is_empty() {
+        String::new()
+    } else {
+        format!("https://filfox.info/en/message/{cid}")
+    }
+}
+
+/// Templates an allocator replaced, the others fall back to the defaults.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommentTemplates {
+    overrides: HashMap<CommentKind, String>,
+}
+
+impl CommentTemplates {
+    /// Checks that every template only uses known variables and closes its
+    /// sections.
+    pub fn new(overrides: HashMap<CommentKind, String>) -> Result<Self, LDNError> {
+        for (kind, template) in &overrides {
+            validate(template)
+                .map_err(|e| LDNError::New(format!("Invalid comment template {kind:?}: {e}")))?;
+        }
+        Ok(Self { overrides })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, LDNError> {
+        let overrides = serde_json::from_str(json)
+            .map_err(|e| LDNError::New(format!("Failed to parse comment templates: {e}")))?;
+        Self::new(overrides)
+    }
+
+    pub fn to_json(&self) -> Result<String, LDNError> {
+        serde_json::to_string(&self.overrides)
+            .map_err(|e| LDNError::New(format!("Failed to serialize comment templates: {e}")))
+    }
+
+    /// Templates of `other` take precedence.
+    fn merge(mut self, other: CommentTemplates) -> Self {
+        self.overrides.extend(other.overrides);
+        self
+    }
+
+    pub fn render(&self, kind: CommentKind, variables: &CommentVariables) -> String {
+        let template = self
+            .overrides
+            .get(&kind)
+            .map(String::as_str)
+            .unwrap_or(kind.default_template());
+        render(template, variables)
+    }
+
+    /// Templates of an allocator: the repository file overrides the ones set
+    /// in the allocator JSON. Broken overrides are logged and ignored so that
+    /// the application flows keep commenting with the defaults.
+    pub async fn load(db: &DbPool, gh: &GithubWrapper, branch: &str) -> Self {
+        let mut templates = CommentTemplates::default();
+        match get_allocator(db, &gh.owner, &gh.repo).await {
+            Ok(Some(allocator)) => {
+                if let Some(json) = allocator.comment_templates {
+                    match Self::from_json(&json) {
+                        Ok(allocator_templates) => templates = allocator_templates,
+                        Err(e) => log::warn!("{}/{}: {e}", gh.owner, gh.repo),
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!(
+                "Failed to get allocator {}/{} for comment templates: {e}",
+                gh.owner,
+                gh.repo
+            ),
+        }
+        match Self::from_repo(gh, branch).await {
+            Ok(Some(repo_templates)) => templates.merge(repo_templates),
+            Ok(None) => templates,
+            Err(e) => {
+                log::warn!("{}/{}: {e}", gh.owner, gh.repo);
+                templates
+            }
+        }
+    }
+
+    pub async fn from_repo(gh: &GithubWrapper, branch: &str) -> Result<Option<Self>, LDNError> {
+        gh.filplus_comment_templates(branch)
+            .await?
+            .map(|json| Self::from_json(&json))
+            .transpose()
+    }
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+    Open(&'a str),
+    Close(&'a str),
+}
+
+fn tokenize(template: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+        let tag = rest[start + 2..start + end].trim();
+        tokens.push(if let Some(name) = tag.strip_prefix('#') {
+            Token::Open(name.trim())
+        } else if let Some(name) = tag.strip_prefix('/') {
+            Token::Close(name.trim())
+        } else {
+            Token::Variable(tag)
+        });
+        rest = &rest[start + end + 2..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    tokens
+}
+
+fn validate(template: &str) -> Result<(), String> {
+    let known = |name: &str| {
+        if CommentVariables::NAMES.contains(&name) {
+            Ok(())
+        } else {
+            Err(format!("unknown variable `{name}`"))
+        }
+    };
+    let mut sections = Vec::new();
+    for token in tokenize(template) {
+        match token {
+            Token::Text(_) => {}
+            Token::Variable(name) => known(name)?,
+            Token::Open(name) => {
+                known(name)?;
+                sections.push(name);
+            }
+            Token::Close(name) => {
+                if sections.pop() != Some(name) {
+                    return Err(format!("unexpected end of section `{name}`"));
+                }
+            }
+        }
+    }
+    match sections.pop() {
+        Some(name) => Err(format!("section `{name}` is not closed")),
+        None => Ok(()),
+    }
+}
+
+fn render(template: &str, variables: &CommentVariables) -> String {
+    let mut output = String::new();
+    // Depth of the innermost section that is skipped, if any
+    let mut skipped: Option<usize> = None;
+    let mut depth = 0;
+    for token in tokenize(template) {
+        match token {
+            Token::Open(name) => {
+                depth += 1;
+                if skipped.is_none() && variables.get(name).unwrap_or_default().is_empty() {
+                    skipped = Some(depth);
+                }
+            }
+            Token::Close(_) => {
+                if skipped == Some(depth) {
+                    skipped = None;
+                }
+                depth = depth.saturating_sub(1);
+            }
+            _ if skipped.is_some() => {}
+            Token::Text(text) => output.push_str(text),
+            Token::Variable(name) => match variables.get(name) {
+                Some(value) => output.push_str(&value),
+                None => output.push_str(&format!("{{{{{name}}}}}")),
+            },
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::external_services::github_fake::FakeGithub;
+
+    #[test]
+    fn defaults_render_sections_only_for_present_values() {
+        let variables = CommentVariables {
+            message_cid: "bafy1".to_string(),
+            signature_step: "Proposed".to_string(),
+            ..Default::default()
+        };
+        let comment =
+            CommentTemplates::default().render(CommentKind::DatacapRequestSignature, &variables);
+
+        assert!(comment
+            .starts_with("## Request Proposed\nYour Datacap Allocation Request has been proposed"));
+        assert!(comment.contains("\n- https://filfox.info/en/message/bafy1\n\n"));
+        assert!(!comment.contains("{{"));
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        assert!(CommentTemplates::from_json(r#"{"refill": "Hi {{client}}"}"#).is_err());
+        assert!(CommentTemplates::from_json(r#"{"refill": "{{#reason}}open"}"#).is_err());
+        assert!(CommentTemplates::from_json(r#"{"unknown_comment": "Hi"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn repository_file_overrides_default_wording() {
+        let fake = FakeGithub::install();
+        fake.create_repo("owner", "comments-repo", 1);
+        fake.put_file(
+            "owner",
+            "comments-repo",
+            "main",
+            COMMENT_TEMPLATES_FILE,
+            r#"{"application_declined": "Sorry {{client_name}}, see {{verifier_handle}}."}"#,
+        );
+        let gh = GithubWrapper::new("owner".into(), "comments-repo".into(), Some(1)).unwrap();
+
+        let templates = CommentTemplates::from_repo(&gh, "main")
+            .await
+            .unwrap()
+            .unwrap();
+        let variables = CommentVariables {
+            client_name: "Dataset".to_string(),
+            verifier_handle: "@verifier".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            templates.render(CommentKind::ApplicationDeclined, &variables),
+            "Sorry Dataset, see @verifier."
+        );
+        assert_eq!(
+            templates.render(CommentKind::Refill, &variables),
+            "Application is in Refill"
+        );
+    }
+}
@@ -1,5 +1,10 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
+use super::comment_templates::CommentKind;
+use crate::core::kyc::KycProviderConfig;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AllocatorModel {
     pub application: Application,
@@ -29,6 +34,9 @@ pub struct Application {
     pub required_sps: String,
     pub required_replicas: String,
     pub client_contract_address: Option<String>,
+    pub application_store: Option<String>,
+    pub comment_templates: Option<HashMap<CommentKind, String>>,
+    pub kyc_providers: Option<Vec<KycProviderConfig>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use fplus_database::database::allocators::get_allocators;
+use fplus_database::database::applications::{
+    get_applications_with_open_pull_request, get_merged_applications,
+};
+use fplus_database::DbPool;
+use serde::Serialize;
+
+use crate::core::application::file::{AppState, ApplicationFile};
+use crate::error::LDNError;
+use crate::external_services::github::{github_async_new, GithubWrapper};
+
+/// States whose labels the application flows put on issues. An issue carries
+/// exactly one of them, matching the state of its application.
+const STATE_LABELS: [AppState; 12] = [
+    AppState::AdditionalInfoRequired,
+    AppState::AdditionalInfoSubmitted,
+    AppState::KYCRequested,
+    AppState::Submitted,
+    AppState::ChangesRequested,
+    AppState::ReadyToSign,
+    AppState::StartSignDatacap,
+    AppState::Granted,
+    AppState::TotalDatacapReached,
+    AppState::Declined,
+    AppState::ChangingSP,
+    AppState::DecreasingDataCap,
+];
+
+/// Colour and description of the label of a state.
+fn label_style(state: &AppState) -> (&'static str, &'static str) {
+    match state {
+        AppState::AdditionalInfoRequired => {
+            ("fbca04", "Allocator needs more information from the client")
+        }
+        AppState::AdditionalInfoSubmitted => {
+            ("c5def5", "Client answered the request for more information")
+        }
+        AppState::KYCRequested => ("fef2c0", "Client has to complete KYC"),
+        AppState::Submitted => (
+            "0e8a16",
+            "Application is validated and waits for allocator review",
+        ),
+        AppState::ChangesRequested => ("d93f0b", "Client requested changes to the application"),
+        AppState::ReadyToSign => ("1d76db", "Allocation is ready to be proposed"),
+        AppState::StartSignDatacap => ("5319e7", "Allocation is being signed"),
+        AppState::Granted => ("0052cc", "DataCap was granted"),
+        AppState::TotalDatacapReached => ("006b75", "Client received the total DataCap requested"),
+        AppState::Declined => ("b60205", "Application was declined"),
+        AppState::ChangingSP => ("bfd4f2", "Storage provider change is being signed"),
+        AppState::DecreasingDataCap => ("f9d0c4", "DataCap decrease is being signed"),
+        AppState::Error => ("e11d21", "Application could not be processed"),
+    }
+}
+
+/// Label names compared the way they drift in practice: case, dashes and
+/// underscores, and repeated spaces.
+fn normalize(name: &str) -> String {
+    name.to_lowercase()
+        .replace(['-', '_'], " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct LabelSyncReport {
+    pub owner: String,
+    pub repo: String,
+    pub created: Vec<String>,
+    pub renamed: Vec<String>,
+    pub updated: Vec<String>,
+    pub issues_updated: Vec<u64>,
+    pub errors: Vec<String>,
+}
+
+/// Creates the labels the application flows rely on, renames labels whose
+/// name drifted and restores their colour and description.
+pub async fn provision_labels(
+    gh: &GithubWrapper,
+    report: &mut LabelSyncReport,
+) -> Result<(), LDNError> {
+    let existing = gh
+        .list_repo_labels()
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to list labels: {e}")))?;
+
+    for state in STATE_LABELS.iter().chain([&AppState::Error]) {
+        let name = state.as_str();
+        let (color, description) = label_style(state);
+        let current = existing
+            .iter()
+            .find(|label| label.name == name)
+            .or_else(|| {
+                existing
+                    .iter()
+                    .find(|label| normalize(&label.name) == normalize(name))
+            });
+
+        let result = match current {
+            None => gh
+                .create_label(name, color, description)
+                .await
+                .map(|_| report.created.push(name.to_string())),
+            Some(label) if label.name != name => gh
+                .update_label(&label.name, name, color, description)
+                .await
+                .map(|_| report.renamed.push(format!("{} -> {name}", label.name))),
+            Some(label)
+                if !label.color.eq_ignore_ascii_case(color)
+                    || label.description.as_deref() != Some(description) =>
+            {
+                gh.update_label(name, name, color, description)
+                    .await
+                    .map(|_| report.updated.push(name.to_string()))
+            }
+            Some(_) => Ok(()),
+        };
+        if let Err(e) = result {
+            report.errors.push(format!("Label {name}: {e}"));
+        }
+    }
+    Ok(())
+}
+
+/// Replaces state labels on issues that no longer match the state of their
+/// application in the database. The error label is left alone, it is raised
+/// independently of the state.
+async fn sync_issue_labels(
+    db: &DbPool,
+    gh: &GithubWrapper,
+    report: &mut LabelSyncReport,
+) -> Result<(), LDNError> {
+    let owner = Some(gh.owner.clone());
+    let repo = Some(gh.repo.clone());
+    let merged = get_merged_applications(db, owner.clone(), repo.clone())
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get merged applications: {e}")))?;
+    let open = get_applications_with_open_pull_request(db, owner, repo)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get active applications: {e}")))?;
+
+    // Open pull requests hold the newest state of an application
+    let mut states: HashMap<i64, AppState> = HashMap::new();
+    for application in merged.into_iter().chain(open) {
+        let Some(file) = application
+            .application
+            .as_deref()
+            .and_then(|content| ApplicationFile::from_str(content).ok())
+        else {
+            continue;
+        };
+        states.insert(application.issue_number, file.lifecycle.state);
+    }
+
+    for (issue_number, state) in states {
+        let Ok(number) = u64::try_from(issue_number) else {
+            continue;
+        };
+        match sync_issue(gh, number, &state).await {
+            Ok(true) => report.issues_updated.push(number),
+            Ok(false) => {}
+            Err(e) => report.errors.push(format!("Issue {number}: {e}")),
+        }
+    }
+    report.issues_updated.sort_unstable();
+    Ok(())
+}
+
+/// Returns whether the labels of the issue had to be changed.
+async fn sync_issue(gh: &GithubWrapper, number: u64, state: &AppState) -> Result<bool, LDNError> {
+    let issue = gh
+        .list_issue(number)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get issue: {e}")))?;
+    let expected = state.as_str();
+    let stale: Vec<&str> = issue
+        .labels
+        .iter()
+        .map(|label| label.name.as_str())
+        .filter(|name| *name != expected && STATE_LABELS.iter().any(|s| s.as_str() == *name))
+        .collect();
+    let add_expected =
+        STATE_LABELS.contains(state) && !issue.labels.iter().any(|label| label.name == expected);
+    if stale.is_empty() && !add_expected {
+        return Ok(false);
+    }
+
+    for name in stale {
+        gh.remove_issue_label(number, name)
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to remove label {name}: {e}")))?;
+    }
+    if add_expected {
+        gh.add_issue_labels(number, &[expected.to_string()])
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to add label {expected}: {e}")))?;
+    }
+    Ok(true)
+}
+
+/// Provisions the labels of an allocator repository and fixes the state
+/// labels of its issues.
+pub async fn sync_allocator_labels(
+    db: &DbPool,
+    owner: &str,
+    repo: &str,
+) -> Result<LabelSyncReport, LDNError> {
+    let gh = github_async_new(db, owner.to_string(), repo.to_string()).await?;
+    let mut report = LabelSyncReport {
+        owner: owner.to_string(),
+        repo: repo.to_string(),
+        ..Default::default()
+    };
+    provision_labels(&gh, &mut report).await?;
+    sync_issue_labels(db, &gh, &mut report).await?;
+    Ok(report)
+}
+
+pub async fn sync_all_allocator_labels(db: &DbPool) -> Result<Vec<LabelSyncReport>, LDNError> {
+    let allocators = get_allocators(db)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get allocators: {e}")))?;
+    let mut reports = Vec::new();
+    for allocator in allocators {
+        if allocator.installation_id.is_none() {
+            continue;
+        }
+        match sync_allocator_labels(db, &allocator.owner, &allocator.repo).await {
+            Ok(report) => reports.push(report),
+            Err(e) => log::error!(
+                "Failed to sync labels of {}/{}: {e}",
+                allocator.owner,
+                allocator.repo
+            ),
+        }
+    }
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::external_services::github_fake::FakeGithub;
+
+    #[tokio::test]
+    async fn missing_labels_are_created_and_drifted_ones_renamed() {
+        let fake = FakeGithub::install();
+        fake.create_repo("owner", "labels-repo", 1);
+        fake.create_label("owner", "labels-repo", "Ready-To-Sign", "ffffff");
+        fake.create_label("owner", "labels-repo", "granted", "ffffff");
+        let number = fake.create_issue("owner", "labels-repo", "Application", "body");
+        fake.set_issue_labels("owner", "labels-repo", number, &["Ready-To-Sign"]);
+        let gh = GithubWrapper::new("owner".into(), "labels-repo".into(), Some(1)).unwrap();
+
+        let mut report = LabelSyncReport::default();
+        provision_labels(&gh, &mut report).await.unwrap();
+
+        assert!(report.errors.is_empty());
+        assert_eq!(report.renamed, ["Ready-To-Sign -> ready to sign"]);
+        assert_eq!(report.updated, ["granted"]);
+        assert_eq!(report.created.len(), STATE_LABELS.len() - 1);
+        let labels = fake.repo_labels("owner", "labels-repo");
+        assert_eq!(labels.len(), STATE_LABELS.len() + 1);
+        assert!(labels
+            .iter()
+            .any(|(name, color, _)| name == "granted" && color == "0052cc"));
+        assert_eq!(
+            fake.issue_labels("owner", "labels-repo", number),
+            ["ready to sign"]
+        );
+
+        assert!(sync_issue(&gh, number, &AppState::Granted).await.unwrap());
+        assert!(!sync_issue(&gh, number, &AppState::Granted).await.unwrap());
+        assert_eq!(
+            fake.issue_labels("owner", "labels-repo", number),
+            ["granted"]
+        );
+    }
+}
@@ -3,30 +3,49 @@ use fplus_database::database::allocation_amounts::{
     create_allocation_amount, delete_allocation_amounts_by_allocator_id,
 };
 use fplus_database::database::allocators::{
-    create_or_update_allocator, get_allocators, update_allocator_installation_ids,
+    create_or_update_allocator, delete_allocator as delete_allocator_in_db, get_allocators,
+    update_allocator_application_store, update_allocator_comment_templates,
+    update_allocator_installation_ids, update_allocator_kyc_providers,
 };
 use fplus_database::models::allocators::Model;
+use fplus_database::DbPool;
 use octocrab::auth::create_jwt;
 use octocrab::models::repos::{Content, ContentItems};
 
 use crate::config::get_env_var_or_default;
+use crate::core::application::store::ApplicationStoreKind;
+use crate::core::kyc::kyc_providers_to_json;
 use crate::external_services::filecoin::get_multisig_threshold_for_actor;
-use crate::external_services::github::GithubWrapper;
+use crate::external_services::github::{
+    forget_repository_installation, github_app_key, retain_installations,
+    retain_repository_installations, update_repository_installation, GithubWrapper,
+};
 use crate::{base64::decode_allocator_model, error::LDNError};
 
+use self::comment_templates::CommentTemplates;
 use self::file::{
     AccessTokenResponse, AllocatorModel, Installation, InstallationRepositories,
     RepositoriesResponse, RepositoryInfo,
 };
 
 use anyhow::Result;
-use jsonwebtoken::EncodingKey;
 use reqwest::{header, Client};
 use url::Url;
 
 use super::GithubQueryParams;
 
+pub mod comment_templates;
 pub mod file;
+pub mod labels;
+
+/// Branch of allocator repositories holding the files the backend reads.
+pub fn allocator_repo_branch() -> &'static str {
+    match get_env_var_or_default("FILPLUS_ENV").as_str() {
+        "staging" => "staging",
+        "production" => "main",
+        _ => "main",
+    }
+}
 
 pub async fn process_allocator_file(file_name: &str) -> Result<AllocatorModel, LDNError> {
     let owner = get_env_var_or_default("ALLOCATOR_GOVERNANCE_OWNER");
@@ -221,11 +240,7 @@ pub async fn create_file_in_repo(
 
 pub async fn init_allocator_repo(gh: &GithubWrapper) -> Result<(), LDNError> {
     let mut dirs = Vec::new();
-    let branch = match get_env_var_or_default("FILPLUS_ENV").as_str() {
-        "staging" => "staging",
-        "production" => "main",
-        _ => "main",
-    };
+    let branch = allocator_repo_branch();
     let allocator_template_owner = get_env_var_or_default("ALLOCATOR_TEMPLATE_OWNER");
     let allocator_template_repo = get_env_var_or_default("ALLOCATOR_TEMPLATE_REPO");
 
@@ -256,6 +271,16 @@ pub async fn init_allocator_repo(gh: &GithubWrapper) -> Result<(), LDNError> {
         }
     }
 
+    let mut report = labels::LabelSyncReport::default();
+    labels::provision_labels(gh, &mut report).await?;
+    for error in report.errors {
+        log::error!(
+            "Failed to provision labels of {}/{}: {error}",
+            gh.owner,
+            gh.repo
+        );
+    }
+
     Ok(())
 }
 
@@ -263,10 +288,7 @@ pub async fn generate_github_app_jwt() -> Result<String, LDNError> {
     let app_id = get_env_var_or_default("GITHUB_APP_ID")
         .parse::<u64>()
         .map_err(|e| LDNError::New(format!("Parse days to next allocation to i64 failed: {e}")))?;
-    let pem = get_env_var_or_default("GH_PRIVATE_KEY");
-
-    let key = EncodingKey::from_rsa_pem(pem.to_string().as_bytes())
-        .map_err(|e| LDNError::Load(format!("Failed to load RSA PEM: {e}")))?;
+    let key = github_app_key()?;
 
     let token = create_jwt(octocrab::models::AppId(app_id), &key)
         .map_err(|e| LDNError::Load(format!("Failed to create JWT: {e}")))?;
@@ -352,6 +374,7 @@ pub async fn fetch_repositories_for_installation_id(
 }
 
 pub async fn update_installation_ids_in_db(
+    db: &DbPool,
     installation: InstallationRepositories,
 ) -> Result<(), LDNError> {
     let installation_id: i64 = installation
@@ -360,6 +383,7 @@ pub async fn update_installation_ids_in_db(
         .map_err(|e| LDNError::Load(format!("Failed to pasre installation id to i64: {e}")))?;
     for repo in installation.repositories.iter() {
         update_allocator_installation_ids(
+            db,
             repo.owner.clone(),
             repo.slug.clone(),
             Some(installation_id),
@@ -373,20 +397,24 @@ pub async fn update_installation_ids_in_db(
                 e
             ))
         })?;
+        update_repository_installation(&repo.owner, &repo.slug, Some(installation_id));
     }
     Ok(())
 }
 
-pub async fn update_installation_ids_logic() -> Result<(), LDNError> {
+pub async fn update_installation_ids_logic(db: &DbPool) -> Result<(), LDNError> {
     let client = Client::new();
     let jwt = generate_github_app_jwt()
         .await
         .map_err(|e| LDNError::Load(format!("Failed to generate GitHub App JWT: {e}")))?;
 
-    let installation_ids_result = fetch_installation_ids(&client, &jwt).await;
+    let installation_ids = fetch_installation_ids(&client, &jwt)
+        .await
+        .unwrap_or_default();
+    retain_installations(&installation_ids);
     let mut results: Vec<InstallationRepositories> = Vec::new();
 
-    for id in installation_ids_result.unwrap_or_default() {
+    for id in installation_ids {
         let repositories: Vec<RepositoryInfo> =
             fetch_repositories_for_installation_id(&client, &jwt, id)
                 .await
@@ -401,18 +429,36 @@ pub async fn update_installation_ids_logic() -> Result<(), LDNError> {
         });
     }
 
+    let repositories: Vec<(String, String)> = results
+        .iter()
+        .flat_map(|installation| installation.repositories.iter())
+        .map(|repo| (repo.owner.clone(), repo.slug.clone()))
+        .collect();
+    retain_repository_installations(&repositories);
+
     for installation in results.iter() {
-        update_installation_ids_in_db(installation.clone()).await?;
+        update_installation_ids_in_db(db, installation.clone()).await?;
     }
     Ok(())
 }
 
+/// Deletes an allocator together with the cached installation of its
+/// repository.
+pub async fn delete_allocator(db: &DbPool, owner: &str, repo: &str) -> Result<(), LDNError> {
+    delete_allocator_in_db(db, owner, repo)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to delete allocator: {e}")))?;
+    forget_repository_installation(owner, repo);
+    Ok(())
+}
+
 pub async fn force_update_allocators(
+    db: &DbPool,
     files: Vec<String>,
     affected_allocators: Option<Vec<GithubQueryParams>>,
 ) -> Result<(), LDNError> {
     // first get all allocators from db and filter by affected_allocators
-    let allocators = get_allocators()
+    let allocators = get_allocators(db)
         .await
         .map_err(|e| LDNError::Load(e.to_string()))?;
 
@@ -443,11 +489,7 @@ pub async fn force_update_allocators(
         return Ok(());
     }
 
-    let branch = match get_env_var_or_default("FILPLUS_ENV").as_str() {
-        "staging" => "staging",
-        "production" => "main",
-        _ => "main",
-    };
+    let branch = allocator_repo_branch();
     let allocator_template_owner = get_env_var_or_default("ALLOCATOR_TEMPLATE_OWNER");
     let allocator_template_repo = get_env_var_or_default("ALLOCATOR_TEMPLATE_REPO");
 
@@ -522,11 +564,32 @@ pub fn is_valid_fixed_option(option: &str) -> bool {
     number_part.parse::<i32>().is_ok() && allowed_units.contains(&unit_part)
 }
 
-pub async fn create_allocator_from_file(files_changed: Vec<String>) -> Result<(), LDNError> {
+pub async fn create_allocator_from_file(
+    db: &DbPool,
+    files_changed: Vec<String>,
+) -> Result<(), LDNError> {
     for file_name in files_changed {
         log::info!("Starting allocator creation on: {}", file_name);
         let mut model = process_allocator_file(file_name.as_str()).await?;
 
+        if let Some(application_store) = &model.application.application_store {
+            application_store.parse::<ApplicationStoreKind>()?;
+        }
+
+        let comment_templates = model
+            .application
+            .comment_templates
+            .take()
+            .map(|templates| CommentTemplates::new(templates)?.to_json())
+            .transpose()?;
+
+        let kyc_providers = model
+            .application
+            .kyc_providers
+            .as_deref()
+            .map(kyc_providers_to_json)
+            .transpose()?;
+
         let mut quantity_options: Vec<String>;
         if let Some(allocation_amount) = model.application.allocation_amount.clone() {
             if allocation_amount.amount_type.clone().is_none()
@@ -610,6 +673,7 @@ pub async fn create_allocator_from_file(files_changed: Vec<String>) -> Result<()
         }
 
         let allocator_creation_result = create_or_update_allocator(
+            db,
             owner.clone(),
             repo.clone(),
             Some(installation_id),
@@ -633,9 +697,29 @@ pub async fn create_allocator_from_file(files_changed: Vec<String>) -> Result<()
         .map_err(|e| LDNError::New(format!("Create or update allocator failed: {e}")))?;
 
         let allocator_id = allocator_creation_result.id;
+        update_repository_installation(&owner, &repo, Some(installation_id));
+
+        update_allocator_application_store(
+            db,
+            owner.clone(),
+            repo.clone(),
+            model.application.application_store.clone(),
+        )
+        .await
+        .map_err(|e| LDNError::New(format!("Update allocator application store failed: {e}")))?;
+
+        update_allocator_comment_templates(db, owner.clone(), repo.clone(), comment_templates)
+            .await
+            .map_err(|e| {
+                LDNError::New(format!("Update allocator comment templates failed: {e}"))
+            })?;
+
+        update_allocator_kyc_providers(db, owner.clone(), repo.clone(), kyc_providers)
+            .await
+            .map_err(|e| LDNError::New(format!("Update allocator KYC providers failed: {e}")))?;
 
         // Delete all old allocation amounts by allocator id
-        delete_allocation_amounts_by_allocator_id(allocator_id)
+        delete_allocation_amounts_by_allocator_id(db, allocator_id)
             .await
             .map_err(|e| {
                 LDNError::New(format!(
@@ -647,7 +731,7 @@ pub async fn create_allocator_from_file(files_changed: Vec<String>) -> Result<()
             if let Some(allocation_amounts) = allocation_amount.quantity_options {
                 for allocation_amount in allocation_amounts {
                     let parsed_allocation_amount = allocation_amount.replace('%', "");
-                    create_allocation_amount(allocator_id, parsed_allocation_amount)
+                    create_allocation_amount(db, allocator_id, parsed_allocation_amount)
                         .await
                         .map_err(|e| {
                             LDNError::New(format!(
@@ -0,0 +1,395 @@
+use chrono::{DateTime, Duration, Utc};
+use fplus_database::database::allocators::get_allocators;
+use fplus_database::database::allowance_alerts::{
+    get_allowance_report, record_allowance_alert_state, save_allowance_report,
+};
+use fplus_database::database::application_details::get_allocator_allocations_since;
+use fplus_database::database::autoallocations::{
+    get_autoallocation_daily_stats, AutoallocationFilter, GRANT_STATUS_FAILED,
+};
+use fplus_database::models::allocators::Model as AllocatorModel;
+use fplus_database::DbPool;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use size::Size;
+
+use crate::config::get_env_var_or_default;
+use crate::core::autoallocator::profiles::{autoallocator_profiles, AutoallocatorProfile};
+use crate::core::autoallocator::{autoallocator_allowance, largest_tier_amount};
+use crate::error::LDNError;
+use crate::external_services::filecoin::get_allowance_for_address_direct;
+use crate::helpers::parse_size_to_bytes;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AllowanceHolderKind {
+    Autoallocator,
+    Allocator,
+}
+
+impl AllowanceHolderKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AllowanceHolderKind::Autoallocator => "autoallocator",
+            AllowanceHolderKind::Allocator => "allocator",
+        }
+    }
+}
+
+/// DataCap granted by a holder over the last `window_days`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecentUsage {
+    pub volume: u64,
+    pub grants: u64,
+    pub window_days: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlertThresholds {
+    pub min_days: f64,
+    pub min_grants: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AllowanceStatus {
+    pub kind: AllowanceHolderKind,
+    /// The network of an autoallocator, `owner/repo` of an allocator.
+    pub name: String,
+    pub address: String,
+    pub allowance: u64,
+    /// Bytes granted per day on average over the rate window.
+    pub daily_rate: u64,
+    /// `None` when the size of a grant is unknown.
+    pub grants_remaining: Option<u64>,
+    /// `None` when nothing was granted over the rate window.
+    pub days_until_empty: Option<f64>,
+    pub low: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AllowanceReport {
+    pub checked_at: DateTime<Utc>,
+    pub statuses: Vec<AllowanceStatus>,
+    /// Holders whose allowance could not be checked.
+    pub errors: Vec<String>,
+}
+
+impl AllowanceStatus {
+    /// Forecasts from the recent usage. A grant is `grant_amount` when known,
+    /// else the average recent grant.
+    pub fn new(
+        kind: AllowanceHolderKind,
+        name: String,
+        address: String,
+        allowance: u64,
+        usage: RecentUsage,
+        grant_amount: Option<u64>,
+        thresholds: AlertThresholds,
+    ) -> Self {
+        let daily_rate = usage.volume / usage.window_days.max(1);
+        let grant_amount = grant_amount
+            .or_else(|| (usage.grants > 0).then(|| usage.volume / usage.grants))
+            .filter(|amount| *amount > 0);
+        let grants_remaining = grant_amount.map(|amount| allowance / amount);
+        let days_until_empty = (daily_rate > 0).then(|| allowance as f64 / daily_rate as f64);
+        let low = allowance == 0
+            || grants_remaining.is_some_and(|grants| grants < thresholds.min_grants)
+            || days_until_empty.is_some_and(|days| days < thresholds.min_days);
+        AllowanceStatus {
+            kind,
+            name,
+            address,
+            allowance,
+            daily_rate,
+            grants_remaining,
+            days_until_empty,
+            low,
+        }
+    }
+
+    fn alert_message(&self) -> String {
+        let holder = match self.kind {
+            AllowanceHolderKind::Autoallocator => "Autoallocator",
+            AllowanceHolderKind::Allocator => "Allocator",
+        };
+        let mut message = format!(
+            "{holder} {} ({}) is running out of DataCap: {} left",
+            self.name,
+            self.address,
+            Size::from_bytes(self.allowance)
+        );
+        if let Some(grants) = self.grants_remaining {
+            message.push_str(&format!(", about {grants} grants"));
+        }
+        if let Some(days) = self.days_until_empty {
+            message.push_str(&format!(", about {days:.1} days at the current rate"));
+        }
+        message
+    }
+}
+
+fn env_number<T: std::str::FromStr>(key: &str) -> Result<T, LDNError> {
+    get_env_var_or_default(key)
+        .parse::<T>()
+        .map_err(|_| LDNError::New(format!("Failed to parse {key}")))
+}
+
+async fn autoallocator_status(
+    db: &DbPool,
+    profile: &AutoallocatorProfile,
+    since: DateTime<Utc>,
+    window_days: u64,
+    thresholds: AlertThresholds,
+) -> Result<AllowanceStatus, LDNError> {
+    let allowance = autoallocator_allowance(profile).await?;
+    let stats = get_autoallocation_daily_stats(
+        db,
+        &AutoallocationFilter {
+            since: Some(since),
+            network: Some(profile.network.to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .map_err(|e| LDNError::Load(format!("Getting autoallocation stats failed: {e}")))?;
+    let granted = stats.iter().filter(|day| day.status != GRANT_STATUS_FAILED);
+    let usage = RecentUsage {
+        volume: granted.clone().map(|day| day.volume as u64).sum(),
+        grants: granted.map(|day| day.count as u64).sum(),
+        window_days,
+    };
+    Ok(AllowanceStatus::new(
+        AllowanceHolderKind::Autoallocator,
+        profile.network.to_string(),
+        profile.contract_address.to_checksum(None),
+        allowance,
+        usage,
+        Some(largest_tier_amount(profile)?),
+        thresholds,
+    ))
+}
+
+/// The address holding an allocator's DataCap: its contract for contract
+/// allocators, else its multisig.
+fn allocator_datacap_address(allocator: &AllocatorModel) -> Option<String> {
+    let is_contract = allocator
+        .tooling
+        .as_deref()
+        .is_some_and(|tooling| tooling.contains("smart_contract_allocator"));
+    if is_contract {
+        allocator
+            .ma_address
+            .clone()
+            .or_else(|| allocator.address.clone())
+    } else {
+        allocator.multisig_address.clone()
+    }
+}
+
+async fn allocator_status(
+    db: &DbPool,
+    allocator: &AllocatorModel,
+    address: String,
+    since: DateTime<Utc>,
+    window_days: u64,
+    thresholds: AlertThresholds,
+) -> Result<AllowanceStatus, LDNError> {
+    let allowance = get_allowance_for_address_direct(&address)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to retrieve allowance: {e}")))?
+        .parse::<u64>()
+        .map_err(|e| LDNError::Load(format!("Parse allowance to u64 failed: {e}")))?;
+    let allocations = get_allocator_allocations_since(db, &allocator.owner, &allocator.repo, since)
+        .await
+        .map_err(|e| LDNError::Load(format!("Getting allocations failed: {e}")))?;
+    let amounts: Vec<u64> = allocations
+        .iter()
+        .filter_map(|allocation| parse_size_to_bytes(&allocation.amount).ok())
+        .map(|amount| amount.max(0) as u64)
+        .collect();
+    let usage = RecentUsage {
+        volume: amounts.iter().sum(),
+        grants: amounts.len() as u64,
+        window_days,
+    };
+    Ok(AllowanceStatus::new(
+        AllowanceHolderKind::Allocator,
+        format!("{}/{}", allocator.owner, allocator.repo),
+        address,
+        allowance,
+        usage,
+        None,
+        thresholds,
+    ))
+}
+
+async fn send_alert(webhooks: &[String], status: &AllowanceStatus) {
+    let message = status.alert_message();
+    log::warn!("{message}");
+    let client = reqwest::Client::new();
+    // `text` is read by Slack, `content` by Discord.
+    let body = json!({ "text": message, "content": message, "status": status });
+    for webhook in webhooks {
+        let sent = client
+            .post(webhook)
+            .json(&body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = sent {
+            log::error!("Sending allowance alert to {webhook} failed: {e}");
+        }
+    }
+}
+
+/**
+ * Check the allowance of every autoallocator profile and allocator, forecast
+ * when it runs out from the grants of the last `ALLOWANCE_RATE_WINDOW_DAYS`
+ * and alert the `ALLOWANCE_ALERT_WEBHOOKS` about holders that became low
+ *
+ * # Arguments
+ * @param db: &DbPool - The database pool
+ *
+ * # Returns
+ * @return Result<AllowanceReport, LDNError> - The report, also kept for `latest_allowance_report`
+ */
+pub async fn check_allowances(db: &DbPool) -> Result<AllowanceReport, LDNError> {
+    let window_days: u64 = env_number("ALLOWANCE_RATE_WINDOW_DAYS")?;
+    let thresholds = AlertThresholds {
+        min_days: env_number("ALLOWANCE_ALERT_MIN_DAYS")?,
+        min_grants: env_number("ALLOWANCE_ALERT_MIN_GRANTS")?,
+    };
+    let since = Utc::now() - Duration::days(window_days as i64);
+    let mut statuses = Vec::new();
+    let mut errors = Vec::new();
+
+    for profile in autoallocator_profiles()? {
+        match autoallocator_status(db, &profile, since, window_days, thresholds).await {
+            Ok(status) => statuses.push(status),
+            Err(e) => errors.push(format!("Autoallocator {}: {e}", profile.network)),
+        }
+    }
+    let allocators = get_allocators(db)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get allocators: {e}")))?;
+    for allocator in &allocators {
+        let Some(address) = allocator_datacap_address(allocator) else {
+            continue;
+        };
+        match allocator_status(db, allocator, address, since, window_days, thresholds).await {
+            Ok(status) => statuses.push(status),
+            Err(e) => errors.push(format!(
+                "Allocator {}/{}: {e}",
+                allocator.owner, allocator.repo
+            )),
+        }
+    }
+    let report = AllowanceReport {
+        checked_at: Utc::now(),
+        statuses,
+        errors,
+    };
+
+    let webhooks: Vec<String> = get_env_var_or_default("ALLOWANCE_ALERT_WEBHOOKS")
+        .split(',')
+        .map(|webhook| webhook.trim().to_string())
+        .filter(|webhook| !webhook.is_empty())
+        .collect();
+    // The alert state lives in the database, so every instance agrees on which
+    // holders were already low and an alert goes out once.
+    for status in &report.statuses {
+        let became_low =
+            record_allowance_alert_state(db, status.kind.as_str(), &status.name, status.low)
+                .await
+                .map_err(|e| LDNError::Load(format!("Recording alert state failed: {e}")))?;
+        if became_low {
+            send_alert(&webhooks, status).await;
+        }
+    }
+    let serialized = serde_json::to_string(&report)
+        .map_err(|e| LDNError::New(format!("Serialize allowance report failed: {e}")))?;
+    save_allowance_report(db, &serialized)
+        .await
+        .map_err(|e| LDNError::Load(format!("Saving allowance report failed: {e}")))?;
+    Ok(report)
+}
+
+/// The report of the last check, `None` until a check ran.
+pub async fn latest_allowance_report(db: &DbPool) -> Result<Option<AllowanceReport>, LDNError> {
+    let report = get_allowance_report(db)
+        .await
+        .map_err(|e| LDNError::Load(format!("Getting allowance report failed: {e}")))?;
+    report
+        .map(|report| {
+            serde_json::from_str(&report)
+                .map_err(|e| LDNError::Load(format!("Parse allowance report failed: {e}")))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIB: u64 = 1099511627776;
+    const THRESHOLDS: AlertThresholds = AlertThresholds {
+        min_days: 7.0,
+        min_grants: 10,
+    };
+
+    fn status(allowance: u64, usage: RecentUsage, grant_amount: Option<u64>) -> AllowanceStatus {
+        AllowanceStatus::new(
+            AllowanceHolderKind::Autoallocator,
+            "mainnet".to_string(),
+            "0x0".to_string(),
+            allowance,
+            usage,
+            grant_amount,
+            THRESHOLDS,
+        )
+    }
+
+    #[test]
+    fn allowance_is_forecast_from_recent_usage() {
+        let usage = RecentUsage {
+            volume: 14 * TIB,
+            grants: 7,
+            window_days: 7,
+        };
+        let plenty = status(100 * TIB, usage, Some(TIB));
+        assert_eq!(plenty.daily_rate, 2 * TIB);
+        assert_eq!(plenty.grants_remaining, Some(100));
+        assert_eq!(plenty.days_until_empty, Some(50.0));
+        assert!(!plenty.low);
+
+        // Average grant of 2 TiB without a fixed amount, 5 days left.
+        let low = status(10 * TIB, usage, None);
+        assert_eq!(low.grants_remaining, Some(5));
+        assert_eq!(low.days_until_empty, Some(5.0));
+        assert!(low.low);
+
+        let idle = RecentUsage {
+            volume: 0,
+            grants: 0,
+            window_days: 7,
+        };
+        let unknown = status(TIB, idle, None);
+        assert_eq!(
+            (unknown.grants_remaining, unknown.days_until_empty),
+            (None, None)
+        );
+        assert!(!unknown.low);
+        assert!(status(0, idle, None).low);
+
+        // Reports are kept as JSON between checks.
+        let report = AllowanceReport {
+            checked_at: Utc::now(),
+            statuses: vec![plenty, low],
+            errors: vec!["Allocator owner/repo: unreachable".to_string()],
+        };
+        let kept: AllowanceReport =
+            serde_json::from_str(&serde_json::to_string(&report).unwrap()).unwrap();
+        assert_eq!(kept.statuses, report.statuses);
+        assert_eq!(kept.errors, report.errors);
+    }
+}
@@ -298,6 +298,12 @@ pub struct LifeCycle {
     #[serde(rename = "Multisig Address")]
     pub multisig_address: String,
     pub edited: Option<bool>,
+    #[serde(
+        rename = "KYC Completed At",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub kyc_completed_at: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -3,12 +3,12 @@ use std::str::FromStr;
 
 use alloy::{
     network::TransactionBuilder,
-    primitives::{address, Address, Bytes, U256},
+    primitives::{address, keccak256, Address, Bytes, U256},
     providers::{Provider, ProviderBuilder},
     rpc::types::eth::{BlockId, TransactionRequest},
     signers::Signature,
     sol,
-    sol_types::{eip712_domain, SolCall, SolStruct},
+    sol_types::{eip712_domain, Eip712Domain, SolCall, SolStruct},
 };
 
 use crate::config::get_env_var_or_default;
@@ -64,13 +64,19 @@ impl ExpirableSolStruct for KycAutoallocationApproval {
 }
 
 pub async fn verify_on_gitcoin(address_from_signature: &Address) -> Result<f64, LDNError> {
-    let rpc_url = get_env_var_or_default("RPC_URL");
-    let score = get_gitcoin_score_for_address(&rpc_url, *address_from_signature).await?;
-
     let minimum_score = get_env_var_or_default("GITCOIN_MINIMUM_SCORE");
     let minimum_score = minimum_score
         .parse::<f64>()
         .map_err(|e| LDNError::New(format!("Parse minimum score to f64 failed: {e:?}")))?;
+    verify_on_gitcoin_with_minimum_score(address_from_signature, minimum_score).await
+}
+
+pub async fn verify_on_gitcoin_with_minimum_score(
+    address_from_signature: &Address,
+    minimum_score: f64,
+) -> Result<f64, LDNError> {
+    let rpc_url = get_env_var_or_default("RPC_URL");
+    let score = get_gitcoin_score_for_address(&rpc_url, *address_from_signature).await?;
 
     if score <= minimum_score {
         return Err(LDNError::New(format!(
@@ -107,17 +113,21 @@ fn calculate_score(response: Bytes) -> Result<f64, LDNError> {
     Ok(score as f64 / 10000.0)
 }
 
-pub fn get_address_from_signature<T: SolStruct>(
-    message: &T,
-    signature: &str,
-) -> Result<Address, LDNError> {
-    let domain = eip712_domain! {
+/// EIP-712 domain of the messages signed for KYC.
+pub fn kyc_domain() -> Result<Eip712Domain, LDNError> {
+    Ok(eip712_domain! {
         name: "Fil+ KYC",
         version: "1",
         chain_id: get_env_var_or_default("PASSPORT_VERIFIER_CHAIN_ID").parse().map_err(|_| LDNError::New("Parse chain Id to u64 failed".to_string()))?, // Filecoin Chain Id
         verifying_contract: address!("0000000000000000000000000000000000000000"),
-    };
-    let hash = message.eip712_signing_hash(&domain);
+    })
+}
+
+pub fn get_address_from_signature<T: SolStruct>(
+    message: &T,
+    signature: &str,
+) -> Result<Address, LDNError> {
+    let hash = message.eip712_signing_hash(&kyc_domain()?);
     let signature = Signature::from_str(signature)
         .map_err(|e| LDNError::New(format!("Signature parsing failed: {e:?}")))?;
     signature
@@ -125,6 +135,15 @@ pub fn get_address_from_signature<T: SolStruct>(
         .map_err(|e| LDNError::New(format!("Recover address from prehash failed: {e:?}")))
 }
 
+/// Identifies a signature for replay protection. The signature is normalized
+/// first, so its malleated twin has the same hash.
+pub fn signature_hash(signature: &str) -> Result<String, LDNError> {
+    let signature = Signature::from_str(signature)
+        .map_err(|e| LDNError::New(format!("Signature parsing failed: {e:?}")))?;
+    let signature = signature.normalize_s().unwrap_or(signature);
+    Ok(keccak256(signature.as_bytes()).to_string())
+}
+
 #[cfg(test)]
 #[cfg(feature = "online-tests")]
 mod tests {
@@ -35,6 +35,7 @@ impl LifeCycle {
             client_on_chain_address,
             multisig_address,
             edited: Some(false),
+            kyc_completed_at: None,
         }
     }
 
@@ -162,6 +163,18 @@ impl LifeCycle {
             ..self
         }
     }
+    /// Back to Submitted once the client completed the requested KYC.
+    pub fn kyc_completed(self) -> Self {
+        let now = Utc::now().to_string();
+        LifeCycle {
+            state: AppState::Submitted,
+            is_active: true,
+            updated_at: now.clone(),
+            kyc_completed_at: Some(now),
+            ..self
+        }
+    }
+
     pub fn move_back_to_submit_state(self) -> Self {
         LifeCycle {
             state: AppState::Submitted,
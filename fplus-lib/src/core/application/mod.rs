@@ -8,6 +8,9 @@ pub mod file;
 pub mod gitcoin_interaction;
 pub mod lifecycle;
 pub mod sps_change;
+pub mod status_comment;
+pub mod store;
+pub mod versions;
 
 impl file::ApplicationFile {
     #[allow(clippy::too_many_arguments)]
@@ -264,6 +267,13 @@ impl file::ApplicationFile {
         }
     }
 
+    pub fn kyc_completed(&self) -> Self {
+        Self {
+            lifecycle: self.lifecycle.clone().kyc_completed(),
+            ..self.clone()
+        }
+    }
+
     pub fn kyc_request(&self) -> Self {
         let new_life_cycle = self.lifecycle.clone().kyc_request();
         Self {
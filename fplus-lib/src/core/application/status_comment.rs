@@ -0,0 +1,352 @@
+use std::str::FromStr;
+
+use fplus_database::database::applications;
+use fplus_database::DbPool;
+
+use super::file::{AppState, ApplicationFile, LifeCycle, SpsChangeRequest};
+use crate::config::get_env_var_or_default;
+use crate::error::LDNError;
+use crate::external_services::github::{github_async_new, GithubWrapper};
+use crate::helpers::format_size_human_readable;
+
+/// Hidden marker identifying the status comment among the issue comments.
+pub const STATUS_MARKER: &str = "<!-- filplus-status -->";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KycStatus {
+    NotRequested,
+    Requested,
+    Completed,
+}
+
+impl KycStatus {
+    fn from_lifecycle(lifecycle: &LifeCycle) -> Self {
+        if lifecycle.state == AppState::KYCRequested {
+            KycStatus::Requested
+        } else if lifecycle.kyc_completed_at.is_some() {
+            KycStatus::Completed
+        } else {
+            KycStatus::NotRequested
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            KycStatus::NotRequested => "Not requested",
+            KycStatus::Requested => "Requested, waiting for the client",
+            KycStatus::Completed => "Completed",
+        }
+    }
+}
+
+/// Who has to act next on the application and what they have to do.
+fn next_action(application_file: &ApplicationFile) -> Option<(&'static str, String)> {
+    let action = match application_file.lifecycle.state {
+        AppState::AdditionalInfoRequired => (
+            "Client",
+            "edit the issue with the information the allocator asked for".to_string(),
+        ),
+        AppState::AdditionalInfoSubmitted => {
+            ("Allocator", "review the updated application".to_string())
+        }
+        AppState::KYCRequested => ("Client", "complete KYC".to_string()),
+        AppState::Submitted => (
+            "Allocator",
+            "review the application and trigger the allocation".to_string(),
+        ),
+        AppState::ChangesRequested => (
+            "Allocator",
+            "approve or decline the changes to the application".to_string(),
+        ),
+        AppState::ReadyToSign => ("Allocator", "propose the allocation".to_string()),
+        AppState::StartSignDatacap => {
+            let signatures = application_file
+                .get_active_allocation()
+                .map_or(0, |allocation| allocation.signers.0.len());
+            (
+                "Allocator",
+                format!("approve the proposed allocation, {signatures} signature(s) collected"),
+            )
+        }
+        AppState::Granted => (
+            "Client",
+            "use the DataCap granted, a refill is requested once it is used".to_string(),
+        ),
+        AppState::ChangingSP => (
+            "Allocator",
+            "approve the storage provider change".to_string(),
+        ),
+        AppState::DecreasingDataCap => ("Allocator", "approve the DataCap decrease".to_string()),
+        AppState::Error => (
+            "Allocator",
+            "check the error reported on the issue".to_string(),
+        ),
+        AppState::TotalDatacapReached | AppState::Declined => return None,
+    };
+    Some(action)
+}
+
+fn cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "Yes"
+    } else {
+        "No"
+    }
+}
+
+fn signers<'a>(handles: impl Iterator<Item = &'a String>) -> String {
+    let handles: Vec<String> = handles.map(|handle| format!("@{handle}")).collect();
+    if handles.is_empty() {
+        "-".to_string()
+    } else {
+        handles.join(", ")
+    }
+}
+
+fn sps_change_row(request: &SpsChangeRequest) -> String {
+    let allowed_sps = request.allowed_sps.as_ref().map_or("-".to_string(), |sps| {
+        sps.iter()
+            .map(|sp| format!("f0{sp}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    });
+    format!(
+        "| {} | {} | {} | {} | {} | {} |\n",
+        cell(&request.id),
+        cell(&request.created_at),
+        yes_no(request.is_active),
+        cell(&allowed_sps),
+        cell(request.max_deviation.as_deref().unwrap_or("-")),
+        signers(
+            request
+                .signers
+                .0
+                .iter()
+                .map(|signer| &signer.github_username)
+        ),
+    )
+}
+
+/// Body of the status comment of an application.
+pub fn render_status(application_file: &ApplicationFile) -> String {
+    let lifecycle = &application_file.lifecycle;
+    let kyc = KycStatus::from_lifecycle(lifecycle);
+    let mut body = format!(
+        "{STATUS_MARKER}\n## Application status\n\n**State:** {} (`{:?}`)\n",
+        lifecycle.state.as_str(),
+        lifecycle.state
+    );
+    match next_action(application_file) {
+        Some((actor, action)) => body.push_str(&format!("**Next action:** {actor} - {action}\n")),
+        None => body.push_str("**Next action:** None\n"),
+    }
+    body.push_str(&format!("**KYC:** {}\n", kyc.description()));
+
+    body.push_str("\n### Allocations\n");
+    if application_file.allocation.0.is_empty() {
+        body.push_str("No allocations yet.\n");
+    } else {
+        body.push_str("| ID | Type | Amount | Created | Active | Signers |\n");
+        body.push_str("|---|---|---|---|---|---|\n");
+        for allocation in &application_file.allocation.0 {
+            let amount = format_size_human_readable(&allocation.amount)
+                .unwrap_or_else(|_| allocation.amount.clone());
+            body.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                cell(&allocation.id),
+                cell(&allocation.request_type),
+                cell(&amount),
+                cell(&allocation.created_at),
+                yes_no(allocation.is_active),
+                signers(
+                    allocation
+                        .signers
+                        .0
+                        .iter()
+                        .map(|signer| &signer.github_username)
+                ),
+            ));
+        }
+    }
+
+    if let Some(requests) = application_file
+        .allowed_sps
+        .as_ref()
+        .filter(|requests| !requests.0.is_empty())
+    {
+        body.push_str("\n### Storage provider changes\n");
+        body.push_str("| ID | Created | Active | Allowed SPs | Max deviation | Signers |\n");
+        body.push_str("|---|---|---|---|---|---|\n");
+        for request in &requests.0 {
+            body.push_str(&sps_change_row(request));
+        }
+    }
+
+    body.push_str(&format!(
+        "\n_This comment is updated on every change of the application. Last change: {}_\n",
+        lifecycle.updated_at
+    ));
+    body
+}
+
+/// Edits the status comment of the issue, or posts it when the issue has
+/// none yet. Only comments of the bot count, anyone could post the marker.
+pub async fn upsert_status_comment(
+    gh: &GithubWrapper,
+    application_file: &ApplicationFile,
+) -> Result<(), LDNError> {
+    let issue_number = application_file
+        .issue_number
+        .parse::<u64>()
+        .map_err(|e| LDNError::Load(format!("Failed to parse issue number: {e}")))?;
+    let comments = gh
+        .list_issue_comments(issue_number)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to list issue comments: {e}")))?;
+    let body = render_status(application_file);
+
+    let bot_user = get_env_var_or_default("BOT_USER");
+    let existing = comments.iter().find(|comment| {
+        comment.user.login == bot_user
+            && comment
+                .body
+                .as_deref()
+                .is_some_and(|body| body.starts_with(STATUS_MARKER))
+    });
+    match existing {
+        Some(comment) if comment.body.as_deref() == Some(body.as_str()) => Ok(()),
+        Some(comment) => gh
+            .update_issue_comment(comment.id.0, &body)
+            .await
+            .map(|_| ())
+            .map_err(|e| LDNError::Load(format!("Failed to update status comment: {e}"))),
+        None => gh
+            .add_comment_to_issue(issue_number, &body)
+            .await
+            .map(|_| ())
+            .map_err(|e| LDNError::Load(format!("Failed to add status comment: {e}"))),
+    }
+}
+
+/// Renders the status comment from the latest row of the application. Run
+/// by the outbox worker after the write that queued it was committed.
+pub async fn refresh_status_comment(
+    db: &DbPool,
+    owner: &str,
+    repo: &str,
+    application_id: &str,
+) -> Result<(), LDNError> {
+    let application = applications::get_application(
+        db,
+        application_id.to_string(),
+        owner.to_string(),
+        repo.to_string(),
+        None,
+    )
+    .await
+    .map_err(|e| LDNError::Load(format!("Failed to get application {application_id}: {e}")))?;
+    let content = application.application.ok_or(LDNError::Load(format!(
+        "Application {application_id} does not have an application field"
+    )))?;
+    let application_file = ApplicationFile::from_str(&content)
+        .map_err(|e| LDNError::Load(format!("Failed to parse application: {e}")))?;
+    let gh = github_async_new(db, owner.to_string(), repo.to_string()).await?;
+    upsert_status_comment(&gh, &application_file).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::application::file::{Allocation, Allocations, Verifier, Verifiers, Version};
+    use crate::external_services::github_fake::FakeGithub;
+
+    async fn application_file(issue_number: u64) -> ApplicationFile {
+        ApplicationFile::new(
+            issue_number.to_string(),
+            "f2multisig".into(),
+            Version::Text("1.3".to_string()),
+            "f1client".into(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn status_comment_is_edited_in_place() {
+        let fake = FakeGithub::install();
+        fake.create_repo("owner", "status-repo", 1);
+        let number = fake.create_issue("owner", "status-repo", "Application", "body");
+        let gh = GithubWrapper::new("owner".into(), "status-repo".into(), Some(1)).unwrap();
+        gh.add_comment_to_issue(number, "Application is in the process of signing datacap")
+            .await
+            .unwrap();
+        // Not the bot's, so it is neither edited nor mistaken for the status.
+        fake.add_user_comment(
+            "owner",
+            "status-repo",
+            number,
+            "client",
+            &format!("{STATUS_MARKER}\n**State:** granted"),
+        );
+
+        let mut file = application_file(number).await;
+        upsert_status_comment(&gh, &file).await.unwrap();
+
+        file.lifecycle.state = AppState::StartSignDatacap;
+        file.lifecycle.active_request = Some("request".into());
+        file.allocation = Allocations(vec![Allocation {
+            id: "request".into(),
+            request_type: "First".into(),
+            created_at: "2024-01-01".into(),
+            updated_at: "2024-01-01".into(),
+            is_active: true,
+            amount: "1PiB".into(),
+            amount_of_datacap_sent_to_contract: None,
+            signers: Verifiers(vec![Verifier {
+                github_username: "signer".into(),
+                signing_address: "f1signer".into(),
+                created_at: "2024-01-01".into(),
+                message_cid: None,
+                increase_allowance_cid: None,
+            }]),
+        }]);
+        upsert_status_comment(&gh, &file).await.unwrap();
+
+        let comments = fake.issue_comments("owner", "status-repo", number);
+        assert_eq!(comments.len(), 3);
+        assert_eq!(comments[1], format!("{STATUS_MARKER}\n**State:** granted"));
+        let status = &comments[2];
+        assert!(status.starts_with(STATUS_MARKER));
+        assert!(status.contains("**State:** start sign datacap"));
+        assert!(status.contains("1 signature(s) collected"));
+        assert!(status.contains("| request | First | 1.00 PiB | 2024-01-01 | Yes | @signer |"));
+        assert!(status.contains("**KYC:** Not requested"));
+    }
+
+    #[tokio::test]
+    async fn kyc_is_completed_once_the_client_submitted_it() {
+        let file = application_file(1).await;
+        assert_eq!(
+            KycStatus::from_lifecycle(&file.lifecycle),
+            KycStatus::NotRequested
+        );
+        let requested = file.kyc_request();
+        assert_eq!(
+            KycStatus::from_lifecycle(&requested.lifecycle),
+            KycStatus::Requested
+        );
+        let completed = requested.kyc_completed();
+        assert_eq!(completed.lifecycle.state, AppState::Submitted);
+        assert_eq!(
+            KycStatus::from_lifecycle(&completed.lifecycle),
+            KycStatus::Completed
+        );
+        assert!(render_status(&completed).contains("**KYC:** Completed"));
+    }
+}
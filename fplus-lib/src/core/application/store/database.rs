@@ -0,0 +1,245 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use fplus_database::database::application_versions::WriteContext;
+use fplus_database::database::applications as applications_db;
+use fplus_database::models::applications::Model as ApplicationModel;
+use fplus_database::DbPool;
+
+use super::{
+    content_sha, ApplicationChange, ApplicationCommit, ApplicationStore, ApplicationStoreKind,
+    NewApplicationFile, StoredApplicationFile,
+};
+use crate::core::application::file::ApplicationFile;
+use crate::core::{ApplicationFileWithDate, LDNPullRequest, NewPrNumberAndFileSha};
+use crate::error::LDNError;
+
+/// Application files kept only in the `applications` table, for allocators
+/// which do not publish their applications in a repository.
+///
+/// Commits write the row only if it is still at the version they were based
+/// on; otherwise this backend only hands out change numbers and content
+/// shas, and merging and closing a change are the database updates the
+/// callers perform anyway.
+#[derive(Debug)]
+pub struct DatabaseApplicationStore {
+    db: DbPool,
+    owner: String,
+    repo: String,
+}
+
+impl DatabaseApplicationStore {
+    pub fn new(db: &DbPool, owner: String, repo: String) -> Self {
+        Self {
+            db: db.clone(),
+            owner,
+            repo,
+        }
+    }
+
+    async fn next_pr_number(&self) -> Result<u64, LDNError> {
+        applications_db::get_next_pr_number(&self.db, self.owner.clone(), self.repo.clone())
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to get next pull request number: {e}")))
+    }
+
+    fn application_id_from_path(path: &str) -> Result<String, LDNError> {
+        Path::new(path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(|stem| stem.to_string())
+            .ok_or(LDNError::Load(format!("Invalid application path: {path}")))
+    }
+
+    fn to_file_with_date(model: ApplicationModel) -> Option<ApplicationFileWithDate> {
+        let application_file = ApplicationFile::from_str(model.application.as_ref()?).ok()?;
+        Some(ApplicationFileWithDate {
+            branch_name: if model.pr_number == 0 {
+                "main".to_string()
+            } else {
+                LDNPullRequest::application_branch_name(&model.id)
+            },
+            application_file,
+            updated_at: model.updated_at,
+            pr_number: model.pr_number as u64,
+            sha: model.sha.unwrap_or_default(),
+            path: model.path.unwrap_or_default(),
+        })
+    }
+}
+
+#[async_trait]
+impl ApplicationStore for DatabaseApplicationStore {
+    fn kind(&self) -> ApplicationStoreKind {
+        ApplicationStoreKind::Database
+    }
+
+    fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    fn repo(&self) -> &str {
+        &self.repo
+    }
+
+    async fn create_file(
+        &self,
+        file: NewApplicationFile,
+    ) -> Result<NewPrNumberAndFileSha, LDNError> {
+        Ok(NewPrNumberAndFileSha {
+            pr_number: self.next_pr_number().await?,
+            file_sha: content_sha(&file.content),
+        })
+    }
+
+    async fn propose_change(
+        &self,
+        change: ApplicationChange,
+    ) -> Result<NewPrNumberAndFileSha, LDNError> {
+        Ok(NewPrNumberAndFileSha {
+            pr_number: self.next_pr_number().await?,
+            file_sha: content_sha(&change.content),
+        })
+    }
+
+    async fn commit_change(&self, commit: ApplicationCommit) -> Result<String, LDNError> {
+        let application_id = Self::application_id_from_path(&commit.path)?;
+        let pr_number = (commit.branch_name == "main").then_some(0);
+        let application = applications_db::get_application(
+            &self.db,
+            application_id,
+            self.owner.clone(),
+            self.repo.clone(),
+            pr_number,
+        )
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get application: {e}")))?;
+        let conflict = || {
+            LDNError::Conflict(format!(
+                "File {} does not match sha {}",
+                commit.path, commit.file_sha
+            ))
+        };
+        if application
+            .sha
+            .as_deref()
+            .is_some_and(|sha| !sha.is_empty() && sha != commit.file_sha)
+        {
+            return Err(conflict());
+        }
+        let sha = content_sha(&commit.content);
+        let written = applications_db::update_application_file_if_unchanged(
+            &self.db,
+            &application,
+            commit.content.clone(),
+            sha.clone(),
+            &WriteContext::new(commit.message.clone()),
+        )
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to commit application: {e}")))?;
+        if !written {
+            return Err(conflict());
+        }
+        Ok(sha)
+    }
+
+    async fn merge_change(&self, _pr_number: u64) -> Result<(), LDNError> {
+        Ok(())
+    }
+
+    async fn close_change(&self, _pr_number: u64) -> Result<(), LDNError> {
+        Ok(())
+    }
+
+    async fn find_change(
+        &self,
+        application_id: &str,
+        _branch_name: &str,
+    ) -> Result<Option<u64>, LDNError> {
+        let application = applications_db::find_application(
+            &self.db,
+            application_id.to_string(),
+            self.owner.clone(),
+            self.repo.clone(),
+            None,
+        )
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get application: {e}")))?;
+        Ok(application
+            .filter(|application| application.pr_number != 0)
+            .map(|application| application.pr_number as u64))
+    }
+
+    async fn change_branch(&self, pr_number: u64) -> Result<String, LDNError> {
+        let application = applications_db::get_application_by_pr_number(
+            &self.db,
+            self.owner.clone(),
+            self.repo.clone(),
+            pr_number,
+        )
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get application: {e}")))?;
+        Ok(LDNPullRequest::application_branch_name(&application.id))
+    }
+
+    async fn list_active(&self) -> Result<Vec<ApplicationFileWithDate>, LDNError> {
+        let applications = applications_db::get_applications_with_open_pull_request(
+            &self.db,
+            Some(self.owner.clone()),
+            Some(self.repo.clone()),
+        )
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get active applications: {e}")))?;
+        Ok(applications
+            .into_iter()
+            .filter_map(Self::to_file_with_date)
+            .collect())
+    }
+
+    async fn list_merged(&self) -> Result<Vec<ApplicationFileWithDate>, LDNError> {
+        let applications = applications_db::get_merged_applications(
+            &self.db,
+            Some(self.owner.clone()),
+            Some(self.repo.clone()),
+        )
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get merged applications: {e}")))?;
+        Ok(applications
+            .into_iter()
+            .filter_map(Self::to_file_with_date)
+            .collect())
+    }
+
+    async fn read_file(
+        &self,
+        path: &str,
+        branch_name: &str,
+    ) -> Result<Option<StoredApplicationFile>, LDNError> {
+        let application_id = Self::application_id_from_path(path)?;
+        let pr_number = (branch_name == "main").then_some(0);
+        let Some(application) = applications_db::find_application(
+            &self.db,
+            application_id,
+            self.owner.clone(),
+            self.repo.clone(),
+            pr_number,
+        )
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get application: {e}")))?
+        else {
+            return Ok(None);
+        };
+        if branch_name != "main" && application.pr_number == 0 {
+            return Ok(None);
+        }
+        let Some(file) = Self::to_file_with_date(application) else {
+            return Ok(None);
+        };
+        Ok(Some(StoredApplicationFile {
+            application_file: file.application_file,
+            path: path.to_string(),
+            sha: file.sha,
+        }))
+    }
+}
@@ -0,0 +1,255 @@
+use async_trait::async_trait;
+use fplus_database::DbPool;
+use futures::future;
+use octocrab::models::pulls::PullRequest;
+
+use super::{
+    ApplicationChange, ApplicationCommit, ApplicationStore, ApplicationStoreKind,
+    NewApplicationFile, StoredApplicationFile,
+};
+use crate::core::{ApplicationFileWithDate, LDNApplication, NewPrNumberAndFileSha};
+use crate::error::LDNError;
+use crate::external_services::github::{
+    is_conflict, CreateMergeRequestData, CreateRefillMergeRequestData, GithubWrapper,
+};
+
+/// Application files kept in the allocator's GitHub repository, with every
+/// change opened as a pull request against main.
+#[derive(Debug)]
+pub struct GithubApplicationStore {
+    db: DbPool,
+    gh: GithubWrapper,
+}
+
+impl GithubApplicationStore {
+    pub fn new(db: &DbPool, gh: GithubWrapper) -> Self {
+        Self { db: db.clone(), gh }
+    }
+
+    fn issue_link(&self, issue_number: &str) -> String {
+        format!(
+            "https://github.com/{}/{}/issues/{}",
+            self.gh.owner, self.gh.repo, issue_number
+        )
+    }
+}
+
+#[async_trait]
+impl ApplicationStore for GithubApplicationStore {
+    fn kind(&self) -> ApplicationStoreKind {
+        ApplicationStoreKind::Github
+    }
+
+    fn owner(&self) -> &str {
+        &self.gh.owner
+    }
+
+    fn repo(&self) -> &str {
+        &self.gh.repo
+    }
+
+    async fn create_file(
+        &self,
+        file: NewApplicationFile,
+    ) -> Result<NewPrNumberAndFileSha, LDNError> {
+        let (pr, file_sha) = self
+            .gh
+            .create_merge_request(CreateMergeRequestData {
+                issue_link: self.issue_link(&file.issue_number),
+                branch_name: file.branch_name,
+                file_name: file.path,
+                owner_name: file.owner_name,
+                file_content: file.content,
+                commit: file.commit_message,
+                application_id: file.application_id,
+            })
+            .await
+            .map_err(|e| {
+                LDNError::New(format!(
+                    "Application issue {} cannot create merge request /// {}",
+                    file.issue_number, e
+                ))
+            })?;
+
+        Ok(NewPrNumberAndFileSha {
+            pr_number: pr.number,
+            file_sha,
+        })
+    }
+
+    async fn propose_change(
+        &self,
+        change: ApplicationChange,
+    ) -> Result<NewPrNumberAndFileSha, LDNError> {
+        let (pr, file_sha) = self
+            .gh
+            .create_refill_merge_request(CreateRefillMergeRequestData {
+                issue_link: self.issue_link(&change.issue_number),
+                file_name: change.path,
+                file_sha: change.file_sha,
+                branch_name: change.branch_name,
+                file_content: change.content,
+                commit: change.title,
+                application_id: change.application_id,
+            })
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to create a pull request: {e}")))?;
+
+        Ok(NewPrNumberAndFileSha {
+            pr_number: pr.number,
+            file_sha,
+        })
+    }
+
+    async fn commit_change(&self, commit: ApplicationCommit) -> Result<String, LDNError> {
+        let file_update = self
+            .gh
+            .update_file_content(
+                &commit.path,
+                &commit.message,
+                &commit.content,
+                &commit.branch_name,
+                &commit.file_sha,
+            )
+            .await
+            .map_err(|e| {
+                if is_conflict(&e) {
+                    LDNError::Conflict(format!("Failed to add commit: {e}"))
+                } else {
+                    LDNError::Load(format!("Failed to add commit: {e}"))
+                }
+            })?;
+        Ok(file_update.content.sha)
+    }
+
+    async fn merge_change(&self, pr_number: u64) -> Result<(), LDNError> {
+        self.gh
+            .merge_pull_request_and_delete_branch(&pr_number)
+            .await
+            .map_err(|e| {
+                LDNError::Load(format!(
+                    "Failed to merge pull request {pr_number} or delete branch. Reason: {e}"
+                ))
+            })
+    }
+
+    async fn close_change(&self, pr_number: u64) -> Result<(), LDNError> {
+        self.gh.delete_branch_safe(&pr_number).await.map_err(|e| {
+            LDNError::New(format!(
+                "Failed to delete branch for PR number: {pr_number} Reason: {e:?}"
+            ))
+        })
+    }
+
+    async fn find_change(
+        &self,
+        _application_id: &str,
+        branch_name: &str,
+    ) -> Result<Option<u64>, LDNError> {
+        let prs = self
+            .gh
+            .get_pull_request_by_head(branch_name)
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to get pull request by head: {e}")))?;
+        Ok(prs.first().map(|pr| pr.number))
+    }
+
+    async fn change_branch(&self, pr_number: u64) -> Result<String, LDNError> {
+        self.gh
+            .get_branch_name_from_pr(pr_number)
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to get branch name from PR: {e}")))
+    }
+
+    async fn list_active(&self) -> Result<Vec<ApplicationFileWithDate>, LDNError> {
+        let pull_requests = self
+            .gh
+            .list_pull_requests()
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to get list of pull requests: {e}")))?;
+        let pull_requests = future::try_join_all(
+            pull_requests
+                .into_iter()
+                .map(|pr: PullRequest| {
+                    LDNApplication::load_pr_files(
+                        &self.db,
+                        pr,
+                        self.gh.owner.clone(),
+                        self.gh.repo.clone(),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        )
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get list of PR files: {e}")))?;
+
+        let mut apps = Vec::new();
+        for (sha, path, app_file, pr_info) in pull_requests.into_iter().flatten() {
+            if let Some(updated_at) = pr_info.updated_at {
+                apps.push(ApplicationFileWithDate {
+                    application_file: app_file,
+                    updated_at,
+                    pr_number: pr_info.number,
+                    sha,
+                    path,
+                    branch_name: pr_info.head.ref_field.clone(),
+                });
+            }
+        }
+        Ok(apps)
+    }
+
+    async fn list_merged(&self) -> Result<Vec<ApplicationFileWithDate>, LDNError> {
+        let mut all_files_result = self.gh.get_files("applications").await.map_err(|e| {
+            LDNError::Load(format!(
+                "Failed to retrieve all files from GitHub. Reason: {e}"
+            ))
+        })?;
+
+        all_files_result
+            .items
+            .retain(|item| item.download_url.is_some() && item.name.ends_with(".json"));
+
+        let mut apps = Vec::new();
+        for fd in all_files_result.items {
+            let result = self.gh.get_last_modification_date(&fd.path).await;
+
+            if let Ok(updated_at) = result {
+                if let Ok(Some((content, app_file))) = LDNApplication::map_merged(fd).await {
+                    apps.push(ApplicationFileWithDate {
+                        application_file: app_file,
+                        updated_at,
+                        pr_number: 0,
+                        sha: content.sha,
+                        path: content.path,
+                        branch_name: "main".to_string(),
+                    });
+                }
+            } else {
+                log::warn!("Failed to get last modification date for file: {}", fd.path);
+            }
+        }
+        Ok(apps)
+    }
+
+    async fn read_file(
+        &self,
+        path: &str,
+        branch_name: &str,
+    ) -> Result<Option<StoredApplicationFile>, LDNError> {
+        let file = match self.gh.get_file(path, branch_name).await {
+            Ok(file) => file,
+            Err(_) => return Ok(None),
+        };
+        let sha = match file.items.first() {
+            Some(item) => item.sha.clone(),
+            None => return Ok(None),
+        };
+        let application_file = LDNApplication::content_items_to_app_file(file)?;
+        Ok(Some(StoredApplicationFile {
+            application_file,
+            path: path.to_string(),
+            sha,
+        }))
+    }
+}
@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use super::{
+    content_sha, ApplicationChange, ApplicationCommit, ApplicationStore, ApplicationStoreKind,
+    NewApplicationFile, StoredApplicationFile,
+};
+use crate::core::application::file::ApplicationFile;
+use crate::core::{ApplicationFileWithDate, NewPrNumberAndFileSha};
+use crate::error::LDNError;
+
+const MAIN_BRANCH: &str = "main";
+
+#[derive(Debug, Clone)]
+struct StoredContent {
+    content: String,
+    sha: String,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct Change {
+    branch_name: String,
+    path: String,
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    // branch name -> path -> content
+    branches: HashMap<String, HashMap<String, StoredContent>>,
+    // open changes by number
+    changes: HashMap<u64, Change>,
+    last_pr_number: u64,
+    // commits which land just before the next `commit_change`
+    interleaved: Vec<ApplicationCommit>,
+}
+
+/// Application files kept in process memory. Mirrors the GitHub flow closely
+/// enough (branches, sha checks, merges) to exercise application logic in tests.
+/// Tests pick the kind it reports, so callers treat it like that backend.
+#[derive(Debug)]
+pub struct InMemoryApplicationStore {
+    owner: String,
+    repo: String,
+    kind: ApplicationStoreKind,
+    state: Mutex<MemoryState>,
+}
+
+impl InMemoryApplicationStore {
+    pub fn new(owner: String, repo: String, kind: ApplicationStoreKind) -> Self {
+        Self {
+            owner,
+            repo,
+            kind,
+            state: Mutex::new(MemoryState::default()),
+        }
+    }
+
+    fn state(&self) -> Result<std::sync::MutexGuard<'_, MemoryState>, LDNError> {
+        self.state
+            .lock()
+            .map_err(|e| LDNError::Load(format!("In-memory store is poisoned: {e}")))
+    }
+
+    fn open_change(
+        state: &mut MemoryState,
+        branch_name: String,
+        path: String,
+        content: String,
+    ) -> Result<NewPrNumberAndFileSha, LDNError> {
+        if state.branches.contains_key(&branch_name) {
+            return Err(LDNError::Load(format!(
+                "Branch {branch_name} already exists."
+            )));
+        }
+        let mut files = state.branches.get(MAIN_BRANCH).cloned().unwrap_or_default();
+        let file_sha = content_sha(&content);
+        files.insert(
+            path.clone(),
+            StoredContent {
+                content,
+                sha: file_sha.clone(),
+                updated_at: Utc::now(),
+            },
+        );
+        state.branches.insert(branch_name.clone(), files);
+        state.last_pr_number += 1;
+        let pr_number = state.last_pr_number;
+        state
+            .changes
+            .insert(pr_number, Change { branch_name, path });
+        Ok(NewPrNumberAndFileSha {
+            pr_number,
+            file_sha,
+        })
+    }
+
+    /// Lands `commit` right before the next `commit_change`, as if someone
+    /// else committed between a caller's read and its commit.
+    pub fn commit_before_next(&self, commit: ApplicationCommit) -> Result<(), LDNError> {
+        self.state()?.interleaved.push(commit);
+        Ok(())
+    }
+
+    fn apply_commit(
+        state: &mut MemoryState,
+        commit: ApplicationCommit,
+    ) -> Result<String, LDNError> {
+        let file = state
+            .branches
+            .get_mut(&commit.branch_name)
+            .and_then(|files| files.get_mut(&commit.path))
+            .ok_or(LDNError::Load(format!(
+                "File {} not found on branch {}",
+                commit.path, commit.branch_name
+            )))?;
+        if file.sha != commit.file_sha {
+            return Err(LDNError::Conflict(format!(
+                "File {} does not match sha {}",
+                commit.path, commit.file_sha
+            )));
+        }
+        *file = StoredContent {
+            sha: content_sha(&commit.content),
+            content: commit.content,
+            updated_at: Utc::now(),
+        };
+        Ok(file.sha.clone())
+    }
+
+    fn parse(content: &StoredContent) -> Option<ApplicationFile> {
+        ApplicationFile::from_str(&content.content).ok()
+    }
+}
+
+#[async_trait]
+impl ApplicationStore for InMemoryApplicationStore {
+    fn kind(&self) -> ApplicationStoreKind {
+        self.kind
+    }
+
+    fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    fn repo(&self) -> &str {
+        &self.repo
+    }
+
+    async fn create_file(
+        &self,
+        file: NewApplicationFile,
+    ) -> Result<NewPrNumberAndFileSha, LDNError> {
+        let mut state = self.state()?;
+        Self::open_change(&mut state, file.branch_name, file.path, file.content)
+    }
+
+    async fn propose_change(
+        &self,
+        change: ApplicationChange,
+    ) -> Result<NewPrNumberAndFileSha, LDNError> {
+        let mut state = self.state()?;
+        let current_sha = state
+            .branches
+            .get(MAIN_BRANCH)
+            .and_then(|files| files.get(&change.path))
+            .map(|file| file.sha.clone());
+        if current_sha.as_deref() != Some(change.file_sha.as_str()) {
+            return Err(LDNError::Load(format!(
+                "File {} does not match sha {}",
+                change.path, change.file_sha
+            )));
+        }
+        Self::open_change(&mut state, change.branch_name, change.path, change.content)
+    }
+
+    async fn commit_change(&self, commit: ApplicationCommit) -> Result<String, LDNError> {
+        let mut state = self.state()?;
+        for interleaved in std::mem::take(&mut state.interleaved) {
+            Self::apply_commit(&mut state, interleaved)?;
+        }
+        Self::apply_commit(&mut state, commit)
+    }
+
+    async fn merge_change(&self, pr_number: u64) -> Result<(), LDNError> {
+        let mut state = self.state()?;
+        let change = state
+            .changes
+            .remove(&pr_number)
+            .ok_or(LDNError::Load(format!("Change {pr_number} not found")))?;
+        let file = state
+            .branches
+            .remove(&change.branch_name)
+            .and_then(|mut files| files.remove(&change.path))
+            .ok_or(LDNError::Load(format!(
+                "File {} not found on branch {}",
+                change.path, change.branch_name
+            )))?;
+        state
+            .branches
+            .entry(MAIN_BRANCH.to_string())
+            .or_default()
+            .insert(change.path, file);
+        Ok(())
+    }
+
+    async fn close_change(&self, pr_number: u64) -> Result<(), LDNError> {
+        let mut state = self.state()?;
+        if let Some(change) = state.changes.remove(&pr_number) {
+            state.branches.remove(&change.branch_name);
+        }
+        Ok(())
+    }
+
+    async fn find_change(
+        &self,
+        _application_id: &str,
+        branch_name: &str,
+    ) -> Result<Option<u64>, LDNError> {
+        let state = self.state()?;
+        Ok(state
+            .changes
+            .iter()
+            .find(|(_, change)| change.branch_name == branch_name)
+            .map(|(pr_number, _)| *pr_number))
+    }
+
+    async fn change_branch(&self, pr_number: u64) -> Result<String, LDNError> {
+        let state = self.state()?;
+        state
+            .changes
+            .get(&pr_number)
+            .map(|change| change.branch_name.clone())
+            .ok_or(LDNError::Load(format!("Change {pr_number} not found")))
+    }
+
+    async fn list_active(&self) -> Result<Vec<ApplicationFileWithDate>, LDNError> {
+        let state = self.state()?;
+        let mut apps = Vec::new();
+        for (pr_number, change) in state.changes.iter() {
+            let Some(file) = state
+                .branches
+                .get(&change.branch_name)
+                .and_then(|files| files.get(&change.path))
+            else {
+                continue;
+            };
+            if let Some(application_file) = Self::parse(file) {
+                apps.push(ApplicationFileWithDate {
+                    application_file,
+                    updated_at: file.updated_at,
+                    pr_number: *pr_number,
+                    sha: file.sha.clone(),
+                    path: change.path.clone(),
+                    branch_name: change.branch_name.clone(),
+                });
+            }
+        }
+        apps.sort_by_key(|app| app.pr_number);
+        Ok(apps)
+    }
+
+    async fn list_merged(&self) -> Result<Vec<ApplicationFileWithDate>, LDNError> {
+        let state = self.state()?;
+        let mut apps: Vec<ApplicationFileWithDate> = state
+            .branches
+            .get(MAIN_BRANCH)
+            .map(|files| {
+                files
+                    .iter()
+                    .filter_map(|(path, file)| {
+                        Some(ApplicationFileWithDate {
+                            application_file: Self::parse(file)?,
+                            updated_at: file.updated_at,
+                            pr_number: 0,
+                            sha: file.sha.clone(),
+                            path: path.clone(),
+                            branch_name: MAIN_BRANCH.to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        apps.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(apps)
+    }
+
+    async fn read_file(
+        &self,
+        path: &str,
+        branch_name: &str,
+    ) -> Result<Option<StoredApplicationFile>, LDNError> {
+        let state = self.state()?;
+        let Some(file) = state
+            .branches
+            .get(branch_name)
+            .and_then(|files| files.get(path))
+        else {
+            return Ok(None);
+        };
+        let application_file = Self::parse(file).ok_or(LDNError::Load(format!(
+            "Application file {path} is corrupted"
+        )))?;
+        Ok(Some(StoredApplicationFile {
+            application_file,
+            path: path.to_string(),
+            sha: file.sha.clone(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::application::file::{Client, Datacap, Project, Version};
+
+    async fn application_content(id: &str, name: &str) -> String {
+        let application_file = ApplicationFile::new(
+            "1".to_string(),
+            "false".to_string(),
+            Version::Text("1.3".to_string()),
+            id.to_string(),
+            Client {
+                name: name.to_string(),
+                ..Default::default()
+            },
+            Project::default(),
+            Datacap::default(),
+        )
+        .await;
+        serde_json::to_string_pretty(&application_file).unwrap()
+    }
+
+    fn new_file(content: String) -> NewApplicationFile {
+        NewApplicationFile {
+            application_id: "f1test".to_string(),
+            issue_number: "1".to_string(),
+            owner_name: "Client".to_string(),
+            branch_name: "Application/f1test".to_string(),
+            path: "applications/f1test.json".to_string(),
+            content,
+            commit_message: "Start Application".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn created_file_is_active_until_merged() {
+        let store = InMemoryApplicationStore::new(
+            "owner".into(),
+            "repo".into(),
+            ApplicationStoreKind::Database,
+        );
+        let created = store
+            .create_file(new_file(application_content("f1test", "Client").await))
+            .await
+            .unwrap();
+
+        assert_eq!(store.list_active().await.unwrap().len(), 1);
+        assert!(store.list_merged().await.unwrap().is_empty());
+        assert_eq!(
+            store
+                .find_change("f1test", "Application/f1test")
+                .await
+                .unwrap(),
+            Some(created.pr_number)
+        );
+        assert!(store
+            .read_file("applications/f1test.json", "main")
+            .await
+            .unwrap()
+            .is_none());
+
+        store.merge_change(created.pr_number).await.unwrap();
+
+        assert!(store.list_active().await.unwrap().is_empty());
+        let merged = store
+            .read_file("applications/f1test.json", "main")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(merged.sha, created.file_sha);
+        assert_eq!(merged.application_file.id, "f1test");
+    }
+
+    #[tokio::test]
+    async fn commits_require_current_sha() {
+        let store = InMemoryApplicationStore::new(
+            "owner".into(),
+            "repo".into(),
+            ApplicationStoreKind::Database,
+        );
+        let created = store
+            .create_file(new_file(application_content("f1test", "Client").await))
+            .await
+            .unwrap();
+        let commit = ApplicationCommit {
+            path: "applications/f1test.json".to_string(),
+            branch_name: "Application/f1test".to_string(),
+            message: "Update".to_string(),
+            content: application_content("f1test", "Renamed client").await,
+            file_sha: "stale".to_string(),
+        };
+
+        assert!(matches!(
+            store.commit_change(commit.clone()).await,
+            Err(LDNError::Conflict(_))
+        ));
+
+        let new_sha = store
+            .commit_change(ApplicationCommit {
+                file_sha: created.file_sha,
+                ..commit
+            })
+            .await
+            .unwrap();
+        let file = store
+            .read_file("applications/f1test.json", "Application/f1test")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(file.sha, new_sha);
+        assert_eq!(file.application_file.client.name, "Renamed client");
+    }
+
+    #[tokio::test]
+    async fn closed_change_leaves_main_untouched() {
+        let store = InMemoryApplicationStore::new(
+            "owner".into(),
+            "repo".into(),
+            ApplicationStoreKind::Database,
+        );
+        let created = store
+            .create_file(new_file(application_content("f1test", "Client").await))
+            .await
+            .unwrap();
+        store.merge_change(created.pr_number).await.unwrap();
+
+        let proposed = store
+            .propose_change(ApplicationChange {
+                application_id: "f1test".to_string(),
+                issue_number: "1".to_string(),
+                branch_name: "refill".to_string(),
+                path: "applications/f1test.json".to_string(),
+                file_sha: created.file_sha.clone(),
+                content: application_content("f1test", "Refill").await,
+                title: "Refill".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_ne!(proposed.pr_number, created.pr_number);
+
+        store.close_change(proposed.pr_number).await.unwrap();
+
+        assert!(store.list_active().await.unwrap().is_empty());
+        let merged = store.list_merged().await.unwrap();
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].sha, created.file_sha);
+    }
+}
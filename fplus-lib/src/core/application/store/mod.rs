@@ -0,0 +1,214 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use fplus_database::database::allocators::get_allocator;
+use fplus_database::DbPool;
+use sha1::{Digest, Sha1};
+
+use crate::config::get_env_var_or_default;
+use crate::core::application::file::ApplicationFile;
+use crate::core::{ApplicationFileWithDate, NewPrNumberAndFileSha};
+use crate::error::LDNError;
+use crate::external_services::github::github_async_new;
+
+pub mod database;
+pub mod github;
+#[cfg(test)]
+pub mod memory;
+
+pub use self::database::DatabaseApplicationStore;
+pub use self::github::GithubApplicationStore;
+#[cfg(test)]
+pub use self::memory::InMemoryApplicationStore;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplicationStoreKind {
+    Github,
+    Database,
+}
+
+impl ApplicationStoreKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApplicationStoreKind::Github => "github",
+            ApplicationStoreKind::Database => "database",
+        }
+    }
+}
+
+impl FromStr for ApplicationStoreKind {
+    type Err = LDNError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "github" => Ok(ApplicationStoreKind::Github),
+            "database" => Ok(ApplicationStoreKind::Database),
+            other => Err(LDNError::Load(format!(
+                "Unknown application store backend: {other}"
+            ))),
+        }
+    }
+}
+
+/// First version of an application file, stored together with a change
+/// (pull request) that proposes it.
+#[derive(Debug, Clone)]
+pub struct NewApplicationFile {
+    pub application_id: String,
+    pub issue_number: String,
+    pub owner_name: String,
+    pub branch_name: String,
+    pub path: String,
+    pub content: String,
+    pub commit_message: String,
+}
+
+/// New content proposed for an application file which already exists on main.
+#[derive(Debug, Clone)]
+pub struct ApplicationChange {
+    pub application_id: String,
+    pub issue_number: String,
+    pub branch_name: String,
+    pub path: String,
+    pub file_sha: String,
+    pub content: String,
+    pub title: String,
+}
+
+/// Revision added to an already opened change.
+#[derive(Debug, Clone)]
+pub struct ApplicationCommit {
+    pub path: String,
+    pub branch_name: String,
+    pub message: String,
+    pub content: String,
+    pub file_sha: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct StoredApplicationFile {
+    pub application_file: ApplicationFile,
+    pub path: String,
+    pub sha: String,
+}
+
+/// Persistence of application files.
+///
+/// Applications move through "changes": an application file is created or
+/// modified on a branch, revised with further commits and finally merged into
+/// main. On GitHub a change is a pull request, other backends emulate it.
+/// The database `applications` table stays a cache maintained by the callers,
+/// so backends only own the file contents and change bookkeeping.
+#[async_trait]
+pub trait ApplicationStore: Send + Sync + std::fmt::Debug {
+    fn kind(&self) -> ApplicationStoreKind;
+
+    fn owner(&self) -> &str;
+
+    fn repo(&self) -> &str;
+
+    /// Stores a new application file and opens a change for it.
+    async fn create_file(
+        &self,
+        file: NewApplicationFile,
+    ) -> Result<NewPrNumberAndFileSha, LDNError>;
+
+    /// Opens a change proposing new content for an existing application file.
+    async fn propose_change(
+        &self,
+        change: ApplicationChange,
+    ) -> Result<NewPrNumberAndFileSha, LDNError>;
+
+    /// Adds a revision to an open change and returns the new file sha.
+    async fn commit_change(&self, commit: ApplicationCommit) -> Result<String, LDNError>;
+
+    async fn merge_change(&self, pr_number: u64) -> Result<(), LDNError>;
+
+    /// Abandons an open change without merging it.
+    async fn close_change(&self, pr_number: u64) -> Result<(), LDNError>;
+
+    /// Returns the number of the open change for the application on the given branch.
+    async fn find_change(
+        &self,
+        application_id: &str,
+        branch_name: &str,
+    ) -> Result<Option<u64>, LDNError>;
+
+    async fn change_branch(&self, pr_number: u64) -> Result<String, LDNError>;
+
+    async fn list_active(&self) -> Result<Vec<ApplicationFileWithDate>, LDNError>;
+
+    async fn list_merged(&self) -> Result<Vec<ApplicationFileWithDate>, LDNError>;
+
+    async fn read_file(
+        &self,
+        path: &str,
+        branch_name: &str,
+    ) -> Result<Option<StoredApplicationFile>, LDNError>;
+}
+
+/// Returns the store configured for the allocator. Allocators without an
+/// explicit `application_store` fall back to the `APPLICATION_STORE` default.
+pub async fn application_store(
+    db: &DbPool,
+    owner: &str,
+    repo: &str,
+) -> Result<Arc<dyn ApplicationStore>, LDNError> {
+    let allocator = get_allocator(db, owner, repo)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get an allocator: {e}")))?;
+    let kind = allocator
+        .and_then(|allocator| allocator.application_store)
+        .unwrap_or_else(|| get_env_var_or_default("APPLICATION_STORE"))
+        .parse::<ApplicationStoreKind>()?;
+
+    match kind {
+        ApplicationStoreKind::Github => {
+            let gh = github_async_new(db, owner.to_string(), repo.to_string()).await?;
+            Ok(Arc::new(GithubApplicationStore::new(db, gh)))
+        }
+        ApplicationStoreKind::Database => Ok(Arc::new(DatabaseApplicationStore::new(
+            db,
+            owner.to_string(),
+            repo.to_string(),
+        ))),
+    }
+}
+
+/// Git blob hash of the content, so backends without git produce the same
+/// sha GitHub would report for the file.
+pub fn content_sha(content: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(format!("blob {}\0", content.len()).as_bytes());
+    hasher.update(content.as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_sha_matches_git_blob_hash() {
+        // `printf 'hello\n' | git hash-object --stdin`
+        assert_eq!(
+            content_sha("hello\n"),
+            "ce013625030ba8dba906f756967f9e9ca394464a"
+        );
+    }
+
+    #[test]
+    fn store_kind_is_parsed_case_insensitively() {
+        assert_eq!(
+            "Database".parse::<ApplicationStoreKind>().unwrap(),
+            ApplicationStoreKind::Database
+        );
+        assert!("gitlab".parse::<ApplicationStoreKind>().is_err());
+        assert!("memory".parse::<ApplicationStoreKind>().is_err());
+    }
+}
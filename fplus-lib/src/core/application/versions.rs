@@ -0,0 +1,260 @@
+use fplus_database::database::application_versions::{
+    get_application_version, get_application_versions,
+};
+use fplus_database::database::applications::get_application;
+use fplus_database::models::application_versions::Model as ApplicationVersionModel;
+use fplus_database::DbPool;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::LDNError;
+
+#[derive(Deserialize, Debug)]
+pub struct ApplicationVersionQueryParams {
+    pub id: String,
+    pub owner: String,
+    pub repo: String,
+    pub version: i32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ApplicationVersionDiffQueryParams {
+    pub id: String,
+    pub owner: String,
+    pub repo: String,
+    pub from: i32,
+    /// Compared against the current application when missing.
+    pub to: Option<i32>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JsonChange {
+    Added {
+        path: String,
+        value: Value,
+    },
+    Removed {
+        path: String,
+        value: Value,
+    },
+    Changed {
+        path: String,
+        from: Value,
+        to: Value,
+    },
+}
+
+fn pointer_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn diff_values(path: String, from: &Value, to: &Value, changes: &mut Vec<JsonChange>) {
+    match (from, to) {
+        (Value::Object(from), Value::Object(to)) => {
+            for (key, from_value) in from {
+                let path = format!("{path}/{}", pointer_token(key));
+                match to.get(key) {
+                    Some(to_value) => diff_values(path, from_value, to_value, changes),
+                    None => changes.push(JsonChange::Removed {
+                        path,
+                        value: from_value.clone(),
+                    }),
+                }
+            }
+            for (key, to_value) in to {
+                if !from.contains_key(key) {
+                    changes.push(JsonChange::Added {
+                        path: format!("{path}/{}", pointer_token(key)),
+                        value: to_value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(from), Value::Array(to)) => {
+            for (index, from_value) in from.iter().enumerate() {
+                let path = format!("{path}/{index}");
+                match to.get(index) {
+                    Some(to_value) => diff_values(path, from_value, to_value, changes),
+                    None => changes.push(JsonChange::Removed {
+                        path,
+                        value: from_value.clone(),
+                    }),
+                }
+            }
+            for (index, to_value) in to.iter().enumerate().skip(from.len()) {
+                changes.push(JsonChange::Added {
+                    path: format!("{path}/{index}"),
+                    value: to_value.clone(),
+                });
+            }
+        }
+        (from, to) if from != to => changes.push(JsonChange::Changed {
+            path,
+            from: from.clone(),
+            to: to.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Changes turning `from` into `to`, with JSON pointer paths.
+pub fn diff_json(from: &Value, to: &Value) -> Vec<JsonChange> {
+    let mut changes = Vec::new();
+    diff_values(String::new(), from, to, &mut changes);
+    changes
+}
+
+fn parse_application(application: Option<&str>) -> Result<Value, LDNError> {
+    application
+        .map(serde_json::from_str)
+        .transpose()
+        .map_err(|e| LDNError::Load(format!("Failed to parse application JSON: {e}")))
+        .map(Option::unwrap_or_default)
+}
+
+pub async fn application_versions(
+    db: &DbPool,
+    id: &str,
+    owner: &str,
+    repo: &str,
+) -> Result<Vec<ApplicationVersionModel>, LDNError> {
+    get_application_versions(db, id, owner, repo)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get application versions: {e}")))
+}
+
+pub async fn application_version(
+    db: &DbPool,
+    id: &str,
+    owner: &str,
+    repo: &str,
+    version: i32,
+) -> Result<ApplicationVersionModel, LDNError> {
+    get_application_version(db, id, owner, repo, version)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get application version: {e}")))?
+        .ok_or_else(|| LDNError::New(format!("Version {version} of application {id} not found")))
+}
+
+/// Diff between two versions of an application, or between a version and
+/// the current application. Fails with `LDNError::New` only when a version
+/// does not exist.
+pub async fn application_version_diff(
+    db: &DbPool,
+    query: &ApplicationVersionDiffQueryParams,
+) -> Result<Vec<JsonChange>, LDNError> {
+    let from = application_version(db, &query.id, &query.owner, &query.repo, query.from).await?;
+    let to = match query.to {
+        Some(to) => {
+            application_version(db, &query.id, &query.owner, &query.repo, to)
+                .await?
+                .application
+        }
+        None => {
+            get_application(
+                db,
+                query.id.clone(),
+                query.owner.clone(),
+                query.repo.clone(),
+                None,
+            )
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to get application: {e}")))?
+            .application
+        }
+    };
+    Ok(diff_json(
+        &parse_application(from.application.as_deref())?,
+        &parse_application(to.as_deref())?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fplus_database::database::application_versions::WriteContext;
+    use fplus_database::database::applications::{create_application, update_application};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn only_missing_versions_are_not_found() {
+        let db = fplus_database::setup_test_environment().await;
+        create_application(
+            &db,
+            "f1versions".into(),
+            "owner".into(),
+            "repo".into(),
+            1,
+            1,
+            "corrupted".into(),
+            "applications/f1versions.json".into(),
+            None,
+            "sha".into(),
+        )
+        .await
+        .unwrap();
+        update_application(
+            &db,
+            "f1versions".into(),
+            "owner".into(),
+            "repo".into(),
+            1,
+            json!({"Lifecycle": {"State": "Submitted"}}).to_string(),
+            None,
+            "sha2".into(),
+            None,
+            &WriteContext::new("Test"),
+        )
+        .await
+        .unwrap();
+        let query = |from| ApplicationVersionDiffQueryParams {
+            id: "f1versions".into(),
+            owner: "owner".into(),
+            repo: "repo".into(),
+            from,
+            to: None,
+        };
+
+        assert!(matches!(
+            application_version_diff(&db, &query(2)).await,
+            Err(LDNError::New(_))
+        ));
+        assert!(matches!(
+            application_version_diff(&db, &query(1)).await,
+            Err(LDNError::Load(_))
+        ));
+    }
+
+    #[test]
+    fn diff_reports_changes_with_json_pointers() {
+        let from = json!({
+            "Lifecycle": {"State": "ReadyToSign", "Active": true},
+            "Allocation Requests": [{"ID": "a"}],
+            "a/b": 1
+        });
+        let to = json!({
+            "Lifecycle": {"State": "Granted", "Active": true},
+            "Allocation Requests": [{"ID": "a"}, {"ID": "b"}]
+        });
+        assert_eq!(
+            diff_json(&from, &to),
+            vec![
+                JsonChange::Added {
+                    path: "/Allocation Requests/1".into(),
+                    value: json!({"ID": "b"}),
+                },
+                JsonChange::Changed {
+                    path: "/Lifecycle/State".into(),
+                    from: json!("ReadyToSign"),
+                    to: json!("Granted"),
+                },
+                JsonChange::Removed {
+                    path: "/a~1b".into(),
+                    value: json!(1),
+                },
+            ]
+        );
+        assert!(diff_json(&to, &to).is_empty());
+    }
+}
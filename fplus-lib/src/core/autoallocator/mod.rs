@@ -1,52 +1,152 @@
-use crate::core::autoallocator::metaallocator_interaction::add_verified_client;
-use crate::core::get_env_var_or_default;
-use crate::core::verify_on_gitcoin;
+use crate::config::get_env_var_or_default;
+use crate::core::autoallocator::metaallocator_interaction::add_verified_client_request;
+use crate::core::autoallocator::profiles::{
+    profile_for_network, AutoallocatorNetwork, AutoallocatorProfile,
+};
+use crate::core::autoallocator::sybil::check_autoallocation_client;
+use crate::core::autoallocator::tiers::{
+    autoallocation_caps, cap_exceeded, parse_amount, tier_for_score,
+};
+use crate::core::autoallocator::transactions::submit_transaction;
+use crate::core::kyc::{autoallocation_kyc_providers, verify_kyc, KycSubject};
 use crate::core::{LDNApplication, TriggerAutoallocationInfo};
 use crate::error::LDNError;
 use crate::external_services::blockchain::get_allowance_for_address_contract;
 use crate::external_services::filecoin::evm_address_to_filecoin_address;
-use crate::external_services::filecoin::get_allowance_for_address_direct;
+use crate::external_services::filecoin::get_allowance_for_address_on_node;
 use alloy::primitives::Address;
-use alloy::signers::local::PrivateKeySigner;
-use fplus_database::config::get_env_or_throw;
-use fplus_database::database::applications::get_applications_by_client_id;
-use fplus_database::database::autoallocations as autoallocations_db;
+use fplus_database::database::autoallocations::{
+    self as autoallocations_db, AutoallocationGrantOutcome,
+};
+use fplus_database::models::autoallocation_grants::Model as AutoallocationGrantModel;
+use fplus_database::DbPool;
 use std::cmp::min;
 
 pub mod metaallocator_interaction;
+pub mod profiles;
+pub mod sybil;
+pub mod tiers;
+pub mod transactions;
 
-pub async fn trigger_autoallocation(info: &TriggerAutoallocationInfo) -> Result<(), LDNError> {
+/// Sends the autoallocation transaction and returns the grant without waiting
+/// for the transaction to be mined, see `transactions::autoallocation_status`.
+pub async fn trigger_autoallocation(
+    db: &DbPool,
+    info: &TriggerAutoallocationInfo,
+) -> Result<AutoallocationGrantModel, LDNError> {
     let evm_address_from_signature =
         LDNApplication::verify_kyc_data_and_get_eth_address(&info.message, &info.signature)?;
-    verify_on_gitcoin(&evm_address_from_signature).await?;
     let fil_client_address = &info.message.client_fil_address;
-    let client_applications = get_applications_by_client_id(fil_client_address)
+    let verification = verify_kyc(
+        &autoallocation_kyc_providers(),
+        &KycSubject {
+            client_id: fil_client_address.clone(),
+            address: evm_address_from_signature,
+            attestation: None,
+        },
+    )
+    .await?;
+    let score = verification.score.ok_or_else(|| {
+        LDNError::Load(format!(
+            "{} gave no score to pick an autoallocation tier",
+            verification.provider
+        ))
+    })?;
+    let network = AutoallocatorNetwork::from_client_address(fil_client_address)?;
+    let profile = profile_for_network(network)?;
+    check_autoallocation_client(
+        db,
+        &profile,
+        &evm_address_from_signature,
+        fil_client_address,
+    )
+    .await?;
+    let tiers = profile.tiers()?;
+    let amount = tier_for_score(&tiers, score)
+        .ok_or_else(|| {
+            LDNError::Load(format!(
+                "{} score {score} does not qualify for any autoallocation tier",
+                verification.provider
+            ))
+        })?
+        .amount;
+    let grant_amount = i64::try_from(amount)
+        .map_err(|e| LDNError::New(format!("Autoallocation amount is too large: {e}")))?;
+    // The cap check and the grant are one transaction, the grant counts
+    // against the caps until it is marked failed.
+    let grant = match autoallocations_db::create_autoallocation_grant_within_caps(
+        db,
+        evm_address_from_signature,
+        fil_client_address,
+        network.as_str(),
+        grant_amount,
+        score,
+        autoallocation_caps()?.grant_caps(),
+    )
+    .await
+    .map_err(|e| LDNError::New(format!("Create autoallocation grant failed: {e}")))?
+    {
+        AutoallocationGrantOutcome::Granted(grant) => grant,
+        AutoallocationGrantOutcome::CapExceeded {
+            holder,
+            cap,
+            granted,
+        } => return Err(cap_exceeded(cap, granted, amount, &holder)),
+    };
+    let reserved = async {
+        LDNApplication::consume_kyc_signature(
+            db,
+            &info.message,
+            &info.signature,
+            &evm_address_from_signature,
+        )
+        .await?;
+        upsert_autoallocation_if_eligible(db, &evm_address_from_signature, &profile).await
+    }
+    .await;
+    if let Err(e) = reserved {
+        autoallocations_db::set_autoallocation_grant_status(
+            db,
+            grant.id,
+            autoallocations_db::GRANT_STATUS_FAILED,
+        )
         .await
-        .map_err(|e| LDNError::Load(format!("Get applications for client failed: {e}")))?;
-    if !client_applications.is_empty() {
-        return Err(LDNError::Load(
-            "Client already has an application".to_string(),
-        ));
+        .map_err(|err| LDNError::New(format!("Update autoallocation grant failed: {err}")))?;
+        return Err(e);
     }
-    let amount = get_env_var_or_default("AUTOALLOCATION_AMOUNT")
-        .parse::<u64>()
-        .map_err(|e| LDNError::New(format!("Parse days to next allocation to i64 failed: {e}")))?;
-    upsert_autoallocation_if_eligible(&evm_address_from_signature).await?;
-    if let Err(e) = add_verified_client(fil_client_address, &amount).await {
-        autoallocations_db::delete_autoallocation(evm_address_from_signature)
+    let request = add_verified_client_request(&profile, fil_client_address, &amount);
+    let submitted = match request {
+        Ok(request) => submit_transaction(db, &profile, grant.id, request).await,
+        Err(e) => Err(e),
+    };
+    // Once the transaction is recorded the confirmer owns the grant, an error
+    // here means nothing was sent.
+    if let Err(e) = submitted {
+        autoallocations_db::set_autoallocation_grant_status(
+            db,
+            grant.id,
+            autoallocations_db::GRANT_STATUS_FAILED,
+        )
+        .await
+        .map_err(|err| LDNError::New(format!("Update autoallocation grant failed: {err}")))?;
+        autoallocations_db::delete_autoallocation(db, evm_address_from_signature, network.as_str())
             .await
             .map_err(|err| LDNError::New(format!("Delete autoallocation failed: {err}")))?;
         return Err(LDNError::New(format!("Add verified client failed: {e}")));
     }
-    Ok(())
+    Ok(grant)
 }
 
-async fn upsert_autoallocation_if_eligible(evm_client_address: &Address) -> Result<(), LDNError> {
-    let days_to_next_autoallocation = get_env_var_or_default("DAYS_TO_NEXT_AUTOALLOCATION")
-        .parse::<i64>()
-        .map_err(|e| LDNError::New(format!("Parse days to next allocation to i64 failed: {e}")))?;
+async fn upsert_autoallocation_if_eligible(
+    db: &DbPool,
+    evm_client_address: &Address,
+    profile: &AutoallocatorProfile,
+) -> Result<(), LDNError> {
+    let days_to_next_autoallocation = profile.days_to_next_autoallocation()?;
     let rows_affected = autoallocations_db::create_or_update_autoallocation(
+        db,
         evm_client_address,
+        profile.network.as_str(),
         &days_to_next_autoallocation,
     )
     .await
@@ -59,40 +159,60 @@ async fn upsert_autoallocation_if_eligible(evm_client_address: &Address) -> Resu
     Ok(())
 }
 
-pub async fn check_if_allowance_is_sufficient() -> Result<bool, LDNError> {
-    let contract_address = get_env_var_or_default("ALLOCATOR_CONTRACT_ADDRESS");
-    let parsed_contract_address_to_fil = evm_address_to_filecoin_address(&contract_address)
+/// Records the autoallocations made before grants were recorded as legacy
+/// grants of `AUTOALLOCATION_AMOUNT`, the fixed amount granted at the time.
+pub async fn backfill_legacy_autoallocation_grants(db: &DbPool) -> Result<u64, LDNError> {
+    let amount = parse_amount(&get_env_var_or_default("AUTOALLOCATION_AMOUNT"))?;
+    let amount = i64::try_from(amount)
+        .map_err(|e| LDNError::New(format!("Autoallocation amount is too large: {e}")))?;
+    autoallocations_db::backfill_legacy_autoallocation_grants(db, amount)
         .await
-        .map_err(|e| {
-        LDNError::Load(format!("Failed to parse EVM address to FIL address: {e}"))
-    })?;
-    let contract_allowance = get_allowance_for_address_direct(&parsed_contract_address_to_fil)
-        .await
-        .map_err(|e| LDNError::Load(format!("Failed to retrieve allowance: {e}")))?
-        .parse::<u64>()
-        .map_err(|e| LDNError::New(format!("Parse contract allowance to u64 failed: {e}")))?;
+        .map_err(|e| LDNError::Load(format!("Backfill autoallocation grants failed: {e}")))
+}
 
-    let parsed_contract_address_to_evm: Address = contract_address.parse().map_err(|e| {
-        LDNError::New(format!(
-            "Failed to get evm address from filecoin address: {e:?}"
-        ))
-    })?;
-    let allocator_private_key = get_env_or_throw("AUTOALLOCATOR_PRIVATE_KEY");
-    let allocator_address = allocator_private_key
-        .parse::<PrivateKeySigner>()
-        .expect("Should parse private key")
-        .address();
+/// The DataCap the autoallocator can still grant: the lower of the allocator
+/// contract's allowance and the autoallocator's allowance on the contract.
+pub async fn autoallocator_allowance(profile: &AutoallocatorProfile) -> Result<u64, LDNError> {
+    let parsed_contract_address_to_fil = evm_address_to_filecoin_address(
+        &profile.rpc_url,
+        &profile.contract_address.to_checksum(None),
+    )
+    .await
+    .map_err(|e| LDNError::Load(format!("Failed to parse EVM address to FIL address: {e}")))?;
+    let contract_allowance =
+        get_allowance_for_address_on_node(&profile.rpc_url, &parsed_contract_address_to_fil)
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to retrieve allowance: {e}")))?
+            .parse::<u64>()
+            .map_err(|e| LDNError::New(format!("Parse contract allowance to u64 failed: {e}")))?;
 
-    let allocator_allowance_on_contract =
-        get_allowance_for_address_contract(&allocator_address, &parsed_contract_address_to_evm)
-            .await?;
+    let allocator_address = profile.signer()?.address();
+    let allocator_allowance_on_contract = get_allowance_for_address_contract(
+        &allocator_address,
+        &profile.contract_address,
+        &profile.rpc_url,
+    )
+    .await?;
 
-    let allowance = min(allocator_allowance_on_contract, contract_allowance);
-    let autoallocation_amount = get_env_var_or_default("AUTOALLOCATION_AMOUNT")
-        .parse::<u64>()
-        .map_err(|e| LDNError::New(format!("Parse days to next allocation to i64 failed: {e}")))?;
-    if allowance < autoallocation_amount {
-        return Ok(false);
-    }
-    Ok(true)
+    Ok(min(allocator_allowance_on_contract, contract_allowance))
+}
+
+/// The amount of the largest tier of the profile, what one grant may take.
+pub fn largest_tier_amount(profile: &AutoallocatorProfile) -> Result<u64, LDNError> {
+    Ok(profile
+        .tiers()?
+        .iter()
+        .map(|tier| tier.amount)
+        .max()
+        .unwrap_or_default())
+}
+
+/// Whether the autoallocator of the network can still serve the largest tier,
+/// so any qualifying client can be served.
+pub async fn check_if_allowance_is_sufficient(
+    network: AutoallocatorNetwork,
+) -> Result<bool, LDNError> {
+    let profile = profile_for_network(network)?;
+    let allowance = autoallocator_allowance(&profile).await?;
+    Ok(allowance >= largest_tier_amount(&profile)?)
 }
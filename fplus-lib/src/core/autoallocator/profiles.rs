@@ -0,0 +1,227 @@
+use std::fmt;
+use std::str::FromStr;
+
+use alloy::primitives::Address;
+use alloy::signers::local::PrivateKeySigner;
+use fvm_shared::address::{Address as FilecoinAddress, Network};
+use serde::{Deserialize, Serialize};
+
+use crate::config::get_env_var_or_default;
+use crate::core::autoallocator::tiers::{
+    autoallocation_tiers, check_tiers_reachable_with_gitcoin, parse_tiers, AutoallocationTier,
+};
+use crate::error::LDNError;
+
+/// The Filecoin network an autoallocation is sent on, told by the prefix of the
+/// client address.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoallocatorNetwork {
+    Mainnet,
+    Calibnet,
+}
+
+impl AutoallocatorNetwork {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AutoallocatorNetwork::Mainnet => "mainnet",
+            AutoallocatorNetwork::Calibnet => "calibnet",
+        }
+    }
+
+    pub fn from_client_address(address: &str) -> Result<Self, LDNError> {
+        match address.get(0..1) {
+            Some("f") => Ok(AutoallocatorNetwork::Mainnet),
+            Some("t") => Ok(AutoallocatorNetwork::Calibnet),
+            _ => Err(LDNError::Load(format!(
+                "Client address {address} is neither a mainnet nor a calibnet address"
+            ))),
+        }
+    }
+
+    /// Parses a client address of this network, without touching the
+    /// process-wide network of `fvm_shared`.
+    pub fn parse_client_address(&self, address: &str) -> Result<FilecoinAddress, LDNError> {
+        let network = match self {
+            AutoallocatorNetwork::Mainnet => Network::Mainnet,
+            AutoallocatorNetwork::Calibnet => Network::Testnet,
+        };
+        network
+            .parse_address(address)
+            .map_err(|e| LDNError::Load(format!("Failed to parse {self} address {address}: {e}")))
+    }
+}
+
+impl fmt::Display for AutoallocatorNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AutoallocatorNetwork {
+    type Err = LDNError;
+
+    fn from_str(network: &str) -> Result<Self, Self::Err> {
+        match network {
+            "mainnet" => Ok(AutoallocatorNetwork::Mainnet),
+            "calibnet" => Ok(AutoallocatorNetwork::Calibnet),
+            _ => Err(LDNError::Load(format!("Unknown network {network}"))),
+        }
+    }
+}
+
+/// Where and how autoallocations are sent on one network.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct AutoallocatorProfile {
+    pub network: AutoallocatorNetwork,
+    pub contract_address: Address,
+    pub rpc_url: String,
+    /// The environment variable holding the signer's private key, so keys stay
+    /// out of the profile list.
+    pub private_key_env: String,
+    /// Amounts per score as in `AUTOALLOCATION_TIERS`, which is used when unset.
+    #[serde(default)]
+    pub tiers: Option<String>,
+    /// Defaults to `DAYS_TO_NEXT_AUTOALLOCATION`.
+    #[serde(default)]
+    pub days_to_next_autoallocation: Option<i64>,
+}
+
+impl AutoallocatorProfile {
+    pub fn signer(&self) -> Result<PrivateKeySigner, LDNError> {
+        std::env::var(&self.private_key_env)
+            .map_err(|_| {
+                LDNError::New(format!(
+                    "{} is not set for the {} autoallocator",
+                    self.private_key_env, self.network
+                ))
+            })?
+            .parse()
+            .map_err(|e| LDNError::New(format!("Parse {} failed: {e}", self.private_key_env)))
+    }
+
+    /// The tiers of the profile, each reachable with `GITCOIN_MINIMUM_SCORE`.
+    pub fn tiers(&self) -> Result<Vec<AutoallocationTier>, LDNError> {
+        let tiers = match &self.tiers {
+            Some(tiers) => parse_tiers(tiers)?,
+            None => autoallocation_tiers()?,
+        };
+        check_tiers_reachable_with_gitcoin(&tiers)?;
+        Ok(tiers)
+    }
+
+    pub fn days_to_next_autoallocation(&self) -> Result<i64, LDNError> {
+        match self.days_to_next_autoallocation {
+            Some(days) => Ok(days),
+            None => get_env_var_or_default("DAYS_TO_NEXT_AUTOALLOCATION")
+                .parse::<i64>()
+                .map_err(|e| {
+                    LDNError::New(format!("Parse days to next allocation to i64 failed: {e}"))
+                }),
+        }
+    }
+}
+
+/// Parses a JSON list of profiles, at most one per network.
+pub fn parse_profiles(profiles: &str) -> Result<Vec<AutoallocatorProfile>, LDNError> {
+    let profiles: Vec<AutoallocatorProfile> = serde_json::from_str(profiles)
+        .map_err(|e| LDNError::New(format!("Parse AUTOALLOCATOR_PROFILES failed: {e}")))?;
+    for (i, profile) in profiles.iter().enumerate() {
+        if profiles[..i]
+            .iter()
+            .any(|other| other.network == profile.network)
+        {
+            return Err(LDNError::New(format!(
+                "AUTOALLOCATOR_PROFILES has more than one {} profile",
+                profile.network
+            )));
+        }
+        profile.tiers()?;
+    }
+    Ok(profiles)
+}
+
+/// Profiles from `AUTOALLOCATOR_PROFILES`. When it is empty, a single mainnet
+/// profile is built from `ALLOCATOR_CONTRACT_ADDRESS`, `GLIF_NODE_URL` and
+/// `AUTOALLOCATOR_PRIVATE_KEY`.
+pub fn autoallocator_profiles() -> Result<Vec<AutoallocatorProfile>, LDNError> {
+    let profiles = get_env_var_or_default("AUTOALLOCATOR_PROFILES");
+    if !profiles.trim().is_empty() {
+        return parse_profiles(&profiles);
+    }
+    let contract_address =
+        Address::parse_checksummed(get_env_var_or_default("ALLOCATOR_CONTRACT_ADDRESS"), None)
+            .map_err(|e| {
+                LDNError::New(format!(
+                    "Parse ALLOCATOR_CONTRACT_ADDRESS to Address failed: {e}"
+                ))
+            })?;
+    Ok(vec![AutoallocatorProfile {
+        network: AutoallocatorNetwork::Mainnet,
+        contract_address,
+        rpc_url: get_env_var_or_default("GLIF_NODE_URL"),
+        private_key_env: "AUTOALLOCATOR_PRIVATE_KEY".to_string(),
+        tiers: None,
+        days_to_next_autoallocation: None,
+    }])
+}
+
+pub fn profile_for_network(
+    network: AutoallocatorNetwork,
+) -> Result<AutoallocatorProfile, LDNError> {
+    autoallocator_profiles()?
+        .into_iter()
+        .find(|profile| profile.network == network)
+        .ok_or_else(|| LDNError::Load(format!("Autoallocation is not configured on {network}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn networks_are_told_by_the_client_address() {
+        let mainnet = AutoallocatorNetwork::from_client_address("f01234").unwrap();
+        let calibnet = AutoallocatorNetwork::from_client_address("t01234").unwrap();
+        assert_eq!(mainnet, AutoallocatorNetwork::Mainnet);
+        assert_eq!(calibnet, AutoallocatorNetwork::Calibnet);
+        assert!(AutoallocatorNetwork::from_client_address("x01234").is_err());
+
+        assert_eq!(
+            calibnet
+                .parse_client_address("t01234")
+                .unwrap()
+                .id()
+                .unwrap(),
+            1234
+        );
+        assert!(calibnet.parse_client_address("f01234").is_err());
+        assert!(mainnet.parse_client_address("f01234").is_ok());
+    }
+
+    #[test]
+    fn profiles_are_parsed_once_per_network() {
+        let profiles = parse_profiles(
+            r#"[
+                {"network": "mainnet", "contract_address": "0x640bD4be149f40714D95aBcD414338bc7CfF39a3", "rpc_url": "https://api.node.glif.io/rpc/v1", "private_key_env": "MAINNET_KEY"},
+                {"network": "calibnet", "contract_address": "0x640bD4be149f40714D95aBcD414338bc7CfF39a3", "rpc_url": "https://api.calibration.node.glif.io/rpc/v1", "private_key_env": "CALIBNET_KEY", "tiers": "30:1GiB", "days_to_next_autoallocation": 1}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[1].network, AutoallocatorNetwork::Calibnet);
+        assert_eq!(profiles[1].tiers().unwrap()[0].amount, 1073741824);
+        assert_eq!(profiles[1].days_to_next_autoallocation().unwrap(), 1);
+
+        let duplicated = r#"[
+            {"network": "mainnet", "contract_address": "0x640bD4be149f40714D95aBcD414338bc7CfF39a3", "rpc_url": "a", "private_key_env": "A"},
+            {"network": "mainnet", "contract_address": "0x640bD4be149f40714D95aBcD414338bc7CfF39a3", "rpc_url": "b", "private_key_env": "B"}
+        ]"#;
+        assert!(parse_profiles(duplicated).is_err());
+
+        let unreachable = r#"[
+            {"network": "calibnet", "contract_address": "0x640bD4be149f40714D95aBcD414338bc7CfF39a3", "rpc_url": "a", "private_key_env": "A", "tiers": "0:1GiB"}
+        ]"#;
+        assert!(parse_profiles(unreachable).is_err());
+    }
+}
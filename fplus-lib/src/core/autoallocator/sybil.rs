@@ -0,0 +1,307 @@
+use std::str::FromStr;
+
+use alloy::primitives::Address;
+use fplus_database::database::applications::get_applications_by_client_id;
+use fplus_database::database::autoallocations::{
+    get_autoallocated_client_addresses, AutoallocationFilter,
+};
+use fplus_database::DbPool;
+use fvm_shared::address::{Address as FilecoinAddress, Payload};
+
+use crate::config::get_env_var_or_default;
+use crate::core::autoallocator::profiles::{AutoallocatorNetwork, AutoallocatorProfile};
+use crate::error::{LDNError, Rejection};
+use crate::external_services::dmob::get_client_allocation;
+use crate::external_services::filecoin::{
+    evm_address_to_filecoin_address, get_verified_client_allowance, state_lookup_id,
+};
+
+/// A check of the client of an autoallocation. `ExistingApplication` always
+/// runs, the others are enabled through `AUTOALLOCATION_SYBIL_CHECKS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SybilCheck {
+    /// The client address is of an allowed type.
+    AddressType,
+    /// The client address has no application in the database.
+    ExistingApplication,
+    /// The EVM wallet was not paired with another client address before.
+    WalletPairing,
+    /// The client address never held DataCap, unless granted by the autoallocator.
+    DatacapHistory,
+    /// The client address was not verified by another allocator.
+    VerifiedElsewhere,
+}
+
+impl SybilCheck {
+    /// The reason code of a rejection by this check.
+    pub fn reason_code(&self) -> &'static str {
+        match self {
+            SybilCheck::AddressType => "address_type_not_allowed",
+            SybilCheck::ExistingApplication => "client_has_application",
+            SybilCheck::WalletPairing => "wallet_paired_with_other_address",
+            SybilCheck::DatacapHistory => "client_had_datacap",
+            SybilCheck::VerifiedElsewhere => "client_verified_by_other_allocator",
+        }
+    }
+
+    fn reject(&self, message: String) -> LDNError {
+        LDNError::Rejected(Rejection {
+            code: self.reason_code().to_string(),
+            message,
+        })
+    }
+}
+
+impl FromStr for SybilCheck {
+    type Err = LDNError;
+
+    fn from_str(check: &str) -> Result<Self, Self::Err> {
+        match check {
+            "address_type" => Ok(SybilCheck::AddressType),
+            "existing_application" => Ok(SybilCheck::ExistingApplication),
+            "wallet_pairing" => Ok(SybilCheck::WalletPairing),
+            "datacap_history" => Ok(SybilCheck::DatacapHistory),
+            "verified_elsewhere" => Ok(SybilCheck::VerifiedElsewhere),
+            _ => Err(LDNError::New(format!("Unknown sybil check {check}"))),
+        }
+    }
+}
+
+/// Parses a comma separated list of checks.
+pub fn parse_checks(checks: &str) -> Result<Vec<SybilCheck>, LDNError> {
+    checks
+        .split(',')
+        .map(str::trim)
+        .filter(|check| !check.is_empty())
+        .map(SybilCheck::from_str)
+        .collect()
+}
+
+/// The type of an address as written after the network prefix: `0` to `3`
+/// by protocol, `4` followed by the namespace for delegated addresses, e.g.
+/// `410` for an EVM address.
+pub fn address_type(address: &FilecoinAddress) -> String {
+    match address.payload() {
+        Payload::ID(_) => "0".to_string(),
+        Payload::Secp256k1(_) => "1".to_string(),
+        Payload::Actor(_) => "2".to_string(),
+        Payload::BLS(_) => "3".to_string(),
+        Payload::Delegated(delegated) => format!("4{}", delegated.namespace()),
+    }
+}
+
+/// Whether the type is in a list such as `f1,f3,f410`, every type is allowed
+/// by an empty list. The network prefix of the list entries is ignored.
+pub fn address_type_is_allowed(address_type: &str, allowed: &str) -> bool {
+    let mut allowed = allowed
+        .split(',')
+        .map(|allowed| allowed.trim().trim_start_matches(['f', 't']))
+        .filter(|allowed| !allowed.is_empty())
+        .peekable();
+    allowed.peek().is_none() || allowed.any(|allowed| allowed == address_type)
+}
+
+/// The checks to run: the existing application check first, then the ones
+/// enabled in `checks`.
+pub fn enabled_checks(checks: &str) -> Result<Vec<SybilCheck>, LDNError> {
+    let mut enabled = vec![SybilCheck::ExistingApplication];
+    for check in parse_checks(checks)? {
+        if !enabled.contains(&check) {
+            enabled.push(check);
+        }
+    }
+    Ok(enabled)
+}
+
+/**
+ * Check that the client of an autoallocation has no application, then run the
+ * sybil checks enabled in `AUTOALLOCATION_SYBIL_CHECKS`
+ *
+ * # Arguments
+ * @param profile: &AutoallocatorProfile - The profile of the client's network
+ * @param evm_wallet_address: &Address - The wallet that signed the request
+ * @param client_fil_address: &str - The address to receive DataCap
+ *
+ * # Returns
+ * @return Result<(), LDNError> - `LDNError::Rejected` with the reason code of the first failed check
+ */
+pub async fn check_autoallocation_client(
+    db: &DbPool,
+    profile: &AutoallocatorProfile,
+    evm_wallet_address: &Address,
+    client_fil_address: &str,
+) -> Result<(), LDNError> {
+    for check in enabled_checks(&get_env_var_or_default("AUTOALLOCATION_SYBIL_CHECKS"))? {
+        match check {
+            SybilCheck::AddressType => {
+                let address = profile.network.parse_client_address(client_fil_address)?;
+                let address_type = address_type(&address);
+                let allowed = get_env_var_or_default("AUTOALLOCATION_ALLOWED_ADDRESS_TYPES");
+                if !address_type_is_allowed(&address_type, &allowed) {
+                    return Err(check.reject(format!(
+                        "Addresses of type {address_type} cannot receive autoallocations, allowed types are {allowed}"
+                    )));
+                }
+            }
+            SybilCheck::ExistingApplication => {
+                let applications =
+                    get_applications_by_client_id(db, &client_fil_address.to_string())
+                        .await
+                        .map_err(|e| {
+                            LDNError::Load(format!("Get applications for client failed: {e}"))
+                        })?;
+                if !applications.is_empty() {
+                    return Err(check.reject("Client already has an application".to_string()));
+                }
+            }
+            SybilCheck::WalletPairing => {
+                let paired = previous_grants(
+                    db,
+                    AutoallocationFilter {
+                        evm_wallet_address: Some((*evm_wallet_address).into()),
+                        ..Default::default()
+                    },
+                )
+                .await?
+                .into_iter()
+                .find(|address| address != client_fil_address);
+                if let Some(paired) = paired {
+                    return Err(check.reject(format!(
+                        "Wallet {evm_wallet_address} already received an autoallocation for {paired}"
+                    )));
+                }
+            }
+            SybilCheck::DatacapHistory => {
+                check_datacap_history(db, profile, client_fil_address).await?;
+            }
+            SybilCheck::VerifiedElsewhere => {
+                check_verified_elsewhere(profile, client_fil_address).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The client addresses of the grants matching the filter, failed and legacy
+/// grants aside.
+async fn previous_grants(
+    db: &DbPool,
+    filter: AutoallocationFilter,
+) -> Result<Vec<String>, LDNError> {
+    get_autoallocated_client_addresses(db, &filter)
+        .await
+        .map_err(|e| LDNError::Load(format!("Get autoallocation history failed: {e}")))
+}
+
+async fn check_datacap_history(
+    db: &DbPool,
+    profile: &AutoallocatorProfile,
+    client_fil_address: &str,
+) -> Result<(), LDNError> {
+    // DataCap granted by the autoallocator is limited by the caps instead.
+    let granted_before = !previous_grants(
+        db,
+        AutoallocationFilter {
+            client_fil_address: Some(client_fil_address.to_string()),
+            network: Some(profile.network.to_string()),
+            ..Default::default()
+        },
+    )
+    .await?
+    .is_empty();
+    if granted_before {
+        return Ok(());
+    }
+    let allowance = get_verified_client_allowance(&profile.rpc_url, client_fil_address)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to retrieve allowance: {e}")))?;
+    let holds_datacap = allowance.is_some_and(|allowance| allowance != "0");
+    // DataStats only follows mainnet.
+    let had_datacap = profile.network == AutoallocatorNetwork::Mainnet
+        && get_client_allocation(client_fil_address)
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to retrieve client history: {e}")))?
+            .count
+            .is_some();
+    if holds_datacap || had_datacap {
+        return Err(SybilCheck::DatacapHistory.reject(format!(
+            "Client {client_fil_address} already received DataCap"
+        )));
+    }
+    Ok(())
+}
+
+async fn check_verified_elsewhere(
+    profile: &AutoallocatorProfile,
+    client_fil_address: &str,
+) -> Result<(), LDNError> {
+    // DataStats only follows mainnet.
+    if profile.network != AutoallocatorNetwork::Mainnet {
+        return Ok(());
+    }
+    let verifications = get_client_allocation(client_fil_address)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to retrieve client history: {e}")))?
+        .data;
+    if verifications.is_empty() {
+        return Ok(());
+    }
+    let contract_address = evm_address_to_filecoin_address(
+        &profile.rpc_url,
+        &profile.contract_address.to_checksum(None),
+    )
+    .await
+    .map_err(|e| LDNError::Load(format!("Failed to parse EVM address to FIL address: {e}")))?;
+    let contract_id = state_lookup_id(&profile.rpc_url, &contract_address)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to look up contract ID: {e}")))?;
+    let other_verifier = verifications
+        .into_iter()
+        .filter_map(|verification| verification.verifier_address_id)
+        .find(|verifier| *verifier != contract_id);
+    if let Some(verifier) = other_verifier {
+        return Err(SybilCheck::VerifiedElsewhere.reject(format!(
+            "Client {client_fil_address} was already verified by allocator {verifier}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_are_parsed_from_the_list() {
+        assert_eq!(
+            parse_checks("address_type, wallet_pairing,,").unwrap(),
+            vec![SybilCheck::AddressType, SybilCheck::WalletPairing]
+        );
+        assert!(parse_checks("address_type,unknown").is_err());
+
+        // The existing application check runs even when no check is enabled.
+        assert_eq!(
+            enabled_checks("").unwrap(),
+            vec![SybilCheck::ExistingApplication]
+        );
+        assert_eq!(
+            enabled_checks("wallet_pairing,existing_application").unwrap(),
+            vec![SybilCheck::ExistingApplication, SybilCheck::WalletPairing]
+        );
+    }
+
+    #[test]
+    fn address_types_are_matched_without_network_prefix() {
+        let calibnet = AutoallocatorNetwork::Calibnet;
+        let id = calibnet.parse_client_address("t01234").unwrap();
+        let evm = FilecoinAddress::new_delegated(10, &[0x11; 20]).unwrap();
+        assert_eq!(address_type(&id), "0");
+        assert_eq!(address_type(&evm), "410");
+
+        assert!(address_type_is_allowed("410", "f1,f3,f410"));
+        assert!(address_type_is_allowed("1", "f1, f3"));
+        assert!(!address_type_is_allowed("0", "f1,f3,f410"));
+        assert!(!address_type_is_allowed("41", "f1,f3,f410"));
+        assert!(address_type_is_allowed("0", ""));
+    }
+}
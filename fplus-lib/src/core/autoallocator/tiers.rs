@@ -0,0 +1,177 @@
+use crate::config::get_env_var_or_default;
+use crate::error::LDNError;
+use crate::helpers::parse_size_to_bytes;
+use fplus_database::database::autoallocations::AutoallocationGrantCaps;
+
+/// Amount granted to clients whose Gitcoin Passport score is at least `min_score`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoallocationTier {
+    pub min_score: f64,
+    pub amount: u64,
+}
+
+/// Lifetime limits of the DataCap autoallocated to one EVM wallet and to one
+/// client address, `None` when unlimited.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AutoallocationCaps {
+    pub per_wallet: Option<u64>,
+    pub per_client: Option<u64>,
+}
+
+pub(crate) fn parse_amount(amount: &str) -> Result<u64, LDNError> {
+    let bytes = parse_size_to_bytes(amount.trim())?;
+    u64::try_from(bytes)
+        .map_err(|_| LDNError::New(format!("Autoallocation amount {amount} is negative")))
+}
+
+/// Parses tiers written as `score:amount` pairs separated by commas, e.g.
+/// `30:1TiB,50:5TiB`. The tiers are returned ordered by score.
+pub fn parse_tiers(tiers: &str) -> Result<Vec<AutoallocationTier>, LDNError> {
+    let mut tiers = tiers
+        .split(',')
+        .filter(|tier| !tier.trim().is_empty())
+        .map(|tier| {
+            let (min_score, amount) = tier.split_once(':').ok_or_else(|| {
+                LDNError::New(format!(
+                    "Autoallocation tier '{tier}' is not in the form score:amount"
+                ))
+            })?;
+            let min_score = min_score.trim().parse::<f64>().map_err(|e| {
+                LDNError::New(format!("Parse autoallocation tier score failed: {e}"))
+            })?;
+            Ok(AutoallocationTier {
+                min_score,
+                amount: parse_amount(amount)?,
+            })
+        })
+        .collect::<Result<Vec<_>, LDNError>>()?;
+    tiers.sort_by(|a, b| a.min_score.total_cmp(&b.min_score));
+    Ok(tiers)
+}
+
+fn gitcoin_minimum_score() -> Result<f64, LDNError> {
+    get_env_var_or_default("GITCOIN_MINIMUM_SCORE")
+        .parse::<f64>()
+        .map_err(|e| LDNError::New(format!("Parse minimum score to f64 failed: {e}")))
+}
+
+/// Fails when a tier starts below `minimum_score`. Scores below it never pass
+/// the Gitcoin check, so such a tier would silently be granted from the
+/// minimum on.
+pub fn check_tiers_reachable(
+    tiers: &[AutoallocationTier],
+    minimum_score: f64,
+) -> Result<(), LDNError> {
+    match tiers.iter().find(|tier| tier.min_score < minimum_score) {
+        Some(tier) => Err(LDNError::New(format!(
+            "Autoallocation tier from score {} is below GITCOIN_MINIMUM_SCORE {minimum_score}",
+            tier.min_score
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Checks that every tier can be reached with `GITCOIN_MINIMUM_SCORE`.
+pub fn check_tiers_reachable_with_gitcoin(tiers: &[AutoallocationTier]) -> Result<(), LDNError> {
+    check_tiers_reachable(tiers, gitcoin_minimum_score()?)
+}
+
+/// Tiers from `AUTOALLOCATION_TIERS`. When it is empty, a single tier grants
+/// `AUTOALLOCATION_AMOUNT` from `GITCOIN_MINIMUM_SCORE` on.
+pub fn autoallocation_tiers() -> Result<Vec<AutoallocationTier>, LDNError> {
+    let tiers = get_env_var_or_default("AUTOALLOCATION_TIERS");
+    if !tiers.trim().is_empty() {
+        return parse_tiers(&tiers);
+    }
+    Ok(vec![AutoallocationTier {
+        min_score: gitcoin_minimum_score()?,
+        amount: parse_amount(&get_env_var_or_default("AUTOALLOCATION_AMOUNT"))?,
+    }])
+}
+
+/// The tier with the highest minimum score reached by `score`.
+pub fn tier_for_score(tiers: &[AutoallocationTier], score: f64) -> Option<&AutoallocationTier> {
+    tiers.iter().rev().find(|tier| score >= tier.min_score)
+}
+
+fn parse_cap(key: &str) -> Result<Option<u64>, LDNError> {
+    let cap = get_env_var_or_default(key);
+    if cap.trim().is_empty() {
+        return Ok(None);
+    }
+    parse_amount(&cap).map(Some)
+}
+
+/// Caps from `AUTOALLOCATION_WALLET_CAP` and `AUTOALLOCATION_CLIENT_CAP`.
+pub fn autoallocation_caps() -> Result<AutoallocationCaps, LDNError> {
+    Ok(AutoallocationCaps {
+        per_wallet: parse_cap("AUTOALLOCATION_WALLET_CAP")?,
+        per_client: parse_cap("AUTOALLOCATION_CLIENT_CAP")?,
+    })
+}
+
+impl AutoallocationCaps {
+    /// The caps as stored amounts, a cap beyond `i64::MAX` can not be reached.
+    pub fn grant_caps(&self) -> AutoallocationGrantCaps {
+        let cap = |cap: Option<u64>| cap.map(|cap| i64::try_from(cap).unwrap_or(i64::MAX));
+        AutoallocationGrantCaps {
+            per_wallet: cap(self.per_wallet),
+            per_client: cap(self.per_client),
+        }
+    }
+}
+
+/// The error of a grant of `amount` that would take `holder` over `cap`.
+pub fn cap_exceeded(cap: i64, already_granted: i64, amount: u64, holder: &str) -> LDNError {
+    LDNError::Load(format!(
+        "Autoallocation of {amount} B would exceed the lifetime cap of {cap} B for {holder}, {already_granted} B were already granted"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIB: u64 = 1099511627776;
+
+    #[test]
+    fn tiers_are_parsed_and_matched_by_score() {
+        let tiers = parse_tiers("50:5TiB, 30:1099511627776").unwrap();
+        assert_eq!(
+            tiers,
+            vec![
+                AutoallocationTier {
+                    min_score: 30.0,
+                    amount: TIB
+                },
+                AutoallocationTier {
+                    min_score: 50.0,
+                    amount: 5 * TIB
+                },
+            ]
+        );
+        assert_eq!(tier_for_score(&tiers, 29.9), None);
+        assert_eq!(tier_for_score(&tiers, 42.0).unwrap().amount, TIB);
+        assert_eq!(tier_for_score(&tiers, 50.0).unwrap().amount, 5 * TIB);
+        assert!(parse_tiers("30-1TiB").is_err());
+    }
+
+    #[test]
+    fn tiers_below_the_gitcoin_minimum_are_rejected() {
+        let tiers = parse_tiers("30:1TiB,50:5TiB").unwrap();
+        assert!(check_tiers_reachable(&tiers, 30.0).is_ok());
+        assert!(check_tiers_reachable(&tiers, 40.0).is_err());
+
+        let caps = AutoallocationCaps {
+            per_wallet: Some(2 * TIB),
+            per_client: Some(u64::MAX),
+        };
+        assert_eq!(
+            caps.grant_caps(),
+            AutoallocationGrantCaps {
+                per_wallet: Some(2 * TIB as i64),
+                per_client: Some(i64::MAX),
+            }
+        );
+    }
+}
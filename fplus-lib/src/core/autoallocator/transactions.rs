@@ -0,0 +1,624 @@
+use std::time::Duration;
+
+use alloy::{
+    consensus::TxEnvelope,
+    eips::eip2718::{Decodable2718, Encodable2718},
+    network::{EthereumWallet, ReceiptResponse, TransactionBuilder},
+    primitives::{hex, Address, TxHash},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::eth::TransactionRequest,
+};
+use chrono::Utc;
+use fplus_database::database::autoallocation_transactions::{
+    create_autoallocation_transaction, create_autoallocation_transaction_with_next_nonce,
+    get_autoallocation_transactions_by_grant, get_pending_autoallocation_transactions,
+    record_autoallocation_transaction_receipt, set_autoallocation_transaction_status,
+    NewAutoallocationTransaction, TX_STATUS_BROADCAST_UNKNOWN, TX_STATUS_FAILED, TX_STATUS_PENDING,
+    TX_STATUS_REPLACED,
+};
+use fplus_database::database::autoallocations::{
+    delete_autoallocation, get_autoallocation_grant, set_autoallocation_grant_status,
+    GRANT_STATUS_FAILED, GRANT_STATUS_GRANTED,
+};
+use fplus_database::database::leader_leases::acquire_leader_lease;
+use fplus_database::models::autoallocation_grants::Model as AutoallocationGrantModel;
+use fplus_database::models::autoallocation_transactions::Model as AutoallocationTransactionModel;
+use fplus_database::DbPool;
+use serde::Serialize;
+
+use crate::config::get_env_var_or_default;
+use crate::core::autoallocator::profiles::{profile_for_network, AutoallocatorProfile};
+use crate::error::LDNError;
+
+/// Name of the lease held by the instance running the confirmer.
+const CONFIRMER_LEASE: &str = "autoallocation_confirmer";
+
+/// An autoallocation grant with the transactions sent for it.
+#[derive(Serialize, Debug, Clone)]
+pub struct AutoallocationStatus {
+    pub grant: AutoallocationGrantModel,
+    pub transactions: Vec<AutoallocationTransactionModel>,
+}
+
+fn load_error<E: std::fmt::Display>(context: &str) -> impl Fn(E) -> LDNError + '_ {
+    move |e| LDNError::Load(format!("{context}: {e}"))
+}
+
+fn autoallocator_wallet(
+    profile: &AutoallocatorProfile,
+) -> Result<(EthereumWallet, Address), LDNError> {
+    let signer = profile.signer()?;
+    let address = signer.address();
+    Ok((EthereumWallet::from(signer), address))
+}
+
+async fn rpc_provider(profile: &AutoallocatorProfile) -> Result<impl Provider, LDNError> {
+    ProviderBuilder::new()
+        .on_builtin(&profile.rpc_url)
+        .await
+        .map_err(|e| LDNError::New(format!("Building provider failed: {e}")))
+}
+
+fn env_number<T: std::str::FromStr>(key: &str) -> Result<T, LDNError> {
+    get_env_var_or_default(key)
+        .parse::<T>()
+        .map_err(|_| LDNError::New(format!("Failed to parse {key}")))
+}
+
+/// The fee of a replacement: `bump_percent` above the previous fee, or the
+/// current estimate if it is higher.
+fn bumped_fee(previous: u128, estimate: u128, bump_percent: u128) -> u128 {
+    (previous + previous * bump_percent / 100).max(estimate)
+}
+
+/// Signs the request into a transaction to record for the grant.
+async fn sign_transaction(
+    wallet: &EthereumWallet,
+    network: &str,
+    grant_id: i64,
+    request: TransactionRequest,
+) -> Result<NewAutoallocationTransaction, LDNError> {
+    let nonce = request
+        .nonce
+        .ok_or_else(|| LDNError::New("Transaction has no nonce".to_string()))?;
+    let max_fee_per_gas = request.max_fee_per_gas.unwrap_or_default();
+    let max_priority_fee_per_gas = request.max_priority_fee_per_gas.unwrap_or_default();
+    let envelope = request
+        .build(wallet)
+        .await
+        .map_err(|e| LDNError::New(format!("Signing transaction failed: {e}")))?;
+    Ok(NewAutoallocationTransaction {
+        grant_id,
+        network: network.to_string(),
+        tx_hash: envelope.tx_hash().to_string(),
+        nonce: nonce as i64,
+        raw_transaction: hex::encode_prefixed(envelope.encoded_2718()),
+        max_fee_per_gas: max_fee_per_gas.to_string(),
+        max_priority_fee_per_gas: max_priority_fee_per_gas.to_string(),
+    })
+}
+
+/// Broadcasts a recorded transaction. A failed send does not tell whether the
+/// node got the transaction, so it is left to the confirmer as
+/// `broadcast_unknown` rather than failed.
+async fn broadcast(
+    db: &DbPool,
+    provider: &impl Provider,
+    transaction: AutoallocationTransactionModel,
+) -> Result<AutoallocationTransactionModel, LDNError> {
+    let raw_transaction = hex::decode(&transaction.raw_transaction)
+        .map_err(|e| LDNError::Load(format!("Invalid raw transaction: {e}")))?;
+    if let Err(e) = provider.send_raw_transaction(&raw_transaction).await {
+        let error = format!("Broadcasting transaction failed: {e}");
+        log::warn!(
+            "Autoallocation transaction {} left to the confirmer: {error}",
+            transaction.tx_hash
+        );
+        // Left pending, the transaction is still followed by the confirmer.
+        if let Err(e) = set_autoallocation_transaction_status(
+            db,
+            transaction.id,
+            TX_STATUS_BROADCAST_UNKNOWN,
+            Some(&error),
+        )
+        .await
+        {
+            log::error!(
+                "Updating autoallocation transaction {} failed: {e}",
+                transaction.tx_hash
+            );
+            return Ok(transaction);
+        }
+        return Ok(AutoallocationTransactionModel {
+            status: TX_STATUS_BROADCAST_UNKNOWN.to_string(),
+            last_error: Some(error),
+            ..transaction
+        });
+    }
+    Ok(transaction)
+}
+
+/**
+ * Send a transaction for an autoallocation grant without waiting for it to be
+ * mined. The confirmer follows it from the database. The transaction is
+ * recorded before it is broadcast, so once this returns it is tracked even if
+ * the broadcast failed.
+ *
+ * # Arguments
+ * @param db: &DbPool - The database pool
+ * @param profile: &AutoallocatorProfile - The network to send the transaction on
+ * @param grant_id: i64 - The grant the transaction is sent for
+ * @param request: TransactionRequest - The call to send
+ */
+pub async fn submit_transaction(
+    db: &DbPool,
+    profile: &AutoallocatorProfile,
+    grant_id: i64,
+    request: TransactionRequest,
+) -> Result<AutoallocationTransactionModel, LDNError> {
+    let network = profile.network.as_str();
+    let (wallet, from) = autoallocator_wallet(profile)?;
+    let provider = rpc_provider(profile).await?;
+    let chain_id = provider
+        .get_chain_id()
+        .await
+        .map_err(load_error("Getting chain id failed"))?;
+    let fees = provider
+        .estimate_eip1559_fees(None)
+        .await
+        .map_err(load_error("Estimating fees failed"))?;
+
+    let account_nonce = provider
+        .get_transaction_count(from)
+        .pending()
+        .await
+        .map_err(load_error("Getting nonce failed"))?;
+    let request = request
+        .with_from(from)
+        .with_chain_id(chain_id)
+        .with_max_fee_per_gas(fees.max_fee_per_gas)
+        .with_max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+    let gas_limit = provider
+        .estimate_gas(&request)
+        .await
+        .map_err(load_error("Estimating gas failed"))?;
+    let request = request.with_gas_limit(gas_limit);
+    // The nonce is picked under the wallet's lock row, above the on-chain
+    // pending nonce and every tracked transaction that may still be mined.
+    let transaction = create_autoallocation_transaction_with_next_nonce(
+        db,
+        network,
+        &from.to_checksum(None),
+        account_nonce as i64,
+        |nonce| async move {
+            sign_transaction(&wallet, network, grant_id, request.with_nonce(nonce as u64))
+                .await
+                .map_err(|e| e.to_string())
+        },
+    )
+    .await
+    .map_err(load_error("Recording transaction failed"))?;
+    broadcast(db, &provider, transaction).await
+}
+
+/// Marks the grant granted or failed. A failed grant frees the wallet for
+/// another autoallocation right away.
+async fn finish_grant(db: &DbPool, grant_id: i64, succeeded: bool) -> Result<(), LDNError> {
+    let status = if succeeded {
+        GRANT_STATUS_GRANTED
+    } else {
+        GRANT_STATUS_FAILED
+    };
+    set_autoallocation_grant_status(db, grant_id, status)
+        .await
+        .map_err(load_error("Updating grant failed"))?;
+    if !succeeded {
+        if let Some(grant) = get_autoallocation_grant(db, grant_id)
+            .await
+            .map_err(load_error("Getting grant failed"))?
+        {
+            delete_autoallocation(db, grant.evm_wallet_address, &grant.network)
+                .await
+                .map_err(load_error("Deleting autoallocation failed"))?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_hash(transaction: &AutoallocationTransactionModel) -> Result<TxHash, LDNError> {
+    transaction
+        .tx_hash
+        .parse()
+        .map_err(|e| LDNError::Load(format!("Invalid transaction hash: {e}")))
+}
+
+async fn replace_transaction(
+    db: &DbPool,
+    provider: &impl Provider,
+    wallet: &EthereumWallet,
+    from: Address,
+    transaction: &AutoallocationTransactionModel,
+) -> Result<(), LDNError> {
+    let raw_transaction = hex::decode(&transaction.raw_transaction)
+        .map_err(|e| LDNError::Load(format!("Invalid raw transaction: {e}")))?;
+    let envelope = TxEnvelope::decode_2718(&mut raw_transaction.as_slice())
+        .map_err(|e| LDNError::Load(format!("Decoding transaction failed: {e}")))?;
+    let fees = provider
+        .estimate_eip1559_fees(None)
+        .await
+        .map_err(load_error("Estimating fees failed"))?;
+    let bump_percent: u128 = env_number("AUTOALLOCATOR_GAS_BUMP_PERCENT")?;
+    let previous_max_fee = transaction.max_fee_per_gas.parse().unwrap_or_default();
+    let previous_priority_fee = transaction
+        .max_priority_fee_per_gas
+        .parse()
+        .unwrap_or_default();
+
+    let request = <TransactionRequest as From<TxEnvelope>>::from(envelope)
+        .with_from(from)
+        .with_max_fee_per_gas(bumped_fee(
+            previous_max_fee,
+            fees.max_fee_per_gas,
+            bump_percent,
+        ))
+        .with_max_priority_fee_per_gas(bumped_fee(
+            previous_priority_fee,
+            fees.max_priority_fee_per_gas,
+            bump_percent,
+        ));
+    let replacement =
+        sign_transaction(wallet, &transaction.network, transaction.grant_id, request).await?;
+    let replacement = create_autoallocation_transaction(db, &replacement)
+        .await
+        .map_err(load_error("Recording transaction failed"))?;
+    let replacement = broadcast(db, provider, replacement).await?;
+    set_autoallocation_transaction_status(db, transaction.id, TX_STATUS_REPLACED, None)
+        .await
+        .map_err(load_error("Updating transaction failed"))?;
+    log::info!(
+        "Autoallocation transaction {} replaced by {}",
+        transaction.tx_hash,
+        replacement.tx_hash
+    );
+    Ok(())
+}
+
+/// The transactions sent with `nonce`. Any of them may be the one that gets
+/// mined, including the replaced ones and the ones whose broadcast is unknown.
+fn nonce_candidates(
+    transactions: Vec<AutoallocationTransactionModel>,
+    nonce: i64,
+) -> Vec<AutoallocationTransactionModel> {
+    transactions
+        .into_iter()
+        .filter(|candidate| {
+            candidate.nonce == nonce
+                && [
+                    TX_STATUS_PENDING,
+                    TX_STATUS_REPLACED,
+                    TX_STATUS_BROADCAST_UNKNOWN,
+                ]
+                .contains(&candidate.status.as_str())
+        })
+        .collect()
+}
+
+async fn track_transaction(
+    db: &DbPool,
+    provider: &impl Provider,
+    wallet: &EthereumWallet,
+    from: Address,
+    account_nonce: u64,
+    transaction: &AutoallocationTransactionModel,
+) -> Result<(), LDNError> {
+    let candidates = nonce_candidates(
+        get_autoallocation_transactions_by_grant(db, transaction.grant_id)
+            .await
+            .map_err(load_error("Getting grant transactions failed"))?,
+        transaction.nonce,
+    );
+    for candidate in &candidates {
+        let Some(receipt) = provider
+            .get_transaction_receipt(parse_hash(candidate)?)
+            .await
+            .map_err(load_error("Getting receipt failed"))?
+        else {
+            continue;
+        };
+        record_autoallocation_transaction_receipt(
+            db,
+            candidate.id,
+            receipt.status(),
+            receipt.gas_used() as i64,
+            receipt.block_number().map(|block| block as i64),
+        )
+        .await
+        .map_err(load_error("Recording receipt failed"))?;
+        for other in candidates
+            .iter()
+            .filter(|other| other.id != candidate.id && other.status != TX_STATUS_REPLACED)
+        {
+            set_autoallocation_transaction_status(db, other.id, TX_STATUS_REPLACED, None)
+                .await
+                .map_err(load_error("Updating transaction failed"))?;
+        }
+        return finish_grant(db, transaction.grant_id, receipt.status()).await;
+    }
+
+    let stuck_after = chrono::Duration::seconds(env_number("AUTOALLOCATOR_STUCK_AFTER_SECS")?);
+    let is_stuck = Utc::now() - transaction.created_at > stuck_after;
+    if account_nonce > transaction.nonce as u64 {
+        // None of the transactions with this nonce was mined, another one took
+        // it. Only then is the grant known to have failed and its cooldown
+        // released. The receipt may lag behind the nonce, give it until the
+        // transaction is stuck.
+        if is_stuck {
+            set_autoallocation_transaction_status(
+                db,
+                transaction.id,
+                TX_STATUS_FAILED,
+                Some("Nonce was used by another transaction"),
+            )
+            .await
+            .map_err(load_error("Updating transaction failed"))?;
+            finish_grant(db, transaction.grant_id, false).await?;
+        }
+        return Ok(());
+    }
+
+    let known_to_node = provider
+        .get_transaction_by_hash(parse_hash(transaction)?)
+        .await
+        .map_err(load_error("Getting transaction failed"))?
+        .is_some();
+    if !known_to_node {
+        // Dropped from the mempool, or the server stopped before broadcasting.
+        let raw_transaction = hex::decode(&transaction.raw_transaction)
+            .map_err(|e| LDNError::Load(format!("Invalid raw transaction: {e}")))?;
+        let _ = provider
+            .send_raw_transaction(&raw_transaction)
+            .await
+            .map_err(load_error("Rebroadcasting transaction failed"))?;
+        log::info!(
+            "Autoallocation transaction {} rebroadcast",
+            transaction.tx_hash
+        );
+        if transaction.status == TX_STATUS_BROADCAST_UNKNOWN {
+            set_autoallocation_transaction_status(db, transaction.id, TX_STATUS_PENDING, None)
+                .await
+                .map_err(load_error("Updating transaction failed"))?;
+        }
+        return Ok(());
+    }
+    if transaction.status == TX_STATUS_BROADCAST_UNKNOWN {
+        set_autoallocation_transaction_status(db, transaction.id, TX_STATUS_PENDING, None)
+            .await
+            .map_err(load_error("Updating transaction failed"))?;
+    }
+
+    let max_replacements: usize = env_number("AUTOALLOCATOR_MAX_REPLACEMENTS")?;
+    if is_stuck && candidates.len() <= max_replacements {
+        replace_transaction(db, provider, wallet, from, transaction).await?;
+    }
+    Ok(())
+}
+
+/// The confirmer's lease in `leader_leases`, held by one process.
+pub struct ConfirmerLease {
+    holder: String,
+    lease_secs: i64,
+}
+
+impl ConfirmerLease {
+    pub fn new(lease_secs: i64) -> Self {
+        Self {
+            holder: uuidv4::uuid::v4(),
+            lease_secs,
+        }
+    }
+
+    /// Takes or renews the lease, `false` when another instance holds it.
+    async fn renew(&self, db: &DbPool) -> Result<bool, LDNError> {
+        acquire_leader_lease(db, CONFIRMER_LEASE, &self.holder, self.lease_secs)
+            .await
+            .map_err(load_error(
+                "Taking the autoallocation confirmer lease failed",
+            ))
+    }
+}
+
+/**
+ * Follow the pending autoallocation transactions with the profile of their
+ * network: record receipts, rebroadcast transactions the node does not know and
+ * replace stuck ones with higher fees. The lease is renewed before each
+ * transaction, the round stops once another instance took it over.
+ *
+ * # Arguments
+ * @param lease: &ConfirmerLease - The lease of this instance
+ *
+ * # Returns
+ * @return Result<usize, LDNError> - The number of pending transactions
+ */
+pub async fn confirm_pending_transactions(
+    db: &DbPool,
+    lease: &ConfirmerLease,
+) -> Result<usize, LDNError> {
+    let pending = get_pending_autoallocation_transactions(db)
+        .await
+        .map_err(load_error("Getting pending transactions failed"))?;
+    let mut networks: Vec<&str> = pending
+        .iter()
+        .map(|transaction| transaction.network.as_str())
+        .collect();
+    networks.sort_unstable();
+    networks.dedup();
+    for network in networks {
+        let transactions: Vec<&AutoallocationTransactionModel> = pending
+            .iter()
+            .filter(|transaction| transaction.network == network)
+            .collect();
+        match confirm_network_transactions(db, lease, network, &transactions).await {
+            Ok(true) => {}
+            Ok(false) => {
+                log::warn!("Lost the autoallocation confirmer lease, stopping the round");
+                break;
+            }
+            Err(e) => {
+                log::error!("Following autoallocation transactions on {network} failed: {e}")
+            }
+        }
+    }
+    Ok(pending.len())
+}
+
+/// Follows the transactions of a network, `false` when the lease was lost.
+async fn confirm_network_transactions(
+    db: &DbPool,
+    lease: &ConfirmerLease,
+    network: &str,
+    pending: &[&AutoallocationTransactionModel],
+) -> Result<bool, LDNError> {
+    let profile = profile_for_network(network.parse()?)?;
+    let (wallet, from) = autoallocator_wallet(&profile)?;
+    let provider = rpc_provider(&profile).await?;
+    let account_nonce = provider
+        .get_transaction_count(from)
+        .latest()
+        .await
+        .map_err(load_error("Getting nonce failed"))?;
+    for transaction in pending {
+        if !lease.renew(db).await? {
+            return Ok(false);
+        }
+        if let Err(e) =
+            track_transaction(db, &provider, &wallet, from, account_nonce, transaction).await
+        {
+            log::error!(
+                "Tracking autoallocation transaction {} failed: {e}",
+                transaction.tx_hash
+            );
+        }
+    }
+    Ok(true)
+}
+
+/// Polls the pending autoallocation transactions forever, every
+/// `AUTOALLOCATOR_CONFIRMER_INTERVAL_SECS` seconds. Only the instance holding
+/// the confirmer's lease polls, the others take over once it expires after
+/// `AUTOALLOCATOR_CONFIRMER_LEASE_SECS`. The lease must outlast the pause
+/// between rounds, so it is raised to twice the interval when shorter.
+/// Transactions left pending by a restart are picked up on the first round.
+pub async fn run_autoallocation_confirmer(db: DbPool) {
+    let interval = env_number("AUTOALLOCATOR_CONFIRMER_INTERVAL_SECS").unwrap_or(15);
+    let mut lease_secs = env_number("AUTOALLOCATOR_CONFIRMER_LEASE_SECS").unwrap_or(60);
+    let min_lease_secs = confirmer_min_lease_secs(interval);
+    if lease_secs < min_lease_secs {
+        log::warn!(
+            "AUTOALLOCATOR_CONFIRMER_LEASE_SECS {lease_secs} is too short for rounds every {interval}s, using {min_lease_secs}"
+        );
+        lease_secs = min_lease_secs;
+    }
+    let lease = ConfirmerLease::new(lease_secs);
+    loop {
+        match lease.renew(&db).await {
+            Ok(true) => {
+                if let Err(e) = confirm_pending_transactions(&db, &lease).await {
+                    log::error!("Autoallocation confirmer failed: {e}");
+                }
+            }
+            Ok(false) => {}
+            Err(e) => log::error!("{e}"),
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+/// The shortest lease which survives the pause between two rounds and the
+/// tracking of one transaction.
+fn confirmer_min_lease_secs(interval: u64) -> i64 {
+    i64::try_from(interval.saturating_mul(2)).unwrap_or(i64::MAX)
+}
+
+/**
+ * Get an autoallocation grant with its transactions
+ *
+ * # Arguments
+ * @param db: &DbPool - The database pool
+ * @param grant_id: i64 - The ID of the grant
+ */
+pub async fn autoallocation_status(
+    db: &DbPool,
+    grant_id: i64,
+) -> Result<AutoallocationStatus, LDNError> {
+    let grant = get_autoallocation_grant(db, grant_id)
+        .await
+        .map_err(load_error("Getting grant failed"))?
+        .ok_or_else(|| LDNError::New(format!("Autoallocation {grant_id} not found")))?;
+    let transactions = get_autoallocation_transactions_by_grant(db, grant_id)
+        .await
+        .map_err(load_error("Getting grant transactions failed"))?;
+    Ok(AutoallocationStatus {
+        grant,
+        transactions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(id: i64, nonce: i64, status: &str) -> AutoallocationTransactionModel {
+        AutoallocationTransactionModel {
+            id,
+            grant_id: 1,
+            tx_hash: format!("0x{id}"),
+            nonce,
+            raw_transaction: "0x".to_string(),
+            max_fee_per_gas: "1".to_string(),
+            max_priority_fee_per_gas: "1".to_string(),
+            status: status.to_string(),
+            network: "mainnet".to_string(),
+            gas_used: None,
+            block_number: None,
+            last_error: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn every_transaction_sent_with_the_nonce_may_be_mined() {
+        let transactions = vec![
+            transaction(1, 4, TX_STATUS_REPLACED),
+            transaction(2, 4, TX_STATUS_BROADCAST_UNKNOWN),
+            transaction(3, 4, TX_STATUS_PENDING),
+            transaction(4, 4, TX_STATUS_FAILED),
+            transaction(5, 5, TX_STATUS_PENDING),
+        ];
+        let candidates: Vec<i64> = nonce_candidates(transactions, 4)
+            .iter()
+            .map(|candidate| candidate.id)
+            .collect();
+        assert_eq!(candidates, [1, 2, 3]);
+    }
+
+    #[test]
+    fn replacement_fees_are_bumped_or_follow_the_estimate() {
+        assert_eq!(bumped_fee(100, 50, 25), 125);
+        assert_eq!(bumped_fee(100, 200, 25), 200);
+        assert_eq!(bumped_fee(0, 10, 25), 10);
+    }
+
+    #[tokio::test]
+    async fn confirmer_lease_is_renewed_by_its_holder_only() {
+        let db = fplus_database::setup_test_environment().await;
+        let leader = ConfirmerLease::new(60);
+        let follower = ConfirmerLease::new(60);
+        assert!(leader.renew(&db).await.unwrap());
+        assert!(!follower.renew(&db).await.unwrap());
+        assert!(leader.renew(&db).await.unwrap());
+
+        assert_eq!(confirmer_min_lease_secs(15), 30);
+        assert_eq!(confirmer_min_lease_secs(u64::MAX), i64::MAX);
+    }
+}
@@ -0,0 +1,176 @@
+use alloy::{primitives::Address, sol};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::core::application::gitcoin_interaction::get_address_from_signature;
+use crate::error::LDNError;
+
+use super::{KycProvider, KycSubject, KycVerification};
+
+sol! {
+    #[derive(Deserialize, Debug)]
+    struct KycAttestation {
+        string client_id;
+        address subject;
+        string level;
+        string evidence;
+        string issued_at;
+        string expires_at;
+    }
+}
+
+/// An attestation signed by a KYC vendor, in the domain of the KYC messages.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SignedKycAttestation {
+    pub message: KycAttestation,
+    pub signature: String,
+}
+
+/// Accepts attestations signed by one of the vendor's signers for the client
+/// and the address that signed the request.
+#[derive(Debug, Clone)]
+pub struct SignedAttestationKycProvider {
+    name: String,
+    signers: Vec<Address>,
+    accepted_levels: Option<Vec<String>>,
+}
+
+impl SignedAttestationKycProvider {
+    pub fn new(name: String, signers: Vec<Address>, accepted_levels: Option<Vec<String>>) -> Self {
+        Self {
+            name,
+            signers,
+            accepted_levels,
+        }
+    }
+
+    fn check(&self, subject: &KycSubject, now: DateTime<Utc>) -> Result<KycVerification, LDNError> {
+        let attestation = subject
+            .attestation
+            .as_ref()
+            .ok_or_else(|| LDNError::Load("No attestation was submitted".to_string()))?;
+        let message = &attestation.message;
+        let signer = get_address_from_signature(message, &attestation.signature)?;
+        if !self.signers.contains(&signer) {
+            return Err(LDNError::Load(format!(
+                "Attestation is signed by {signer}, which is not a signer of {}",
+                self.name
+            )));
+        }
+        if message.client_id != subject.client_id || message.subject != subject.address {
+            return Err(LDNError::Load(format!(
+                "Attestation is for client {} and address {}",
+                message.client_id, message.subject
+            )));
+        }
+        let issued_at = parse_date(&message.issued_at)?;
+        let expires_at = parse_date(&message.expires_at)?;
+        if now < issued_at || now > expires_at {
+            return Err(LDNError::Load(format!(
+                "Attestation is valid from {} to {}",
+                message.issued_at, message.expires_at
+            )));
+        }
+        if let Some(levels) = &self.accepted_levels {
+            if !levels.contains(&message.level) {
+                return Err(LDNError::Load(format!(
+                    "Attestation level {} is not accepted",
+                    message.level
+                )));
+            }
+        }
+        Ok(KycVerification {
+            provider: self.name.clone(),
+            score: None,
+            level: Some(message.level.clone()),
+            expires_at: Some(expires_at),
+            evidence: Some(message.evidence.clone()).filter(|evidence| !evidence.is_empty()),
+        })
+    }
+}
+
+fn parse_date(date: &str) -> Result<DateTime<Utc>, LDNError> {
+    DateTime::parse_from_rfc3339(date)
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|e| LDNError::Load(format!("Parse {date} to DateTime failed: {e}")))
+}
+
+#[async_trait]
+impl KycProvider for SignedAttestationKycProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn verify(&self, subject: &KycSubject) -> Result<KycVerification, LDNError> {
+        self.check(subject, Utc::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::address;
+    use alloy::signers::{local::PrivateKeySigner, SignerSync};
+    use alloy::sol_types::SolStruct;
+
+    use crate::core::application::gitcoin_interaction::kyc_domain;
+
+    use super::*;
+
+    fn attestation(signer: &PrivateKeySigner, subject: Address) -> SignedKycAttestation {
+        let message = KycAttestation {
+            client_id: "f01234".into(),
+            subject,
+            level: "full".into(),
+            evidence: "case-42".into(),
+            issued_at: "2026-01-01T00:00:00Z".into(),
+            expires_at: "2026-12-31T00:00:00Z".into(),
+        };
+        let hash = message.eip712_signing_hash(&kyc_domain().unwrap());
+        let signature = signer.sign_hash_sync(&hash).unwrap();
+        SignedKycAttestation {
+            message,
+            signature: format!("0x{}", alloy::hex::encode(signature.as_bytes())),
+        }
+    }
+
+    #[test]
+    fn attestations_are_checked_against_signers_subject_and_dates() {
+        let vendor = PrivateKeySigner::random();
+        let client = address!("7638462f3a5f2cdb49609bf4947ae396f9088949");
+        let provider = SignedAttestationKycProvider::new(
+            "Acme KYC".to_string(),
+            vec![vendor.address()],
+            Some(vec!["full".to_string()]),
+        );
+        let subject = KycSubject {
+            client_id: "f01234".to_string(),
+            address: client,
+            attestation: Some(attestation(&vendor, client)),
+        };
+        let now = parse_date("2026-06-01T00:00:00Z").unwrap();
+
+        let verification = provider.check(&subject, now).unwrap();
+        assert_eq!(verification.level.as_deref(), Some("full"));
+        assert_eq!(verification.evidence.as_deref(), Some("case-42"));
+        assert_eq!(
+            verification.to_string(),
+            "Acme KYC attestation of level `full` (evidence `case-42`)"
+        );
+
+        let expired = parse_date("2027-01-01T00:00:00Z").unwrap();
+        assert!(provider.check(&subject, expired).is_err());
+
+        let other_client = KycSubject {
+            address: address!("79e214f3aa3101997ffe810a57eca4586e3bdeb2"),
+            ..subject.clone()
+        };
+        assert!(provider.check(&other_client, now).is_err());
+
+        let other_vendor = KycSubject {
+            attestation: Some(attestation(&PrivateKeySigner::random(), client)),
+            ..subject
+        };
+        assert!(provider.check(&other_vendor, now).is_err());
+    }
+}
@@ -0,0 +1,43 @@
+use async_trait::async_trait;
+
+use crate::core::application::gitcoin_interaction::{
+    verify_on_gitcoin, verify_on_gitcoin_with_minimum_score,
+};
+use crate::error::LDNError;
+
+use super::{KycProvider, KycSubject, KycVerification};
+
+/// Verifies the Gitcoin Passport score of the address that signed the request.
+#[derive(Debug, Clone)]
+pub struct GitcoinKycProvider {
+    minimum_score: Option<f64>,
+}
+
+impl GitcoinKycProvider {
+    pub fn new(minimum_score: Option<f64>) -> Self {
+        Self { minimum_score }
+    }
+}
+
+#[async_trait]
+impl KycProvider for GitcoinKycProvider {
+    fn name(&self) -> &str {
+        "Gitcoin Passport"
+    }
+
+    async fn verify(&self, subject: &KycSubject) -> Result<KycVerification, LDNError> {
+        let score = match self.minimum_score {
+            Some(minimum_score) => {
+                verify_on_gitcoin_with_minimum_score(&subject.address, minimum_score).await?
+            }
+            None => verify_on_gitcoin(&subject.address).await?,
+        };
+        Ok(KycVerification {
+            provider: self.name().to_string(),
+            score: Some(score),
+            level: None,
+            expires_at: None,
+            evidence: Some(subject.address.to_checksum(None)),
+        })
+    }
+}
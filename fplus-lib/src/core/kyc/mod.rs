@@ -0,0 +1,206 @@
+use std::fmt;
+
+use alloy::primitives::Address;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use fplus_database::database::allocators::get_allocator;
+use fplus_database::DbPool;
+use serde::{Deserialize, Serialize};
+
+use crate::error::LDNError;
+
+pub mod attestation;
+pub mod gitcoin;
+
+pub use self::attestation::{KycAttestation, SignedAttestationKycProvider, SignedKycAttestation};
+pub use self::gitcoin::GitcoinKycProvider;
+
+/// A KYC provider as configured in the `kyc_providers` list of an allocator
+/// JSON file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KycProviderConfig {
+    /// Gitcoin Passport score on Optimism.
+    Gitcoin {
+        /// Defaults to `GITCOIN_MINIMUM_SCORE`.
+        #[serde(default)]
+        minimum_score: Option<f64>,
+    },
+    /// An attestation signed by the allocator's own KYC vendor.
+    SignedAttestation {
+        name: String,
+        /// Addresses the vendor signs attestations with.
+        signers: Vec<Address>,
+        /// Levels accepted by the allocator, any level when unset.
+        #[serde(default)]
+        accepted_levels: Option<Vec<String>>,
+    },
+}
+
+impl KycProviderConfig {
+    pub fn provider(&self) -> Box<dyn KycProvider> {
+        match self {
+            KycProviderConfig::Gitcoin { minimum_score } => {
+                Box::new(GitcoinKycProvider::new(*minimum_score))
+            }
+            KycProviderConfig::SignedAttestation {
+                name,
+                signers,
+                accepted_levels,
+            } => Box::new(SignedAttestationKycProvider::new(
+                name.clone(),
+                signers.clone(),
+                accepted_levels.clone(),
+            )),
+        }
+    }
+}
+
+/// Checks a list of provider configurations and serializes it for the
+/// `kyc_providers` column.
+pub fn kyc_providers_to_json(providers: &[KycProviderConfig]) -> Result<String, LDNError> {
+    if providers.is_empty() {
+        return Err(LDNError::New(
+            "kyc_providers must list at least one provider".to_string(),
+        ));
+    }
+    for provider in providers {
+        if let KycProviderConfig::SignedAttestation { name, signers, .. } = provider {
+            if signers.is_empty() {
+                return Err(LDNError::New(format!("KYC provider {name} has no signers")));
+            }
+        }
+    }
+    serde_json::to_string(providers)
+        .map_err(|e| LDNError::New(format!("Serialize KYC providers failed: {e}")))
+}
+
+/// What the client submitted to be verified.
+#[derive(Debug, Clone)]
+pub struct KycSubject {
+    pub client_id: String,
+    /// The address that signed the KYC request.
+    pub address: Address,
+    pub attestation: Option<SignedKycAttestation>,
+}
+
+/// The outcome of a successful verification.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KycVerification {
+    pub provider: String,
+    pub score: Option<f64>,
+    pub level: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Where the verification can be looked up, e.g. the vendor's case ID.
+    pub evidence: Option<String>,
+}
+
+impl fmt::Display for KycVerification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(score) = self.score {
+            return write!(f, "{} score `{}`", self.provider, score.round() as i64);
+        }
+        write!(f, "{} attestation", self.provider)?;
+        if let Some(level) = &self.level {
+            write!(f, " of level `{level}`")?;
+        }
+        if let Some(evidence) = &self.evidence {
+            write!(f, " (evidence `{evidence}`)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Verifies the identity of a client.
+#[async_trait]
+pub trait KycProvider: Send + Sync + fmt::Debug {
+    fn name(&self) -> &str;
+
+    /// Returns an error when the subject is not verified by this provider.
+    async fn verify(&self, subject: &KycSubject) -> Result<KycVerification, LDNError>;
+}
+
+/// Returns the providers configured for the allocator. Allocators without
+/// `kyc_providers` use Gitcoin Passport.
+pub async fn kyc_providers(
+    db: &DbPool,
+    owner: &str,
+    repo: &str,
+) -> Result<Vec<KycProviderConfig>, LDNError> {
+    let allocator = get_allocator(db, owner, repo)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get an allocator: {e}")))?;
+    match allocator.and_then(|allocator| allocator.kyc_providers) {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| LDNError::Load(format!("Parse KYC providers failed: {e}"))),
+        None => Ok(vec![KycProviderConfig::Gitcoin {
+            minimum_score: None,
+        }]),
+    }
+}
+
+/// The providers verifying the clients of autoallocations. The autoallocator
+/// belongs to no allocator and picks the tier by score, so it only accepts
+/// Gitcoin Passport with `GITCOIN_MINIMUM_SCORE`.
+pub fn autoallocation_kyc_providers() -> Vec<KycProviderConfig> {
+    vec![KycProviderConfig::Gitcoin {
+        minimum_score: None,
+    }]
+}
+
+/// Tries the providers in order and returns the first verification. When
+/// none verifies the subject, the error lists every provider's reason.
+pub async fn verify_kyc(
+    providers: &[KycProviderConfig],
+    subject: &KycSubject,
+) -> Result<KycVerification, LDNError> {
+    let mut reasons = Vec::new();
+    for config in providers {
+        let provider = config.provider();
+        match provider.verify(subject).await {
+            Ok(verification) => return Ok(verification),
+            Err(e) => reasons.push(format!("{}: {e}", provider.name())),
+        }
+    }
+    Err(LDNError::Load(format!(
+        "KYC verification failed. {}",
+        reasons.join("; ")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn providers_are_parsed_from_the_allocator_json() {
+        let providers: Vec<KycProviderConfig> = serde_json::from_str(
+            r#"[
+                {"type": "signed_attestation", "name": "Acme KYC", "signers": ["0x7638462f3a5f2cdb49609bf4947ae396f9088949"], "accepted_levels": ["full"]},
+                {"type": "gitcoin", "minimum_score": 20}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(providers[0].provider().name(), "Acme KYC");
+        assert_eq!(
+            providers[1],
+            KycProviderConfig::Gitcoin {
+                minimum_score: Some(20.0)
+            }
+        );
+        assert!(kyc_providers_to_json(&providers).is_ok());
+        assert!(kyc_providers_to_json(&[]).is_err());
+
+        let without_signers = KycProviderConfig::SignedAttestation {
+            name: "Acme KYC".to_string(),
+            signers: vec![],
+            accepted_levels: None,
+        };
+        assert!(kyc_providers_to_json(&[without_signers]).is_err());
+
+        // Autoallocation tiers need a score.
+        assert!(autoallocation_kyc_providers()
+            .iter()
+            .all(|provider| matches!(provider, KycProviderConfig::Gitcoin { .. })));
+    }
+}
@@ -7,14 +7,16 @@ use alloy::primitives::Address;
 use application::file::{SpsChangeRequest, StorageProviderChangeVerifier};
 use chrono::{DateTime, Local, Utc};
 use fplus_database::database::applications::get_application;
+use fplus_database::database::autoallocations::AutoallocationFilter;
 use fplus_database::database::comparable_applications::create_comparable_application;
+use fplus_database::database::outbox::enqueue_outbox_messages;
+use fplus_database::database::used_signatures;
 use fplus_database::models::comparable_applications::ApplicationComparableData;
-use futures::future;
+use fplus_database::DbPool;
 use octocrab::models::{
     pulls::PullRequest,
     repos::{Content, ContentItems},
 };
-use rayon::prelude::*;
 use reqwest::Response;
 use serde::{Deserialize, Serialize};
 use serde_json::from_str;
@@ -31,26 +33,32 @@ use crate::{
     core::application::{
         file::Allocations,
         gitcoin_interaction::{
-            get_address_from_signature, verify_on_gitcoin, ExpirableSolStruct, KycApproval,
+            get_address_from_signature, signature_hash, ExpirableSolStruct, KycApproval,
             KycAutoallocationApproval,
         },
+        store::{
+            application_store, content_sha, ApplicationChange, ApplicationCommit, ApplicationStore,
+            ApplicationStoreKind, NewApplicationFile,
+        },
     },
     error::LDNError,
     external_services::{
         filecoin::{get_allowance_for_address_direct, get_multisig_threshold_for_actor},
-        github::{
-            github_async_new, CreateMergeRequestData, CreateRefillMergeRequestData, GithubWrapper,
-        },
+        github::{github_async_new, GithubWrapper},
     },
     helpers::{
         is_allocator_allowance_bigger_than_allocation_amount, parse_size_to_bytes, process_amount,
     },
-    parsers::ParsedIssue,
+    parsers::{
+        validation::{validate_issue, AllocatorRequirements, ValidationReport},
+        ParsedIssue,
+    },
 };
 use fplus_database::database::allocation_amounts::get_allocation_quantity_options;
 use fplus_database::database::{
     self,
     allocators::{get_allocator, update_allocator_threshold},
+    application_versions::WriteContext,
 };
 
 use fplus_database::models::applications::Model as ApplicationModel;
@@ -60,12 +68,22 @@ use self::application::file::{
     DeepCompare, ValidVerifierList, VerifierInput,
 };
 
+use crate::core::allocator::allocator_repo_branch;
+use crate::core::allocator::comment_templates::{CommentKind, CommentTemplates, CommentVariables};
 use crate::core::application::file::{Allocation, DecreaseClientAllowanceVerifier};
+use crate::core::autoallocator::profiles::AutoallocatorNetwork;
+use crate::core::kyc::{
+    kyc_providers, verify_kyc, KycSubject, KycVerification, SignedKycAttestation,
+};
+use crate::core::outbox::{outbox_messages, status_comment_messages, SideEffect};
 use std::collections::HashSet;
 
 pub mod allocator;
+pub mod allowance_monitor;
 pub mod application;
 pub mod autoallocator;
+pub mod kyc;
+pub mod outbox;
 
 #[derive(Deserialize)]
 pub struct CreateApplicationInfo {
@@ -74,6 +92,14 @@ pub struct CreateApplicationInfo {
     pub repo: String,
 }
 
+#[derive(Deserialize)]
+pub struct ValidateIssueInfo {
+    pub issue_number: String,
+    pub owner: String,
+    pub repo: String,
+    pub post_comment: Option<bool>,
+}
+
 #[derive(Deserialize)]
 pub struct TriggerSSAInfo {
     pub amount: String,
@@ -81,7 +107,7 @@ pub struct TriggerSSAInfo {
     pub early_refill_comment: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct NewPrNumberAndFileSha {
     pub pr_number: u64,
     pub file_sha: String,
@@ -162,13 +188,37 @@ pub struct MoreInfoNeeded {
     pub verifier_message: String,
 }
 
+/// Times an application update is re-applied after a concurrent change
+/// before the conflict is reported to the caller.
+const MAX_CONFLICT_RETRIES: usize = 3;
+
+/// New application file produced by an action, together with the commit
+/// message and whatever the action needs after committing.
+struct FileUpdate<T> {
+    file: ApplicationFile,
+    commit_message: String,
+    outcome: T,
+}
+
+struct CommittedFileUpdate<T> {
+    file: ApplicationFile,
+    content: String,
+    sha: String,
+    outcome: T,
+}
+
 #[derive(Debug)]
 pub struct LDNApplication {
+    db: DbPool,
     github: GithubWrapper,
+    store: Arc<dyn ApplicationStore>,
     pub application_id: String,
     pub file_sha: String,
     pub file_name: String,
     pub branch_name: String,
+    /// The version the client acts on, from `If-Match`. Commits are based on
+    /// it and fail instead of being re-applied when the file moved on.
+    expected_sha: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -229,9 +279,48 @@ pub struct AllocatorUpdateForceInfo {
     pub allocators: Option<Vec<GithubQueryParams>>,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct SignedAllocationsQueryParams {
+    pub since: DateTime<Utc>,
+    pub until: Option<DateTime<Utc>>,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct LastAutoallocationQueryParams {
     pub evm_wallet_address: Address,
+    pub network: Option<AutoallocatorNetwork>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AutoallocatorNetworkQueryParams {
+    pub network: Option<AutoallocatorNetwork>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct AutoallocationHistoryQueryParams {
+    pub evm_wallet_address: Option<Address>,
+    pub client_fil_address: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub status: Option<String>,
+    pub network: Option<AutoallocatorNetwork>,
+    /// Size of a history page, ignored by the stats.
+    pub limit: Option<u64>,
+    /// `next_cursor` of the previous history page, ignored by the stats.
+    pub cursor: Option<i64>,
+}
+
+impl From<AutoallocationHistoryQueryParams> for AutoallocationFilter {
+    fn from(query: AutoallocationHistoryQueryParams) -> Self {
+        AutoallocationFilter {
+            evm_wallet_address: query.evm_wallet_address.map(Into::into),
+            client_fil_address: query.client_fil_address,
+            since: query.since,
+            until: query.until,
+            status: query.status,
+            network: query.network.map(|network| network.to_string()),
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -276,6 +365,9 @@ pub struct VerifierActionsQueryParams {
 pub struct SubmitKYCInfo {
     pub message: KycApproval,
     pub signature: String,
+    /// Required by allocators verifying clients with a signed attestation.
+    #[serde(default)]
+    pub attestation: Option<SignedKycAttestation>,
 }
 
 #[derive(Debug, Clone)]
@@ -285,6 +377,7 @@ pub struct ApplicationFileWithDate {
     pub pr_number: u64,
     pub sha: String,
     pub path: String,
+    pub branch_name: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -307,11 +400,12 @@ pub struct AllocationObject {
 
 impl LDNApplication {
     pub async fn single_active(
+        db: &DbPool,
         pr_number: u64,
         owner: String,
         repo: String,
     ) -> Result<ApplicationFile, LDNError> {
-        let gh = github_async_new(owner, repo).await?;
+        let gh = github_async_new(db, owner, repo).await?;
         let (_, pull_request) = gh
             .get_pull_request_files(pr_number)
             .await
@@ -340,6 +434,7 @@ impl LDNApplication {
     }
 
     async fn get_pr_files_and_app(
+        db: &DbPool,
         owner: String,
         repo: String,
         pr_number: u64,
@@ -350,7 +445,7 @@ impl LDNApplication {
         )>,
         LDNError,
     > {
-        let gh = github_async_new(owner, repo).await?;
+        let gh = github_async_new(db, owner, repo).await?;
         let files = match gh.get_pull_request_files(pr_number).await {
             Ok(files) => files,
             Err(_) => return Ok(None),
@@ -381,11 +476,12 @@ impl LDNApplication {
     }
 
     async fn load_pr_files(
+        db: &DbPool,
         pr: PullRequest,
         owner: String,
         repo: String,
     ) -> Result<Option<(String, String, ApplicationFile, PullRequest)>, LDNError> {
-        let result = Self::get_pr_files_and_app(owner.clone(), repo.clone(), pr.number).await;
+        let result = Self::get_pr_files_and_app(db, owner.clone(), repo.clone(), pr.number).await;
         if let Some((files, app)) = result? {
             Ok(Some((
                 files
@@ -409,24 +505,39 @@ impl LDNApplication {
     }
 
     async fn get_application_model(
+        db: &DbPool,
         application_id: String,
         owner: String,
         repo: String,
     ) -> Result<ApplicationModel, LDNError> {
         let app_model_result =
-            database::applications::get_application(application_id, owner, repo, None)
+            database::applications::get_application(db, application_id, owner, repo, None)
                 .await
                 .map_err(|e| LDNError::Load(format!("Failed to get application: {e}")))?;
         Ok(app_model_result)
     }
 
     pub async fn load_from_db(
+        db: &DbPool,
         application_id: String,
         owner: String,
         repo: String,
     ) -> Result<ApplicationFile, LDNError> {
+        Self::load_versioned_from_db(db, application_id, owner, repo)
+            .await
+            .map(|(application_file, _)| application_file)
+    }
+
+    /// Application file together with its sha, the version token clients send
+    /// back in `If-Match` when acting on the application.
+    pub async fn load_versioned_from_db(
+        db: &DbPool,
+        application_id: String,
+        owner: String,
+        repo: String,
+    ) -> Result<(ApplicationFile, Option<String>), LDNError> {
         let app_model =
-            Self::get_application_model(application_id.clone(), owner.clone(), repo.clone())
+            Self::get_application_model(db, application_id.clone(), owner.clone(), repo.clone())
                 .await?;
 
         let app_str = app_model.application.ok_or_else(|| {
@@ -435,16 +546,20 @@ impl LDNApplication {
             ))
         })?;
 
-        ApplicationFile::from_str(&app_str)
-            .map_err(|e| LDNError::Load(format!("Failed to parse application file from DB: {e}")))
+        let application_file = ApplicationFile::from_str(&app_str).map_err(|e| {
+            LDNError::Load(format!("Failed to parse application file from DB: {e}"))
+        })?;
+        Ok((application_file, app_model.sha))
     }
 
     pub async fn application_with_allocation_amount(
+        db: &DbPool,
         application_id: String,
         owner: String,
         repo: String,
     ) -> Result<ApplicationWithAllocation, LDNError> {
         let app_model = database::applications::get_application(
+            db,
             application_id.clone(),
             owner.clone(),
             repo.clone(),
@@ -464,7 +579,7 @@ impl LDNApplication {
             LDNError::Load(format!("Failed to parse application file from DB: {e}"))
         })?;
 
-        let db_allocator = get_allocator(&owner, &repo)
+        let db_allocator = get_allocator(db, &owner, &repo)
             .await
             .map_err(|e| LDNError::Load(format!("Failed to get an allocator: {e}")))?
             .ok_or(LDNError::Load("Allocator not found.".to_string()))?;
@@ -473,11 +588,12 @@ impl LDNApplication {
             .allocation_amount_type
             .unwrap_or("".to_string());
 
-        let allocation_amount_quantity_options = get_allocation_quantity_options(db_allocator.id)
-            .await
-            .map_err(|e| {
-                LDNError::Load(format!("Failed to get allocation_quantity_options: {e}"))
-            })?;
+        let allocation_amount_quantity_options =
+            get_allocation_quantity_options(db, db_allocator.id)
+                .await
+                .map_err(|e| {
+                    LDNError::Load(format!("Failed to get allocation_quantity_options: {e}"))
+                })?;
 
         Ok(ApplicationWithAllocation {
             allocation: {
@@ -491,56 +607,46 @@ impl LDNApplication {
     }
 
     pub async fn load(
+        db: &DbPool,
         application_id: String,
         owner: String,
         repo: String,
     ) -> Result<Self, LDNError> {
-        let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
-        let pull_requests = gh
-            .list_pull_requests()
-            .await
-            .map_err(|e| LDNError::Load(format!("Failed to get list of pull requests: {e}")))?;
-        let pull_requests = future::try_join_all(
-            pull_requests
-                .into_iter()
-                .map(|pr: PullRequest| {
-                    LDNApplication::load_pr_files(pr, owner.clone(), repo.clone())
-                })
-                .collect::<Vec<_>>(),
-        )
-        .await?;
-        let result = pull_requests
-            .par_iter()
-            .filter(|pr| {
-                if let Some(r) = pr {
-                    r.2.id.clone() == application_id.clone()
-                } else {
-                    false
-                }
-            })
-            .collect::<Vec<_>>();
-        if let Some(Some(r)) = result.first() {
+        let gh = github_async_new(db, owner.to_string(), repo.to_string()).await?;
+        let store = application_store(db, &owner, &repo).await?;
+        let active_app = store
+            .list_active()
+            .await?
+            .into_iter()
+            .find(|app| app.application_file.id == application_id);
+        if let Some(app) = active_app {
             return Ok(Self {
+                db: db.clone(),
                 github: gh,
-                application_id: r.2.id.clone(),
-                file_sha: r.0.clone(),
-                file_name: r.1.clone(),
-                branch_name: r.3.head.ref_field.clone(),
+                store,
+                application_id: app.application_file.id,
+                file_sha: app.sha,
+                file_name: app.path,
+                branch_name: app.branch_name,
+                expected_sha: None,
             });
         }
 
-        let app = Self::single_merged(application_id, owner.clone(), repo.clone()).await?;
+        let app = Self::single_merged(db, application_id, owner.clone(), repo.clone()).await?;
         Ok(Self {
+            db: db.clone(),
             github: gh,
+            store,
             application_id: app.1.file.id.clone(),
             file_sha: app.0.sha.clone(),
             file_name: app.0.path.clone(),
             branch_name: "main".to_string(),
+            expected_sha: None,
         })
     }
 
-    pub async fn all_applications() -> Result<Vec<ApplicationResponse>, LDNError> {
-        let applications = database::applications::get_active_applications()
+    pub async fn all_applications(db: &DbPool) -> Result<Vec<ApplicationResponse>, LDNError> {
+        let applications = database::applications::get_active_applications(db)
             .await
             .map_err(|e| {
                 LDNError::Load(format!("Failed to get applications from the database: {e}"))
@@ -549,8 +655,10 @@ impl LDNApplication {
         Ok(applications_response)
     }
 
-    pub async fn get_closed_applications() -> Result<Vec<ApplicationResponse>, LDNError> {
-        let applications = database::applications::get_closed_applications()
+    pub async fn get_closed_applications(
+        db: &DbPool,
+    ) -> Result<Vec<ApplicationResponse>, LDNError> {
+        let applications = database::applications::get_closed_applications(db)
             .await
             .map_err(|e| {
                 LDNError::Load(format!("Failed to get applications from the database: {e}"))
@@ -560,22 +668,26 @@ impl LDNApplication {
     }
 
     pub async fn get_allocator_closed_applications(
+        db: &DbPool,
         owner: &str,
         repo: &str,
     ) -> Result<Vec<ApplicationResponse>, LDNError> {
-        let applications = database::applications::get_allocator_closed_applications(owner, repo)
-            .await
-            .map_err(|e| {
-                LDNError::Load(format!("Failed to get applications from the database: {e}"))
-            })?;
+        let applications =
+            database::applications::get_allocator_closed_applications(db, owner, repo)
+                .await
+                .map_err(|e| {
+                    LDNError::Load(format!("Failed to get applications from the database: {e}"))
+                })?;
         let applications_response = Self::prepare_applications_response(applications);
         Ok(applications_response)
     }
 
     pub async fn get_applications_by_client_contract_address(
+        db: &DbPool,
         client_contract_address: &str,
     ) -> Result<Vec<ApplicationResponse>, LDNError> {
         let applications = database::applications::get_applications_by_client_contract_address(
+            db,
             client_contract_address,
         )
         .await
@@ -606,12 +718,14 @@ impl LDNApplication {
     }
 
     pub async fn active(
+        db: &DbPool,
         owner: String,
         repo: String,
         filter: Option<String>,
     ) -> Result<Vec<ApplicationResponse>, LDNError> {
         // Get all active applications from the database.
         let active_apps = database::applications::get_applications_with_open_pull_request(
+            db,
             Some(owner),
             Some(repo),
         )
@@ -651,106 +765,85 @@ impl LDNApplication {
     }
 
     pub async fn active_apps_with_last_update(
+        db: &DbPool,
         owner: String,
         repo: String,
         filter: Option<String>,
     ) -> Result<Vec<ApplicationFileWithDate>, LDNError> {
-        let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
-        let mut apps: Vec<ApplicationFileWithDate> = Vec::new();
-        let pull_requests = gh
-            .list_pull_requests()
-            .await
-            .map_err(|e| LDNError::Load(format!("Failed to get list of pull requests: {e}")))?;
-        let pull_requests = future::try_join_all(
-            pull_requests
-                .into_iter()
-                .map(|pr: PullRequest| {
-                    LDNApplication::load_pr_files(pr, owner.clone(), repo.clone())
-                })
-                .collect::<Vec<_>>(),
-        )
-        .await
-        .map_err(|e| LDNError::Load(format!("Failed to get list of PR files: {e}")))?;
-        for (sha, path, app_file, pr_info) in pull_requests.into_iter().flatten() {
-            if let Some(updated_at) = pr_info.updated_at {
-                let app_with_date = ApplicationFileWithDate {
-                    application_file: app_file.clone(),
-                    updated_at,
-                    pr_number: pr_info.number,
-                    sha,
-                    path,
-                };
-
-                if filter.as_ref().is_none_or(|f| &app_file.id == f) {
-                    apps.push(app_with_date);
-                }
-            }
+        let store = application_store(db, &owner, &repo).await?;
+        let mut apps = store.list_active().await?;
+        if let Some(filter_val) = filter {
+            apps.retain(|app| app.application_file.id == filter_val);
         }
         Ok(apps)
     }
 
     pub async fn merged_apps_with_last_update(
+        db: &DbPool,
         owner: String,
         repo: String,
         filter: Option<String>,
     ) -> Result<Vec<ApplicationFileWithDate>, LDNError> {
-        let gh = Arc::new(github_async_new(owner.to_string(), repo.to_string()).await?);
-
-        let applications_path = "applications";
-        let mut all_files_result = gh.get_files(applications_path).await.map_err(|e| {
-            LDNError::Load(format!(
-                "Failed to retrieve all files from GitHub. Reason: {e}"
-            ))
-        })?;
-
-        all_files_result
-            .items
-            .retain(|item| item.download_url.is_some() && item.name.ends_with(".json"));
-
-        let mut application_files_with_date: Vec<ApplicationFileWithDate> = vec![];
-        for fd in all_files_result.items {
-            let gh_clone = Arc::clone(&gh);
-            let result = gh_clone.get_last_modification_date(&fd.path).await;
-
-            if let Ok(updated_at) = result {
-                let map_result = LDNApplication::map_merged(fd).await;
-
-                if let Ok(Some((content, app_file))) = map_result {
-                    application_files_with_date.push(ApplicationFileWithDate {
-                        application_file: app_file,
-                        updated_at,
-                        pr_number: 0,
-                        sha: content.sha,
-                        path: content.path,
-                    });
-                }
-            } else {
-                log::warn!("Failed to get last modification date for file: {}", fd.path);
-            }
+        let store = application_store(db, &owner, &repo).await?;
+        let mut apps = store.list_merged().await?;
+        if let Some(filter_val) = filter {
+            apps.retain(|app| app.application_file.id == filter_val);
         }
-
-        let filtered_files: Vec<ApplicationFileWithDate> = if let Some(filter_val) = filter {
-            application_files_with_date
-                .into_iter()
-                .filter(|f| f.application_file.id == filter_val)
-                .collect()
-        } else {
-            application_files_with_date
-        };
-
-        Ok(filtered_files)
+        Ok(apps)
     }
 
     /// Create New Application
-    pub async fn new_from_issue(info: CreateApplicationInfo) -> Result<Self, LDNError> {
+    pub async fn new_from_issue(
+        db: &DbPool,
+        info: CreateApplicationInfo,
+    ) -> Result<Self, LDNError> {
         let issue_number = info.issue_number;
-        let gh = github_async_new(info.owner.to_string(), info.repo.to_string()).await?;
-        let (mut parsed_ldn, issue_reporter_handle) = LDNApplication::parse_application_issue(
+        let gh = github_async_new(db, info.owner.to_string(), info.repo.to_string()).await?;
+        let store = application_store(db, &info.owner, &info.repo).await?;
+        let (issue_body, labels, issue_reporter_handle) = Self::fetch_application_issue(
+            db,
             issue_number.clone(),
             info.owner.clone(),
             info.repo.clone(),
         )
         .await?;
+        let report =
+            Self::application_issue_report(db, &info.owner, &info.repo, &issue_body, &labels)
+                .await?;
+        if report.has_errors() {
+            Self::add_comment_to_issue(
+                db,
+                issue_number.clone(),
+                info.owner.clone(),
+                info.repo.clone(),
+                report.to_comment(),
+            )
+            .await?;
+            Self::add_error_label(
+                db,
+                issue_number.clone(),
+                "".to_string(),
+                info.owner.clone(),
+                info.repo.clone(),
+            )
+            .await?;
+            return Err(LDNError::New(
+                "Application issue failed validation".to_string(),
+            ));
+        }
+        // Warnings, e.g. fewer replicas than the allocator requires, are
+        // reported to the client without holding the application back.
+        if !report.is_empty() {
+            Self::add_comment_to_issue(
+                db,
+                issue_number.clone(),
+                info.owner.clone(),
+                info.repo.clone(),
+                report.to_comment(),
+            )
+            .await?;
+        }
+        let mut parsed_ldn = ParsedIssue::from_issue(&issue_body, &labels)?;
 
         parsed_ldn.datacap.total_requested_amount =
             process_amount(parsed_ldn.datacap.total_requested_amount.clone());
@@ -767,9 +860,9 @@ impl LDNApplication {
             "false".to_string()
         };
 
-        match gh.get_file(&file_name, &branch_name).await {
+        match store.read_file(&file_name, &branch_name).await? {
             // If the file does not exist, create a new application file
-            Err(_) => {
+            None => {
                 log::info!("File not found, creating new application file");
                 let application_file = ApplicationFile::new(
                     issue_number.clone(),
@@ -782,7 +875,7 @@ impl LDNApplication {
                 )
                 .await;
 
-                let applications = database::applications::get_active_applications()
+                let applications = database::applications::get_active_applications(db)
                     .await
                     .map_err(|e| LDNError::Load(format!("Failed to get applications: {e}")))?;
 
@@ -793,6 +886,7 @@ impl LDNApplication {
                     // Add a comment to the GitHub issue
                     log::info!("Application already exists in the database");
                     Self::issue_pathway_mismatch_comment(
+                        db,
                         issue_number.clone(),
                         info.owner.clone(),
                         info.repo.clone(),
@@ -813,6 +907,7 @@ impl LDNApplication {
                             log::info!("Allowance found and is not zero. Value is {}", allowance);
                             // If allowance is found and is not zero, issue the pathway mismatch comment
                             Self::issue_pathway_mismatch_comment(
+                                db,
                                 issue_number.clone(),
                                 info.owner.clone(),
                                 info.repo.clone(),
@@ -833,6 +928,7 @@ impl LDNApplication {
                             if e.to_string().contains("DMOB api") {
                                 log::error!("Error getting allowance for address. Unable to access blockchain data");
                                 Self::add_error_label(
+                                    db,
                                     issue_number.clone(),
                                     "".to_string(),
                                     info.owner.clone(),
@@ -841,6 +937,7 @@ impl LDNApplication {
                                 .await?;
 
                                 Self::add_comment_to_issue(
+                                    db,
                                     issue_number.clone(),
                                     info.owner.clone(),
                                     info.repo.clone(),
@@ -860,6 +957,7 @@ impl LDNApplication {
                     if client_allocation.count.is_some() {
                         log::info!("Allocation found for client {}", application_id);
                         Self::issue_pathway_mismatch_comment(
+                            db,
                             issue_number,
                             info.owner,
                             info.repo,
@@ -879,6 +977,7 @@ impl LDNApplication {
                     Ok(f) => f,
                     Err(e) => {
                         Self::add_error_label(
+                            db,
                             application_file.issue_number.clone(),
                             "".to_string(),
                             info.owner.clone(),
@@ -891,18 +990,21 @@ impl LDNApplication {
                     }
                 };
                 let app_id = parsed_ldn.id.clone();
-                let file_sha = LDNPullRequest::create_pr_for_new_application(
+                let NewPrNumberAndFileSha {
+                    pr_number,
+                    file_sha,
+                } = LDNPullRequest::create_pr_for_new_application(
+                    store.as_ref(),
                     issue_number.clone(),
                     parsed_ldn.client.name.clone(),
                     branch_name.clone(),
                     LDNPullRequest::application_path(&app_id),
                     file_content.clone(),
-                    info.owner.clone(),
-                    info.repo.clone(),
                     application_id.clone(),
                 )
                 .await?;
                 Self::issue_waiting_for_gov_review(
+                    db,
                     issue_number.clone(),
                     info.owner.clone(),
                     info.repo.clone(),
@@ -910,85 +1012,82 @@ impl LDNApplication {
                 )
                 .await?;
                 Self::update_issue_labels(
+                    db,
                     application_file.issue_number.clone(),
                     &[AppState::Submitted.as_str(), "waiting for allocator review"],
                     info.owner.clone(),
                     info.repo.clone(),
                 )
                 .await?;
-                let prs = gh
-                    .get_pull_request_by_head(&branch_name)
-                    .await
-                    .map_err(|e| {
-                        LDNError::Load(format!("Failed to get pull request by head: {e}"))
-                    })?;
+                let issue_number = issue_number.parse::<i64>().map_err(|e| {
+                    LDNError::New(format!(
+                        "Parse issue number: {issue_number} to i64 failed. {e}"
+                    ))
+                })?;
+                database::applications::create_application(
+                    db,
+                    application_id.clone(),
+                    info.owner.clone(),
+                    info.repo.clone(),
+                    pr_number,
+                    issue_number,
+                    file_content,
+                    LDNPullRequest::application_path(&app_id),
+                    Some(issue_reporter_handle),
+                    file_sha.clone(),
+                )
+                .await
+                .map_err(|e| {
+                    LDNError::New(format!(
+                        "Application issue {application_id} cannot create application in DB /// {e}"
+                    ))
+                })?;
+                queue_status_comment(db, &info.owner, &info.repo, &application_id).await;
+                let comparable_data = ApplicationComparableData {
+                    project_desc: parsed_ldn.project.history.clone(),
+                    stored_data_desc: parsed_ldn.project.stored_data_desc.clone(),
+                    data_owner_name: parsed_ldn.client.name.clone(),
+                    data_set_sample: parsed_ldn.project.data_sample_link.clone(),
+                };
 
-                if let Some(pr) = prs.first() {
-                    let number = pr.number;
-                    let issue_number = issue_number.parse::<i64>().map_err(|e| {
-                        LDNError::New(format!(
-                            "Parse issue number: {issue_number} to i64 failed. {e}"
-                        ))
-                    })?;
-                    database::applications::create_application(
-                        application_id.clone(),
-                        info.owner.clone(),
-                        info.repo.clone(),
-                        number,
-                        issue_number,
-                        file_content,
-                        LDNPullRequest::application_path(&app_id),
-                        Some(issue_reporter_handle),
-                        file_sha.clone(),
-                    )
-                    .await
-                    .map_err(|e| {
-                        LDNError::New(format!(
-                            "Application issue {application_id} cannot create application in DB /// {e}"
-                        ))
-                    })?;
-                    let comparable_data = ApplicationComparableData {
-                        project_desc: parsed_ldn.project.history.clone(),
-                        stored_data_desc: parsed_ldn.project.stored_data_desc.clone(),
-                        data_owner_name: parsed_ldn.client.name.clone(),
-                        data_set_sample: parsed_ldn.project.data_sample_link.clone(),
-                    };
-
-                    detect_similar_applications(
-                        &parsed_ldn.id,
-                        &comparable_data,
-                        &info.owner,
-                        &info.repo,
-                        &(issue_number as u64),
-                    )
-                    .await?;
-                    create_comparable_application(
-                        &application_id,
-                        &ApplicationComparableData {
-                            project_desc: application_file.project.history,
-                            stored_data_desc: application_file.project.stored_data_desc,
-                            data_owner_name: application_file.client.name,
-                            data_set_sample: application_file.project.data_sample_link,
-                        },
-                    )
-                    .await
-                    .map_err(|e| {
-                        LDNError::New(format!("Failed to create application in DB: {e}"))
-                    })?;
-                }
+                detect_similar_applications(
+                    db,
+                    &parsed_ldn.id,
+                    &comparable_data,
+                    &info.owner,
+                    &info.repo,
+                    &(issue_number as u64),
+                )
+                .await?;
+                create_comparable_application(
+                    db,
+                    &application_id,
+                    &ApplicationComparableData {
+                        project_desc: application_file.project.history,
+                        stored_data_desc: application_file.project.stored_data_desc,
+                        data_owner_name: application_file.client.name,
+                        data_set_sample: application_file.project.data_sample_link,
+                    },
+                )
+                .await
+                .map_err(|e| LDNError::New(format!("Failed to create application in DB: {e}")))?;
 
                 Ok(LDNApplication {
+                    db: db.clone(),
                     github: gh,
+                    store,
                     application_id,
                     file_sha,
                     file_name,
                     branch_name,
+                    expected_sha: None,
                 })
             }
 
             // If the file already exists, return an error
-            Ok(_) => {
+            Some(_) => {
                 let app_model = Self::get_application_model(
+                    db,
                     application_id.clone(),
                     info.owner.clone(),
                     info.repo.clone(),
@@ -1002,6 +1101,7 @@ impl LDNApplication {
 
                 // Add a comment to the GitHub issue
                 Self::issue_pathway_mismatch_comment(
+                    db,
                     issue_number.clone(),
                     info.owner.clone(),
                     info.repo.clone(),
@@ -1017,6 +1117,19 @@ impl LDNApplication {
         }
     }
 
+    fn ensure_governance_review_state(app_state: AppState) -> Result<(), LDNError> {
+        if app_state != AppState::KYCRequested
+            && app_state != AppState::Submitted
+            && app_state != AppState::AdditionalInfoRequired
+            && app_state != AppState::AdditionalInfoSubmitted
+        {
+            return Err(LDNError::Load(format!(
+                "Application state is {app_state:?}. Expected one of the following  KYCRequested, Submitted, AdditionalInfoRequired or AdditionalInfoSubmitted"
+            )));
+        }
+        Ok(())
+    }
+
     /// Move application from Governance Review to Proposal
     pub async fn complete_governance_review(
         &self,
@@ -1027,20 +1140,10 @@ impl LDNApplication {
         client_contract_address: Option<String>,
         reason_for_not_using_client_smart_contract: Option<String>,
     ) -> Result<ApplicationFile, LDNError> {
-        let app_state = self.app_state().await?;
-        if app_state != AppState::KYCRequested
-            && app_state != AppState::Submitted
-            && app_state != AppState::AdditionalInfoRequired
-            && app_state != AppState::AdditionalInfoSubmitted
-        {
-            return Err(LDNError::Load(format!(
-                "Application state is {app_state:?}. Expected one of the following  KYCRequested, Submitted, AdditionalInfoRequired or AdditionalInfoSubmitted"
-            )));
-        }
-        let app_file: ApplicationFile = self.file().await?;
+        Self::ensure_governance_review_state(self.app_state().await?)?;
         let allocation_amount_parsed = process_amount(allocation_amount.clone());
 
-        let db_allocator = get_allocator(&owner, &repo)
+        let db_allocator = get_allocator(&self.db, &owner, &repo)
             .await
             .map_err(|e| LDNError::Load(format!("Failed to get an allocator. /// {e}")))?
             .ok_or(LDNError::Load("Allocator not found.".to_string()))?;
@@ -1072,41 +1175,52 @@ impl LDNApplication {
             allocation_amount_parsed,
         );
 
-        let app_file = app_file.complete_governance_review(
-            actor.clone(),
-            request,
-            client_contract_address.clone(),
-        );
-        let file_content = serde_json::to_string_pretty(&app_file)
-            .map_err(|e| LDNError::Load(format!("Failed to pare into string: {e}")))?;
+        let update = self
+            .commit_file_update(|app_file| {
+                Self::ensure_governance_review_state(app_file.lifecycle.get_state())?;
+                Ok(FileUpdate {
+                    file: app_file.complete_governance_review(
+                        actor.clone(),
+                        request.clone(),
+                        client_contract_address.clone(),
+                    ),
+                    commit_message: LDNPullRequest::application_move_to_proposal_commit(&actor),
+                    outcome: (),
+                })
+            })
+            .await?;
+        let app_file = update.file;
+        let file_content = update.content;
+        let new_file_sha = update.sha;
         let app_path = &self.file_name.clone();
         let app_branch = self.branch_name.clone();
-        Self::issue_datacap_request_trigger(app_file.clone(), owner.clone(), repo.clone()).await?;
-        let new_file_sha = LDNPullRequest::add_commit_to(
-            app_path.to_string(),
-            app_branch.clone(),
-            LDNPullRequest::application_move_to_proposal_commit(&actor),
-            file_content.clone(),
-            self.file_sha.clone(),
+        Self::issue_datacap_request_trigger(
+            &self.db,
+            app_file.clone(),
             owner.clone(),
             repo.clone(),
         )
         .await?;
-        let prs = self
-            .github
-            .get_pull_request_by_head(&app_branch)
-            .await
-            .map_err(|e| LDNError::Load(format!("Failed to get pull request by head: {e}")))?;
-        if let Some(pr) = prs.first() {
+        let pr_number = self
+            .store
+            .find_change(&self.application_id, &app_branch)
+            .await?;
+        if let Some(pr_number) = pr_number {
             database::applications::update_application(
+                &self.db,
                 app_file.id.clone(),
                 owner.clone(),
                 repo.clone(),
-                pr.number,
+                pr_number,
                 file_content,
                 Some(app_path.clone()),
                 new_file_sha,
                 client_contract_address.clone(),
+                &WriteContext {
+                    actor: Some(actor.clone()),
+                    side_effects: status_comment_messages(&owner, &repo, &app_file.id)?,
+                    ..WriteContext::new("Governance review completed")
+                },
             )
             .await
             .map_err(|e| {
@@ -1117,6 +1231,7 @@ impl LDNApplication {
             })?;
 
             Self::issue_datacap_allocation_requested(
+                &self.db,
                 app_file.clone(),
                 app_file.get_active_allocation(),
                 owner.clone(),
@@ -1124,6 +1239,7 @@ impl LDNApplication {
             )
             .await?;
             Self::update_issue_labels(
+                &self.db,
                 app_file.issue_number.clone(),
                 &[AppState::ReadyToSign.as_str()],
                 owner.clone(),
@@ -1131,6 +1247,7 @@ impl LDNApplication {
             )
             .await?;
             Self::add_comment_to_issue(
+                &self.db,
                 app_file.issue_number.clone(),
                 owner.clone(),
                 repo.clone(),
@@ -1141,6 +1258,7 @@ impl LDNApplication {
                 reason_for_not_using_client_smart_contract
             {
                 Self::add_comment_to_issue(
+                    &self.db,
                     app_file.issue_number.clone(),
                     owner.clone(),
                     repo.clone(),
@@ -1155,6 +1273,24 @@ impl LDNApplication {
         Ok(app_file)
     }
 
+    fn ensure_proposal_allowed(
+        app_file: &ApplicationFile,
+        request_id: &str,
+    ) -> Result<(), LDNError> {
+        let app_state = app_file.lifecycle.get_state();
+        if app_state != AppState::ReadyToSign {
+            return Err(LDNError::Load(format!(
+                "Application state is {app_state:?}. Expected ReadyToSign"
+            )));
+        }
+        if !app_file.allocation.is_active(request_id.to_string()) {
+            return Err(LDNError::Load(format!(
+                "Request {request_id} is not active"
+            )));
+        }
+        Ok(())
+    }
+
     /// Move application from Proposal to Approved
     pub async fn complete_new_application_proposal(
         &self,
@@ -1167,7 +1303,7 @@ impl LDNApplication {
     ) -> Result<ApplicationFile, LDNError> {
         // TODO: Convert DB errors to LDN Error
         // Get multisig threshold from the database
-        let db_allocator = get_allocator(&owner, &repo)
+        let db_allocator = get_allocator(&self.db, &owner, &repo)
             .await
             .map_err(|e| LDNError::Load(format!("Failed to get an allocator. /// {e}")))?
             .ok_or(LDNError::Load("Allocator not found.".to_string()))?;
@@ -1185,7 +1321,14 @@ impl LDNApplication {
         // If blockchain threshold is available and different from DB, update DB (placeholder for update logic)
         if let Some(blockchain_threshold) = blockchain_threshold {
             if blockchain_threshold != db_threshold {
-                match update_allocator_threshold(&owner, &repo, blockchain_threshold as i32).await {
+                match update_allocator_threshold(
+                    &self.db,
+                    &owner,
+                    &repo,
+                    blockchain_threshold as i32,
+                )
+                .await
+                {
                     Ok(_) => log::info!("Database updated with new multisig threshold"),
                     Err(e) => log::error!("Failed to update database: {}", e),
                 };
@@ -1207,25 +1350,10 @@ impl LDNApplication {
                 )
                 .await;
         }
-        let app_state = self.app_state().await?;
-        if app_state != AppState::ReadyToSign {
-            return Err(LDNError::Load(format!(
-                "Application state is {app_state:?}. Expected ReadyToSign"
-            )));
-        }
-        let mut app_file: ApplicationFile = self.file().await?;
-        if !app_file.allocation.is_active(request_id.clone()) {
-            return Err(LDNError::Load(format!(
-                "Request {request_id} is not active"
-            )));
-        }
-        app_file = app_file.update_lifecycle_after_sign_datacap_proposal(&signer.github_username);
-        app_file = app_file.add_signer_to_allocation(signer.clone().into(), &request_id);
-        if let Some(amount) = amount_of_datacap_sent_to_contract {
-            app_file = app_file.set_amount_of_dc_sent_to_contract(&request_id, &amount);
-        }
-        if let Some(new_allocation_amount) = new_allocation_amount {
-            if app_file.allocation.0.len() > 1 {
+        let app_file: ApplicationFile = self.file().await?;
+        Self::ensure_proposal_allowed(&app_file, &request_id)?;
+        let new_allocation_amount = match new_allocation_amount {
+            Some(new_allocation_amount) if app_file.allocation.0.len() > 1 => {
                 let contract_address = db_allocator
                     .tooling
                     .filter(|tooling| tooling.contains("smart_contract_allocator"))
@@ -1242,42 +1370,57 @@ impl LDNApplication {
                 )
                 .await?;
 
-                let parsed_allocation_amount = process_amount(new_allocation_amount);
-
-                app_file.adjust_active_allocation_amount(parsed_allocation_amount)?;
+                Some(process_amount(new_allocation_amount))
             }
-        }
-
-        let file_content = serde_json::to_string_pretty(&app_file)
-            .map_err(|e| LDNError::Load(format!("Failed to pare into string: {e}")))?;
+            _ => None,
+        };
 
-        let new_file_sha = LDNPullRequest::add_commit_to(
-            self.file_name.to_string(),
-            self.branch_name.clone(),
-            LDNPullRequest::application_move_to_approval_commit(&signer.signing_address),
-            file_content.clone(),
-            self.file_sha.clone(),
-            owner.clone(),
-            repo.clone(),
-        )
-        .await?;
+        let update = self
+            .commit_file_update(|app_file| {
+                Self::ensure_proposal_allowed(&app_file, &request_id)?;
+                let mut app_file = app_file
+                    .update_lifecycle_after_sign_datacap_proposal(&signer.github_username)
+                    .add_signer_to_allocation(signer.clone().into(), &request_id);
+                if let Some(amount) = &amount_of_datacap_sent_to_contract {
+                    app_file = app_file.set_amount_of_dc_sent_to_contract(&request_id, amount);
+                }
+                if let Some(amount) = &new_allocation_amount {
+                    app_file.adjust_active_allocation_amount(amount.clone())?;
+                }
+                Ok(FileUpdate {
+                    file: app_file,
+                    commit_message: LDNPullRequest::application_move_to_approval_commit(
+                        &signer.signing_address,
+                    ),
+                    outcome: (),
+                })
+            })
+            .await?;
+        let app_file = update.file;
+        let file_content = update.content;
+        let new_file_sha = update.sha;
 
-        let prs = self
-            .github
-            .get_pull_request_by_head(&self.branch_name)
-            .await
-            .map_err(|e| LDNError::Load(format!("Failed to get pull request by head: {e}")))?;
+        let pr_number = self
+            .store
+            .find_change(&self.application_id, &self.branch_name)
+            .await?;
 
-        if let Some(pr) = prs.first() {
+        if let Some(pr_number) = pr_number {
             database::applications::update_application(
+                &self.db,
                 app_file.id.clone(),
                 owner.clone(),
                 repo.clone(),
-                pr.number,
+                pr_number,
                 file_content,
                 Some(self.file_name.clone()),
                 new_file_sha,
                 app_file.client_contract_address.clone(),
+                &WriteContext {
+                    actor: Some(signer.github_username.clone()),
+                    side_effects: status_comment_messages(&owner, &repo, &app_file.id)?,
+                    ..WriteContext::new("Allocation proposed")
+                },
             )
             .await
             .map_err(|e| {
@@ -1288,6 +1431,7 @@ impl LDNApplication {
             })?;
 
             Self::add_comment_to_issue(
+                &self.db,
                 app_file.issue_number.clone(),
                 owner.clone(),
                 repo.clone(),
@@ -1296,6 +1440,7 @@ impl LDNApplication {
             .await?;
 
             Self::issue_datacap_request_signature(
+                &self.db,
                 app_file.clone(),
                 "Proposed".to_string(),
                 owner.clone(),
@@ -1303,6 +1448,7 @@ impl LDNApplication {
             )
             .await?;
             Self::update_issue_labels(
+                &self.db,
                 app_file.issue_number.clone(),
                 &[AppState::StartSignDatacap.as_str()],
                 owner.clone(),
@@ -1336,7 +1482,7 @@ impl LDNApplication {
         let comment: &str;
         let app_state: AppState;
         let threshold_to_use =
-            Self::get_allocator_threshold_and_update_if_needed(&owner, &repo).await?;
+            Self::get_allocator_threshold_and_update_if_needed(&self.db, &owner, &repo).await?;
         if threshold_to_use < 2 {
             let sps_change_request =
                 SpsChangeRequest::new(&request_id, allowed_sps, max_deviation, &signer, false);
@@ -1399,13 +1545,13 @@ impl LDNApplication {
                 .map_err(|e| LDNError::Load(format!("Failed to pare into string: {e}")))?;
 
             LDNPullRequest::create_pr_for_existing_application(
+                &self.db,
+                self.store.as_ref(),
                 app_file.id.clone(),
                 parsed_app_file,
                 self.file_name.clone(),
                 request_id.clone(),
                 self.file_sha.clone(),
-                owner,
-                repo,
                 true,
                 app_file.issue_number.clone(),
                 pr_title,
@@ -1434,7 +1580,7 @@ impl LDNApplication {
             )));
         }
 
-        let db_allocator = get_allocator(&owner, &repo)
+        let db_allocator = get_allocator(&self.db, &owner, &repo)
             .await
             .map_err(|e| LDNError::Load(format!("Failed to get an allocator. /// {e}")))?
             .ok_or(LDNError::Load("Allocator not found.".to_string()))?;
@@ -1534,7 +1680,7 @@ impl LDNApplication {
             format!("-{amount_to_decrease}"),
         );
         let threshold_to_use =
-            Self::get_allocator_threshold_and_update_if_needed(owner, repo).await?;
+            Self::get_allocator_threshold_and_update_if_needed(&self.db, owner, repo).await?;
         let app_file_with_new_allocation_request = if threshold_to_use < 2 {
             app_file.start_decrease_request(
                 &decrease_request,
@@ -1559,13 +1705,13 @@ impl LDNApplication {
             app_file_with_new_allocation_request.client.name.clone()
         );
         LDNPullRequest::create_pr_for_existing_application(
+            &self.db,
+            self.store.as_ref(),
             app_file.id.clone(),
             parsed_app_file,
             self.file_name.clone(),
             request_id.clone(),
             self.file_sha.clone(),
-            owner.to_string(),
-            repo.to_string(),
             true,
             app_file.issue_number.clone(),
             pr_title,
@@ -1573,6 +1719,7 @@ impl LDNApplication {
         .await?;
 
         Self::add_comment_to_issue(
+            &self.db,
             app_file.issue_number.clone(),
             owner.to_string(),
             repo.to_string(),
@@ -1587,6 +1734,7 @@ impl LDNApplication {
         };
 
         Self::issue_datacap_request_signature(
+            &self.db,
             app_file_with_new_allocation_request.clone(),
             signature_step,
             owner.into(),
@@ -1596,6 +1744,7 @@ impl LDNApplication {
 
         if threshold_to_use > 1 {
             Self::update_issue_labels(
+                &self.db,
                 app_file.issue_number.clone(),
                 &[AppState::DecreasingDataCap.as_str()],
                 owner.to_string(),
@@ -1604,6 +1753,7 @@ impl LDNApplication {
             .await?;
         } else {
             Self::add_comment_to_issue(
+                &self.db,
                 app_file.issue_number.clone(),
                 owner.to_string(),
                 repo.to_string(),
@@ -1622,7 +1772,7 @@ impl LDNApplication {
         request_id: &str,
     ) -> Result<(), LDNError> {
         let threshold_to_use =
-            Self::get_allocator_threshold_and_update_if_needed(owner, repo).await?;
+            Self::get_allocator_threshold_and_update_if_needed(&self.db, owner, repo).await?;
 
         let mut app_file: ApplicationFile = self.file().await?;
         let app_state = app_file.lifecycle.state.clone();
@@ -1665,6 +1815,7 @@ impl LDNApplication {
                 LDNPullRequest::application_move_to_confirmed_commit(&verifier.signing_address);
             signature_step = "Approved".to_string();
             Self::update_issue_labels(
+                &self.db,
                 app_file.issue_number.clone(),
                 &[AppState::Granted.as_str()],
                 owner.into(),
@@ -1683,16 +1834,23 @@ impl LDNApplication {
         )
         .await?;
 
-        Self::issue_datacap_request_signature(app_file, signature_step, owner.into(), repo.into())
-            .await?;
+        Self::issue_datacap_request_signature(
+            &self.db,
+            app_file,
+            signature_step,
+            owner.into(),
+            repo.into(),
+        )
+        .await?;
         Ok(())
     }
 
     async fn get_allocator_threshold_and_update_if_needed(
+        db: &DbPool,
         owner: &str,
         repo: &str,
     ) -> Result<u64, LDNError> {
-        let db_allocator = get_allocator(owner, repo)
+        let db_allocator = get_allocator(db, owner, repo)
             .await
             .map_err(|e| LDNError::Load(format!("Failed to get an allocator. /// {e}")))?
             .ok_or(LDNError::Load("Allocator not found.".to_string()))?;
@@ -1707,7 +1865,7 @@ impl LDNApplication {
             .unwrap_or(db_threshold);
 
         if blockchain_threshold != db_threshold {
-            update_allocator_threshold(owner, repo, blockchain_threshold as i32)
+            update_allocator_threshold(db, owner, repo, blockchain_threshold as i32)
                 .await
                 .map_err(|e| {
                     LDNError::Load(format!("Failed to update allocator threshold: {e}"))
@@ -1727,127 +1885,179 @@ impl LDNApplication {
         amount_of_datacap_sent_to_contract: Option<String>,
     ) -> Result<ApplicationFile, LDNError> {
         // Get multisig threshold from the database
-        let db_allocator = get_allocator(&owner, &repo)
+        let db_allocator = get_allocator(&self.db, &owner, &repo)
             .await
             .map_err(|e| LDNError::Load(format!("Failed to get an allocator. /// {e}")))?
             .ok_or(LDNError::Load("Allocator not found.".to_string()))?;
         let threshold_to_use = db_allocator.multisig_threshold.unwrap_or(2) as usize;
 
-        let app_state = self.app_state().await?;
+        let new_allocation_amount = new_allocation_amount.map(process_amount);
+        let update = self
+            .commit_file_update(|mut app_file| {
+                let app_state = app_file.lifecycle.get_state();
+                if app_state != AppState::StartSignDatacap
+                    && !(threshold_to_use == 1 && app_state == AppState::ReadyToSign)
+                {
+                    return Err(LDNError::New(format!(
+                        "Application issue {} cannot be approved in its current state",
+                        self.application_id
+                    )));
+                }
 
-        if app_state != AppState::StartSignDatacap
-            && !(threshold_to_use == 1 && app_state == AppState::ReadyToSign)
-        {
-            return Err(LDNError::New(format!(
-                "Application issue {} cannot be approved in its current state",
-                self.application_id
-            )));
-        }
-
-        let mut app_file: ApplicationFile = self.file().await?;
+                // Find the signers that already signed
+                let current_signers = app_file.get_active_allocation_signers(&request_id);
+                // // Check if the signer has already signed
+                if current_signers
+                    .iter()
+                    .any(|s| s.signing_address == signer.signing_address)
+                {
+                    return Err(LDNError::New(format!(
+                        "Signer {} has already approved this application",
+                        signer.signing_address
+                    )));
+                }
 
-        // Find the signers that already signed
-        let current_signers = app_file.get_active_allocation_signers(&request_id);
-        // // Check if the signer has already signed
-        if current_signers
-            .iter()
-            .any(|s| s.signing_address == signer.signing_address)
-        {
-            return Err(LDNError::New(format!(
-                "Signer {} has already approved this application",
-                signer.signing_address
-            )));
-        }
+                // Check if the number of signers meets or exceeds the multisig threshold
+                if current_signers.len() >= threshold_to_use {
+                    return Err(LDNError::New(
+                        "No additional signatures needed as the multisig threshold is already met"
+                            .to_string(),
+                    ));
+                }
 
-        // Check if the number of signers meets or exceeds the multisig threshold
-        let multisig_threshold_usize = threshold_to_use as usize;
-        if current_signers.len() >= multisig_threshold_usize {
-            return Err(LDNError::New(
-                "No additional signatures needed as the multisig threshold is already met"
-                    .to_string(),
-            ));
-        }
+                if let Some(new_allocation_amount) = &new_allocation_amount {
+                    if app_file.allocation.0.len() > 1 {
+                        app_file.adjust_active_allocation_amount(new_allocation_amount.clone())?;
+                    }
+                }
+                if let Some(amount) = &amount_of_datacap_sent_to_contract {
+                    app_file = app_file.set_amount_of_dc_sent_to_contract(&request_id, amount);
+                }
+                let signing_will_be_completed = (current_signers.len() + 1) == threshold_to_use;
+                if signing_will_be_completed {
+                    let lifecycle = app_file.lifecycle.clone();
+                    Ok(FileUpdate {
+                        file: app_file.add_signer_to_allocation_and_complete(
+                            signer.clone().into(),
+                            request_id.clone(),
+                            lifecycle,
+                        ),
+                        commit_message: LDNPullRequest::application_move_to_confirmed_commit(
+                            &signer.signing_address,
+                        ),
+                        outcome: ("Approved", AppState::Granted.as_str()),
+                    })
+                } else {
+                    Ok(FileUpdate {
+                        file: app_file
+                            .update_lifecycle_after_sign_datacap_proposal(&signer.github_username)
+                            .add_signer_to_allocation(signer.clone().into(), &request_id),
+                        commit_message: LDNPullRequest::application_signed(&signer.signing_address),
+                        outcome: ("Signed", AppState::StartSignDatacap.as_str()),
+                    })
+                }
+            })
+            .await?;
+        let app_file = update.file;
+        let (signature_step, label) = update.outcome;
+        let comment = "Application is Granted";
 
-        // Check the allowance for the address
-        if let Some(new_allocation_amount) = new_allocation_amount {
-            if app_file.allocation.0.len() > 1 {
-                let new_allocation_amount_parsed = process_amount(new_allocation_amount);
+        let pr_number = self
+            .store
+            .find_change(&self.application_id, &self.branch_name)
+            .await?
+            .ok_or(LDNError::New(
+                "No pull request found for the given branch".to_string(),
+            ))?;
 
-                app_file.adjust_active_allocation_amount(new_allocation_amount_parsed)?;
+        let issue_number = app_file.issue_number.parse::<u64>().map_err(|e| {
+            LDNError::New(format!(
+                "Parse issue number: {} to u64 failed. {e}",
+                app_file.issue_number
+            ))
+        })?;
+        let issue_comment = |body: String| SideEffect::IssueComment {
+            owner: owner.clone(),
+            repo: repo.clone(),
+            issue_number,
+            body,
+        };
+        let mut side_effects = vec![
+            issue_comment(
+                Self::datacap_request_signature_comment(
+                    &self.db,
+                    &app_file,
+                    signature_step.to_string(),
+                    &owner,
+                    &repo,
+                )
+                .await?,
+            ),
+            issue_comment(comment.to_string()),
+            SideEffect::IssueLabels {
+                owner: owner.clone(),
+                repo: repo.clone(),
+                issue_number,
+                labels: vec![label.to_string()],
+            },
+        ];
+        if app_file.allocation.0.len() == 1 {
+            if let Some(client_contract_address) = app_file.client_contract_address.clone() {
+                side_effects.push(issue_comment(format!("## Allocation has been made through the Client Smart Contract\nContract address: `{client_contract_address}`\n[How to create verified DDO deals using DataCap?](https://github.com/fidlabs/contract-metaallocator/blob/main/HowToUseClientSmartContract.md#this-step-by-step-guide-explains-how-to-create-verified-ddo-deals-using-datacap-granted-through-a-client-smart-contract-with-boost)")));
             }
         }
-        if let Some(amount) = amount_of_datacap_sent_to_contract {
-            app_file = app_file.set_amount_of_dc_sent_to_contract(&request_id, &amount);
-        }
-        let commit_message;
-        let signature_step;
-        let comment;
-        let label;
-        let signing_will_be_completed = (current_signers.len() + 1) == threshold_to_use;
-        if signing_will_be_completed {
-            app_file = app_file.add_signer_to_allocation_and_complete(
-                signer.clone().into(),
-                request_id.clone(),
-                app_file.lifecycle.clone(),
-            );
-            commit_message =
-                LDNPullRequest::application_move_to_confirmed_commit(&signer.signing_address);
-            signature_step = "Approved".to_string();
-            comment = "Application is Granted";
-            label = AppState::Granted.as_str();
-        } else {
-            app_file =
-                app_file.update_lifecycle_after_sign_datacap_proposal(&signer.github_username);
-            app_file = app_file.add_signer_to_allocation(signer.clone().into(), &request_id);
-            commit_message = LDNPullRequest::application_signed(&signer.signing_address);
-            signature_step = "Signed".to_string();
-            comment = "Application is Granted";
-            label = AppState::StartSignDatacap.as_str();
-        }
-
-        self.update_and_commit_application_state(
-            app_file.clone(),
-            self.github.owner.clone(),
-            self.github.repo.clone(),
-            self.file_sha.clone(),
-            self.branch_name.clone(),
-            self.file_name.clone(),
-            commit_message,
-        )
-        .await?;
 
-        Self::issue_datacap_request_signature(
-            app_file.clone(),
-            signature_step,
+        // The comments and labels are applied by the outbox worker once the
+        // database update is committed.
+        database::applications::update_application(
+            &self.db,
+            app_file.id.clone(),
             owner.clone(),
             repo.clone(),
+            pr_number,
+            update.content,
+            Some(self.file_name.clone()),
+            update.sha,
+            app_file.client_contract_address.clone(),
+            &WriteContext {
+                actor: Some(signer.github_username.clone()),
+                side_effects: [
+                    outbox_messages(&owner, &repo, &app_file.id, &side_effects)?,
+                    status_comment_messages(&owner, &repo, &app_file.id)?,
+                ]
+                .concat(),
+                ..WriteContext::new("Allocation approved")
+            },
         )
-        .await?;
-
-        self.issue_updates(&app_file.issue_number, comment, label)
-            .await?;
-
-        if app_file.allocation.0.len() == 1 {
-            if let Some(client_contract_address) = app_file.client_contract_address.clone() {
-                Self::add_comment_to_issue(
-                    app_file.issue_number.clone(),
-                    owner.clone(),
-                    repo.clone(),
-                    format!("## Allocation has been made through the Client Smart Contract\nContract address: `{client_contract_address}`\n[How to create verified DDO deals using DataCap?](https://github.com/fidlabs/contract-metaallocator/blob/main/HowToUseClientSmartContract.md#this-step-by-step-guide-explains-how-to-create-verified-ddo-deals-using-datacap-granted-through-a-client-smart-contract-with-boost)"),
-                )
-                .await?;
-            }
-        }
+        .await
+        .map_err(|e| {
+            LDNError::Load(format!(
+                "Failed to update the application in the database: {e}"
+            ))
+        })?;
 
         Ok(app_file)
     }
 
     async fn parse_application_issue(
+        db: &DbPool,
         issue_number: String,
         owner: String,
         repo: String,
     ) -> Result<(ParsedIssue, String), LDNError> {
-        let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
+        let (issue_body, labels, reporter) =
+            Self::fetch_application_issue(db, issue_number, owner, repo).await?;
+        Ok((ParsedIssue::from_issue(&issue_body, &labels)?, reporter))
+    }
+
+    /// Body, label names and author of an application issue.
+    async fn fetch_application_issue(
+        db: &DbPool,
+        issue_number: String,
+        owner: String,
+        repo: String,
+    ) -> Result<(String, Vec<String>, String), LDNError> {
+        let gh = github_async_new(db, owner.to_string(), repo.to_string()).await?;
         let parsed_issue_number = issue_number
             .parse::<u64>()
             .map_err(|e| LDNError::New(format!("Parse issue number to u64 failed: {e}")))?;
@@ -1857,7 +2067,8 @@ impl LDNApplication {
             ))
         })?;
         if let Some(issue_body) = issue.body {
-            Ok((ParsedIssue::from_issue_body(&issue_body)?, issue.user.login))
+            let labels: Vec<String> = issue.labels.into_iter().map(|label| label.name).collect();
+            Ok((issue_body, labels, issue.user.login))
         } else {
             Err(LDNError::Load(format!(
                 "Failed to retrieve issue {} from GitHub. Reason: {}",
@@ -1866,6 +2077,50 @@ impl LDNApplication {
         }
     }
 
+    /// Validates the fields of an application issue against the issue template
+    /// and the allocator requirements. When `post_comment` is set and problems
+    /// are found, they are posted to the issue as a checklist.
+    pub async fn validate_application_issue(
+        db: &DbPool,
+        issue_number: String,
+        owner: String,
+        repo: String,
+        post_comment: bool,
+    ) -> Result<ValidationReport, LDNError> {
+        let (issue_body, labels, _) =
+            Self::fetch_application_issue(db, issue_number.clone(), owner.clone(), repo.clone())
+                .await?;
+        let report =
+            Self::application_issue_report(db, &owner, &repo, &issue_body, &labels).await?;
+
+        if post_comment && !report.is_empty() {
+            Self::add_comment_to_issue(db, issue_number, owner, repo, report.to_comment()).await?;
+        }
+        Ok(report)
+    }
+
+    /// Checks an issue already fetched against the template and the
+    /// requirements of the allocator.
+    async fn application_issue_report(
+        db: &DbPool,
+        owner: &str,
+        repo: &str,
+        issue_body: &str,
+        labels: &[String],
+    ) -> Result<ValidationReport, LDNError> {
+        let requirements = get_allocator(db, owner, repo)
+            .await
+            .map_err(|e| LDNError::Load(format!("Failed to get an allocator: {e}")))?
+            .map(|allocator| {
+                AllocatorRequirements::from_settings(
+                    allocator.required_replicas.as_deref(),
+                    allocator.required_sps.as_deref(),
+                )
+            })
+            .unwrap_or_default();
+        validate_issue(issue_body, labels, &requirements)
+    }
+
     pub async fn check_application_exists(
         app_model: ApplicationModel,
         application_id: String,
@@ -1895,6 +2150,7 @@ impl LDNApplication {
 
     pub async fn total_dc_reached(&self) -> Result<bool, LDNError> {
         let application_model = get_application(
+            &self.db,
             self.application_id.clone(),
             self.github.owner.clone(),
             self.github.repo.clone(),
@@ -1930,33 +2186,59 @@ impl LDNApplication {
             .map_err(|e| LDNError::Load(format!("Failed to pare into string: {e}")))?;
         let NewPrNumberAndFileSha { pr_number, .. } =
             LDNPullRequest::create_pr_for_existing_application(
+                &self.db,
+                self.store.as_ref(),
                 clompleted_application.id.clone(),
                 parsed_app_file,
                 self.file_name.clone(),
                 format!("{}-total-dc-reached", clompleted_application.id),
                 self.file_sha.clone(),
-                application_model.owner.clone(),
-                application_model.repo.clone(),
                 true,
                 application_model.issue_number.to_string(),
                 format!("Total Datacap reached for {}", clompleted_application.id),
             )
             .await?;
 
-        let gh = github_async_new(self.github.owner.clone(), self.github.repo.clone()).await?;
-
-        gh.merge_pull_request_and_delete_branch(&pr_number)
-            .await
-            .map_err(|e| {
-                LDNError::Load(format!(
-                    "Failed to merge pull request {pr_number} or delete branch. Reason: {e}"
-                ))
-            })?;
-
+        let (owner, repo) = (self.github.owner.clone(), self.github.repo.clone());
+        let issue_number = application_model.issue_number as u64;
+        let side_effects = [
+            SideEffect::MergeChange {
+                owner: owner.clone(),
+                repo: repo.clone(),
+                pr_number,
+            },
+            SideEffect::IssueComment {
+                owner: owner.clone(),
+                repo: repo.clone(),
+                issue_number,
+                body: "Application is Completed".to_string(),
+            },
+            SideEffect::IssueLabels {
+                owner: owner.clone(),
+                repo: repo.clone(),
+                issue_number,
+                labels: vec![AppState::TotalDatacapReached.as_str().to_string()],
+            },
+            SideEffect::StatusComment {
+                owner: owner.clone(),
+                repo: repo.clone(),
+                application_id: clompleted_application.id.clone(),
+            },
+        ];
         database::applications::merge_application_by_pr_number(
-            self.github.owner.clone(),
-            self.github.repo.clone(),
+            &self.db,
+            owner.clone(),
+            repo.clone(),
             pr_number,
+            &WriteContext {
+                side_effects: outbox_messages(
+                    &owner,
+                    &repo,
+                    &clompleted_application.id,
+                    &side_effects,
+                )?,
+                ..WriteContext::new("Total DataCap reached")
+            },
         )
         .await
         .map_err(|e| {
@@ -1964,20 +2246,6 @@ impl LDNApplication {
                 "Failed to update application in database. Reason: {e}"
             ))
         })?;
-        Self::add_comment_to_issue(
-            application_model.issue_number.to_string().clone(),
-            application_model.owner.clone(),
-            application_model.repo.clone(),
-            "Application is Completed".to_string(),
-        )
-        .await?;
-        Self::update_issue_labels(
-            application_model.issue_number.to_string().clone(),
-            &[AppState::TotalDatacapReached.as_str()],
-            application_model.owner.clone(),
-            application_model.repo.clone(),
-        )
-        .await?;
         Ok(true)
     }
 
@@ -1994,24 +2262,23 @@ impl LDNApplication {
 
     pub async fn file(&self) -> Result<ApplicationFile, LDNError> {
         let file = self
-            .github
-            .get_file(&self.file_name, &self.branch_name)
-            .await
-            .map_err(|e| {
-                LDNError::Load(format!(
-                    "Application issue {} file does not exist /// {}",
-                    self.application_id, e
-                ))
-            })?;
+            .store
+            .read_file(&self.file_name, &self.branch_name)
+            .await?
+            .ok_or(LDNError::Load(format!(
+                "Application issue {} file does not exist",
+                self.application_id
+            )))?;
 
-        LDNApplication::content_items_to_app_file(file)
+        Ok(file.application_file)
     }
 
     pub async fn fetch_verifiers(
+        db: &DbPool,
         owner: String,
         repo: String,
     ) -> Result<ValidVerifierList, LDNError> {
-        let allocator = database::allocators::get_allocator(&owner, &repo)
+        let allocator = database::allocators::get_allocator(db, &owner, &repo)
             .await
             .map_err(|e| LDNError::Load(format!("Failed to retrieve allocators /// {e}")))?
             .ok_or(LDNError::Load("Allocator not found".to_string()))?;
@@ -2032,11 +2299,12 @@ impl LDNApplication {
     }
 
     async fn single_merged(
+        db: &DbPool,
         application_id: String,
         owner: String,
         repo: String,
     ) -> Result<(ApplicationGithubInfo, ApplicationResponse), LDNError> {
-        LDNApplication::merged(owner, repo)
+        LDNApplication::merged(db, owner, repo)
             .await?
             .into_iter()
             .find(|(_, app)| app.file.id == application_id)
@@ -2078,11 +2346,13 @@ impl LDNApplication {
     }
 
     pub async fn merged(
+        db: &DbPool,
         owner: String,
         repo: String,
     ) -> Result<Vec<(ApplicationGithubInfo, ApplicationResponse)>, LDNError> {
         // Retrieve all applications in the main branch from the database.
         let merged_app_models = database::applications::get_merged_applications(
+            db,
             Some(owner.clone()),
             Some(repo.clone()),
         )
@@ -2114,7 +2384,7 @@ impl LDNApplication {
             }
         }
 
-        let active_apps = Self::active(owner, repo, None).await?;
+        let active_apps = Self::active(db, owner, repo, None).await?;
         let mut apps: Vec<(ApplicationGithubInfo, ApplicationResponse)> = vec![];
         for app in merged_apps {
             if !active_apps.iter().any(|a| a.file.id == app.1.file.id)
@@ -2127,9 +2397,9 @@ impl LDNApplication {
         Ok(apps)
     }
 
-    async fn refill(verfier: &str, refill_info: RefillInfo) -> Result<bool, LDNError> {
+    async fn refill(db: &DbPool, verfier: &str, refill_info: RefillInfo) -> Result<bool, LDNError> {
         let apps =
-            LDNApplication::merged(refill_info.owner.clone(), refill_info.repo.clone()).await?;
+            LDNApplication::merged(db, refill_info.owner.clone(), refill_info.repo.clone()).await?;
         if let Some((content, mut app)) = apps
             .into_iter()
             .find(|(_, app)| app.file.id == refill_info.id)
@@ -2144,7 +2414,10 @@ impl LDNApplication {
             );
             let app_file = app.file.start_refill_request(new_request);
             Self::issue_refill(
-                app.file.issue_number.clone(),
+                db,
+                &app.file,
+                verfier,
+                format!("{}{}", refill_info.amount, refill_info.amount_type),
                 refill_info.owner.clone(),
                 refill_info.repo.clone(),
                 refill_info.early_refill_comment,
@@ -2154,14 +2427,15 @@ impl LDNApplication {
             let pr_title = format!("Datacap for {}", app.file.client.name.clone());
             let parsed_app_file = serde_json::to_string_pretty(&app_file)
                 .map_err(|e| LDNError::Load(format!("Failed to pare into string: {e}")))?;
+            let store = application_store(db, &refill_info.owner, &refill_info.repo).await?;
             LDNPullRequest::create_pr_for_existing_application(
+                db,
+                store.as_ref(),
                 app.file.id.clone(),
                 parsed_app_file,
                 content.path.clone(), // filename
                 request_id.clone(),
                 content.sha,
-                refill_info.owner,
-                refill_info.repo,
                 true,
                 app_file.issue_number.clone(),
                 pr_title,
@@ -2172,10 +2446,10 @@ impl LDNApplication {
         Err(LDNError::Load("Failed to get application file".to_string()))
     }
 
-    pub async fn notify_refill(info: NotifyRefillInfo) -> Result<(), LDNError> {
+    pub async fn notify_refill(db: &DbPool, info: NotifyRefillInfo) -> Result<(), LDNError> {
         let label = "Refill needed";
 
-        let gh = github_async_new(info.owner.clone(), info.repo.clone()).await?;
+        let gh = github_async_new(db, info.owner.clone(), info.repo.clone()).await?;
         let issue_number = info.issue_number.parse().map_err(|e| {
             LDNError::Load(format!("Failed to parse issue number to number: {e:?}"))
         })?;
@@ -2192,6 +2466,7 @@ impl LDNApplication {
             "Client used 75% of the allocated DataCap. Consider allocating next tranche.",
         );
         Self::add_comment_to_issue(
+            db,
             info.issue_number.clone(),
             info.owner.clone(),
             info.repo.clone(),
@@ -2199,6 +2474,7 @@ impl LDNApplication {
         )
         .await?;
         Self::update_issue_labels(
+            db,
             info.issue_number.clone(),
             &[label],
             info.owner.clone(),
@@ -2209,6 +2485,7 @@ impl LDNApplication {
     }
 
     pub async fn validate_merge_application(
+        db: &DbPool,
         pr_number: u64,
         owner: String,
         repo: String,
@@ -2217,7 +2494,7 @@ impl LDNApplication {
         log::info!("- Validating merge for PR number {}", pr_number,);
 
         let application =
-            LDNApplication::single_active(pr_number, owner.clone(), repo.clone()).await?;
+            LDNApplication::single_active(db, pr_number, owner.clone(), repo.clone()).await?;
 
         // conditions for automerge:
         // 1. Application is in Granted state
@@ -2244,10 +2521,10 @@ impl LDNApplication {
             }
             log::info!("- Application is in a valid state!");
 
-            Self::merge_application_and_delete_branch(pr_number, owner, repo).await?;
+            Self::merge_application_and_delete_branch(db, pr_number, owner, repo).await?;
             return Ok(true);
         } else if application.lifecycle.get_state() == AppState::Declined {
-            Self::merge_application_and_delete_branch(pr_number, owner, repo).await?;
+            Self::merge_application_and_delete_branch(db, pr_number, owner, repo).await?;
             return Ok(true);
         }
 
@@ -2256,32 +2533,44 @@ impl LDNApplication {
     }
 
     pub async fn merge_application_and_delete_branch(
+        db: &DbPool,
         pr_number: u64,
         owner: String,
         repo: String,
     ) -> Result<bool, LDNError> {
-        let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
-
-        gh.merge_pull_request_and_delete_branch(&pr_number)
-            .await
-            .map_err(|e| {
-                LDNError::Load(format!(
-                    "Failed to merge pull request {pr_number} or delete branch. Reason: {e}"
-                ))
-            })?;
+        let store = application_store(db, &owner, &repo).await?;
+        let application = database::applications::get_application_by_pr_number(
+            db,
+            owner.clone(),
+            repo.clone(),
+            pr_number,
+        )
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get application: {e}")))?;
+        store.merge_change(pr_number).await?;
 
-        database::applications::merge_application_by_pr_number(owner, repo, pr_number)
-            .await
-            .map_err(|e| {
-                LDNError::Load(format!(
-                    "Failed to update application in database. Reason: {e}"
-                ))
-            })?;
+        database::applications::merge_application_by_pr_number(
+            db,
+            owner.clone(),
+            repo.clone(),
+            pr_number,
+            &WriteContext {
+                side_effects: status_comment_messages(&owner, &repo, &application.id)?,
+                ..WriteContext::new("Change merged")
+            },
+        )
+        .await
+        .map_err(|e| {
+            LDNError::Load(format!(
+                "Failed to update application in database. Reason: {e}"
+            ))
+        })?;
 
         Ok(true)
     }
 
     pub async fn validate_flow(
+        db: &DbPool,
         pr_number: u64,
         actor: &str,
         owner: String,
@@ -2294,7 +2583,7 @@ impl LDNApplication {
             actor
         );
 
-        let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
+        let gh = github_async_new(db, owner.to_string(), repo.to_string()).await?;
         let author = gh
             .get_last_commit_author(pr_number)
             .await
@@ -2373,6 +2662,7 @@ impl LDNApplication {
     }
 
     pub async fn validate_trigger(
+        db: &DbPool,
         pr_number: u64,
         actor: &str,
         owner: String,
@@ -2386,7 +2676,7 @@ impl LDNApplication {
         );
 
         if let Ok(application_file) =
-            LDNApplication::single_active(pr_number, owner.clone(), repo.clone()).await
+            LDNApplication::single_active(db, pr_number, owner.clone(), repo.clone()).await
         {
             if !application_file.lifecycle.get_active_status() {
                 log::info!("No trigger to validate. Application lifecycle is inactive so the Total DC was reached.");
@@ -2395,7 +2685,8 @@ impl LDNApplication {
             let validated_by = application_file.lifecycle.validated_by.clone();
             let validated_at = application_file.lifecycle.validated_at.clone();
             let app_state = application_file.lifecycle.get_state();
-            let valid_verifier_list = Self::fetch_verifiers(owner.clone(), repo.clone()).await?;
+            let valid_verifier_list =
+                Self::fetch_verifiers(db, owner.clone(), repo.clone()).await?;
             // let bot_user = get_env_var_or_default("BOT_USER");
 
             if application_file.lifecycle.edited.unwrap_or(false) {
@@ -2542,6 +2833,94 @@ impl LDNApplication {
         Ok(false)
     }
 
+    /// Applies `update` to the latest application file on the branch and
+    /// commits the result. When the file changes between reading and
+    /// committing, the store rejects the commit with `LDNError::Conflict`;
+    /// the file is then read again and `update` re-applied, so an action that
+    /// is no longer valid fails with its own error instead of overwriting the
+    /// concurrent change.
+    async fn commit_file_update<T, F>(&self, update: F) -> Result<CommittedFileUpdate<T>, LDNError>
+    where
+        F: Fn(ApplicationFile) -> Result<FileUpdate<T>, LDNError>,
+    {
+        let mut attempt = 0;
+        loop {
+            let stored = self
+                .store
+                .read_file(&self.file_name, &self.branch_name)
+                .await?
+                .ok_or(LDNError::Load(format!(
+                    "Application issue {} file does not exist",
+                    self.application_id
+                )))?;
+            let FileUpdate {
+                file,
+                commit_message,
+                outcome,
+            } = update(stored.application_file)?;
+            let content = serde_json::to_string_pretty(&file)
+                .map_err(|e| LDNError::Load(format!("Failed to pare into string: {e}")))?;
+            // A client acting on a version gets the change since reported
+            // instead of its action re-applied on top of it.
+            let file_sha = self.expected_sha.clone().unwrap_or(stored.sha);
+
+            match LDNPullRequest::add_commit_to(
+                self.store.as_ref(),
+                self.file_name.clone(),
+                self.branch_name.clone(),
+                commit_message,
+                content.clone(),
+                file_sha,
+            )
+            .await
+            {
+                Ok(sha) => {
+                    return Ok(CommittedFileUpdate {
+                        file,
+                        content,
+                        sha,
+                        outcome,
+                    })
+                }
+                Err(LDNError::Conflict(e)) if self.expected_sha.is_some() => {
+                    return Err(LDNError::PreconditionFailed(format!(
+                        "Application {} changed since the version it was loaded at: {e}",
+                        self.application_id
+                    )))
+                }
+                Err(LDNError::Conflict(e)) if attempt < MAX_CONFLICT_RETRIES => {
+                    attempt += 1;
+                    log::warn!(
+                        "Application {} changed while committing, retrying ({attempt}/{MAX_CONFLICT_RETRIES}): {e}",
+                        self.application_id
+                    );
+                }
+                Err(LDNError::Conflict(e)) => {
+                    return Err(LDNError::Conflict(format!(
+                        "Application {} keeps changing concurrently, try again: {e}",
+                        self.application_id
+                    )))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Fails with `LDNError::Conflict` when the application file is no longer
+    /// at `expected_sha`, e.g. the version a client read before acting on it.
+    /// Otherwise the commits of the action are based on that version, so a
+    /// change after loading fails with `LDNError::PreconditionFailed`.
+    pub fn expect_version(&mut self, expected_sha: &str) -> Result<(), LDNError> {
+        if self.file_sha != expected_sha {
+            return Err(LDNError::Conflict(format!(
+                "Application {} is at version {}, not {expected_sha}",
+                self.application_id, self.file_sha
+            )));
+        }
+        self.expected_sha = Some(expected_sha.to_string());
+        Ok(())
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn update_and_commit_application_state(
         &self,
@@ -2560,6 +2939,7 @@ impl LDNApplication {
             Ok(f) => f,
             Err(e) => {
                 Self::add_error_label(
+                    &self.db,
                     db_application_file.issue_number.clone(),
                     "".to_string(),
                     owner.clone(),
@@ -2572,49 +2952,82 @@ impl LDNApplication {
             }
         };
 
-        // Commit the changes to the branch
-        let new_file_sha = LDNPullRequest::add_commit_to(
-            filename.clone(),
-            branch_name.clone(),
-            commit_message,
-            file_content.clone(),
-            sha.clone(),
+        let pr_number = self
+            .store
+            .find_change(&db_application_file.id, &branch_name)
+            .await?
+            .ok_or(LDNError::New(
+                "No pull request found for the given branch".to_string(),
+            ))?;
+        let application = database::applications::get_application(
+            &self.db,
+            db_application_file.id.clone(),
             owner.clone(),
             repo.clone(),
+            Some(pr_number),
         )
-        .await?;
-
-        // Retrieve and update the pull request
-        let prs = self
-            .github
-            .get_pull_request_by_head(&branch_name)
-            .await
-            .map_err(|e| LDNError::Load(format!("Failed to get pull request: {e}")))?;
-
-        if let Some(pr) = prs.first() {
-            database::applications::update_application(
-                db_application_file.id.clone(),
-                owner.clone(),
-                repo.clone(),
-                pr.number,
-                file_content,
-                Some(filename.clone()),
-                new_file_sha,
-                db_application_file.client_contract_address.clone(),
-            )
-            .await
-            .map_err(|e| {
-                LDNError::Load(format!(
-                    "Failed to update the application in the database: {e}"
-                ))
-            })?;
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to get application: {e}")))?;
+        let conflict = || {
+            let message = format!(
+                "Application {} changed since sha {sha}, reload it and try again",
+                db_application_file.id
+            );
+            if self.expected_sha.as_deref() == Some(sha.as_str()) {
+                LDNError::PreconditionFailed(message)
+            } else {
+                LDNError::Conflict(message)
+            }
+        };
+        if application.sha.as_deref() != Some(sha.as_str()) {
+            return Err(conflict());
+        }
 
-            Ok(db_application_file)
-        } else {
-            Err(LDNError::New(
-                "No pull request found for the given branch".to_string(),
+        // The database is written first; the commit to GitHub is applied by
+        // the outbox worker once the write is committed. Database stores are
+        // written by the update itself.
+        let mut side_effects = vec![];
+        if self.store.kind() == ApplicationStoreKind::Github {
+            side_effects.push(SideEffect::CommitChange {
+                owner: owner.clone(),
+                repo: repo.clone(),
+                path: filename,
+                branch_name,
+                message: commit_message.clone(),
+                content: file_content.clone(),
+                file_sha: sha.clone(),
+            });
+        }
+        side_effects.push(SideEffect::StatusComment {
+            owner: owner.clone(),
+            repo: repo.clone(),
+            application_id: db_application_file.id.clone(),
+        });
+        let written = database::applications::update_application_file_if_unchanged(
+            &self.db,
+            &application,
+            file_content.clone(),
+            content_sha(&file_content),
+            &WriteContext {
+                side_effects: outbox_messages(
+                    &owner,
+                    &repo,
+                    &db_application_file.id,
+                    &side_effects,
+                )?,
+                ..WriteContext::new(commit_message)
+            },
+        )
+        .await
+        .map_err(|e| {
+            LDNError::Load(format!(
+                "Failed to update the application in the database: {e}"
             ))
+        })?;
+        if !written {
+            return Err(conflict());
         }
+        Ok(db_application_file)
     }
 
     pub async fn approve_changes(self, owner: String, repo: String) -> Result<String, LDNError> {
@@ -2624,6 +3037,7 @@ impl LDNApplication {
         let application_id: String = self.application_id.clone();
 
         let db_application_model = database::applications::get_application(
+            &self.db,
             application_id,
             owner.clone(),
             repo.clone(),
@@ -2726,6 +3140,7 @@ impl LDNApplication {
         )
         .await?;
         Self::issue_changes_approved(
+            &self.db,
             db_application_file.issue_number.clone(),
             owner,
             repo,
@@ -2736,14 +3151,15 @@ impl LDNApplication {
     }
 
     pub async fn check_for_changes(
+        db: &DbPool,
         pr_number: u64,
         owner: String,
         repo: String,
     ) -> Result<bool, LDNError> {
         log::info!("Starting check_for_changes:");
 
-        let gh: GithubWrapper = github_async_new(owner.clone(), repo.clone()).await?;
-        let result = Self::get_pr_files_and_app(owner.clone(), repo.clone(), pr_number).await;
+        let gh: GithubWrapper = github_async_new(db, owner.clone(), repo.clone()).await?;
+        let result = Self::get_pr_files_and_app(db, owner.clone(), repo.clone(), pr_number).await;
 
         let sha: String;
         let filename: String;
@@ -2787,6 +3203,7 @@ impl LDNApplication {
         let application_id: String = application_file.id.clone();
 
         let db_application_str_result = database::applications::get_application(
+            db,
             application_file.id.clone(),
             owner.clone(),
             repo.clone(),
@@ -2833,6 +3250,7 @@ impl LDNApplication {
             Ok(f) => f,
             Err(e) => {
                 Self::add_error_label(
+                    db,
                     application_file.issue_number.clone(),
                     "".to_string(),
                     owner.clone(),
@@ -2865,6 +3283,7 @@ impl LDNApplication {
         let new_file_sha = file_update.content.sha;
 
         match database::applications::get_application_by_pr_number(
+            db,
             owner.clone(),
             repo.clone(),
             pr_number,
@@ -2873,6 +3292,7 @@ impl LDNApplication {
         {
             Ok(_) => {
                 database::applications::update_application(
+                    db,
                     application_id,
                     owner.clone(),
                     repo.clone(),
@@ -2881,6 +3301,7 @@ impl LDNApplication {
                     Some(filename.clone()),
                     new_file_sha,
                     application_file.client_contract_address.clone(),
+                    &WriteContext::new("Pull request changed on GitHub"),
                 )
                 .await
                 .map_err(|e| {
@@ -2900,6 +3321,7 @@ impl LDNApplication {
                     )
                     .await?;
                 database::applications::create_application(
+                    db,
                     application_id,
                     owner.clone(),
                     repo.clone(),
@@ -2920,6 +3342,7 @@ impl LDNApplication {
         let differences = application_file.compare(&db_application_file);
 
         Self::issue_changes_requested(
+            db,
             application_file.clone(),
             owner.clone(),
             repo.clone(),
@@ -2931,6 +3354,7 @@ impl LDNApplication {
     }
 
     pub async fn validate_approval(
+        db: &DbPool,
         pr_number: u64,
         owner: String,
         repo: String,
@@ -2938,7 +3362,7 @@ impl LDNApplication {
         log::info!("Starting validate_approval:");
         log::info!("Validating approval for PR number {}", pr_number);
         let application_file =
-            LDNApplication::single_active(pr_number, owner.clone(), repo.clone()).await?;
+            LDNApplication::single_active(db, pr_number, owner.clone(), repo.clone()).await?;
 
         if !application_file.lifecycle.get_active_status() {
             log::info!("No approval to validate. Application lifecycle is inactive so the Total DC was reached.");
@@ -2968,7 +3392,7 @@ impl LDNApplication {
                 .find_one(active_request_id)
                 .ok_or(LDNError::Load("Failed to get active request".to_string()))?;
 
-            let db_allocator = get_allocator(&owner, &repo)
+            let db_allocator = get_allocator(db, &owner, &repo)
                 .await
                 .map_err(|e| LDNError::Load(format!("Failed to get an allocator. /// {e}")))?
                 .ok_or(LDNError::Load("Allocator not found.".to_string()))?;
@@ -2989,7 +3413,7 @@ impl LDNApplication {
             let signer_gh_handle = signer.github_username.clone();
 
             let valid_verifiers: ValidVerifierList =
-                Self::fetch_verifiers(owner.clone(), repo.clone()).await?;
+                Self::fetch_verifiers(db, owner.clone(), repo.clone()).await?;
 
             if valid_verifiers.is_valid(&signer_gh_handle) {
                 log::info!("Val Approval (G)- Validated!");
@@ -3005,6 +3429,7 @@ impl LDNApplication {
     }
 
     pub async fn validate_proposal(
+        db: &DbPool,
         pr_number: u64,
         owner: String,
         repo: String,
@@ -3012,7 +3437,7 @@ impl LDNApplication {
         log::info!("Starting validate_proposal:");
         log::info!("- Validating proposal for PR number {}", pr_number);
         let application_file =
-            LDNApplication::single_active(pr_number, owner.clone(), repo.clone()).await?;
+            LDNApplication::single_active(db, pr_number, owner.clone(), repo.clone()).await?;
 
         if !application_file.lifecycle.get_active_status() {
             log::info!("No proposal to validate. Application lifecycle is inactive so the Total DC was reached.");
@@ -3041,7 +3466,8 @@ impl LDNApplication {
                     .first()
                     .ok_or(LDNError::Load("Failed to get signer".to_string()))?;
                 let signer_gh_handle = signer.github_username.clone();
-                let valid_verifiers = Self::fetch_verifiers(owner.clone(), repo.clone()).await?;
+                let valid_verifiers =
+                    Self::fetch_verifiers(db, owner.clone(), repo.clone()).await?;
                 if valid_verifiers.is_valid(&signer_gh_handle) {
                     log::info!("Val Proposal (SSD) - Validated!");
                     return Ok(true);
@@ -3067,10 +3493,14 @@ impl LDNApplication {
      * # Returns
      * `Result<LDNApplication, LDNError>` - The updated application.
      */
-    pub async fn update_from_issue(info: CreateApplicationInfo) -> Result<Self, LDNError> {
+    pub async fn update_from_issue(
+        db: &DbPool,
+        info: CreateApplicationInfo,
+    ) -> Result<Self, LDNError> {
         // Get the PR number from the issue number.
         let issue_number = info.issue_number.clone();
         let (mut parsed_ldn, _) = LDNApplication::parse_application_issue(
+            db,
             issue_number.clone(),
             info.owner.clone(),
             info.repo.clone(),
@@ -3079,6 +3509,7 @@ impl LDNApplication {
         let application_id = parsed_ldn.id.clone();
 
         let application_model = match Self::get_application_model(
+            db,
             application_id.clone(),
             info.owner.clone(),
             info.repo.clone(),
@@ -3095,18 +3526,19 @@ impl LDNApplication {
                 })?;
                 //Application Id has not been found. That means the user has modified the wallet address
                 let application = database::applications::get_application_by_issue_number(
+                    db,
                     info.owner.clone(),
                     info.repo.clone(),
                     parsed_issue_number,
                 )
                 .await;
                 if application.is_ok() {
-                    Self::add_comment_to_issue(issue_number, info.owner.clone(),info.repo.clone(), "Application exist. If you have modified the wallet address, please create a new application.".to_string()).await?;
+                    Self::add_comment_to_issue(db, issue_number, info.owner.clone(),info.repo.clone(), "Application exist. If you have modified the wallet address, please create a new application.".to_string()).await?;
                     return Err(LDNError::New(format!(
                         "Application exist: {application_id}"
                     )));
                 } else {
-                    return Self::new_from_issue(info).await;
+                    return Self::new_from_issue(db, info).await;
                 }
             }
         };
@@ -3118,14 +3550,16 @@ impl LDNApplication {
 
         //Application was granted. Create a new PR with the updated application file, as if it was a new application
         if application_model.pr_number == 0 {
-            return Self::create_pr_from_issue_modification(parsed_ldn, application_model).await;
+            return Self::create_pr_from_issue_modification(db, parsed_ldn, application_model)
+                .await;
         }
 
         //Application was in another state. Update PR and add "edited = true" to the application file
-        Self::edit_pr_from_issue_modification(parsed_ldn, application_model).await
+        Self::edit_pr_from_issue_modification(db, parsed_ldn, application_model).await
     }
 
     pub async fn edit_pr_from_issue_modification(
+        db: &DbPool,
         parsed_ldn: ParsedIssue,
         application_model: ApplicationModel,
     ) -> Result<Self, LDNError> {
@@ -3137,6 +3571,7 @@ impl LDNApplication {
             LDNError::Load(format!("Failed to parse application file from DB: {e}"))
         })?;
         let gh = github_async_new(
+            db,
             application_model.owner.to_string(),
             application_model.repo.to_string(),
         )
@@ -3158,6 +3593,7 @@ impl LDNApplication {
         if pr_application.lifecycle.get_state() == AppState::AdditionalInfoRequired {
             pr_application.lifecycle.state = AppState::AdditionalInfoSubmitted;
             let _ = Self::issue_additional_info_submitted(
+                db,
                 pr_application.issue_number.clone(),
                 application_model.owner.clone(),
                 application_model.repo.clone(),
@@ -3190,6 +3626,7 @@ impl LDNApplication {
             Ok(f) => f,
             Err(e) => {
                 Self::add_error_label(
+                    db,
                     app_file.issue_number.clone(),
                     "".to_string(),
                     application_model.owner.clone(),
@@ -3203,10 +3640,11 @@ impl LDNApplication {
         };
 
         //Create a new commit with the updated application file
-        let branch_name = gh
-            .get_branch_name_from_pr(application_model.pr_number as u64)
-            .await
-            .map_err(|e| LDNError::Load(format!("Failed to get branch name from PR: {e}")))?;
+        let store =
+            application_store(db, &application_model.owner, &application_model.repo).await?;
+        let branch_name = store
+            .change_branch(application_model.pr_number as u64)
+            .await?;
 
         let path = application_model
             .path
@@ -3217,6 +3655,7 @@ impl LDNApplication {
             .clone()
             .ok_or(LDNError::Load("Failed to get sha".to_string()))?;
         let new_file_sha = LDNPullRequest::add_commit_to(
+            store.as_ref(),
             path.clone(),
             branch_name.clone(),
             format!(
@@ -3225,27 +3664,31 @@ impl LDNApplication {
             ),
             file_content.clone(),
             sha.clone(),
-            application_model.owner.clone(),
-            application_model.repo.clone(),
         )
         .await?;
 
         if app_file.allocation.0.is_empty() {
-            let prs = gh
-                .get_pull_request_by_head(&branch_name)
-                .await
-                .map_err(|e| LDNError::Load(format!("Failed to get pull request by head: {e}")))?;
+            let pr_number = store.find_change(&application_id, &branch_name).await?;
 
-            if let Some(pr) = prs.first() {
+            if let Some(pr_number) = pr_number {
                 database::applications::update_application(
+                    db,
                     app_file.id.clone(),
                     application_model.owner.clone(),
                     application_model.repo.clone(),
-                    pr.number,
+                    pr_number,
                     file_content,
                     application_model.path.clone(),
                     new_file_sha,
                     app_file.client_contract_address,
+                    &WriteContext {
+                        side_effects: status_comment_messages(
+                            &application_model.owner,
+                            &application_model.repo,
+                            &app_file.id,
+                        )?,
+                        ..WriteContext::new("Application issue edited")
+                    },
                 )
                 .await
                 .map_err(|e| {
@@ -3258,11 +3701,14 @@ impl LDNApplication {
         };
 
         Ok(LDNApplication {
+            db: db.clone(),
             github: gh,
+            store,
             application_id,
             file_sha: sha,
             file_name: path,
             branch_name,
+            expected_sha: None,
         })
     }
 
@@ -3278,9 +3724,12 @@ impl LDNApplication {
             let evm_contract_address =
                 filecoin_address_to_evm_address_type(&contract_address).await?;
 
-            let client_allowance_on_contract =
-                get_allowance_for_address_contract(&evm_user_address, &evm_contract_address)
-                    .await?;
+            let client_allowance_on_contract = get_allowance_for_address_contract(
+                &evm_user_address,
+                &evm_contract_address,
+                &get_env_var_or_default("GLIF_NODE_URL"),
+            )
+            .await?;
             let contract_allowance = get_allowance_for_address_direct(&contract_address)
                 .await
                 .map_err(|e| LDNError::Load(format!("Failed to retrieve allowance: {e}")))?;
@@ -3315,6 +3764,7 @@ impl LDNApplication {
     }
 
     pub async fn create_pr_from_issue_modification(
+        db: &DbPool,
         parsed_ldn: ParsedIssue,
         application_model: ApplicationModel,
     ) -> Result<Self, LDNError> {
@@ -3326,6 +3776,7 @@ impl LDNApplication {
         })?;
 
         let gh = github_async_new(
+            db,
             application_model.owner.to_string(),
             application_model.repo.to_string(),
         )
@@ -3386,6 +3837,7 @@ impl LDNApplication {
             Ok(f) => f,
             Err(e) => {
                 Self::add_error_label(
+                    db,
                     application_file.issue_number.clone(),
                     "".to_string(),
                     application_model.owner.clone(),
@@ -3416,15 +3868,20 @@ impl LDNApplication {
             .clone()
             .ok_or(LDNError::Load("Failed to get sha".to_string()))?;
 
+        // GitHub backed changes are cached by the check_for_changes run on the new
+        // pull request, other backends have no such hook and must be cached here.
+        let store =
+            application_store(db, &application_model.owner, &application_model.repo).await?;
+        let should_create_in_db = store.kind() != ApplicationStoreKind::Github;
         LDNPullRequest::create_pr_for_existing_application(
+            db,
+            store.as_ref(),
             application_id.clone(),
             file_content.clone(),
             LDNPullRequest::application_path(&application_id),
             uuid,
             sha,
-            application_model.owner.clone(),
-            application_model.repo.clone(),
-            false,
+            should_create_in_db,
             application_file.issue_number.clone(),
             pr_title,
         )
@@ -3435,11 +3892,14 @@ impl LDNApplication {
             .ok_or(LDNError::Load("Failed to get sha".to_string()))?;
 
         Ok(LDNApplication {
+            db: db.clone(),
             github: gh,
+            store,
             application_id,
             file_sha: sha,
             file_name,
             branch_name,
+            expected_sha: None,
         })
     }
 
@@ -3477,12 +3937,13 @@ impl LDNApplication {
     }
 
     async fn add_comment_to_issue(
+        db: &DbPool,
         issue_number: String,
         owner: String,
         repo: String,
         comment: String,
     ) -> Result<bool, LDNError> {
-        let gh = github_async_new(owner, repo).await?;
+        let gh = github_async_new(db, owner, repo).await?;
 
         let issue_number = issue_number.parse::<u64>().map_err(|e| {
             LDNError::New(format!(
@@ -3502,6 +3963,7 @@ impl LDNApplication {
     }
 
     async fn issue_waiting_for_gov_review(
+        db: &DbPool,
         issue_number: String,
         owner: String,
         repo: String,
@@ -3509,6 +3971,7 @@ impl LDNApplication {
     ) -> Result<bool, LDNError> {
         let allocator_tech_url = get_env_var_or_default("ALLOCATOR_TECH_URL");
         Self::add_comment_to_issue(
+            db,
             issue_number,
             owner.clone(),
             repo.clone(),
@@ -3520,6 +3983,7 @@ impl LDNApplication {
     }
 
     async fn issue_pathway_mismatch_comment(
+        db: &DbPool,
         issue_number: String,
         info_owner: String,
         info_repo: String,
@@ -3560,19 +4024,42 @@ impl LDNApplication {
 
         dbg!(&comment);
 
-        Self::add_comment_to_issue(issue_number, info_owner, info_repo, comment).await?;
+        Self::add_comment_to_issue(db, issue_number, info_owner, info_repo, comment).await?;
 
         Ok(true)
     }
 
+    async fn add_templated_comment(
+        db: &DbPool,
+        issue_number: String,
+        owner: String,
+        repo: String,
+        kind: CommentKind,
+        variables: &CommentVariables,
+    ) -> Result<bool, LDNError> {
+        let comment = Self::render_templated_comment(db, &owner, &repo, kind, variables).await?;
+        Self::add_comment_to_issue(db, issue_number, owner, repo, comment).await
+    }
+
+    async fn render_templated_comment(
+        db: &DbPool,
+        owner: &str,
+        repo: &str,
+        kind: CommentKind,
+        variables: &CommentVariables,
+    ) -> Result<String, LDNError> {
+        let gh = github_async_new(db, owner.to_string(), repo.to_string()).await?;
+        Ok(CommentTemplates::load(db, &gh, allocator_repo_branch())
+            .await
+            .render(kind, variables))
+    }
+
     async fn issue_datacap_request_trigger(
+        db: &DbPool,
         application_file: ApplicationFile,
         owner: String,
         repo: String,
     ) -> Result<bool, LDNError> {
-        let client_address = application_file.lifecycle.client_on_chain_address.clone();
-        let total_requested = application_file.datacap.total_requested_amount.clone();
-        let weekly_allocation = application_file.datacap.weekly_allocation.clone();
         let allocation_amount = application_file
             .allocation
             .0
@@ -3581,27 +4068,26 @@ impl LDNApplication {
             .ok_or(LDNError::Load("Failed to get allocation".to_string()))?
             .amount
             .clone();
+        let variables = CommentVariables {
+            amount: allocation_amount,
+            verifier_handle: application_file.lifecycle.validated_by.clone(),
+            ..CommentVariables::from_application(&application_file)
+        };
 
-        let comment = format!(
-            "### Datacap Request Trigger
-**Total DataCap requested**
-> {total_requested}
-
-**Expected weekly DataCap usage rate**
-> {weekly_allocation}
-
-**DataCap Amount - First Tranche**
-> {allocation_amount}
-
-**Client address**
-> {client_address}"
-        );
-
-        Self::add_comment_to_issue(application_file.issue_number, owner, repo, comment).await?;
+        Self::add_templated_comment(
+            db,
+            application_file.issue_number,
+            owner,
+            repo,
+            CommentKind::DatacapRequestTrigger,
+            &variables,
+        )
+        .await?;
         Ok(true)
     }
 
     async fn issue_changes_requested(
+        db: &DbPool,
         application_file: ApplicationFile,
         owner: String,
         repo: String,
@@ -3617,6 +4103,7 @@ _(NEW vs OLD)_
         );
 
         Self::add_comment_to_issue(
+            db,
             application_file.issue_number.clone(),
             owner.clone(),
             repo.clone(),
@@ -3625,6 +4112,7 @@ _(NEW vs OLD)_
         .await?;
 
         Self::update_issue_labels(
+            db,
             application_file.issue_number,
             &[AppState::ChangesRequested.as_str()],
             owner,
@@ -3635,6 +4123,7 @@ _(NEW vs OLD)_
     }
 
     async fn issue_changes_approved(
+        db: &DbPool,
         issue_number: String,
         owner: String,
         repo: String,
@@ -3642,64 +4131,73 @@ _(NEW vs OLD)_
     ) -> Result<bool, LDNError> {
         let comment = "#### Issue information change request has been approved.".to_string();
 
-        Self::add_comment_to_issue(issue_number.clone(), owner.clone(), repo.clone(), comment)
-            .await?;
+        Self::add_comment_to_issue(
+            db,
+            issue_number.clone(),
+            owner.clone(),
+            repo.clone(),
+            comment,
+        )
+        .await?;
 
-        Self::update_issue_labels(issue_number, &[new_state.as_str()], owner, repo).await?;
+        Self::update_issue_labels(db, issue_number, &[new_state.as_str()], owner, repo).await?;
         Ok(true)
     }
 
     async fn issue_datacap_allocation_requested(
+        db: &DbPool,
         application_file: ApplicationFile,
         active_allocation: Option<&Allocation>,
         owner: String,
         repo: String,
     ) -> Result<bool, LDNError> {
-        let mut parsed_requested_datacap = String::new();
-        let mut id = String::new();
-
+        let mut variables = CommentVariables {
+            verifier_handle: application_file.lifecycle.validated_by.clone(),
+            ..CommentVariables::from_application(&application_file)
+        };
         if let Some(allocation) = active_allocation {
-            parsed_requested_datacap = format_size_human_readable(&allocation.amount)?;
-            id.clone_from(&allocation.id);
+            variables.amount = format_size_human_readable(&allocation.amount)?;
+            variables.allocation_id.clone_from(&allocation.id);
         }
-        let comment = format!(
-            "## DataCap Allocation requested
-
-#### Multisig Notary address
-> {}
-
-#### Client address
-> {}
-
-#### DataCap allocation requested
-> {}
 
-#### Id
-> {}",
-            application_file.datacap.identifier.clone(),
-            application_file.lifecycle.client_on_chain_address.clone(),
-            parsed_requested_datacap,
-            id
-        );
-
-        Self::add_comment_to_issue(application_file.issue_number, owner, repo, comment).await?;
+        Self::add_templated_comment(
+            db,
+            application_file.issue_number,
+            owner,
+            repo,
+            CommentKind::DatacapAllocationRequested,
+            &variables,
+        )
+        .await?;
         Ok(true)
     }
 
-    fn filfox_link(cid: &str) -> String {
-        if !cid.is_empty() {
-            format!("- https://filfox.info/en/message/{cid}")
-        } else {
-            String::new()
-        }
-    }
-
     async fn issue_datacap_request_signature(
+        db: &DbPool,
         application_file: ApplicationFile,
         signature_step: String,
         owner: String,
         repo: String,
     ) -> Result<(), LDNError> {
+        let comment = Self::datacap_request_signature_comment(
+            db,
+            &application_file,
+            signature_step,
+            &owner,
+            &repo,
+        )
+        .await?;
+        Self::add_comment_to_issue(db, application_file.issue_number, owner, repo, comment).await?;
+        Ok(())
+    }
+
+    async fn datacap_request_signature_comment(
+        db: &DbPool,
+        application_file: &ApplicationFile,
+        signature_step: String,
+        owner: &str,
+        repo: &str,
+    ) -> Result<String, LDNError> {
         let last_allocation = application_file
             .get_last_request_allowance()
             .ok_or(LDNError::Load("Last allocation not found".into()))?;
@@ -3708,90 +4206,100 @@ _(NEW vs OLD)_
             .0
             .last()
             .ok_or(LDNError::Load("Failed to get last verifier".into()))?;
-        let signature_step_lowercase = signature_step.to_lowercase();
-        let message_cid = last_sign_info.message_cid.clone().unwrap_or_default();
-        let increase_allowance_cid = last_sign_info
-            .increase_allowance_cid
-            .clone()
-            .unwrap_or_default();
-        let signing_address = last_sign_info.signing_address.clone();
-        let client_address = application_file.id.clone();
-        let parsed_requested_datacap = format_size_human_readable(&last_allocation.amount)?;
-        let request_id = last_allocation.id.clone();
-        let increase_allowance_cid_filfox_link = Self::filfox_link(&increase_allowance_cid);
-
-        let message_cid_filfox_link = Self::filfox_link(&message_cid);
+        let variables = CommentVariables {
+            client_address: application_file.id.clone(),
+            amount: format_size_human_readable(&last_allocation.amount)?,
+            allocation_id: last_allocation.id.clone(),
+            message_cid: last_sign_info.message_cid.clone().unwrap_or_default(),
+            increase_allowance_cid: last_sign_info
+                .increase_allowance_cid
+                .clone()
+                .unwrap_or_default(),
+            signer_address: last_sign_info.signing_address.clone(),
+            signature_step,
+            verifier_handle: last_sign_info.github_username.clone(),
+            ..CommentVariables::from_application(application_file)
+        };
 
-        let comment = format!(
-            "## Request {signature_step}
-Your Datacap Allocation Request has been {signature_step_lowercase} by the Notary
-#### Message sent to Filecoin Network
-> {message_cid} {increase_allowance_cid}
-#### Address
-> {client_address}
-#### Datacap Allocated
-> {parsed_requested_datacap}
-#### Signer Address
-> {signing_address}
-#### Id
-> {request_id}
-#### You can check the status here: 
-{message_cid_filfox_link}
-{increase_allowance_cid_filfox_link}
-",
-        );
-        Self::add_comment_to_issue(application_file.issue_number, owner, repo, comment).await?;
-        Ok(())
+        Self::render_templated_comment(
+            db,
+            owner,
+            repo,
+            CommentKind::DatacapRequestSignature,
+            &variables,
+        )
+        .await
     }
 
     async fn issue_refill(
-        issue_number: String,
+        db: &DbPool,
+        application_file: &ApplicationFile,
+        verifier: &str,
+        amount: String,
         owner: String,
         repo: String,
         early_refill_comment: Option<String>,
     ) -> Result<bool, LDNError> {
-        Self::add_comment_to_issue(
+        let issue_number = application_file.issue_number.clone();
+        let mut variables = CommentVariables {
+            amount,
+            verifier_handle: verifier.to_string(),
+            ..CommentVariables::from_application(application_file)
+        };
+        Self::add_templated_comment(
+            db,
             issue_number.clone(),
             owner.clone(),
             repo.clone(),
-            "Application is in Refill".to_string(),
+            CommentKind::Refill,
+            &variables,
         )
         .await?;
         if let Some(early_refill_comment) = early_refill_comment {
-            Self::add_comment_to_issue(
+            variables.reason = early_refill_comment;
+            Self::add_templated_comment(
+                db,
                 issue_number.clone(),
                 owner.clone(),
                 repo.clone(),
-                format!("## Reason for triggering a new allocation despite 75% of the previous one not being utilized\n {early_refill_comment}"),
+                CommentKind::EarlyRefill,
+                &variables,
             )
             .await?;
         }
-        Self::update_issue_labels(issue_number, &["Refill"], owner, repo).await?;
+        Self::update_issue_labels(db, issue_number, &["Refill"], owner, repo).await?;
         Ok(true)
     }
 
     async fn issue_additional_info_required(
-        issue_number: String,
+        db: &DbPool,
+        application_file: &ApplicationFile,
         owner: String,
         repo: String,
         verifier_message: String,
     ) -> Result<bool, LDNError> {
-        let comment = format!(
-            "## Additional Information Requested
-#### A verifier has reviewed your application and has issued the following message:
-
-> {verifier_message}
-
-_The initial issue can be edited in order to solve the request of the verifier. The changes will be reflected in the application and an automatic comment will be posted in order to let the verifiers know the updated application can be reviewed._"
-        );
+        let issue_number = application_file.issue_number.clone();
+        let variables = CommentVariables {
+            verifier_message,
+            ..CommentVariables::from_application(application_file)
+        };
 
-        Self::add_comment_to_issue(issue_number.clone(), owner.clone(), repo.clone(), comment)
+        Self::add_templated_comment(
+            db,
+            issue_number.clone(),
+            owner.clone(),
+            repo.clone(),
+            CommentKind::AdditionalInfoRequired,
+            &variables,
+        )
+        .await?;
+        Self::update_issue_labels(db, issue_number, &["Additional Info Required"], owner, repo)
             .await?;
-        Self::update_issue_labels(issue_number, &["Additional Info Required"], owner, repo).await?;
         Ok(true)
     }
 
     async fn issue_additional_info_submitted(
+        db: &DbPool,
         issue_number: String,
         owner: String,
         repo: String,
@@ -3800,33 +4308,55 @@ _The initial issue can be edited in order to solve the request of the verifier.
             "#### The application's issue was edited after additional information was requested"
                 .to_string();
 
-        Self::add_comment_to_issue(issue_number.clone(), owner.clone(), repo.clone(), comment)
-            .await?;
-        Self::update_issue_labels(issue_number, &["Additional Info Submitted"], owner, repo)
-            .await?;
+        Self::add_comment_to_issue(
+            db,
+            issue_number.clone(),
+            owner.clone(),
+            repo.clone(),
+            comment,
+        )
+        .await?;
+        Self::update_issue_labels(
+            db,
+            issue_number,
+            &["Additional Info Submitted"],
+            owner,
+            repo,
+        )
+        .await?;
         Ok(true)
     }
 
     async fn issue_application_declined(
-        issue_number: String,
+        db: &DbPool,
+        application_file: &ApplicationFile,
         owner: String,
         repo: String,
     ) -> Result<bool, LDNError> {
-        let comment = "### The application has been declined.".to_string();
+        let issue_number = application_file.issue_number.clone();
+        let variables = CommentVariables::from_application(application_file);
 
-        Self::add_comment_to_issue(issue_number.clone(), owner.clone(), repo.clone(), comment)
-            .await?;
-        Self::update_issue_labels(issue_number, &["Declined"], owner, repo).await?;
+        Self::add_templated_comment(
+            db,
+            issue_number.clone(),
+            owner.clone(),
+            repo.clone(),
+            CommentKind::ApplicationDeclined,
+            &variables,
+        )
+        .await?;
+        Self::update_issue_labels(db, issue_number, &["Declined"], owner, repo).await?;
         Ok(true)
     }
 
     async fn add_error_label(
+        db: &DbPool,
         issue_number: String,
         comment: String,
         owner: String,
         repo: String,
     ) -> Result<(), LDNError> {
-        let gh = github_async_new(owner, repo).await?;
+        let gh = github_async_new(db, owner, repo).await?;
         let num: u64 = issue_number.parse().expect("Not a valid integer");
         gh.add_error_label(num, comment)
             .await
@@ -3841,12 +4371,13 @@ _The initial issue can be edited in order to solve the request of the verifier.
     }
 
     async fn update_issue_labels(
+        db: &DbPool,
         issue_number: String,
         new_labels: &[&str],
         owner: String,
         repo: String,
     ) -> Result<(), LDNError> {
-        let gh = github_async_new(owner, repo).await?;
+        let gh = github_async_new(db, owner, repo).await?;
         let num: u64 = issue_number.parse().expect("Not a valid integer");
         let new_labels: Vec<String> = new_labels.iter().map(|&s| s.to_string()).collect();
         gh.replace_issue_labels(num, &new_labels)
@@ -3861,11 +4392,17 @@ _The initial issue can be edited in order to solve the request of the verifier.
         Ok(())
     }
 
-    pub async fn cache_renewal_active(owner: String, repo: String) -> Result<(), LDNError> {
+    pub async fn cache_renewal_active(
+        db: &DbPool,
+        owner: String,
+        repo: String,
+    ) -> Result<(), LDNError> {
         let active_from_gh: Vec<ApplicationFileWithDate> =
-            LDNApplication::active_apps_with_last_update(owner.clone(), repo.clone(), None).await?;
+            LDNApplication::active_apps_with_last_update(db, owner.clone(), repo.clone(), None)
+                .await?;
         let active_from_db: Vec<ApplicationModel> =
             database::applications::get_applications_with_open_pull_request(
+                db,
                 Some(owner.clone()),
                 Some(repo.clone()),
             )
@@ -3885,6 +4422,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
                     let parsed_app_file = serde_json::to_string_pretty(&gh_app.application_file)
                         .map_err(|e| LDNError::Load(format!("Failed to pare into string: {e}")))?;
                     database::applications::update_application(
+                        db,
                         db_app.id.clone(),
                         owner.clone(),
                         repo.clone(),
@@ -3893,6 +4431,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
                         None,
                         gh_app.sha.clone(),
                         gh_app.application_file.client_contract_address.clone(),
+                        &WriteContext::new("Cache renewal"),
                     )
                     .await
                     .map_err(|e| {
@@ -3908,10 +4447,12 @@ _The initial issue can be edited in order to solve the request of the verifier.
             } else {
                 // If the app is not in GH, call the delete_application function
                 database::applications::delete_application(
+                    db,
                     db_app.id.clone(),
                     owner.clone(),
                     repo.clone(),
                     db_app.pr_number as u64,
+                    &WriteContext::new("Cache renewal: not found on GitHub"),
                 )
                 .await
                 .map_err(|_| LDNError::New("Failed to delete application".to_string()))?;
@@ -3937,13 +4478,14 @@ _The initial issue can be edited in order to solve the request of the verifier.
                 let parsed_app_file = serde_json::to_string_pretty(&gh_app.application_file)
                     .map_err(|e| LDNError::Load(format!("Failed to pare into string: {e}")))?;
                 // Call the create_application function if the GH app is not in DB
-                let gh: GithubWrapper = github_async_new(owner.clone(), repo.clone()).await?;
+                let gh: GithubWrapper = github_async_new(db, owner.clone(), repo.clone()).await?;
                 let issue_reporter_handle = gh
                     .get_issue_reporter_handle(
                         &issue_number.try_into().expect("Value must be non-negative"),
                     )
                     .await?;
                 database::applications::create_application(
+                    db,
                     gh_app.application_file.id.clone(),
                     owner.clone(),
                     repo.clone(),
@@ -3964,11 +4506,17 @@ _The initial issue can be edited in order to solve the request of the verifier.
         Ok(())
     }
 
-    pub async fn cache_renewal_merged(owner: String, repo: String) -> Result<(), LDNError> {
+    pub async fn cache_renewal_merged(
+        db: &DbPool,
+        owner: String,
+        repo: String,
+    ) -> Result<(), LDNError> {
         let merged_from_gh: Vec<ApplicationFileWithDate> =
-            LDNApplication::merged_apps_with_last_update(owner.clone(), repo.clone(), None).await?;
+            LDNApplication::merged_apps_with_last_update(db, owner.clone(), repo.clone(), None)
+                .await?;
         let merged_from_db: Vec<ApplicationModel> =
             database::applications::get_merged_applications(
+                db,
                 Some(owner.clone()),
                 Some(repo.clone()),
             )
@@ -3989,6 +4537,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
                     let parsed_app_file = serde_json::to_string_pretty(&gh_app.application_file)
                         .map_err(|e| LDNError::Load(format!("Failed to pare into string: {e}")))?;
                     database::applications::update_application(
+                        db,
                         db_app.id.clone(),
                         owner.clone(),
                         repo.clone(),
@@ -3997,6 +4546,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
                         Some(gh_app.path.clone()),
                         gh_app.sha.clone(),
                         gh_app.application_file.client_contract_address.clone(),
+                        &WriteContext::new("Cache renewal"),
                     )
                     .await
                     .map_err(|e| {
@@ -4012,10 +4562,12 @@ _The initial issue can be edited in order to solve the request of the verifier.
             } else {
                 // If the app is not in GH, call the delete_application function
                 database::applications::delete_application(
+                    db,
                     db_app.id.clone(),
                     owner.clone(),
                     repo.clone(),
                     db_app.pr_number as u64,
+                    &WriteContext::new("Cache renewal: not found on GitHub"),
                 )
                 .await
                 .map_err(|e| LDNError::Load(format!("Failed to delete application: {e}")))?;
@@ -4040,13 +4592,14 @@ _The initial issue can be edited in order to solve the request of the verifier.
                 // Call the create_application function if the GH app is not in DB
                 let parsed_app_file = serde_json::to_string_pretty(&gh_app.application_file)
                     .map_err(|e| LDNError::Load(format!("Failed to pare into string: {e}")))?;
-                let gh: GithubWrapper = github_async_new(owner.clone(), repo.clone()).await?;
+                let gh: GithubWrapper = github_async_new(db, owner.clone(), repo.clone()).await?;
                 let issue_reporter_handle = gh
                     .get_issue_reporter_handle(
                         &issue_number.try_into().expect("Value must be non-negative"),
                     )
                     .await?;
                 database::applications::create_application(
+                    db,
                     gh_app.application_file.id.clone(),
                     owner.clone(),
                     repo.clone(),
@@ -4069,6 +4622,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
 
     pub async fn decline_application(&self, owner: String, repo: String) -> Result<(), LDNError> {
         let app_model = database::applications::get_application(
+            &self.db,
             self.application_id.clone(),
             owner.clone(),
             repo.clone(),
@@ -4103,13 +4657,13 @@ _The initial issue can be edited in order to solve the request of the verifier.
             let file_content = serde_json::to_string_pretty(&declined_application)
                 .map_err(|e| LDNError::Load(format!("Failed to pare into string: {e}")))?;
             LDNPullRequest::create_pr_for_existing_application(
+                &self.db,
+                self.store.as_ref(),
                 app_model.id.clone(),
                 file_content,
                 self.file_name.clone(),
                 format!("{}-decline-application", app_model.id),
                 self.file_sha.clone(),
-                owner.clone(),
-                repo.clone(),
                 true,
                 app_model.issue_number.to_string().clone(),
                 format!("Decline application: {}", declined_application.client.name),
@@ -4129,7 +4683,8 @@ _The initial issue can be edited in order to solve the request of the verifier.
         }
 
         LDNApplication::issue_application_declined(
-            application_file.issue_number.clone(),
+            &self.db,
+            &application_file,
             owner.clone(),
             repo.clone(),
         )
@@ -4144,12 +4699,14 @@ _The initial issue can be edited in order to solve the request of the verifier.
     }
 
     pub async fn reopen_declined_application(
+        db: &DbPool,
         owner: &str,
         repo: &str,
         verifier: &str,
         application_id: &str,
     ) -> Result<(), LDNError> {
         let app_model = database::applications::get_application(
+            db,
             application_id.into(),
             owner.into(),
             repo.into(),
@@ -4203,15 +4760,16 @@ _The initial issue can be edited in order to solve the request of the verifier.
         let sha = app_model
             .sha
             .ok_or(LDNError::Load("Failed to get application sha".to_string()))?;
+        let store = application_store(db, owner, repo).await?;
         let NewPrNumberAndFileSha { pr_number, .. } =
             LDNPullRequest::create_pr_for_existing_application(
+                db,
+                store.as_ref(),
                 app_model.id.clone(),
                 file_content,
                 path,
                 format!("{}-reopen-application", app_model.id),
                 sha,
-                owner.into(),
-                repo.into(),
                 true,
                 app_model.issue_number.to_string().clone(),
                 format!("Reopen application: {}", reopen_application.client.name),
@@ -4219,6 +4777,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
             .await?;
 
         Self::add_comment_to_issue(
+            db,
             app_model.issue_number.to_string(),
             owner.into(),
             repo.into(),
@@ -4226,6 +4785,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
         )
         .await?;
         Self::update_issue_labels(
+            db,
             app_model.issue_number.to_string(),
             &[issue_label],
             owner.into(),
@@ -4234,20 +4794,18 @@ _The initial issue can be edited in order to solve the request of the verifier.
         .await?;
 
         if !application_file.allocation.0.is_empty() {
-            let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
-
-            gh.merge_pull_request_and_delete_branch(&pr_number)
-                .await
-                .map_err(|e| {
-                    LDNError::Load(format!(
-                        "Failed to merge pull request {pr_number} or delete branch. Reason: {e}"
-                    ))
-                })?;
+            store.merge_change(pr_number).await?;
 
             database::applications::merge_application_by_pr_number(
+                db,
                 owner.to_string(),
                 repo.to_string(),
                 pr_number,
+                &WriteContext {
+                    actor: Some(verifier.to_string()),
+                    side_effects: status_comment_messages(owner, repo, &application_file.id)?,
+                    ..WriteContext::new("Declined application reopened")
+                },
             )
             .await
             .map_err(|e| {
@@ -4338,6 +4896,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
         let application_id: String = self.application_id.clone();
 
         let db_application_file_str_result = database::applications::get_application(
+            &self.db,
             application_id,
             owner.clone(),
             repo.clone(),
@@ -4382,7 +4941,8 @@ _The initial issue can be edited in order to solve the request of the verifier.
             .await?;
 
         let _ = Self::issue_additional_info_required(
-            db_application_file.issue_number.clone(),
+            &self.db,
+            &db_application_file,
             owner.clone(),
             repo.clone(),
             verifier_message.clone(),
@@ -4393,9 +4953,13 @@ _The initial issue can be edited in order to solve the request of the verifier.
     }
 
     pub async fn request_kyc(self, id: &str, owner: &str, repo: &str) -> Result<(), LDNError> {
-        let app_model =
-            Self::get_application_model(id.to_string(), owner.to_string(), repo.to_string())
-                .await?;
+        let app_model = Self::get_application_model(
+            &self.db,
+            id.to_string(),
+            owner.to_string(),
+            repo.to_string(),
+        )
+        .await?;
 
         let app_str = app_model.application.ok_or_else(|| {
             LDNError::Load(format!(
@@ -4447,6 +5011,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
         );
 
         Self::add_comment_to_issue(
+            &self.db,
             issue_number.into(),
             self.github.owner.clone(),
             self.github.repo.clone(),
@@ -4472,13 +5037,15 @@ _The initial issue can be edited in order to solve the request of the verifier.
     }
 
     pub async fn trigger_ssa(
+        db: &DbPool,
         id: &str,
         owner: &str,
         repo: &str,
         verifier: &str,
         info: TriggerSSAInfo,
     ) -> Result<(), LDNError> {
-        let app_model = Self::get_application_model(id.into(), owner.into(), repo.into()).await?;
+        let app_model =
+            Self::get_application_model(db, id.into(), owner.into(), repo.into()).await?;
 
         let app_str = app_model.application.ok_or_else(|| {
             LDNError::Load(format!(
@@ -4519,7 +5086,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
             repo: app_model.repo,
             early_refill_comment: info.early_refill_comment,
         };
-        Self::refill(verifier, refill_info).await?;
+        Self::refill(db, verifier, refill_info).await?;
         Ok(())
     }
 
@@ -4528,7 +5095,8 @@ _The initial issue can be edited in order to solve the request of the verifier.
         let repo = &info.message.allocator_repo_name;
         let owner = &info.message.allocator_repo_owner;
         let app_model =
-            Self::get_application_model(client_id.clone(), owner.clone(), repo.clone()).await?;
+            Self::get_application_model(&self.db, client_id.clone(), owner.clone(), repo.clone())
+                .await?;
 
         let app_str = app_model.application.ok_or_else(|| {
             LDNError::Load(format!(
@@ -4550,8 +5118,55 @@ _The initial issue can be edited in order to solve the request of the verifier.
         let address_from_signature =
             LDNApplication::verify_kyc_data_and_get_eth_address(&info.message, &info.signature)?;
 
-        let score = verify_on_gitcoin(&address_from_signature).await?;
-        let application_file = application_file.move_back_to_submit_state();
+        let providers = kyc_providers(&self.db, owner, repo).await?;
+        let subject = KycSubject {
+            client_id: client_id.clone(),
+            address: address_from_signature,
+            attestation: info.attestation.clone(),
+        };
+        let verification = verify_kyc(&providers, &subject).await?;
+
+        // Consumed before the state changes, so a replayed message is rejected
+        // even while the first submission is in progress, and released again
+        // when the submission fails.
+        LDNApplication::consume_kyc_signature(
+            &self.db,
+            &info.message,
+            &info.signature,
+            &address_from_signature,
+        )
+        .await?;
+        let submitted = self
+            .complete_kyc(
+                application_file,
+                app_model.path,
+                app_model.sha,
+                owner,
+                repo,
+                &verification,
+                &address_from_signature,
+            )
+            .await;
+        if submitted.is_err() {
+            if let Err(e) = LDNApplication::release_kyc_signature(&self.db, &info.signature).await {
+                log::error!("Failed to release KYC signature: {e}");
+            }
+        }
+        submitted
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn complete_kyc(
+        &self,
+        application_file: ApplicationFile,
+        path: Option<String>,
+        sha: Option<String>,
+        owner: &str,
+        repo: &str,
+        verification: &KycVerification,
+        address_from_signature: &Address,
+    ) -> Result<(), LDNError> {
+        let application_file = application_file.kyc_completed();
 
         let parsed_issue_number = &application_file.issue_number.parse::<u64>().map_err(|e| {
             LDNError::New(format!(
@@ -4560,41 +5175,40 @@ _The initial issue can be edited in order to solve the request of the verifier.
             ))
         })?;
 
-        self.issue_updates_for_kyc_submit(parsed_issue_number, &score, &address_from_signature)
-            .await?;
+        let path = path.ok_or(LDNError::Load("Failed to get path".to_string()))?;
 
-        let path = app_model
-            .path
-            .ok_or(LDNError::Load("Failed to get path".to_string()))?;
-
-        let sha = app_model
-            .sha
-            .ok_or(LDNError::Load("Failed to get sha".to_string()))?;
+        let sha = sha.ok_or(LDNError::Load("Failed to get sha".to_string()))?;
 
+        // Only one submission moves the application out of KYC requested, the
+        // others get a conflict.
         self.update_and_commit_application_state(
             application_file.clone(),
-            owner.clone(),
-            repo.clone(),
+            owner.to_string(),
+            repo.to_string(),
             sha,
             LDNPullRequest::application_branch_name(&application_file.id),
             path,
             "KYC submitted".to_string(),
         )
         .await?;
-        Ok(())
+
+        self.issue_updates_for_kyc_submit(parsed_issue_number, verification, address_from_signature)
+            .await
     }
 
     async fn issue_updates_for_kyc_submit(
         &self,
         issue_number: &u64,
-        score: &f64,
+        verification: &KycVerification,
         eth_address: &Address,
     ) -> Result<(), LDNError> {
         let comment = format!(
-            "KYC completed for client address `{}` with Optimism address `{}` and passport score `{}`.", &self.application_id, eth_address, score.round() as i64
+            "KYC completed for client address `{}` with address `{}` and {}.",
+            &self.application_id, eth_address, verification
         );
 
         Self::add_comment_to_issue(
+            &self.db,
             issue_number.to_string(),
             self.github.owner.clone(),
             self.github.repo.clone(),
@@ -4659,6 +5273,42 @@ _The initial issue can be edited in order to solve the request of the verifier.
         Ok(address_from_signature)
     }
 
+    /// Records the signature of an accepted message, so the same signed message
+    /// cannot be submitted again before it expires.
+    async fn consume_kyc_signature<T: ExpirableSolStruct>(
+        db: &DbPool,
+        message: &T,
+        signature: &str,
+        signer: &Address,
+    ) -> Result<(), LDNError> {
+        let expires_at = DateTime::parse_from_rfc3339(message.get_expires_at())
+            .map_err(|e| LDNError::New(format!("Parse &str to DateTime failed: {e:?}")))?
+            .with_timezone(&Utc);
+        let consumed = used_signatures::consume_signature(
+            db,
+            &signature_hash(signature)?,
+            T::NAME,
+            &signer.to_checksum(None),
+            expires_at,
+        )
+        .await
+        .map_err(|e| LDNError::Load(format!("Recording signature failed: {e}")))?;
+        if !consumed {
+            return Err(LDNError::Load(
+                "This signed message was already used".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Releases the signature of a message whose request failed before it
+    /// changed anything, so the message can be submitted again.
+    async fn release_kyc_signature(db: &DbPool, signature: &str) -> Result<(), LDNError> {
+        used_signatures::release_signature(db, &signature_hash(signature)?)
+            .await
+            .map_err(|e| LDNError::Load(format!("Releasing signature failed: {e}")))
+    }
+
     pub async fn remove_pending_allocation(
         &self,
         client_id: &str,
@@ -4666,7 +5316,8 @@ _The initial issue can be edited in order to solve the request of the verifier.
         repo: &str,
     ) -> Result<(), LDNError> {
         let app_model =
-            Self::get_application_model(client_id.into(), owner.into(), repo.into()).await?;
+            Self::get_application_model(&self.db, client_id.into(), owner.into(), repo.into())
+                .await?;
 
         let application_file =
             Self::get_application_file_with_active_allocation(&app_model).await?;
@@ -4709,7 +5360,8 @@ _The initial issue can be edited in order to solve the request of the verifier.
         repo: &str,
     ) -> Result<(), LDNError> {
         let app_model =
-            Self::get_application_model(client_id.into(), owner.into(), repo.into()).await?;
+            Self::get_application_model(&self.db, client_id.into(), owner.into(), repo.into())
+                .await?;
 
         let application_file =
             Self::get_application_file_with_active_allocation(&app_model).await?;
@@ -4756,20 +5408,22 @@ _The initial issue can be edited in order to solve the request of the verifier.
     }
 
     async fn remove_pending_refill(&self, pr_number: &u64) -> Result<(), LDNError> {
-        self.github
-            .delete_branch_safe(pr_number)
-            .await
-            .map_err(|e| {
-                LDNError::New(format!(
-                    "Failed to delete branch for PR number: {pr_number} Reason: {e:?}"
-                ))
-            })?;
+        self.store.close_change(*pr_number).await?;
 
         database::applications::delete_application(
+            &self.db,
             self.application_id.clone(),
             self.github.owner.clone(),
             self.github.repo.clone(),
             *pr_number,
+            &WriteContext {
+                side_effects: status_comment_messages(
+                    &self.github.owner,
+                    &self.github.repo,
+                    &self.application_id,
+                )?,
+                ..WriteContext::new("Pending refill removed")
+            },
         )
         .await
         .map_err(|e| {
@@ -4805,6 +5459,7 @@ _The initial issue can be edited in order to solve the request of the verifier.
         label: &str,
     ) -> Result<(), LDNError> {
         Self::add_comment_to_issue(
+            &self.db,
             issue_number.into(),
             self.github.owner.clone(),
             self.github.repo.clone(),
@@ -4856,6 +5511,20 @@ _The initial issue can be edited in order to solve the request of the verifier.
     }
 }
 
+/// Queues the status comment of an application that was just created. Failures
+/// are logged only, the status comment must never fail the change.
+async fn queue_status_comment(db: &DbPool, owner: &str, repo: &str, application_id: &str) {
+    let result = match status_comment_messages(owner, repo, application_id) {
+        Ok(messages) => enqueue_outbox_messages(db, &messages)
+            .await
+            .map_err(|e| LDNError::Load(e.to_string())),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        log::warn!("Failed to queue status comment of {owner}/{repo}/{application_id}: {e}");
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct LDNPullRequest {
     pub branch_name: String,
@@ -4867,107 +5536,75 @@ pub struct LDNPullRequest {
 impl LDNPullRequest {
     #[allow(clippy::too_many_arguments)]
     async fn create_pr_for_new_application(
+        store: &dyn ApplicationStore,
         issue_number: String,
         owner_name: String,
         app_branch_name: String,
         file_name: String,
         file_content: String,
-        owner: String,
-        repo: String,
         application_id: String,
-    ) -> Result<String, LDNError> {
+    ) -> Result<NewPrNumberAndFileSha, LDNError> {
         let initial_commit = Self::application_initial_commit(&owner_name, &issue_number);
-        let gh: GithubWrapper = github_async_new(owner.to_string(), repo.to_string()).await?;
-        let head_hash = gh.get_main_branch_sha().await?;
-        let create_ref_request = gh
-            .build_create_ref_request(app_branch_name.clone(), head_hash)
-            .map_err(|e| {
-                LDNError::New(format!(
-                    "Application issue {issue_number} cannot create branch /// {e}"
-                ))
-            })?;
-
-        let issue_link = format!("https://github.com/{owner}/{repo}/issues/{issue_number}");
-
-        let (_pr, file_sha) = gh
-            .create_merge_request(CreateMergeRequestData {
-                issue_link,
-                branch_name: app_branch_name,
-                file_name,
-                owner_name,
-                ref_request: create_ref_request,
-                file_content,
-                commit: initial_commit,
+        store
+            .create_file(NewApplicationFile {
                 application_id,
+                issue_number,
+                owner_name,
+                branch_name: app_branch_name,
+                path: file_name,
+                content: file_content,
+                commit_message: initial_commit,
             })
             .await
-            .map_err(|e| {
-                LDNError::New(format!(
-                    "Application issue {issue_number} cannot create merge request /// {e}"
-                ))
-            })?;
-
-        Ok(file_sha)
     }
 
     #[allow(clippy::too_many_arguments)]
     async fn create_pr_for_existing_application(
+        db: &DbPool,
+        store: &dyn ApplicationStore,
         application_id: String,
         file_content: String,
         file_name: String,
         branch_name: String,
         file_sha: String,
-        owner: String,
-        repo: String,
         should_create_in_db: bool,
         issue_number: String,
         pr_title: String,
     ) -> Result<NewPrNumberAndFileSha, LDNError> {
-        let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
-        let head_hash = gh.get_main_branch_sha().await?;
-        let create_ref_request = gh
-            .build_create_ref_request(branch_name.clone(), head_hash)
-            .map_err(|e| {
-                LDNError::New(format!(
-                    "Application issue {application_id} cannot create branch /// {e}"
-                ))
-            })?;
-
-        let issue_link = format!("https://github.com/{owner}/{repo}/issues/{issue_number}");
-
-        let (pr, file_sha) = gh
-            .create_refill_merge_request(CreateRefillMergeRequestData {
-                issue_link,
-                file_name: file_name.clone(),
-                file_sha: file_sha.clone(),
-                ref_request: create_ref_request,
-                branch_name,
-                file_content: file_content.clone(),
-                commit: pr_title,
+        let new_pr = store
+            .propose_change(ApplicationChange {
                 application_id: application_id.clone(),
+                issue_number: issue_number.clone(),
+                branch_name,
+                path: file_name.clone(),
+                file_sha,
+                content: file_content.clone(),
+                title: pr_title,
             })
-            .await
-            .map_err(|e| LDNError::Load(format!("Failed to create a pull request: {e}")))?;
+            .await?;
 
         if should_create_in_db {
             let issue_number = issue_number
                 .parse::<i64>()
                 .map_err(|e| LDNError::New(format!("Parse issue number to i64 failed: {e}")))?;
+            let gh =
+                github_async_new(db, store.owner().to_string(), store.repo().to_string()).await?;
             let issue_reporter_handle = gh
                 .get_issue_reporter_handle(
                     &issue_number.try_into().expect("Value must be non-negative"),
                 )
                 .await?;
             database::applications::create_application(
+                db,
                 application_id.clone(),
-                owner,
-                repo,
-                pr.number,
+                store.owner().to_string(),
+                store.repo().to_string(),
+                new_pr.pr_number,
                 issue_number,
                 file_content,
                 file_name,
                 Some(issue_reporter_handle),
-                file_sha.clone(),
+                new_pr.file_sha.clone(),
             )
             .await
             .map_err(|e| {
@@ -4975,47 +5612,38 @@ impl LDNPullRequest {
                     "Application issue {application_id} cannot create branch /// {e}"
                 ))
             })?;
+            queue_status_comment(db, store.owner(), store.repo(), &application_id).await;
         }
-        Ok(NewPrNumberAndFileSha {
-            pr_number: pr.number,
-            file_sha,
-        })
+        Ok(new_pr)
     }
 
     pub async fn add_commit_to(
+        store: &dyn ApplicationStore,
         path: String,
         branch_name: String,
         commit_message: String,
         new_content: String,
         file_sha: String,
-        owner: String,
-        repo: String,
     ) -> Result<String, LDNError> {
-        let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
-        let file_update = gh
-            .update_file_content(
-                &path,
-                &commit_message,
-                &new_content,
-                &branch_name,
-                &file_sha,
-            )
+        store
+            .commit_change(ApplicationCommit {
+                path,
+                branch_name,
+                message: commit_message,
+                content: new_content,
+                file_sha,
+            })
             .await
-            .map_err(|e| LDNError::Load(format!("Failed to add commit: {e}")))?;
-        let file_sha = file_update.content.sha;
-        Ok(file_sha)
     }
 
     pub async fn close_pull_request(
-        owner: String,
-        repo: String,
+        store: &dyn ApplicationStore,
         pr_number: u64,
     ) -> Result<(), LDNError> {
-        let gh = github_async_new(owner.clone(), repo.clone()).await?;
-        gh.close_pull_request(pr_number).await.map_err(|e| {
-            LDNError::New(format!("Error closing pull request {pr_number} /// {e}"))
-        })?;
-        Ok(())
+        store
+            .close_change(pr_number)
+            .await
+            .map_err(|e| LDNError::New(format!("Error closing pull request {pr_number} /// {e}")))
     }
 
     pub(super) fn application_branch_name(application_id: &str) -> String {
@@ -5051,29 +5679,6 @@ pub fn get_file_sha(content: &ContentItems) -> Option<String> {
     content.items.first().map(|item| item.clone().sha)
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-
-//     #[tokio::test]
-//     async fn test_update_pr_from_isue() {
-//         let _ = fplus_database::setup_test_environment().await;
-//         let info = CreateApplicationInfo {
-//             issue_number: "28".to_string(),
-//             owner: "clriesco".to_string(),
-//             repo: "king-charles-staging".to_string(),
-//         };
-//         match LDNApplication::update_from_issue(info).await {
-//             Ok(app) => {
-//                 log::info!("Application updated: {:?}", app);
-//             }
-//             Err(e) => {
-//                 log::error!("Failed to update application: {}", e);
-//             }
-//         }
-//     }
-// }
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -5128,6 +5733,558 @@ mod tests {
         assert_eq!(application_file.lifecycle.state, AppState::Submitted);
     }
 
+    #[tokio::test]
+    async fn concurrent_change_is_reloaded_and_reapplied() {
+        use crate::core::application::store::InMemoryApplicationStore;
+        use crate::external_services::github_fake::FakeGithub;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let fake = FakeGithub::install();
+        fake.create_repo("owner", "conflict-repo", 1);
+        let store = Arc::new(InMemoryApplicationStore::new(
+            "owner".into(),
+            "conflict-repo".into(),
+            ApplicationStoreKind::Database,
+        ));
+        let application_file = ApplicationFile::new(
+            "1".into(),
+            "false".into(),
+            application::file::Version::Text("1.3".to_string()),
+            "f1test".into(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        )
+        .await;
+        let path = "applications/f1test.json".to_string();
+        let branch_name = "Application/f1test".to_string();
+        let created = store
+            .create_file(NewApplicationFile {
+                application_id: "f1test".into(),
+                issue_number: "1".into(),
+                owner_name: "Client".into(),
+                branch_name: branch_name.clone(),
+                path: path.clone(),
+                content: serde_json::to_string_pretty(&application_file).unwrap(),
+                commit_message: "Start Application".into(),
+            })
+            .await
+            .unwrap();
+        let mut ldn_application = LDNApplication {
+            db: fplus_database::setup_test_environment().await,
+            github: GithubWrapper::new("owner".into(), "conflict-repo".into(), Some(1)).unwrap(),
+            store: store.clone(),
+            application_id: "f1test".into(),
+            file_sha: created.file_sha.clone(),
+            file_name: path.clone(),
+            branch_name: branch_name.clone(),
+            expected_sha: None,
+        };
+        assert!(matches!(
+            ldn_application.expect_version("stale"),
+            Err(LDNError::Conflict(_))
+        ));
+
+        // Another verifier commits between our read and our commit.
+        let mut concurrent = application_file.clone();
+        concurrent.client.name = "Concurrent".into();
+        store
+            .commit_before_next(ApplicationCommit {
+                path: path.clone(),
+                branch_name: branch_name.clone(),
+                message: "Concurrent change".into(),
+                content: serde_json::to_string_pretty(&concurrent).unwrap(),
+                file_sha: created.file_sha.clone(),
+            })
+            .unwrap();
+
+        let attempts = AtomicUsize::new(0);
+        let update = ldn_application
+            .commit_file_update(|mut app_file| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                let seen_name = app_file.client.name.clone();
+                app_file.client.industry = "Updated".into();
+                Ok(FileUpdate {
+                    file: app_file,
+                    commit_message: "Update".into(),
+                    outcome: seen_name,
+                })
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(update.outcome, "Concurrent");
+        let stored = store.read_file(&path, &branch_name).await.unwrap().unwrap();
+        assert_eq!(stored.sha, update.sha);
+        assert_eq!(stored.application_file.client.name, "Concurrent");
+        assert_eq!(stored.application_file.client.industry, "Updated");
+
+        // A client acting on the version it read gets the concurrent change
+        // reported instead.
+        ldn_application.file_sha = update.sha.clone();
+        ldn_application.expect_version(&update.sha).unwrap();
+        concurrent.client.name = "Concurrent again".into();
+        store
+            .commit_before_next(ApplicationCommit {
+                path: path.clone(),
+                branch_name: branch_name.clone(),
+                message: "Concurrent change".into(),
+                content: serde_json::to_string_pretty(&concurrent).unwrap(),
+                file_sha: update.sha.clone(),
+            })
+            .unwrap();
+        let attempts = AtomicUsize::new(0);
+        let rejected = ldn_application
+            .commit_file_update(|app_file| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Ok(FileUpdate {
+                    file: app_file,
+                    commit_message: "Update".into(),
+                    outcome: (),
+                })
+            })
+            .await;
+        assert!(matches!(rejected, Err(LDNError::PreconditionFailed(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        let stored = store.read_file(&path, &branch_name).await.unwrap().unwrap();
+        assert_eq!(stored.application_file.client.name, "Concurrent again");
+    }
+
+    const GH_OWNER: &str = "fake-owner";
+    const GH_INSTALLATION_ID: u64 = 4242;
+    const CLIENT_ADDRESS: &str = "f1qoxqy3npwcvoqy7gpstm65lejcy7pkd3hqqekna";
+
+    fn issue_body(address: &str, industry: &str) -> String {
+        [
+            ("Data Owner Name", "Dataset"),
+            ("Data Owner Country/Region", "Germany"),
+            ("Data Owner Industry", industry),
+            ("Website", "https://example.com"),
+            ("On-chain address for first allocation", address),
+            ("Total amount of DataCap being requested", "5PiB"),
+            ("Weekly allocation of DataCap requested", "1PiB"),
+            ("Number of replicas to store", "4"),
+        ]
+        .iter()
+        .map(|(heading, value)| format!("### {heading}\n\n{value}\n\n"))
+        .collect()
+    }
+
+    /// Fake repository with an allocator row pointing at it.
+    async fn github_allocator(
+        repo: &str,
+    ) -> (crate::external_services::github_fake::FakeGithub, DbPool) {
+        let fake = crate::external_services::github_fake::FakeGithub::install();
+        fake.create_repo(GH_OWNER, repo, GH_INSTALLATION_ID);
+        let db = fplus_database::setup_test_environment().await;
+        database::allocators::create_or_update_allocator(
+            &db,
+            GH_OWNER.to_string(),
+            repo.to_string(),
+            Some(GH_INSTALLATION_ID as i64),
+            Some("f2multisig".to_string()),
+            None,
+            Some(2),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        (fake, db)
+    }
+
+    /// Opens the pull request and the database row of the application in
+    /// `issue_body`, as `new_from_issue` does once the chain checks passed.
+    async fn open_application(db: &DbPool, repo: &str, issue_number: u64, body: &str) -> u64 {
+        let parsed = ParsedIssue::from_issue_body(body).unwrap();
+        let application_file = ApplicationFile::new(
+            issue_number.to_string(),
+            "false".into(),
+            parsed.version,
+            parsed.id.clone(),
+            parsed.client,
+            parsed.project,
+            parsed.datacap,
+        )
+        .await;
+        let content = serde_json::to_string_pretty(&application_file).unwrap();
+        let store = application_store(db, GH_OWNER, repo).await.unwrap();
+        let created = LDNPullRequest::create_pr_for_new_application(
+            store.as_ref(),
+            issue_number.to_string(),
+            "Dataset".into(),
+            LDNPullRequest::application_branch_name(&parsed.id),
+            LDNPullRequest::application_path(&parsed.id),
+            content.clone(),
+            parsed.id.clone(),
+        )
+        .await
+        .unwrap();
+        database::applications::create_application(
+            db,
+            parsed.id.clone(),
+            GH_OWNER.to_string(),
+            repo.to_string(),
+            created.pr_number,
+            issue_number as i64,
+            content,
+            LDNPullRequest::application_path(&parsed.id),
+            None,
+            created.file_sha,
+        )
+        .await
+        .unwrap();
+        created.pr_number
+    }
+
+    #[tokio::test]
+    async fn invalid_issue_is_reported_on_github() {
+        let (fake, db) = github_allocator("invalid-issue-repo").await;
+        let issue_number = fake.create_issue(
+            GH_OWNER,
+            "invalid-issue-repo",
+            "Application",
+            &issue_body("_No response_", "Science"),
+        );
+
+        let result = LDNApplication::new_from_issue(
+            &db,
+            CreateApplicationInfo {
+                issue_number: issue_number.to_string(),
+                owner: GH_OWNER.to_string(),
+                repo: "invalid-issue-repo".to_string(),
+            },
+        )
+        .await;
+
+        assert!(result.is_err());
+        let comments = fake.issue_comments(GH_OWNER, "invalid-issue-repo", issue_number);
+        assert!(comments[0].contains("**On-chain address for first allocation**"));
+        assert!(fake
+            .issue_labels(GH_OWNER, "invalid-issue-repo", issue_number)
+            .contains(&AppState::Error.as_str().to_string()));
+        assert!(fake
+            .open_pull_requests(GH_OWNER, "invalid-issue-repo")
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn issue_edit_updates_the_open_pull_request() {
+        let repo = "edited-issue-repo";
+        let (fake, db) = github_allocator(repo).await;
+        let issue_number = fake.create_issue(
+            GH_OWNER,
+            repo,
+            "Application",
+            &issue_body(CLIENT_ADDRESS, "Science"),
+        );
+        let pr_number = open_application(
+            &db,
+            repo,
+            issue_number,
+            &issue_body(CLIENT_ADDRESS, "Science"),
+        )
+        .await;
+        fake.set_issue_body(
+            GH_OWNER,
+            repo,
+            issue_number,
+            &issue_body(CLIENT_ADDRESS, "Agriculture"),
+        );
+
+        LDNApplication::update_from_issue(
+            &db,
+            CreateApplicationInfo {
+                issue_number: issue_number.to_string(),
+                owner: GH_OWNER.to_string(),
+                repo: repo.to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let branch = LDNPullRequest::application_branch_name(CLIENT_ADDRESS);
+        let path = LDNPullRequest::application_path(CLIENT_ADDRESS);
+        let on_branch =
+            ApplicationFile::from_str(&fake.file(GH_OWNER, repo, &branch, &path).unwrap()).unwrap();
+        assert_eq!(on_branch.client.industry, "Agriculture");
+        assert_eq!(
+            fake.open_pull_requests(GH_OWNER, repo)
+                .iter()
+                .map(|(number, _)| *number)
+                .collect::<Vec<_>>(),
+            vec![pr_number]
+        );
+        let in_db = LDNApplication::load_from_db(
+            &db,
+            CLIENT_ADDRESS.to_string(),
+            GH_OWNER.to_string(),
+            repo.to_string(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(in_db.client.industry, "Agriculture");
+    }
+
+    #[tokio::test]
+    async fn merged_application_lands_on_main() {
+        let repo = "merged-application-repo";
+        let (fake, db) = github_allocator(repo).await;
+        let body = issue_body(CLIENT_ADDRESS, "Science");
+        let issue_number = fake.create_issue(GH_OWNER, repo, "Application", &body);
+        let pr_number = open_application(&db, repo, issue_number, &body).await;
+        let branch = LDNPullRequest::application_branch_name(CLIENT_ADDRESS);
+        let path = LDNPullRequest::application_path(CLIENT_ADDRESS);
+        assert!(fake.file(GH_OWNER, repo, "main", &path).is_none());
+
+        LDNApplication::merge_application_and_delete_branch(
+            &db,
+            pr_number,
+            GH_OWNER.to_string(),
+            repo.to_string(),
+        )
+        .await
+        .unwrap();
+
+        assert!(fake.file(GH_OWNER, repo, "main", &path).is_some());
+        assert!(!fake.branches(GH_OWNER, repo).contains(&branch));
+        assert!(fake.open_pull_requests(GH_OWNER, repo).is_empty());
+        assert!(fake.issue_comments(GH_OWNER, repo, issue_number).is_empty());
+        // The status comment is refreshed out of band, from the merged row.
+        assert_eq!(outbox::process_outbox(&db).await.unwrap(), 1);
+        let comments = fake.issue_comments(GH_OWNER, repo, issue_number);
+        assert_eq!(comments.len(), 1);
+        assert!(comments[0].starts_with(crate::core::application::status_comment::STATUS_MARKER));
+        let model = database::applications::get_application(
+            &db,
+            CLIENT_ADDRESS.to_string(),
+            GH_OWNER.to_string(),
+            repo.to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(model.pr_number, 0);
+    }
+
+    #[tokio::test]
+    async fn state_change_is_committed_through_the_outbox() {
+        let repo = "outbox-commit-repo";
+        let (fake, db) = github_allocator(repo).await;
+        let body = issue_body(CLIENT_ADDRESS, "Science");
+        let issue_number = fake.create_issue(GH_OWNER, repo, "Application", &body);
+        open_application(&db, repo, issue_number, &body).await;
+        let row = database::applications::get_application(
+            &db,
+            CLIENT_ADDRESS.to_string(),
+            GH_OWNER.to_string(),
+            repo.to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+        let application = LDNApplication {
+            db: db.clone(),
+            github: GithubWrapper::new(
+                GH_OWNER.into(),
+                repo.into(),
+                Some(GH_INSTALLATION_ID as i64),
+            )
+            .unwrap(),
+            store: application_store(&db, GH_OWNER, repo).await.unwrap(),
+            application_id: CLIENT_ADDRESS.to_string(),
+            file_sha: row.sha.unwrap(),
+            file_name: LDNPullRequest::application_path(CLIENT_ADDRESS),
+            branch_name: LDNPullRequest::application_branch_name(CLIENT_ADDRESS),
+            expected_sha: None,
+        };
+        let path = application.file_name.clone();
+        let branch = application.branch_name.clone();
+        let on_github = || {
+            ApplicationFile::from_str(&fake.file(GH_OWNER, repo, &branch, &path).unwrap()).unwrap()
+        };
+        let requested = on_github().kyc_request();
+
+        application
+            .update_and_commit_application_state(
+                requested,
+                GH_OWNER.to_string(),
+                repo.to_string(),
+                application.file_sha.clone(),
+                branch.clone(),
+                path.clone(),
+                "KYC requested".to_string(),
+            )
+            .await
+            .unwrap();
+        let stale = application
+            .update_and_commit_application_state(
+                on_github(),
+                GH_OWNER.to_string(),
+                repo.to_string(),
+                application.file_sha.clone(),
+                branch.clone(),
+                path.clone(),
+                "Concurrent change".to_string(),
+            )
+            .await;
+        assert!(matches!(stale, Err(LDNError::Conflict(_))));
+
+        let in_db = database::applications::get_application(
+            &db,
+            CLIENT_ADDRESS.to_string(),
+            GH_OWNER.to_string(),
+            repo.to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(on_github().lifecycle.state, AppState::Submitted);
+        // The commit and the status comment.
+        assert_eq!(outbox::process_outbox(&db).await.unwrap(), 1);
+        assert_eq!(outbox::process_outbox(&db).await.unwrap(), 1);
+        assert_eq!(on_github().lifecycle.state, AppState::KYCRequested);
+        assert_eq!(
+            in_db.sha.unwrap(),
+            content_sha(&fake.file(GH_OWNER, repo, &branch, &path).unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn consumed_kyc_signature_leaves_the_application_in_kyc_requested() {
+        use alloy::signers::{local::PrivateKeySigner, SignerSync};
+        use alloy::sol_types::SolStruct;
+        use chrono::Duration;
+
+        use crate::core::application::gitcoin_interaction::kyc_domain;
+        use crate::core::kyc::attestation::KycAttestation;
+
+        let repo = "kyc-replay-repo";
+        let (fake, db) = github_allocator(repo).await;
+        let vendor = PrivateKeySigner::random();
+        let client = PrivateKeySigner::random();
+        database::allocators::update_allocator_kyc_providers(
+            &db,
+            GH_OWNER.to_string(),
+            repo.to_string(),
+            Some(format!(
+                r#"[{{"type": "signed_attestation", "name": "Acme KYC", "signers": ["{}"]}}]"#,
+                vendor.address()
+            )),
+        )
+        .await
+        .unwrap();
+        let body = issue_body(CLIENT_ADDRESS, "Science");
+        let issue_number = fake.create_issue(GH_OWNER, repo, "Application", &body);
+        open_application(&db, repo, issue_number, &body).await;
+        let application = |sha: String| async {
+            LDNApplication {
+                db: db.clone(),
+                github: GithubWrapper::new(
+                    GH_OWNER.into(),
+                    repo.into(),
+                    Some(GH_INSTALLATION_ID as i64),
+                )
+                .unwrap(),
+                store: application_store(&db, GH_OWNER, repo).await.unwrap(),
+                application_id: CLIENT_ADDRESS.to_string(),
+                file_sha: sha,
+                file_name: LDNPullRequest::application_path(CLIENT_ADDRESS),
+                branch_name: LDNPullRequest::application_branch_name(CLIENT_ADDRESS),
+                expected_sha: None,
+            }
+        };
+        let stored = || async {
+            database::applications::get_application(
+                &db,
+                CLIENT_ADDRESS.to_string(),
+                GH_OWNER.to_string(),
+                repo.to_string(),
+                None,
+            )
+            .await
+            .unwrap()
+        };
+        let row = stored().await;
+        let requested = ApplicationFile::from_str(&row.application.unwrap())
+            .unwrap()
+            .kyc_request();
+        application(row.sha.clone().unwrap())
+            .await
+            .update_and_commit_application_state(
+                requested,
+                GH_OWNER.to_string(),
+                repo.to_string(),
+                row.sha.unwrap(),
+                LDNPullRequest::application_branch_name(CLIENT_ADDRESS),
+                LDNPullRequest::application_path(CLIENT_ADDRESS),
+                "KYC requested".to_string(),
+            )
+            .await
+            .unwrap();
+        while outbox::process_outbox(&db).await.unwrap() > 0 {}
+
+        let now = Utc::now();
+        let message = KycApproval {
+            message: "Connect your Fil+ application with your wallet".into(),
+            client_id: CLIENT_ADDRESS.into(),
+            issued_at: (now - Duration::hours(1)).to_rfc3339(),
+            expires_at: (now + Duration::hours(1)).to_rfc3339(),
+            allocator_repo_name: repo.into(),
+            allocator_repo_owner: GH_OWNER.into(),
+        };
+        let signature = client
+            .sign_hash_sync(&message.eip712_signing_hash(&kyc_domain().unwrap()))
+            .unwrap();
+        let attestation = KycAttestation {
+            client_id: CLIENT_ADDRESS.into(),
+            subject: client.address(),
+            level: "full".into(),
+            evidence: String::new(),
+            issued_at: (now - Duration::hours(1)).to_rfc3339(),
+            expires_at: (now + Duration::hours(1)).to_rfc3339(),
+        };
+        let attestation_signature = vendor
+            .sign_hash_sync(&attestation.eip712_signing_hash(&kyc_domain().unwrap()))
+            .unwrap();
+        let info = SubmitKYCInfo {
+            message,
+            signature: format!("0x{}", alloy::hex::encode(signature.as_bytes())),
+            attestation: Some(SignedKycAttestation {
+                message: attestation,
+                signature: format!("0x{}", alloy::hex::encode(attestation_signature.as_bytes())),
+            }),
+        };
+        LDNApplication::consume_kyc_signature(
+            &db,
+            &info.message,
+            &info.signature,
+            &client.address(),
+        )
+        .await
+        .unwrap();
+
+        let row = stored().await;
+        let replayed = application(row.sha.unwrap()).await.submit_kyc(&info).await;
+        assert!(
+            matches!(replayed, Err(LDNError::Load(message)) if message.contains("already used"))
+        );
+        let row = stored().await;
+        let state = ApplicationFile::from_str(&row.application.unwrap())
+            .unwrap()
+            .lifecycle
+            .state;
+        assert_eq!(state, AppState::KYCRequested);
+        assert_eq!(outbox::process_outbox(&db).await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn test_date_is_expired() {
         let message: KycApproval = KycApproval {
@@ -5168,161 +6325,3 @@ mod tests {
         assert!(!is_from_future.unwrap())
     }
 }
-
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     use env_logger::{Builder, Env};
-//     use tokio::time::{sleep, Duration};
-
-//     static OWNER: &str = "keyko-io";
-//     static REPO: &str = "test-philip-second";
-
-//     #[tokio::test]
-//     async fn end_to_end() {
-//         // Set logging
-//         Builder::from_env(Env::default().default_filter_or("info")).init();
-//         log::info!("Starting end-to-end test");
-
-//         // Test Creating an application
-//         let _ = fplus_database::setup_test_environment().await;
-//         let gh = github_async_new(OWNER.to_string(), REPO.to_string()).await;
-
-//         log::info!("Creating a new LDNApplication from issue");
-//         let ldn_application: LDNApplication = match LDNApplication::new_from_issue(CreateApplicationInfo {
-//             issue_number: "37".to_string(),
-//             owner: OWNER.to_string(),
-//             repo: REPO.to_string()
-//         })
-//         .await
-//         {
-//             Ok(app) => app,
-//             Err(e) => {
-//                 log::error!("Failed to create LDNApplication: {}", e);
-//                 return;
-//             }
-//         };
-
-//         let application_id = ldn_application.application_id.to_string();
-//         log::info!("LDNApplication created with ID: {}", application_id);
-
-//         // Validate file creation
-//         log::info!("Validating file creation for application");
-//         if let Err(e) = gh
-//             .get_file(&ldn_application.file_name, &ldn_application.branch_name)
-//             .await
-//         {
-//             log::warn!(
-//                 "File validation failed for application ID {}: {}",
-//                 application_id,
-//                 e
-//             );
-//         }
-
-//         // Validate pull request creation
-//         log::info!("Validating pull request creation for application");
-//         if let Err(e) = gh
-//             .get_pull_request_by_head(&LDNPullRequest::application_branch_name(
-//                 application_id.as_str(),
-//             ))
-//             .await
-//         {
-//             log::warn!(
-//                 "Pull request validation failed for application ID {}: {}",
-//                 application_id,
-//                 e
-//             );
-//         }
-
-//         sleep(Duration::from_millis(2000)).await;
-
-//         // Test Triggering an application
-//         log::info!("Loading application for triggering");
-//         let ldn_application_before_trigger =
-//             match LDNApplication::load(application_id.clone(), OWNER.to_string(), REPO.to_string()).await {
-//                 Ok(app) => app,
-//                 Err(e) => {
-//                     log::error!("Failed to load application for triggering: {}", e);
-//                     return;
-//                 }
-//             };
-
-//         log::info!("Completing allocator review");
-//         if let Err(e) = ldn_application_before_trigger
-//             .complete_governance_review(
-//                 "actor_address".to_string(),
-//                 OWNER.to_string(),
-//                 REPO.to_string())
-//             .await
-//         {
-//             log::error!("Failed to complete allocator review: {}", e);
-//             return;
-//         }
-
-//         let ldn_application_after_trigger = match LDNApplication::load(
-//             application_id.clone(),
-//             OWNER.to_string(),
-//             REPO.to_string()
-//         ).await
-//         {
-//             Ok(app) => app,
-//             Err(e) => {
-//                 log::error!("Failed to load application after triggering: {}", e);
-//                 return;
-//             }
-//         };
-
-//         assert_eq!(
-//             ldn_application_after_trigger.app_state().await.unwrap(),
-//             AppState::ReadyToSign
-//         );
-//         log::info!("Application state updated to ReadyToSign");
-//         sleep(Duration::from_millis(2000)).await;
-
-//         // Cleanup
-//         log::info!("Starting cleanup process");
-//         let head = &LDNPullRequest::application_branch_name(&application_id);
-//         match gh.get_pull_request_by_head(head).await {
-//             Ok(prs) => {
-//                 if let Some(pr) = prs.get(0) {
-//                     let number = pr.number;
-//                     match gh.merge_pull_request(number).await {
-//                         Ok(_) => log::info!("Merged pull request {}", number),
-//                         Err(_) => log::info!("Pull request {} was already merged", number),
-//                     };
-//                 }
-//             }
-//             Err(e) => log::warn!("Failed to get pull request by head: {}", e),
-//         };
-
-//         sleep(Duration::from_millis(3000)).await;
-
-//         let file = match gh.get_file(&ldn_application.file_name, "main").await {
-//             Ok(f) => f,
-//             Err(e) => {
-//                 log::error!("Failed to get file: {}", e);
-//                 return;
-//             }
-//         };
-
-//         let file_sha = file.items[0].sha.clone();
-//         let remove_file_request = gh
-//             .delete_file(&ldn_application.file_name, "main", "remove file", &file_sha)
-//             .await;
-//         let remove_branch_request = gh
-//             .build_remove_ref_request(LDNPullRequest::application_branch_name(&application_id))
-//             .unwrap();
-
-//         if let Err(e) = gh.remove_branch(remove_branch_request).await {
-//             log::warn!("Failed to remove branch: {}", e);
-//         }
-//         if let Err(e) = remove_file_request {
-//             log::warn!("Failed to remove file: {}", e);
-//         }
-
-//         log::info!(
-//             "End-to-end test completed for application ID: {}",
-//             application_id
-//         );
-//     }
-// }
@@ -0,0 +1,311 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use fplus_database::database::outbox::{
+    claim_due_outbox_messages, delete_done_outbox_messages, mark_outbox_message_done,
+    mark_outbox_message_failed, NewOutboxMessage,
+};
+use fplus_database::models::outbox_messages::Model as OutboxMessageModel;
+use fplus_database::DbPool;
+use serde::{Deserialize, Serialize};
+
+use crate::config::get_env_var_or_default;
+use crate::core::application::status_comment::refresh_status_comment;
+use crate::core::application::store::{
+    application_store, content_sha, ApplicationCommit, ApplicationStoreKind,
+};
+use crate::error::LDNError;
+use crate::external_services::github::github_async_new;
+
+/// How long a claimed message stays hidden from other workers.
+const LEASE_SECS: i64 = 300;
+const BATCH_SIZE: u64 = 20;
+const RETRY_BASE_SECS: i64 = 30;
+const RETRY_MAX_SECS: i64 = 3600;
+
+/// A GitHub side effect of an application change. Side effects are recorded
+/// in the outbox in the same transaction as the database change and applied
+/// by the outbox worker, at least once.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SideEffect {
+    IssueComment {
+        owner: String,
+        repo: String,
+        issue_number: u64,
+        body: String,
+    },
+    /// Replaces all labels of the issue.
+    IssueLabels {
+        owner: String,
+        repo: String,
+        issue_number: u64,
+        labels: Vec<String>,
+    },
+    /// Merges the change (pull request) into main.
+    MergeChange {
+        owner: String,
+        repo: String,
+        pr_number: u64,
+    },
+    /// Commits the application file the database was updated with to the
+    /// branch of its change.
+    CommitChange {
+        owner: String,
+        repo: String,
+        path: String,
+        branch_name: String,
+        message: String,
+        content: String,
+        /// Sha of the file the commit is based on.
+        file_sha: String,
+    },
+    /// Renders the status comment from the latest row of the application.
+    StatusComment {
+        owner: String,
+        repo: String,
+        application_id: String,
+    },
+}
+
+impl SideEffect {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SideEffect::IssueComment { .. } => "issue_comment",
+            SideEffect::IssueLabels { .. } => "issue_labels",
+            SideEffect::MergeChange { .. } => "merge_change",
+            SideEffect::CommitChange { .. } => "commit_change",
+            SideEffect::StatusComment { .. } => "status_comment",
+        }
+    }
+
+    pub async fn apply(&self, db: &DbPool) -> Result<(), LDNError> {
+        match self {
+            SideEffect::IssueComment {
+                owner,
+                repo,
+                issue_number,
+                body,
+            } => {
+                let gh = github_async_new(db, owner.clone(), repo.clone()).await?;
+                gh.add_comment_to_issue(*issue_number, body)
+                    .await
+                    .map_err(|e| {
+                        LDNError::Load(format!(
+                            "Failed to add comment to issue {issue_number}: {e}"
+                        ))
+                    })?;
+            }
+            SideEffect::IssueLabels {
+                owner,
+                repo,
+                issue_number,
+                labels,
+            } => {
+                let gh = github_async_new(db, owner.clone(), repo.clone()).await?;
+                gh.replace_issue_labels(*issue_number, labels)
+                    .await
+                    .map_err(|e| {
+                        LDNError::Load(format!(
+                            "Failed to replace labels of issue {issue_number}: {e}"
+                        ))
+                    })?;
+            }
+            SideEffect::MergeChange {
+                owner,
+                repo,
+                pr_number,
+            } => {
+                let store = application_store(db, owner, repo).await?;
+                if store.kind() == ApplicationStoreKind::Github {
+                    // A previous attempt may have merged before failing to delete the branch.
+                    let gh = github_async_new(db, owner.clone(), repo.clone()).await?;
+                    let pull_request =
+                        gh.get_pull_request_by_number(*pr_number)
+                            .await
+                            .map_err(|e| {
+                                LDNError::Load(format!(
+                                    "Failed to get pull request {pr_number}: {e}"
+                                ))
+                            })?;
+                    if pull_request.merged_at.is_some() {
+                        return Ok(());
+                    }
+                }
+                store.merge_change(*pr_number).await?;
+            }
+            SideEffect::CommitChange {
+                owner,
+                repo,
+                path,
+                branch_name,
+                message,
+                content,
+                file_sha,
+            } => {
+                let store = application_store(db, owner, repo).await?;
+                // A previous attempt may have committed before failing to be marked done.
+                let current = store.read_file(path, branch_name).await?;
+                if current.is_some_and(|current| current.sha == content_sha(content)) {
+                    return Ok(());
+                }
+                store
+                    .commit_change(ApplicationCommit {
+                        path: path.clone(),
+                        branch_name: branch_name.clone(),
+                        message: message.clone(),
+                        content: content.clone(),
+                        file_sha: file_sha.clone(),
+                    })
+                    .await?;
+            }
+            SideEffect::StatusComment {
+                owner,
+                repo,
+                application_id,
+            } => refresh_status_comment(db, owner, repo, application_id).await?,
+        }
+        Ok(())
+    }
+}
+
+/// Prefix of the aggregates of all applications of an allocator.
+pub fn allocator_aggregates(owner: &str, repo: &str) -> String {
+    format!("{owner}/{repo}/")
+}
+
+/// Outbox rows for the side effects of an application. They are applied in
+/// order, each one only after the previous one succeeded.
+pub fn outbox_messages(
+    owner: &str,
+    repo: &str,
+    application_id: &str,
+    side_effects: &[SideEffect],
+) -> Result<Vec<NewOutboxMessage>, LDNError> {
+    side_effects
+        .iter()
+        .map(|side_effect| {
+            Ok(NewOutboxMessage {
+                aggregate: format!("{}{application_id}", allocator_aggregates(owner, repo)),
+                kind: side_effect.kind().to_string(),
+                payload: serde_json::to_string(side_effect)
+                    .map_err(|e| LDNError::Load(format!("Failed to serialize side effect: {e}")))?,
+            })
+        })
+        .collect()
+}
+
+/// Outbox row refreshing the status comment of an application, to record
+/// with the write that changes the application.
+pub fn status_comment_messages(
+    owner: &str,
+    repo: &str,
+    application_id: &str,
+) -> Result<Vec<NewOutboxMessage>, LDNError> {
+    outbox_messages(
+        owner,
+        repo,
+        application_id,
+        &[SideEffect::StatusComment {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            application_id: application_id.to_string(),
+        }],
+    )
+}
+
+/// Delay before the next attempt, doubling with every failed attempt.
+fn retry_delay(attempts: i32) -> i64 {
+    let exponent = attempts.clamp(1, 16) as u32 - 1;
+    (RETRY_BASE_SECS * 2_i64.pow(exponent)).min(RETRY_MAX_SECS)
+}
+
+async fn process_message(db: &DbPool, message: &OutboxMessageModel, max_attempts: i32) {
+    let result = match serde_json::from_str::<SideEffect>(&message.payload) {
+        Ok(side_effect) => side_effect.apply(db).await,
+        Err(e) => Err(LDNError::Load(format!("Failed to parse side effect: {e}"))),
+    };
+    let update = match result {
+        Ok(()) => mark_outbox_message_done(db, message.id).await,
+        Err(e) => {
+            let retry_at = (message.attempts < max_attempts)
+                .then(|| Utc::now() + chrono::Duration::seconds(retry_delay(message.attempts)));
+            if retry_at.is_none() {
+                log::error!(
+                    "Outbox message {} ({}) moved to dead letters: {e}",
+                    message.id,
+                    message.kind
+                );
+            } else {
+                log::warn!(
+                    "Outbox message {} ({}) failed, attempt {}: {e}",
+                    message.id,
+                    message.kind,
+                    message.attempts
+                );
+            }
+            mark_outbox_message_failed(db, message.id, &e.to_string(), retry_at).await
+        }
+    };
+    if let Err(e) = update {
+        log::error!("Failed to update outbox message {}: {e}", message.id);
+    }
+}
+
+/// Applies the side effects that are due.
+///
+/// # Returns
+/// The number of messages that were processed.
+pub async fn process_outbox(db: &DbPool) -> Result<usize, LDNError> {
+    let max_attempts = get_env_var_or_default("OUTBOX_MAX_ATTEMPTS")
+        .parse::<i32>()
+        .map_err(|e| LDNError::Load(format!("Failed to parse OUTBOX_MAX_ATTEMPTS: {e}")))?;
+    let messages = claim_due_outbox_messages(db, BATCH_SIZE, LEASE_SECS)
+        .await
+        .map_err(|e| LDNError::Load(format!("Failed to claim outbox messages: {e}")))?;
+    for message in &messages {
+        process_message(db, message, max_attempts).await;
+    }
+    Ok(messages.len())
+}
+
+/// Polls the outbox forever, every `OUTBOX_POLL_INTERVAL_SECS` seconds while it
+/// is empty.
+pub async fn run_outbox_worker(db: DbPool) {
+    let interval = get_env_var_or_default("OUTBOX_POLL_INTERVAL_SECS")
+        .parse::<u64>()
+        .unwrap_or(5);
+    loop {
+        match process_outbox(&db).await {
+            Ok(0) => tokio::time::sleep(Duration::from_secs(interval)).await,
+            Ok(_) => {}
+            Err(e) => {
+                log::error!("Outbox worker failed: {e}");
+                tokio::time::sleep(Duration::from_secs(interval)).await;
+            }
+        }
+    }
+}
+
+/// Deletes the messages applied more than `OUTBOX_RETENTION_DAYS` days ago.
+pub async fn prune_outbox(db: &DbPool) -> Result<u64, LDNError> {
+    let retention_days = get_env_var_or_default("OUTBOX_RETENTION_DAYS")
+        .parse::<i64>()
+        .unwrap_or(7);
+    delete_done_outbox_messages(db, Utc::now() - chrono::Duration::days(retention_days))
+        .await
+        .map_err(|e| LDNError::Load(format!("Pruning outbox messages failed: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_up_to_the_maximum() {
+        assert_eq!(retry_delay(1), 30);
+        assert_eq!(retry_delay(2), 60);
+        assert_eq!(retry_delay(4), 240);
+        assert_eq!(retry_delay(12), RETRY_MAX_SECS);
+    }
+}
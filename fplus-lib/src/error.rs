@@ -14,6 +14,18 @@ use serde::{Deserialize, Serialize};
 pub enum LDNError {
     New(String),
     Load(String),
+    /// The application file changed since the version the operation was based on.
+    Conflict(String),
+    /// The application file is no longer at the version the client expected.
+    PreconditionFailed(String),
+    /// The request was refused by a policy, with a code telling which one.
+    Rejected(Rejection),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rejection {
+    pub code: String,
+    pub message: String,
 }
 
 impl Display for LDNError {
@@ -25,6 +37,15 @@ impl Display for LDNError {
             LDNError::New(e) => {
                 write!(f, "New: {e}")
             }
+            LDNError::Conflict(e) => {
+                write!(f, "Conflict: {e}")
+            }
+            LDNError::PreconditionFailed(e) => {
+                write!(f, "Precondition failed: {e}")
+            }
+            LDNError::Rejected(e) => {
+                write!(f, "Rejected: {}: {}", e.code, e.message)
+            }
         }
     }
 }
@@ -36,6 +57,9 @@ impl MessageBody for LDNError {
         match self {
             LDNError::Load(e) => BodySize::Sized(e.len() as u64),
             LDNError::New(e) => BodySize::Sized(e.len() as u64),
+            LDNError::Conflict(e) => BodySize::Sized(e.len() as u64),
+            LDNError::PreconditionFailed(e) => BodySize::Sized(e.len() as u64),
+            LDNError::Rejected(e) => BodySize::Sized(e.message.len() as u64),
         }
     }
 
@@ -46,6 +70,9 @@ impl MessageBody for LDNError {
         match Pin::<&mut LDNError>::into_inner(self) {
             LDNError::Load(e) => Poll::Ready(Some(Ok(Bytes::from(e.clone())))),
             LDNError::New(e) => Poll::Ready(Some(Ok(Bytes::from(e.clone())))),
+            LDNError::Conflict(e) => Poll::Ready(Some(Ok(Bytes::from(e.clone())))),
+            LDNError::PreconditionFailed(e) => Poll::Ready(Some(Ok(Bytes::from(e.clone())))),
+            LDNError::Rejected(e) => Poll::Ready(Some(Ok(Bytes::from(e.message.clone())))),
         }
     }
 }
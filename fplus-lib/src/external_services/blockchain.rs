@@ -10,7 +10,7 @@ use alloy::{
     sol_types::SolCall,
 };
 
-use crate::{config::get_env_var_or_default, error::LDNError};
+use crate::error::LDNError;
 
 use super::filecoin::filecoin_address_to_evm_address;
 
@@ -97,10 +97,10 @@ impl BlockchainData {
 pub async fn get_allowance_for_address_contract(
     evm_user_address: &Address,
     evm_contract_address: &Address,
+    rpc_url: &str,
 ) -> Result<u64, LDNError> {
-    let rpc_url = get_env_var_or_default("GLIF_NODE_URL");
     let provider = ProviderBuilder::new()
-        .on_builtin(&rpc_url)
+        .on_builtin(rpc_url)
         .await
         .map_err(|e| LDNError::New(format!("Invalid RPC URL: {e:?}")))?;
 
@@ -3,7 +3,8 @@ use serde_json::json;
 use crate::{
     config::get_env_var_or_default,
     models::filecoin::{
-        StateReadStateResponse, StateVerifiedClientStatusResponse, StateVerifierStatusResponse,
+        OptionalVerifiedClientStatusResponse, StateLookupIdResponse, StateReadStateResponse,
+        StateVerifiedClientStatusResponse, StateVerifierStatusResponse,
     },
 };
 
@@ -36,18 +37,27 @@ pub async fn get_multisig_threshold_for_actor(actor_address: &str) -> Result<u64
 }
 
 pub async fn get_allowance_for_address_direct(address: &str) -> Result<String, reqwest::Error> {
-    let allowance = get_allowance_for_client(address).await;
+    get_allowance_for_address_on_node(&get_env_var_or_default("GLIF_NODE_URL"), address).await
+}
+
+/// Like `get_allowance_for_address_direct`, asking the node at `node_url`.
+pub async fn get_allowance_for_address_on_node(
+    node_url: &str,
+    address: &str,
+) -> Result<String, reqwest::Error> {
+    let allowance = get_allowance_for_client(node_url, address).await;
     if let Ok(allowance) = allowance {
         if allowance != "0" {
             return Ok(allowance);
         }
     }
-    get_allowance_for_verifier(address).await
+    get_allowance_for_verifier(node_url, address).await
 }
 
-pub async fn get_allowance_for_verifier(address: &str) -> Result<String, reqwest::Error> {
-    let node_url = get_env_var_or_default("GLIF_NODE_URL");
-
+pub async fn get_allowance_for_verifier(
+    node_url: &str,
+    address: &str,
+) -> Result<String, reqwest::Error> {
     let client = reqwest::Client::new();
     let body = json!({
         "jsonrpc": "2.0",
@@ -56,7 +66,7 @@ pub async fn get_allowance_for_verifier(address: &str) -> Result<String, reqwest
         "id": 1
     });
 
-    let request = client.post(&node_url).json(&body);
+    let request = client.post(node_url).json(&body);
 
     let response = request
         .send()
@@ -66,9 +76,10 @@ pub async fn get_allowance_for_verifier(address: &str) -> Result<String, reqwest
     Ok(response.result)
 }
 
-pub async fn get_allowance_for_client(address: &str) -> Result<String, reqwest::Error> {
-    let node_url = get_env_var_or_default("GLIF_NODE_URL");
-
+pub async fn get_allowance_for_client(
+    node_url: &str,
+    address: &str,
+) -> Result<String, reqwest::Error> {
     let client = reqwest::Client::new();
     let body = json!({
         "jsonrpc": "2.0",
@@ -77,7 +88,7 @@ pub async fn get_allowance_for_client(address: &str) -> Result<String, reqwest::
         "id": 1
     });
 
-    let request = client.post(&node_url).json(&body);
+    let request = client.post(node_url).json(&body);
 
     let response = request
         .send()
@@ -108,9 +119,10 @@ pub async fn filecoin_address_to_evm_address(address: &str) -> Result<String, re
     Ok(response.result)
 }
 
-pub async fn evm_address_to_filecoin_address(address: &str) -> Result<String, reqwest::Error> {
-    let node_url = get_env_var_or_default("GLIF_NODE_URL");
-
+pub async fn evm_address_to_filecoin_address(
+    node_url: &str,
+    address: &str,
+) -> Result<String, reqwest::Error> {
     let client = reqwest::Client::new();
     let body = json!({
         "jsonrpc": "2.0",
@@ -119,7 +131,7 @@ pub async fn evm_address_to_filecoin_address(address: &str) -> Result<String, re
         "id": 0
     });
 
-    let request = client.post(&node_url).json(&body);
+    let request = client.post(node_url).json(&body);
 
     let response = request
         .send()
@@ -128,3 +140,45 @@ pub async fn evm_address_to_filecoin_address(address: &str) -> Result<String, re
         .await?;
     Ok(response.result)
 }
+
+/// The DataCap of a verified client, `None` when the address is not one.
+pub async fn get_verified_client_allowance(
+    node_url: &str,
+    address: &str,
+) -> Result<Option<String>, reqwest::Error> {
+    let client = reqwest::Client::new();
+    let body = json!({
+        "jsonrpc": "2.0",
+        "method": "Filecoin.StateVerifiedClientStatus",
+        "params": [address, null],
+        "id": 1
+    });
+
+    let response = client
+        .post(node_url)
+        .json(&body)
+        .send()
+        .await?
+        .json::<OptionalVerifiedClientStatusResponse>()
+        .await?;
+    Ok(response.result)
+}
+
+pub async fn state_lookup_id(node_url: &str, address: &str) -> Result<String, reqwest::Error> {
+    let client = reqwest::Client::new();
+    let body = json!({
+        "jsonrpc": "2.0",
+        "method": "Filecoin.StateLookupID",
+        "params": [address, null],
+        "id": 1
+    });
+
+    let response = client
+        .post(node_url)
+        .json(&body)
+        .send()
+        .await?
+        .json::<StateLookupIdResponse>()
+        .await?;
+    Ok(response.result)
+}
@@ -1,9 +1,11 @@
 #![allow(dead_code)]
 use fplus_database::database::allocators::get_allocator;
+use fplus_database::DbPool;
 use http::header::USER_AGENT;
 use http::{Request, Uri};
 use hyper_rustls::HttpsConnectorBuilder;
 
+use jsonwebtoken::EncodingKey;
 use octocrab::auth::AppAuth;
 use octocrab::models::issues::{Comment, Issue};
 use octocrab::models::pulls::PullRequest;
@@ -14,14 +16,22 @@ use octocrab::params::{pulls::State as PullState, State};
 use octocrab::service::middleware::base_uri::BaseUriLayer;
 use octocrab::service::middleware::extra_headers::ExtraHeadersLayer;
 use octocrab::{AuthState, Error as OctocrabError, GitHubError, Octocrab, OctocrabBuilder, Page};
+use once_cell::sync::{Lazy, OnceCell};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use reqwest::header::HeaderValue;
 use serde::{Deserialize, Serialize};
 use snafu::GenerateImplicitData;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use crate::config::get_env_var_or_default;
 use crate::core::application::file::AppState;
+use crate::core::application::store::content_sha;
 use crate::error::LDNError;
+#[cfg(test)]
+use crate::external_services::github_fake;
+use crate::external_services::github_rate_limit::RateLimitLayer;
 
 const GITHUB_API_URL: &str = "https://api.github.com";
 
@@ -49,7 +59,6 @@ struct GithubParams {
 #[derive(Debug)]
 pub struct CreateRefillMergeRequestData {
     pub issue_link: String,
-    pub ref_request: Request<String>,
     pub file_content: String,
     pub file_name: String,
     pub branch_name: String,
@@ -62,7 +71,6 @@ pub struct CreateRefillMergeRequestData {
 pub struct CreateMergeRequestData {
     pub issue_link: String,
     pub owner_name: String,
-    pub ref_request: Request<String>,
     pub file_content: String,
     pub file_name: String,
     pub branch_name: String,
@@ -70,6 +78,42 @@ pub struct CreateMergeRequestData {
     pub application_id: String,
 }
 
+/// File written by a multi-file commit. A `content` of `None` deletes the
+/// file, `expected_sha` rejects the commit when the file on the parent commit
+/// differs from the one the change was based on.
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: String,
+    pub content: Option<String>,
+    pub expected_sha: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct CreateMultiFileMergeRequestData {
+    pub branch_name: String,
+    pub commit_message: String,
+    pub title: String,
+    pub body: String,
+    pub files: Vec<FileChange>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CommittedFiles {
+    pub commit_sha: String,
+    /// Blob sha of every written (not deleted) file by path
+    pub file_shas: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GitObject {
+    sha: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct GitCommit {
+    tree: GitObject,
+}
+
 #[derive(Debug)]
 pub struct GithubWrapper {
     pub inner: Arc<Octocrab>,
@@ -92,13 +136,135 @@ struct Author {
     name: String,
 }
 
-pub async fn github_async_new(owner: String, repo: String) -> Result<GithubWrapper, LDNError> {
-    let allocator = get_allocator(owner.as_str(), repo.as_str())
-        .await
-        .map_err(|e| LDNError::Load(format!("Failed to get allocator: {e}")))?
-        .ok_or(LDNError::Load("Allocator not found".to_string()))?;
+/// Installation tokens are valid for an hour. Octocrab requests one lazily on
+/// the first call of a client and only drops it after a 401, so clients are
+/// rebuilt before the oldest possible token gets close to expiring.
+const INSTALLATION_CLIENT_TTL: Duration = Duration::from_secs(50 * 60);
+
+type RepositoryKey = (String, String);
 
-    let installation_id = allocator.installation_id;
+struct CachedClient {
+    octocrab: Arc<Octocrab>,
+    created_at: Instant,
+}
+
+/// Octocrab clients by installation id (`None` for the app itself). Cloning an
+/// `Arc<Octocrab>` shares the HTTP connection pool and the installation token.
+static INSTALLATION_CLIENTS: Lazy<Mutex<HashMap<Option<u64>, CachedClient>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Installation ids of allocator repositories, saving the allocator lookup in
+/// `github_async_new`.
+static REPOSITORY_INSTALLATIONS: Lazy<Mutex<HashMap<RepositoryKey, Option<i64>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static GITHUB_APP_KEY: OnceCell<EncodingKey> = OnceCell::new();
+
+/// Private key of the GitHub App, read from `GH_PRIVATE_KEY` or
+/// `gh-private-key.pem` the first time it is needed.
+pub fn github_app_key() -> Result<EncodingKey, LDNError> {
+    GITHUB_APP_KEY
+        .get_or_try_init(|| {
+            let gh_private_key = std::env::var("GH_PRIVATE_KEY").unwrap_or_else(|_| {
+                log::warn!(
+                    "GH_PRIVATE_KEY not found in .env file, attempting to read from gh-private-key.pem"
+                );
+                std::fs::read_to_string("gh-private-key.pem").unwrap_or_else(|e| {
+                    log::error!("Failed to read gh-private-key.pem. Error: {:?}", e);
+                    std::process::exit(1);
+                })
+            });
+            EncodingKey::from_rsa_pem(gh_private_key.as_bytes())
+                .map_err(|e| LDNError::Load(format!("Failed to get encoding key: {e}")))
+        })
+        .cloned()
+}
+
+/// Drops the cached client of an installation, e.g. after it was removed.
+pub fn invalidate_installation(installation_id: u64) {
+    if let Ok(mut clients) = INSTALLATION_CLIENTS.lock() {
+        clients.remove(&Some(installation_id));
+    }
+}
+
+pub(crate) fn clear_installation_clients() {
+    if let Ok(mut clients) = INSTALLATION_CLIENTS.lock() {
+        clients.clear();
+    }
+}
+
+/// Drops every cached client whose installation is not in `installation_ids`.
+pub fn retain_installations(installation_ids: &[u64]) {
+    if let Ok(mut clients) = INSTALLATION_CLIENTS.lock() {
+        clients.retain(|id, _| id.is_none_or(|id| installation_ids.contains(&id)));
+    }
+}
+
+/// Records the installation id of a repository after it changed in the
+/// database, dropping the client of the previous installation.
+pub fn update_repository_installation(owner: &str, repo: &str, installation_id: Option<i64>) {
+    let previous = REPOSITORY_INSTALLATIONS
+        .lock()
+        .ok()
+        .and_then(|mut repos| repos.insert((owner.to_string(), repo.to_string()), installation_id));
+    if let Some(Some(previous)) = previous {
+        if Some(previous) != installation_id {
+            if let Ok(previous) = u64::try_from(previous) {
+                invalidate_installation(previous);
+            }
+        }
+    }
+}
+
+/// Forgets the installation id of a repository whose allocator was deleted.
+pub fn forget_repository_installation(owner: &str, repo: &str) {
+    if let Ok(mut repos) = REPOSITORY_INSTALLATIONS.lock() {
+        repos.remove(&(owner.to_string(), repo.to_string()));
+    }
+}
+
+/// Forgets the installation ids of every repository not in `repositories`, so
+/// the next client of a repository the app lost access to reads it again.
+pub fn retain_repository_installations(repositories: &[(String, String)]) {
+    if let Ok(mut repos) = REPOSITORY_INSTALLATIONS.lock() {
+        repos.retain(|key, _| repositories.contains(key));
+    }
+}
+
+/// Whether GitHub rejected a write because the file or branch moved past the
+/// sha it was based on (409 on contents, 422 on non fast-forward ref updates).
+pub fn is_conflict(error: &OctocrabError) -> bool {
+    let message = match error {
+        OctocrabError::GitHub { source, .. } => source.message.clone(),
+        OctocrabError::Other { source, .. } => source.to_string(),
+        _ => return false,
+    };
+    message.contains("does not match") || message.contains("not a fast forward")
+}
+
+pub async fn github_async_new(
+    db: &DbPool,
+    owner: String,
+    repo: String,
+) -> Result<GithubWrapper, LDNError> {
+    let cached = REPOSITORY_INSTALLATIONS
+        .lock()
+        .ok()
+        .and_then(|repos| repos.get(&(owner.clone(), repo.clone())).cloned());
+
+    let installation_id = match cached {
+        Some(installation_id) => installation_id,
+        None => {
+            let allocator = get_allocator(db, owner.as_str(), repo.as_str())
+                .await
+                .map_err(|e| LDNError::Load(format!("Failed to get allocator: {e}")))?
+                .ok_or(LDNError::Load("Allocator not found".to_string()))?;
+            if let Ok(mut repos) = REPOSITORY_INSTALLATIONS.lock() {
+                repos.insert((owner.clone(), repo.clone()), allocator.installation_id);
+            }
+            allocator.installation_id
+        }
+    };
 
     GithubWrapper::new(owner, repo, installation_id)
 }
@@ -109,6 +275,48 @@ impl GithubWrapper {
         repo: String,
         installation_id: Option<i64>,
     ) -> Result<Self, LDNError> {
+        let installation_id = installation_id
+            .map(|id| {
+                u64::try_from(id)
+                    .map_err(|e| LDNError::Load(format!("Invalid installation id {id}: {e}")))
+            })
+            .transpose()?;
+
+        let mut clients = INSTALLATION_CLIENTS
+            .lock()
+            .map_err(|e| LDNError::Load(format!("GitHub client registry is poisoned: {e}")))?;
+        let inner = match clients.get(&installation_id) {
+            Some(cached) if cached.created_at.elapsed() < INSTALLATION_CLIENT_TTL => {
+                cached.octocrab.clone()
+            }
+            _ => {
+                let octocrab = Arc::new(Self::build_octocrab(installation_id)?);
+                clients.insert(
+                    installation_id,
+                    CachedClient {
+                        octocrab: octocrab.clone(),
+                        created_at: Instant::now(),
+                    },
+                );
+                octocrab
+            }
+        };
+
+        Ok(Self { owner, repo, inner })
+    }
+
+    fn build_octocrab(installation_id: Option<u64>) -> Result<Octocrab, LDNError> {
+        #[cfg(test)]
+        if let Some(fake) = github_fake::installed() {
+            return Ok(OctocrabBuilder::new_empty()
+                .with_service(fake)
+                .with_layer(&RateLimitLayer::new(installation_id))
+                .with_layer(&BaseUriLayer::new(Uri::from_static(GITHUB_API_URL)))
+                .with_auth(AuthState::None)
+                .build()
+                .expect("Could not create Octocrab instance"));
+        }
+
         let app_id_str = get_env_var_or_default("GITHUB_APP_ID");
 
         let app_id = app_id_str.parse::<u64>().unwrap_or_else(|_| {
@@ -116,16 +324,6 @@ impl GithubWrapper {
             0
         });
 
-        let gh_private_key = std::env::var("GH_PRIVATE_KEY").unwrap_or_else(|_| {
-            log::warn!(
-                "GH_PRIVATE_KEY not found in .env file, attempting to read from gh-private-key.pem"
-            );
-            std::fs::read_to_string("gh-private-key.pem").unwrap_or_else(|e| {
-                log::error!("Failed to read gh-private-key.pem. Error: {:?}", e);
-                std::process::exit(1);
-            })
-        });
-
         let connector = HttpsConnectorBuilder::new()
             .with_native_roots() // enabled the `rustls-native-certs` feature in hyper-rustls
             .https_only()
@@ -136,11 +334,11 @@ impl GithubWrapper {
             .pool_idle_timeout(std::time::Duration::from_secs(15))
             .build(connector);
 
-        let key = jsonwebtoken::EncodingKey::from_rsa_pem(gh_private_key.as_bytes())
-            .map_err(|e| LDNError::Load(format!("Failed to get encoding key: {e}")))?;
+        let key = github_app_key()?;
         let header_value = HeaderValue::from_static("octocrab");
         let octocrab = OctocrabBuilder::new_empty()
             .with_service(client)
+            .with_layer(&RateLimitLayer::new(installation_id))
             .with_layer(&BaseUriLayer::new(Uri::from_static(GITHUB_API_URL)))
             .with_layer(&ExtraHeadersLayer::new(Arc::new(vec![(
                 USER_AGENT,
@@ -153,19 +351,9 @@ impl GithubWrapper {
             .build()
             .expect("Could not create Octocrab instance");
 
-        let octocrab = if let Some(installation_id) = installation_id {
-            let installation_id: u64 = installation_id
-                .try_into()
-                .expect("Installation Id sucessfully parsed to u64");
-            octocrab.installation(installation_id.into())
-        } else {
-            octocrab
-        };
-
-        Ok(Self {
-            owner,
-            repo,
-            inner: Arc::new(octocrab),
+        Ok(match installation_id {
+            Some(installation_id) => octocrab.installation(installation_id.into()),
+            None => octocrab,
         })
     }
 
@@ -279,6 +467,102 @@ impl GithubWrapper {
         Ok(page.into_iter().any(|label| label.name == expected_label))
     }
 
+    pub async fn add_issue_labels(
+        &self,
+        number: u64,
+        labels: &[String],
+    ) -> Result<(), OctocrabError> {
+        self.inner
+            .issues(&self.owner, &self.repo)
+            .add_labels(number, labels)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_issue_label(&self, number: u64, label: &str) -> Result<(), OctocrabError> {
+        self.inner
+            .issues(&self.owner, &self.repo)
+            .remove_label(number, label)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_issue_comments(&self, number: u64) -> Result<Vec<Comment>, OctocrabError> {
+        let page = self
+            .inner
+            .issues(&self.owner, &self.repo)
+            .list_comments(number)
+            .per_page(100)
+            .send()
+            .await?;
+        self.inner.all_pages(page).await
+    }
+
+    pub async fn update_issue_comment(
+        &self,
+        comment_id: u64,
+        body: &str,
+    ) -> Result<Comment, OctocrabError> {
+        self.inner
+            .patch(
+                format!(
+                    "/repos/{}/{}/issues/comments/{comment_id}",
+                    self.owner, self.repo
+                ),
+                Some(&serde_json::json!({ "body": body })),
+            )
+            .await
+    }
+
+    pub async fn list_repo_labels(&self) -> Result<Vec<Label>, OctocrabError> {
+        let page = self
+            .inner
+            .issues(&self.owner, &self.repo)
+            .list_labels_for_repo()
+            .per_page(100)
+            .send()
+            .await?;
+        self.inner.all_pages(page).await
+    }
+
+    pub async fn create_label(
+        &self,
+        name: &str,
+        color: &str,
+        description: &str,
+    ) -> Result<Label, OctocrabError> {
+        self.inner
+            .issues(&self.owner, &self.repo)
+            .create_label(name, color, description)
+            .await
+    }
+
+    /// Renames a label and resets its colour and description. Issues keep the
+    /// label under its new name.
+    pub async fn update_label(
+        &self,
+        current_name: &str,
+        new_name: &str,
+        color: &str,
+        description: &str,
+    ) -> Result<Label, OctocrabError> {
+        self.inner
+            .patch(
+                format!(
+                    "/repos/{}/{}/labels/{}",
+                    self.owner,
+                    self.repo,
+                    utf8_percent_encode(current_name, NON_ALPHANUMERIC)
+                ),
+                Some(&serde_json::json!({
+                    "new_name": new_name,
+                    "color": color,
+                    "description": description,
+                })),
+            )
+            .await
+    }
+
     pub async fn list_pull_requests(&self) -> Result<Vec<PullRequest>, OctocrabError> {
         let iid = self
             .inner
@@ -610,7 +894,6 @@ impl GithubWrapper {
     ) -> Result<(PullRequest, String), OctocrabError> {
         let CreateRefillMergeRequestData {
             issue_link,
-            ref_request,
             file_content,
             file_name,
             branch_name,
@@ -618,23 +901,26 @@ impl GithubWrapper {
             file_sha,
             application_id,
         } = data;
-        let branch_exists = self.check_if_branch_exists(&branch_name).await?;
-        if branch_exists {
-            return Err(OctocrabError::Other {
-                source: format!("Branch {branch_name} already exists.").into(),
-                backtrace: GenerateImplicitData::generate(),
-            });
-        }
-        self.create_branch(ref_request).await?;
-        let file_update = self
-            .update_file_content(&file_name, &commit, &file_content, &branch_name, &file_sha)
-            .await?;
         let allocator_tech_url = get_env_var_or_default("ALLOCATOR_TECH_URL");
         let pr_body = format!("[Link to related GitHub issue]({})\n[Link to your application on Allocator.tech]({}/application/{}/{}/{})",issue_link, allocator_tech_url, self.owner, self.repo, application_id);
-        let pr = self
-            .create_pull_request(&commit, &branch_name, &pr_body.to_string())
+        let (pr, committed) = self
+            .create_multi_file_merge_request(CreateMultiFileMergeRequestData {
+                branch_name,
+                commit_message: commit.clone(),
+                title: commit,
+                body: pr_body,
+                files: vec![FileChange {
+                    path: file_name.clone(),
+                    content: Some(file_content),
+                    expected_sha: Some(file_sha),
+                }],
+            })
             .await?;
-        let new_file_sha = file_update.content.sha;
+        let new_file_sha = committed
+            .file_shas
+            .get(&file_name)
+            .cloned()
+            .unwrap_or_default();
         Ok((pr, new_file_sha))
     }
 
@@ -644,7 +930,6 @@ impl GithubWrapper {
     ) -> Result<(PullRequest, String), OctocrabError> {
         let CreateMergeRequestData {
             issue_link,
-            ref_request,
             owner_name,
             file_content,
             file_name,
@@ -652,20 +937,204 @@ impl GithubWrapper {
             commit,
             application_id,
         } = data;
-        let _create_branch_res = self.create_branch(ref_request).await?;
-        let add_file_res = self
-            .add_file(&file_name, &file_content, &commit, &branch_name)
-            .await?;
-        let file_sha = add_file_res.content.sha;
         let allocator_tech_url = get_env_var_or_default("ALLOCATOR_TECH_URL");
         let pr_body = format!("[Link to related GitHub issue]({})\n[Link to application on Allocator.tech]({}/application/{}/{}/{})",issue_link, allocator_tech_url, self.owner, self.repo, application_id);
-        let pr = self
-            .create_pull_request(&format!("Datacap for {owner_name}"), &branch_name, pr_body)
+        let (pr, committed) = self
+            .create_multi_file_merge_request(CreateMultiFileMergeRequestData {
+                branch_name,
+                commit_message: commit,
+                title: format!("Datacap for {owner_name}"),
+                body: pr_body,
+                files: vec![FileChange {
+                    path: file_name.clone(),
+                    content: Some(file_content),
+                    expected_sha: None,
+                }],
+            })
             .await?;
+        let file_sha = committed
+            .file_shas
+            .get(&file_name)
+            .cloned()
+            .unwrap_or_default();
 
         Ok((pr, file_sha))
     }
 
+    /// Creates a branch from main whose single commit writes all `files`, and
+    /// opens a pull request for it. The branch ref is only created once the
+    /// commit exists, and is removed again when the pull request cannot be
+    /// opened, so a failure never leaves a half-written branch behind.
+    pub async fn create_multi_file_merge_request(
+        &self,
+        data: CreateMultiFileMergeRequestData,
+    ) -> Result<(PullRequest, CommittedFiles), OctocrabError> {
+        let CreateMultiFileMergeRequestData {
+            branch_name,
+            commit_message,
+            title,
+            body,
+            files,
+        } = data;
+        let committed = self
+            .create_branch_with_files(&branch_name, &commit_message, &files)
+            .await?;
+        match self.create_pull_request(&title, &branch_name, body).await {
+            Ok(pr) => Ok((pr, committed)),
+            Err(e) => {
+                self.cleanup_branch(&branch_name).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Creates `branch_name` from main pointing at a new commit with `files`.
+    pub async fn create_branch_with_files(
+        &self,
+        branch_name: &str,
+        message: &str,
+        files: &[FileChange],
+    ) -> Result<CommittedFiles, OctocrabError> {
+        if self.check_if_branch_exists(branch_name).await? {
+            return Err(OctocrabError::Other {
+                source: format!("Branch {branch_name} already exists.").into(),
+                backtrace: GenerateImplicitData::generate(),
+            });
+        }
+        let main_sha = self.get_branch_head_sha("main").await?;
+        let committed = self.create_files_commit(&main_sha, message, files).await?;
+        let _: RefData = self
+            .inner
+            .post(
+                format!("/repos/{}/{}/git/refs", self.owner, self.repo),
+                Some(&serde_json::json!({
+                    "ref": format!("refs/heads/{branch_name}"),
+                    "sha": committed.commit_sha,
+                })),
+            )
+            .await?;
+        Ok(committed)
+    }
+
+    pub async fn get_branch_head_sha(&self, branch_name: &str) -> Result<String, OctocrabError> {
+        let reference: RefData = self
+            .inner
+            .get(
+                format!(
+                    "/repos/{}/{}/git/ref/heads/{}",
+                    self.owner, self.repo, branch_name
+                ),
+                None::<&()>,
+            )
+            .await?;
+        Ok(reference.object.sha)
+    }
+
+    /// Creates a tree and a commit for `files` on top of `parent_sha` without
+    /// moving any ref.
+    async fn create_files_commit(
+        &self,
+        parent_sha: &str,
+        message: &str,
+        files: &[FileChange],
+    ) -> Result<CommittedFiles, OctocrabError> {
+        self.verify_file_shas(parent_sha, files).await?;
+
+        let parent: GitCommit = self
+            .inner
+            .get(
+                format!(
+                    "/repos/{}/{}/git/commits/{}",
+                    self.owner, self.repo, parent_sha
+                ),
+                None::<&()>,
+            )
+            .await?;
+
+        let mut file_shas = HashMap::new();
+        let tree_entries: Vec<serde_json::Value> = files
+            .iter()
+            .map(|file| match &file.content {
+                Some(content) => {
+                    file_shas.insert(file.path.clone(), content_sha(content));
+                    serde_json::json!({
+                        "path": file.path,
+                        "mode": "100644",
+                        "type": "blob",
+                        "content": content,
+                    })
+                }
+                None => serde_json::json!({
+                    "path": file.path,
+                    "mode": "100644",
+                    "type": "blob",
+                    "sha": null,
+                }),
+            })
+            .collect();
+
+        let tree: GitObject = self
+            .inner
+            .post(
+                format!("/repos/{}/{}/git/trees", self.owner, self.repo),
+                Some(&serde_json::json!({
+                    "base_tree": parent.tree.sha,
+                    "tree": tree_entries,
+                })),
+            )
+            .await?;
+
+        let commit: GitObject = self
+            .inner
+            .post(
+                format!("/repos/{}/{}/git/commits", self.owner, self.repo),
+                Some(&serde_json::json!({
+                    "message": message,
+                    "tree": tree.sha,
+                    "parents": [parent_sha],
+                })),
+            )
+            .await?;
+
+        Ok(CommittedFiles {
+            commit_sha: commit.sha,
+            file_shas,
+        })
+    }
+
+    async fn verify_file_shas(
+        &self,
+        commit_sha: &str,
+        files: &[FileChange],
+    ) -> Result<(), OctocrabError> {
+        for file in files {
+            let Some(expected_sha) = &file.expected_sha else {
+                continue;
+            };
+            let current = self.get_file(&file.path, commit_sha).await?;
+            let current_sha = current.items.first().map(|item| item.sha.as_str());
+            if current_sha != Some(expected_sha.as_str()) {
+                return Err(OctocrabError::Other {
+                    source: format!("File {} does not match sha {}", file.path, expected_sha)
+                        .into(),
+                    backtrace: GenerateImplicitData::generate(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    async fn cleanup_branch(&self, branch_name: &str) {
+        match self.build_remove_ref_request(branch_name.to_string()) {
+            Ok(request) => {
+                if !self.remove_branch(request).await.unwrap_or(false) {
+                    log::error!("Failed to clean up branch {branch_name}");
+                }
+            }
+            Err(e) => log::error!("Failed to clean up branch {branch_name}: {e}"),
+        }
+    }
+
     pub async fn merge_pull_request(&self, number: u64) -> Result<(), OctocrabError> {
         let _merge_res = self
             .inner
@@ -841,6 +1310,32 @@ impl GithubWrapper {
             })
     }
 
+    /// Raw JSON of the comment templates file, `None` when the allocator did
+    /// not add one.
+    pub async fn filplus_comment_templates(
+        &self,
+        branch: &str,
+    ) -> Result<Option<String>, LDNError> {
+        let path = crate::core::allocator::comment_templates::COMMENT_TEMPLATES_FILE;
+        let mut items = match self.get_file(path, branch).await {
+            Ok(items) => items,
+            Err(octocrab::Error::GitHub {
+                source: GitHubError { message, .. },
+                ..
+            }) if message == "Not Found" => return Ok(None),
+            Err(e) => {
+                return Err(LDNError::Load(format!(
+                    "Failed to load {path} file from repository {}/{}: {e}",
+                    self.owner, self.repo
+                )))
+            }
+        };
+        Ok(items
+            .take_items()
+            .pop()
+            .and_then(|content| content.decoded_content()))
+    }
+
     pub async fn get_issue_reporter_handle(&self, issue_number: &u64) -> Result<String, LDNError> {
         let issue = self.list_issue(*issue_number).await.map_err(|e| {
             LDNError::Load(format!(
@@ -0,0 +1,1509 @@
+//! In-memory stand-in for the GitHub REST API, compiled only for tests.
+//!
+//! [`FakeGithub`] is a tower service which `GithubWrapper` uses instead of the
+//! HTTPS client once [`FakeGithub::install`] was called, so application flows
+//! can run in `cargo test` without real repositories. It models repositories,
+//! branches, contents, pull requests, issues, labels, comments and
+//! installations closely enough for the requests made by `GithubWrapper`.
+//!
+//! Raw file downloads (`download_url`) are not served, so listings which
+//! fetch merged files with `reqwest` still need the database store.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use chrono::{DateTime, Utc};
+use futures::future::{ready, Ready};
+use http::{Method, Request, Response, StatusCode};
+use hyper::Body;
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+use tower::Service;
+
+use crate::config::get_env_var_or_default;
+use crate::core::application::store::content_sha;
+use crate::external_services::github::{
+    clear_installation_clients, update_repository_installation,
+};
+
+const API_URL: &str = "https://api.github.com";
+const FAKE_USER: &str = "fake-github-user";
+
+static INSTALLED: OnceCell<FakeGithub> = OnceCell::new();
+
+/// Fake used by every `GithubWrapper` built after it was installed.
+pub(crate) fn installed() -> Option<FakeGithub> {
+    INSTALLED.get().cloned()
+}
+
+type HandlerResult = Result<(StatusCode, Value), (StatusCode, String)>;
+
+#[derive(Debug, Clone)]
+struct FakeCommit {
+    tree: String,
+    parents: Vec<String>,
+    message: String,
+    date: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct FakeComment {
+    id: u64,
+    body: String,
+    user: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct FakeIssue {
+    number: u64,
+    title: String,
+    body: String,
+    user: String,
+    open: bool,
+    labels: Vec<String>,
+    comments: Vec<FakeComment>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct FakePull {
+    number: u64,
+    title: String,
+    body: String,
+    head: String,
+    base: String,
+    // Base commit when the pull request was opened, the diff is taken against it
+    base_sha: String,
+    open: bool,
+    merged: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct FakeLabel {
+    color: String,
+    description: String,
+}
+
+#[derive(Debug, Default)]
+struct FakeRepo {
+    installation_id: u64,
+    labels: BTreeMap<String, FakeLabel>,
+    branches: BTreeMap<String, String>,
+    commits: HashMap<String, FakeCommit>,
+    // tree sha -> path -> blob sha
+    trees: HashMap<String, BTreeMap<String, String>>,
+    blobs: HashMap<String, String>,
+    issues: BTreeMap<u64, FakeIssue>,
+    pulls: BTreeMap<u64, FakePull>,
+    last_number: u64,
+}
+
+#[derive(Debug, Default)]
+struct FakeState {
+    repos: HashMap<(String, String), FakeRepo>,
+    last_id: u64,
+}
+
+impl FakeState {
+    fn next_id(&mut self) -> u64 {
+        self.last_id += 1;
+        self.last_id
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FakeGithub {
+    state: Arc<Mutex<FakeState>>,
+}
+
+impl FakeGithub {
+    /// Returns the process-wide fake and routes all GitHub clients through it.
+    pub fn install() -> FakeGithub {
+        let fake = INSTALLED.get_or_init(FakeGithub::default).clone();
+        clear_installation_clients();
+        fake
+    }
+
+    /// Creates a repository with an empty main branch, accessible through the
+    /// given installation.
+    pub fn create_repo(&self, owner: &str, repo: &str, installation_id: u64) {
+        let mut state = self.state.lock().unwrap();
+        let mut fake_repo = FakeRepo {
+            installation_id,
+            ..Default::default()
+        };
+        let tree = fake_repo.store_tree(BTreeMap::new());
+        let commit = fake_repo.store_commit(tree, vec![], "Initial commit".to_string());
+        fake_repo.branches.insert("main".to_string(), commit);
+        state
+            .repos
+            .insert((owner.to_string(), repo.to_string()), fake_repo);
+        drop(state);
+        update_repository_installation(owner, repo, Some(installation_id as i64));
+    }
+
+    /// Commits a file directly to a branch.
+    pub fn put_file(&self, owner: &str, repo: &str, branch: &str, path: &str, content: &str) {
+        self.with_repo(owner, repo, |repo| {
+            repo.commit_files(
+                branch,
+                &format!("Add {path}"),
+                [(path.to_string(), Some(content.to_string()))],
+            )
+        })
+        .unwrap();
+    }
+
+    pub fn file(&self, owner: &str, repo: &str, branch: &str, path: &str) -> Option<String> {
+        self.with_repo(owner, repo, |repo| {
+            let files = repo.branch_files(branch)?;
+            let sha = files.get(path).ok_or(not_found())?;
+            Ok(repo.blobs.get(sha).cloned().unwrap_or_default())
+        })
+        .ok()
+    }
+
+    pub fn branches(&self, owner: &str, repo: &str) -> Vec<String> {
+        self.with_repo(owner, repo, |repo| {
+            Ok(repo.branches.keys().cloned().collect())
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn create_issue(&self, owner: &str, repo: &str, title: &str, body: &str) -> u64 {
+        self.with_repo(owner, repo, |repo| {
+            Ok(repo.create_issue(title.to_string(), body.to_string()))
+        })
+        .unwrap()
+    }
+
+    /// Comments on the issue as `user` rather than as the app.
+    pub fn add_user_comment(&self, owner: &str, repo: &str, number: u64, user: &str, body: &str) {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id();
+        let issue = state
+            .repos
+            .get_mut(&(owner.to_string(), repo.to_string()))
+            .unwrap()
+            .issue_mut(number)
+            .unwrap();
+        let now = Utc::now();
+        issue.comments.push(FakeComment {
+            id,
+            body: body.to_string(),
+            user: user.to_string(),
+            created_at: now,
+            updated_at: now,
+        });
+    }
+
+    /// Edits the issue body, as the client does when updating an application.
+    pub fn set_issue_body(&self, owner: &str, repo: &str, number: u64, body: &str) {
+        self.with_repo(owner, repo, |repo| {
+            let issue = repo.issue_mut(number)?;
+            issue.body = body.to_string();
+            issue.updated_at = Utc::now();
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    pub fn create_label(&self, owner: &str, repo: &str, name: &str, color: &str) {
+        self.with_repo(owner, repo, |repo| {
+            repo.labels.insert(
+                name.to_string(),
+                FakeLabel {
+                    color: color.to_string(),
+                    description: String::new(),
+                },
+            );
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    /// Labels of the repository as `(name, color, description)`.
+    pub fn repo_labels(&self, owner: &str, repo: &str) -> Vec<(String, String, String)> {
+        self.with_repo(owner, repo, |repo| {
+            Ok(repo
+                .labels
+                .iter()
+                .map(|(name, label)| (name.clone(), label.color.clone(), label.description.clone()))
+                .collect())
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn set_issue_labels(&self, owner: &str, repo: &str, number: u64, labels: &[&str]) {
+        self.with_repo(owner, repo, |repo| {
+            repo.issue_mut(number)?.labels = labels.iter().map(|label| label.to_string()).collect();
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    pub fn issue_labels(&self, owner: &str, repo: &str, number: u64) -> Vec<String> {
+        self.with_repo(owner, repo, |repo| Ok(repo.issue(number)?.labels.clone()))
+            .unwrap_or_default()
+    }
+
+    pub fn issue_comments(&self, owner: &str, repo: &str, number: u64) -> Vec<String> {
+        self.with_repo(owner, repo, |repo| {
+            Ok(repo
+                .issue(number)?
+                .comments
+                .iter()
+                .map(|comment| comment.body.clone())
+                .collect())
+        })
+        .unwrap_or_default()
+    }
+
+    /// Numbers of the open pull requests with their head branches.
+    pub fn open_pull_requests(&self, owner: &str, repo: &str) -> Vec<(u64, String)> {
+        self.with_repo(owner, repo, |repo| {
+            Ok(repo
+                .pulls
+                .values()
+                .filter(|pull| pull.open)
+                .map(|pull| (pull.number, pull.head.clone()))
+                .collect())
+        })
+        .unwrap_or_default()
+    }
+
+    fn with_repo<T>(
+        &self,
+        owner: &str,
+        repo: &str,
+        f: impl FnOnce(&mut FakeRepo) -> Result<T, (StatusCode, String)>,
+    ) -> Result<T, (StatusCode, String)> {
+        let mut state = self.state.lock().unwrap();
+        let repo = state
+            .repos
+            .get_mut(&(owner.to_string(), repo.to_string()))
+            .ok_or(not_found())?;
+        f(repo)
+    }
+
+    fn handle(&self, request: Request<String>) -> Response<Body> {
+        let (status, body) = match self.route(&request) {
+            Ok((status, body)) => (status, body),
+            Err((status, message)) => (
+                status,
+                json!({
+                    "message": message,
+                    "documentation_url": "https://docs.github.com/rest",
+                }),
+            ),
+        };
+        let body = if body.is_null() {
+            Body::empty()
+        } else {
+            Body::from(body.to_string())
+        };
+        let mut response = Response::new(body);
+        *response.status_mut() = status;
+        response
+    }
+
+    fn route(&self, request: &Request<String>) -> HandlerResult {
+        let path = request.uri().path().trim_start_matches('/').to_string();
+        let segments: Vec<&str> = path.split('/').collect();
+        let query: HashMap<String, String> = request
+            .uri()
+            .query()
+            .map(|query| {
+                url::form_urlencoded::parse(query.as_bytes())
+                    .into_owned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        let body: Value = if request.body().is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(request.body()).map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Problems parsing JSON: {e}"),
+                )
+            })?
+        };
+
+        match segments.as_slice() {
+            ["app", "installations", id, "access_tokens"] => Ok((
+                StatusCode::CREATED,
+                json!({
+                    "token": format!("fake-token-{id}"),
+                    "expires_at": (Utc::now() + chrono::Duration::hours(1)).to_rfc3339(),
+                }),
+            )),
+            ["repos", owner, repo, rest @ ..] => {
+                let mut state = self.state.lock().unwrap();
+                let id = state.next_id();
+                let fake_repo = state
+                    .repos
+                    .get_mut(&(owner.to_string(), repo.to_string()))
+                    .ok_or(not_found())?;
+                let base = format!("{API_URL}/repos/{owner}/{repo}");
+                let ctx = RouteContext {
+                    owner,
+                    base: &base,
+                    id,
+                    query: &query,
+                    body: &body,
+                };
+                fake_repo.route(request.method(), rest, &ctx)
+            }
+            _ => Err(not_found()),
+        }
+    }
+}
+
+impl Service<Request<String>> for FakeGithub {
+    type Response = Response<Body>;
+    type Error = Infallible;
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<String>) -> Self::Future {
+        ready(Ok(self.handle(request)))
+    }
+}
+
+struct RouteContext<'a> {
+    owner: &'a str,
+    base: &'a str,
+    // Fresh id for objects created by the request
+    id: u64,
+    query: &'a HashMap<String, String>,
+    body: &'a Value,
+}
+
+impl RouteContext<'_> {
+    fn body_str(&self, key: &str) -> Option<String> {
+        self.body
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string())
+    }
+
+    fn body_labels(&self) -> Vec<String> {
+        self.body
+            .get("labels")
+            .and_then(|labels| labels.as_array())
+            .map(|labels| {
+                labels
+                    .iter()
+                    .filter_map(|label| label.as_str().map(|label| label.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl FakeRepo {
+    fn route(&mut self, method: &Method, path: &[&str], ctx: &RouteContext) -> HandlerResult {
+        let ok = |value: Value| Ok((StatusCode::OK, value));
+        let created = |value: Value| Ok((StatusCode::CREATED, value));
+
+        match (method.as_str(), path) {
+            ("GET", ["installation"]) => ok(json!({
+                "id": self.installation_id,
+                "account": user_json(ctx.owner),
+                "permissions": {},
+                "events": [],
+            })),
+
+            ("GET", ["branches"]) => ok(Value::Array(
+                self.branches
+                    .iter()
+                    .map(|(name, sha)| {
+                        json!({
+                            "name": name,
+                            "commit": { "sha": sha, "url": format!("{}/commits/{sha}", ctx.base) },
+                            "protected": false,
+                        })
+                    })
+                    .collect(),
+            )),
+
+            ("GET", ["contents", file_path @ ..]) => {
+                let branch = ctx.query.get("ref").map(String::as_str).unwrap_or("main");
+                self.get_contents(&file_path.join("/"), branch, ctx.base)
+                    .map(|value| (StatusCode::OK, value))
+            }
+            ("PUT", ["contents", file_path @ ..]) => {
+                let file_path = file_path.join("/");
+                self.put_contents(&file_path, ctx)
+            }
+            ("DELETE", ["contents", file_path @ ..]) => {
+                let file_path = file_path.join("/");
+                let branch = ctx.body_str("branch").unwrap_or("main".to_string());
+                let current = self.branch_files(&branch)?.get(&file_path).cloned();
+                if current.is_none() || current != ctx.body_str("sha") {
+                    return Err((StatusCode::CONFLICT, format!("{file_path} does not match")));
+                }
+                let message = ctx.body_str("message").unwrap_or_default();
+                let commit = self.commit_files(&branch, &message, [(file_path, None)])?;
+                ok(json!({ "content": null, "commit": { "sha": commit } }))
+            }
+
+            ("GET", ["commits"]) => {
+                let branch = ctx.query.get("sha").map(String::as_str).unwrap_or("main");
+                let head = self.branch_head(branch)?;
+                let commits: Vec<Value> = self
+                    .history(&head)
+                    .into_iter()
+                    .filter(|sha| match ctx.query.get("path") {
+                        Some(path) => self.commit_touches(sha, path),
+                        None => true,
+                    })
+                    .map(|sha| self.repo_commit_json(&sha, ctx.base))
+                    .collect();
+                ok(Value::Array(commits))
+            }
+
+            ("GET", ["git", "ref" | "refs", "heads", branch @ ..]) => {
+                let branch = branch.join("/");
+                let sha = self.branch_head(&branch)?;
+                ok(ref_json(&branch, &sha, ctx.base))
+            }
+            ("POST", ["git", "refs"]) => {
+                let reference = ctx.body_str("ref").unwrap_or_default();
+                let branch = reference.trim_start_matches("refs/heads/").to_string();
+                let sha = ctx.body_str("sha").unwrap_or_default();
+                if self.branches.contains_key(&branch) {
+                    return Err((
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "Reference already exists".to_string(),
+                    ));
+                }
+                if !self.commits.contains_key(&sha) {
+                    return Err((
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "Object does not exist".to_string(),
+                    ));
+                }
+                self.branches.insert(branch.clone(), sha.clone());
+                created(ref_json(&branch, &sha, ctx.base))
+            }
+            ("PATCH", ["git", "refs", "heads", branch @ ..]) => {
+                let branch = branch.join("/");
+                let current = self.branch_head(&branch)?;
+                let sha = ctx.body_str("sha").unwrap_or_default();
+                let force = ctx
+                    .body
+                    .get("force")
+                    .and_then(|force| force.as_bool())
+                    .unwrap_or(false);
+                if !self.commits.contains_key(&sha) {
+                    return Err((
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "Object does not exist".to_string(),
+                    ));
+                }
+                if !force && !self.history(&sha).contains(&current) {
+                    return Err((
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "Update is not a fast forward".to_string(),
+                    ));
+                }
+                self.branches.insert(branch.clone(), sha.clone());
+                ok(ref_json(&branch, &sha, ctx.base))
+            }
+            ("DELETE", ["git", "refs", "heads", branch @ ..]) => {
+                let branch = branch.join("/");
+                self.branches
+                    .remove(&branch)
+                    .ok_or((
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "Reference does not exist".to_string(),
+                    ))
+                    .map(|_| (StatusCode::NO_CONTENT, Value::Null))
+            }
+            ("GET", ["git", "commits", sha]) => {
+                let commit = self.commits.get(*sha).ok_or(not_found())?;
+                ok(json!({
+                    "sha": sha,
+                    "message": commit.message,
+                    "tree": { "sha": commit.tree },
+                    "parents": commit.parents.iter().map(|sha| json!({ "sha": sha })).collect::<Vec<_>>(),
+                }))
+            }
+            ("POST", ["git", "trees"]) => {
+                let mut files = match ctx.body_str("base_tree") {
+                    Some(base_tree) => self.trees.get(&base_tree).cloned().ok_or((
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "Invalid base_tree".to_string(),
+                    ))?,
+                    None => BTreeMap::new(),
+                };
+                let entries = ctx
+                    .body
+                    .get("tree")
+                    .and_then(|tree| tree.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+                for entry in entries {
+                    let path = entry["path"].as_str().unwrap_or_default().to_string();
+                    if let Some(content) = entry.get("content").and_then(|c| c.as_str()) {
+                        let sha = self.store_blob(content.to_string());
+                        files.insert(path, sha);
+                    } else if let Some(sha) = entry.get("sha").and_then(|sha| sha.as_str()) {
+                        files.insert(path, sha.to_string());
+                    } else {
+                        files.remove(&path);
+                    }
+                }
+                let sha = self.store_tree(files);
+                created(json!({ "sha": sha, "tree": [] }))
+            }
+            ("POST", ["git", "commits"]) => {
+                let tree = ctx.body_str("tree").unwrap_or_default();
+                if !self.trees.contains_key(&tree) {
+                    return Err((
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "Tree does not exist".to_string(),
+                    ));
+                }
+                let parents = ctx
+                    .body
+                    .get("parents")
+                    .and_then(|parents| parents.as_array())
+                    .map(|parents| {
+                        parents
+                            .iter()
+                            .filter_map(|parent| parent.as_str().map(|p| p.to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let message = ctx.body_str("message").unwrap_or_default();
+                let sha = self.store_commit(tree.clone(), parents, message);
+                created(json!({ "sha": sha, "tree": { "sha": tree } }))
+            }
+
+            ("GET", ["issues"]) => {
+                let state = ctx.query.get("state").map(String::as_str).unwrap_or("open");
+                ok(Value::Array(
+                    self.issues
+                        .values()
+                        .filter(|issue| state_matches(state, issue.open))
+                        .map(|issue| issue_json(issue, ctx.base))
+                        .collect(),
+                ))
+            }
+            ("POST", ["issues"]) => {
+                let number = self.create_issue(
+                    ctx.body_str("title").unwrap_or_default(),
+                    ctx.body_str("body").unwrap_or_default(),
+                );
+                created(issue_json(self.issue(number)?, ctx.base))
+            }
+            ("GET", ["issues", number]) => ok(issue_json(self.issue(parse(number)?)?, ctx.base)),
+            ("PATCH", ["issues", number]) => {
+                let issue = self.issue_mut(parse(number)?)?;
+                if let Some(title) = ctx.body_str("title") {
+                    issue.title = title;
+                }
+                if let Some(body) = ctx.body_str("body") {
+                    issue.body = body;
+                }
+                if let Some(state) = ctx.body_str("state") {
+                    issue.open = state == "open";
+                }
+                issue.updated_at = Utc::now();
+                ok(issue_json(issue, ctx.base))
+            }
+            ("GET", ["issues", number, "comments"]) => {
+                let issue = self.issue(parse(number)?)?;
+                ok(Value::Array(
+                    issue
+                        .comments
+                        .iter()
+                        .map(|comment| comment_json(comment, issue.number, ctx.base))
+                        .collect(),
+                ))
+            }
+            ("POST", ["issues", number, "comments"]) => {
+                let issue = self.issue_mut(parse(number)?)?;
+                let now = Utc::now();
+                issue.comments.push(FakeComment {
+                    id: ctx.id,
+                    body: ctx.body_str("body").unwrap_or_default(),
+                    // The app comments as its bot user.
+                    user: get_env_var_or_default("BOT_USER"),
+                    created_at: now,
+                    updated_at: now,
+                });
+                issue.updated_at = now;
+                let comment = issue.comments.last().unwrap();
+                created(comment_json(comment, issue.number, ctx.base))
+            }
+            ("PATCH", ["issues", "comments", id]) => {
+                let id = parse(id)?;
+                let (comment, number) = self
+                    .issues
+                    .values_mut()
+                    .find_map(|issue| {
+                        let number = issue.number;
+                        issue
+                            .comments
+                            .iter_mut()
+                            .find(|comment| comment.id == id)
+                            .map(|comment| (comment, number))
+                    })
+                    .ok_or(not_found())?;
+                comment.body = ctx.body_str("body").unwrap_or_default();
+                comment.updated_at = Utc::now();
+                ok(comment_json(comment, number, ctx.base))
+            }
+            ("GET", ["labels"]) => ok(Value::Array(
+                self.labels
+                    .iter()
+                    .map(|(name, label)| repo_label_json(name, label, ctx.base))
+                    .collect(),
+            )),
+            ("POST", ["labels"]) => {
+                let name = ctx.body_str("name").unwrap_or_default();
+                if self
+                    .labels
+                    .keys()
+                    .any(|existing| existing.eq_ignore_ascii_case(&name))
+                {
+                    return Err((
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "Validation Failed: already_exists".to_string(),
+                    ));
+                }
+                let label = FakeLabel {
+                    color: ctx.body_str("color").unwrap_or_default(),
+                    description: ctx.body_str("description").unwrap_or_default(),
+                };
+                let json = repo_label_json(&name, &label, ctx.base);
+                self.labels.insert(name, label);
+                created(json)
+            }
+            ("PATCH", ["labels", name]) => {
+                let name = percent_decode(name);
+                let mut label = self.labels.remove(&name).ok_or(not_found())?;
+                let new_name = ctx.body_str("new_name").unwrap_or(name.clone());
+                if let Some(color) = ctx.body_str("color") {
+                    label.color = color;
+                }
+                if let Some(description) = ctx.body_str("description") {
+                    label.description = description;
+                }
+                for issue in self.issues.values_mut() {
+                    for issue_label in issue.labels.iter_mut() {
+                        if *issue_label == name {
+                            *issue_label = new_name.clone();
+                        }
+                    }
+                }
+                let json = repo_label_json(&new_name, &label, ctx.base);
+                self.labels.insert(new_name, label);
+                ok(json)
+            }
+            ("GET", ["issues", number, "labels"]) => {
+                ok(labels_json(&self.issue(parse(number)?)?.labels, ctx.base))
+            }
+            ("POST", ["issues", number, "labels"]) => {
+                let issue = self.issue_mut(parse(number)?)?;
+                for label in ctx.body_labels() {
+                    if !issue.labels.contains(&label) {
+                        issue.labels.push(label);
+                    }
+                }
+                ok(labels_json(&issue.labels, ctx.base))
+            }
+            ("PUT", ["issues", number, "labels"]) => {
+                let issue = self.issue_mut(parse(number)?)?;
+                issue.labels = ctx.body_labels();
+                ok(labels_json(&issue.labels, ctx.base))
+            }
+            ("DELETE", ["issues", number, "labels", name]) => {
+                let issue = self.issue_mut(parse(number)?)?;
+                let name = percent_decode(name);
+                if !issue.labels.contains(&name) {
+                    return Err((StatusCode::NOT_FOUND, "Label does not exist".to_string()));
+                }
+                issue.labels.retain(|label| *label != name);
+                ok(labels_json(&issue.labels, ctx.base))
+            }
+
+            ("GET", ["pulls"]) => {
+                let state = ctx.query.get("state").map(String::as_str).unwrap_or("open");
+                let head = ctx
+                    .query
+                    .get("head")
+                    .map(|head| head.split_once(':').map_or(head.as_str(), |(_, b)| b));
+                ok(Value::Array(
+                    self.pulls
+                        .values()
+                        .filter(|pull| state_matches(state, pull.open))
+                        .filter(|pull| head.is_none_or(|head| pull.head == head))
+                        .map(|pull| self.pull_json(pull, ctx.base))
+                        .collect(),
+                ))
+            }
+            ("POST", ["pulls"]) => {
+                let head = ctx.body_str("head").unwrap_or_default();
+                let base = ctx.body_str("base").unwrap_or("main".to_string());
+                self.branch_head(&head)?;
+                let base_sha = self.branch_head(&base)?;
+                if self
+                    .pulls
+                    .values()
+                    .any(|pull| pull.open && pull.head == head)
+                {
+                    return Err((
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        format!("A pull request already exists for {}:{head}.", ctx.owner),
+                    ));
+                }
+                self.last_number += 1;
+                let now = Utc::now();
+                let pull = FakePull {
+                    number: self.last_number,
+                    title: ctx.body_str("title").unwrap_or_default(),
+                    body: ctx.body_str("body").unwrap_or_default(),
+                    head,
+                    base,
+                    base_sha,
+                    open: true,
+                    merged: false,
+                    created_at: now,
+                    updated_at: now,
+                };
+                let value = self.pull_json(&pull, ctx.base);
+                self.pulls.insert(pull.number, pull);
+                created(value)
+            }
+            ("GET", ["pulls", number]) => {
+                let pull = self.pull(parse(number)?)?;
+                ok(self.pull_json(pull, ctx.base))
+            }
+            ("PATCH", ["pulls", number]) => {
+                let pull = self.pulls.get_mut(&parse(number)?).ok_or(not_found())?;
+                if let Some(title) = ctx.body_str("title") {
+                    pull.title = title;
+                }
+                if let Some(body) = ctx.body_str("body") {
+                    pull.body = body;
+                }
+                if let Some(state) = ctx.body_str("state") {
+                    pull.open = state == "open";
+                }
+                pull.updated_at = Utc::now();
+                let pull = pull.clone();
+                ok(self.pull_json(&pull, ctx.base))
+            }
+            ("PUT", ["pulls", number, "merge"]) => {
+                let sha = self.merge_pull(parse(number)?)?;
+                ok(
+                    json!({ "sha": sha, "merged": true, "message": "Pull Request successfully merged" }),
+                )
+            }
+            ("GET", ["pulls", number, "files"]) => {
+                let pull = self.pull(parse(number)?)?.clone();
+                let base_files = self.commit_files_of(&pull.base_sha);
+                let head_files = self.branch_files(&pull.head).cloned().unwrap_or_default();
+                ok(Value::Array(
+                    diff(&base_files, &head_files)
+                        .into_iter()
+                        .map(|(path, change)| file_diff_json(&path, change, ctx.base))
+                        .collect(),
+                ))
+            }
+            ("GET", ["pulls", number, "commits"]) => {
+                let pull = self.pull(parse(number)?)?.clone();
+                let head = self.branch_head(&pull.head)?;
+                let base_history = self.history(&pull.base_sha);
+                let mut commits: Vec<Value> = self
+                    .history(&head)
+                    .into_iter()
+                    .filter(|sha| !base_history.contains(sha))
+                    .map(|sha| self.repo_commit_json(&sha, ctx.base))
+                    .collect();
+                commits.reverse();
+                ok(Value::Array(commits))
+            }
+
+            _ => Err(not_found()),
+        }
+    }
+
+    fn store_blob(&mut self, content: String) -> String {
+        let sha = content_sha(&content);
+        self.blobs.insert(sha.clone(), content);
+        sha
+    }
+
+    fn store_tree(&mut self, files: BTreeMap<String, String>) -> String {
+        let sha = content_sha(&format!("tree {files:?}"));
+        self.trees.insert(sha.clone(), files);
+        sha
+    }
+
+    fn store_commit(&mut self, tree: String, parents: Vec<String>, message: String) -> String {
+        let date = Utc::now();
+        let sha = content_sha(&format!(
+            "commit {tree} {parents:?} {message} {} {}",
+            date.timestamp_nanos_opt().unwrap_or_default(),
+            self.commits.len()
+        ));
+        self.commits.insert(
+            sha.clone(),
+            FakeCommit {
+                tree,
+                parents,
+                message,
+                date,
+            },
+        );
+        sha
+    }
+
+    fn branch_head(&self, branch: &str) -> Result<String, (StatusCode, String)> {
+        self.branches.get(branch).cloned().ok_or(not_found())
+    }
+
+    fn commit_files_of(&self, sha: &str) -> BTreeMap<String, String> {
+        self.commits
+            .get(sha)
+            .and_then(|commit| self.trees.get(&commit.tree))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn branch_files(
+        &self,
+        branch: &str,
+    ) -> Result<&BTreeMap<String, String>, (StatusCode, String)> {
+        let head = self.branches.get(branch).ok_or(not_found())?;
+        let commit = self.commits.get(head).ok_or(not_found())?;
+        self.trees.get(&commit.tree).ok_or(not_found())
+    }
+
+    /// Commits reachable from `sha`, newest first.
+    fn history(&self, sha: &str) -> Vec<String> {
+        let mut history = Vec::new();
+        let mut pending = vec![sha.to_string()];
+        while let Some(sha) = pending.pop() {
+            if history.contains(&sha) {
+                continue;
+            }
+            if let Some(commit) = self.commits.get(&sha) {
+                pending.extend(commit.parents.iter().rev().cloned());
+            }
+            history.push(sha);
+        }
+        history.sort_by_key(|sha| std::cmp::Reverse(self.commits.get(sha).map(|c| c.date)));
+        history
+    }
+
+    fn commit_touches(&self, sha: &str, path: &str) -> bool {
+        let Some(commit) = self.commits.get(sha) else {
+            return false;
+        };
+        let files = self.commit_files_of(sha);
+        match commit.parents.first() {
+            Some(parent) => self.commit_files_of(parent).get(path) != files.get(path),
+            None => files.contains_key(path),
+        }
+    }
+
+    fn commit_files<I>(
+        &mut self,
+        branch: &str,
+        message: &str,
+        changes: I,
+    ) -> Result<String, (StatusCode, String)>
+    where
+        I: IntoIterator<Item = (String, Option<String>)>,
+    {
+        let head = self.branch_head(branch)?;
+        let mut files = self.commit_files_of(&head);
+        for (path, content) in changes {
+            match content {
+                Some(content) => {
+                    let sha = self.store_blob(content);
+                    files.insert(path, sha);
+                }
+                None => {
+                    files.remove(&path);
+                }
+            }
+        }
+        let tree = self.store_tree(files);
+        let commit = self.store_commit(tree, vec![head], message.to_string());
+        self.branches.insert(branch.to_string(), commit.clone());
+        Ok(commit)
+    }
+
+    fn get_contents(
+        &self,
+        path: &str,
+        branch: &str,
+        base: &str,
+    ) -> Result<Value, (StatusCode, String)> {
+        // Contents can also be read at a commit sha
+        let files = match self.branch_files(branch) {
+            Ok(files) => files.clone(),
+            Err(e) if self.commits.contains_key(branch) => {
+                let _ = e;
+                self.commit_files_of(branch)
+            }
+            Err(e) => return Err(e),
+        };
+        if let Some(sha) = files.get(path) {
+            let content = self.blobs.get(sha).cloned().unwrap_or_default();
+            return Ok(content_json(path, sha, Some(&content), base));
+        }
+
+        let prefix = if path.is_empty() {
+            String::new()
+        } else {
+            format!("{path}/")
+        };
+        let mut entries: BTreeMap<String, Value> = BTreeMap::new();
+        for (file_path, sha) in files.iter() {
+            let Some(rest) = file_path.strip_prefix(&prefix) else {
+                continue;
+            };
+            match rest.split_once('/') {
+                Some((dir, _)) => {
+                    let dir_path = format!("{prefix}{dir}");
+                    entries
+                        .entry(dir_path.clone())
+                        .or_insert_with(|| dir_json(&dir_path, base));
+                }
+                None => {
+                    entries.insert(file_path.clone(), content_json(file_path, sha, None, base));
+                }
+            }
+        }
+        if entries.is_empty() {
+            return Err(not_found());
+        }
+        Ok(Value::Array(entries.into_values().collect()))
+    }
+
+    fn put_contents(&mut self, path: &str, ctx: &RouteContext) -> HandlerResult {
+        let branch = ctx.body_str("branch").unwrap_or("main".to_string());
+        let current = self.branch_files(&branch)?.get(path).cloned();
+        let expected = ctx.body_str("sha");
+        match (&current, &expected) {
+            (Some(_), None) => {
+                return Err((
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "Invalid request.\n\n\"sha\" wasn't supplied.".to_string(),
+                ))
+            }
+            (Some(current), Some(expected)) if current != expected => {
+                return Err((
+                    StatusCode::CONFLICT,
+                    format!("{path} does not match {expected}"),
+                ))
+            }
+            _ => {}
+        }
+        let encoded = ctx.body_str("content").unwrap_or_default();
+        let content = ::base64::decode(encoded.replace('\n', ""))
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "content is not valid Base64".to_string(),
+            ))?;
+        let message = ctx.body_str("message").unwrap_or_default();
+        let commit = self.commit_files(
+            &branch,
+            &message,
+            [(path.to_string(), Some(content.clone()))],
+        )?;
+        let sha = content_sha(&content);
+        let status = if current.is_some() {
+            StatusCode::OK
+        } else {
+            StatusCode::CREATED
+        };
+        Ok((
+            status,
+            json!({
+                "content": content_json(path, &sha, None, ctx.base),
+                "commit": { "sha": commit, "message": message },
+            }),
+        ))
+    }
+
+    fn merge_pull(&mut self, number: u64) -> Result<String, (StatusCode, String)> {
+        let pull = self.pull(number)?.clone();
+        if !pull.open {
+            return Err((
+                StatusCode::METHOD_NOT_ALLOWED,
+                "Pull Request is not mergeable".to_string(),
+            ));
+        }
+        let base_head = self.branch_head(&pull.base)?;
+        let head = self.branch_head(&pull.head)?;
+        let original = self.commit_files_of(&pull.base_sha);
+        let mut files = self.commit_files_of(&base_head);
+        for (path, change) in diff(&original, &self.commit_files_of(&head)) {
+            if files.get(&path) != original.get(&path) {
+                return Err((StatusCode::METHOD_NOT_ALLOWED, "Merge conflict".to_string()));
+            }
+            match change {
+                FileChangeKind::Removed => files.remove(&path),
+                FileChangeKind::Added(sha) | FileChangeKind::Modified(sha) => {
+                    files.insert(path, sha)
+                }
+            };
+        }
+        let tree = self.store_tree(files);
+        let commit = self.store_commit(
+            tree,
+            vec![base_head, head],
+            format!("Merge pull request #{number} from {}", pull.head),
+        );
+        self.branches.insert(pull.base.clone(), commit.clone());
+        let pull = self.pulls.get_mut(&number).ok_or(not_found())?;
+        pull.open = false;
+        pull.merged = true;
+        pull.updated_at = Utc::now();
+        Ok(commit)
+    }
+
+    fn create_issue(&mut self, title: String, body: String) -> u64 {
+        self.last_number += 1;
+        let now = Utc::now();
+        self.issues.insert(
+            self.last_number,
+            FakeIssue {
+                number: self.last_number,
+                title,
+                body,
+                user: FAKE_USER.to_string(),
+                open: true,
+                labels: vec![],
+                comments: vec![],
+                created_at: now,
+                updated_at: now,
+            },
+        );
+        self.last_number
+    }
+
+    fn issue(&self, number: u64) -> Result<&FakeIssue, (StatusCode, String)> {
+        self.issues.get(&number).ok_or(not_found())
+    }
+
+    fn issue_mut(&mut self, number: u64) -> Result<&mut FakeIssue, (StatusCode, String)> {
+        self.issues.get_mut(&number).ok_or(not_found())
+    }
+
+    fn pull(&self, number: u64) -> Result<&FakePull, (StatusCode, String)> {
+        self.pulls.get(&number).ok_or(not_found())
+    }
+
+    fn pull_json(&self, pull: &FakePull, base: &str) -> Value {
+        let head_sha = self.branches.get(&pull.head).cloned().unwrap_or_default();
+        json!({
+            "url": format!("{base}/pulls/{}", pull.number),
+            "id": pull.number,
+            "number": pull.number,
+            "state": if pull.open { "open" } else { "closed" },
+            "title": pull.title,
+            "body": pull.body,
+            "user": user_json(FAKE_USER),
+            "locked": false,
+            "maintainer_can_modify": true,
+            "html_url": format!("{}/pull/{}", html_url(base), pull.number),
+            "created_at": pull.created_at,
+            "updated_at": pull.updated_at,
+            "merged_at": if pull.merged { Some(pull.updated_at) } else { None },
+            "head": { "ref": pull.head, "sha": head_sha, "label": pull.head },
+            "base": { "ref": pull.base, "sha": pull.base_sha, "label": pull.base },
+        })
+    }
+
+    fn repo_commit_json(&self, sha: &str, base: &str) -> Value {
+        let commit = self.commits.get(sha).cloned();
+        let (tree, parents, message, date) = commit
+            .map(|c| (c.tree, c.parents, c.message, c.date))
+            .unwrap_or_default();
+        let author = json!({ "name": FAKE_USER, "email": "fake@github.com", "date": date });
+        json!({
+            "url": format!("{base}/commits/{sha}"),
+            "sha": sha,
+            "node_id": format!("C_{sha}"),
+            "html_url": format!("{}/commit/{sha}", html_url(base)),
+            "comments_url": format!("{base}/commits/{sha}/comments"),
+            "commit": {
+                "url": format!("{base}/git/commits/{sha}"),
+                "author": author,
+                "committer": author,
+                "message": message,
+                "comment_count": 0,
+                "tree": { "sha": tree, "url": format!("{base}/git/trees/{tree}") },
+            },
+            "author": null,
+            "committer": null,
+            "parents": parents.iter().map(|sha| json!({ "sha": sha })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+enum FileChangeKind {
+    Added(String),
+    Modified(String),
+    Removed,
+}
+
+fn diff(
+    from: &BTreeMap<String, String>,
+    to: &BTreeMap<String, String>,
+) -> Vec<(String, FileChangeKind)> {
+    let mut changes = Vec::new();
+    for (path, sha) in to {
+        match from.get(path) {
+            None => changes.push((path.clone(), FileChangeKind::Added(sha.clone()))),
+            Some(previous) if previous != sha => {
+                changes.push((path.clone(), FileChangeKind::Modified(sha.clone())))
+            }
+            _ => {}
+        }
+    }
+    for path in from.keys() {
+        if !to.contains_key(path) {
+            changes.push((path.clone(), FileChangeKind::Removed));
+        }
+    }
+    changes
+}
+
+fn not_found() -> (StatusCode, String) {
+    (StatusCode::NOT_FOUND, "Not Found".to_string())
+}
+
+fn parse(number: &str) -> Result<u64, (StatusCode, String)> {
+    number.parse().map_err(|_| not_found())
+}
+
+fn percent_decode(value: &str) -> String {
+    url::form_urlencoded::parse(format!("v={value}").as_bytes())
+        .next()
+        .map(|(_, value)| value.into_owned())
+        .unwrap_or_else(|| value.to_string())
+}
+
+fn state_matches(state: &str, open: bool) -> bool {
+    match state {
+        "all" => true,
+        "closed" => !open,
+        _ => open,
+    }
+}
+
+fn html_url(base: &str) -> String {
+    base.replace("https://api.github.com/repos", "https://github.com")
+}
+
+fn user_json(login: &str) -> Value {
+    let url = format!("{API_URL}/users/{login}");
+    json!({
+        "login": login,
+        "id": 1,
+        "node_id": format!("U_{login}"),
+        "avatar_url": format!("https://avatars.githubusercontent.com/{login}"),
+        "gravatar_id": "",
+        "url": url,
+        "html_url": format!("https://github.com/{login}"),
+        "followers_url": format!("{url}/followers"),
+        "following_url": format!("{url}/following"),
+        "gists_url": format!("{url}/gists"),
+        "starred_url": format!("{url}/starred"),
+        "subscriptions_url": format!("{url}/subscriptions"),
+        "organizations_url": format!("{url}/orgs"),
+        "repos_url": format!("{url}/repos"),
+        "events_url": format!("{url}/events"),
+        "received_events_url": format!("{url}/received_events"),
+        "type": "User",
+        "site_admin": false,
+    })
+}
+
+fn label_json(name: &str, base: &str) -> Value {
+    json!({
+        "id": content_sha(name).chars().take(8).fold(0u64, |id, c| id * 16 + c.to_digit(16).unwrap_or(0) as u64),
+        "node_id": format!("L_{name}"),
+        "url": format!("{base}/labels/{}", name.replace(' ', "%20")),
+        "name": name,
+        "description": null,
+        "color": "ededed",
+        "default": false,
+    })
+}
+
+fn repo_label_json(name: &str, label: &FakeLabel, base: &str) -> Value {
+    let mut json = label_json(name, base);
+    json["color"] = json!(label.color);
+    json["description"] = json!(label.description);
+    json
+}
+
+fn labels_json(labels: &[String], base: &str) -> Value {
+    Value::Array(labels.iter().map(|label| label_json(label, base)).collect())
+}
+
+fn issue_json(issue: &FakeIssue, base: &str) -> Value {
+    let url = format!("{base}/issues/{}", issue.number);
+    json!({
+        "id": issue.number,
+        "node_id": format!("I_{}", issue.number),
+        "url": url,
+        "repository_url": base,
+        "labels_url": format!("{url}/labels"),
+        "comments_url": format!("{url}/comments"),
+        "events_url": format!("{url}/events"),
+        "html_url": format!("{}/issues/{}", html_url(base), issue.number),
+        "number": issue.number,
+        "state": if issue.open { "open" } else { "closed" },
+        "state_reason": null,
+        "title": issue.title,
+        "body": issue.body,
+        "user": user_json(&issue.user),
+        "labels": labels_json(&issue.labels, base),
+        "assignees": [],
+        "author_association": "OWNER",
+        "locked": false,
+        "comments": issue.comments.len(),
+        "created_at": issue.created_at,
+        "updated_at": issue.updated_at,
+    })
+}
+
+fn comment_json(comment: &FakeComment, issue_number: u64, base: &str) -> Value {
+    json!({
+        "id": comment.id,
+        "node_id": format!("IC_{}", comment.id),
+        "url": format!("{base}/issues/comments/{}", comment.id),
+        "html_url": format!("{}/issues/{issue_number}#issuecomment-{}", html_url(base), comment.id),
+        "issue_url": format!("{base}/issues/{issue_number}"),
+        "body": comment.body,
+        "user": user_json(&comment.user),
+        "created_at": comment.created_at,
+        "updated_at": comment.updated_at,
+    })
+}
+
+fn ref_json(branch: &str, sha: &str, base: &str) -> Value {
+    json!({
+        "ref": format!("refs/heads/{branch}"),
+        "node_id": format!("REF_{branch}"),
+        "url": format!("{base}/git/refs/heads/{branch}"),
+        "object": {
+            "type": "commit",
+            "sha": sha,
+            "url": format!("{base}/git/commits/{sha}"),
+        },
+    })
+}
+
+fn content_json(path: &str, sha: &str, content: Option<&str>, base: &str) -> Value {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let url = format!("{base}/contents/{path}");
+    json!({
+        "name": name,
+        "path": path,
+        "sha": sha,
+        "encoding": content.map(|_| "base64"),
+        "content": content.map(::base64::encode),
+        "size": content.map_or(0, |content| content.len()),
+        "url": url,
+        "html_url": format!("{}/blob/main/{path}", html_url(base)),
+        "git_url": format!("{base}/git/blobs/{sha}"),
+        "download_url": format!("{}/main/{path}", html_url(base).replace("https://github.com", "https://raw.githubusercontent.com")),
+        "type": "file",
+        "_links": {
+            "git": format!("{base}/git/blobs/{sha}"),
+            "html": format!("{}/blob/main/{path}", html_url(base)),
+            "self": url,
+        },
+        "license": null,
+    })
+}
+
+fn dir_json(path: &str, base: &str) -> Value {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let url = format!("{base}/contents/{path}");
+    json!({
+        "name": name,
+        "path": path,
+        "sha": content_sha(path),
+        "encoding": null,
+        "content": null,
+        "size": 0,
+        "url": url,
+        "html_url": null,
+        "git_url": null,
+        "download_url": null,
+        "type": "dir",
+        "_links": { "git": null, "html": null, "self": url },
+        "license": null,
+    })
+}
+
+fn file_diff_json(path: &str, change: FileChangeKind, base: &str) -> Value {
+    let (status, sha, additions, deletions) = match change {
+        FileChangeKind::Added(sha) => ("added", sha, 1, 0),
+        FileChangeKind::Modified(sha) => ("modified", sha, 1, 1),
+        FileChangeKind::Removed => ("removed", String::new(), 0, 1),
+    };
+    json!({
+        "sha": sha,
+        "filename": path,
+        "status": status,
+        "additions": additions,
+        "deletions": deletions,
+        "changes": additions + deletions,
+        "blob_url": format!("{}/blob/{sha}/{path}", html_url(base)),
+        "raw_url": format!("{}/raw/{sha}/{path}", html_url(base)),
+        "contents_url": format!("{base}/contents/{path}"),
+        "patch": null,
+        "previous_filename": null,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::external_services::github::{
+        github_async_new, CreateMergeRequestData, CreateRefillMergeRequestData, GithubWrapper,
+    };
+
+    const OWNER: &str = "fake-owner";
+    const INSTALLATION_ID: u64 = 9001;
+
+    fn setup(repo: &str) -> (FakeGithub, GithubWrapper) {
+        let fake = FakeGithub::install();
+        fake.create_repo(OWNER, repo, INSTALLATION_ID);
+        let gh = GithubWrapper::new(
+            OWNER.to_string(),
+            repo.to_string(),
+            Some(INSTALLATION_ID as i64),
+        )
+        .unwrap();
+        (fake, gh)
+    }
+
+    fn merge_request(branch_name: &str, content: &str) -> CreateMergeRequestData {
+        CreateMergeRequestData {
+            issue_link: "https://github.com/fake-owner/issues/1".to_string(),
+            owner_name: "Client".to_string(),
+            file_content: content.to_string(),
+            file_name: "applications/f1test.json".to_string(),
+            branch_name: branch_name.to_string(),
+            commit: "Start Application".to_string(),
+            application_id: "f1test".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn issue_labels_and_comments() {
+        let (fake, gh) = setup("issues-repo");
+        let number = fake.create_issue(OWNER, "issues-repo", "Application", "body");
+
+        gh.add_comment_to_issue(number, "Hello").await.unwrap();
+        gh.update_issue_labels(number, &["validated"])
+            .await
+            .unwrap();
+        gh.update_issue_labels(number, &["granted"]).await.unwrap();
+
+        assert_eq!(fake.issue_comments(OWNER, "issues-repo", number), ["Hello"]);
+        assert!(gh.issue_has_label(number, "granted").await.unwrap());
+        assert_eq!(
+            gh.get_issue_reporter_handle(&number).await.unwrap(),
+            FAKE_USER
+        );
+    }
+
+    #[tokio::test]
+    async fn merge_request_is_committed_and_merged() {
+        let (fake, _) = setup("merge-repo");
+        let db = fplus_database::setup_test_environment().await;
+        let gh = github_async_new(&db, OWNER.to_string(), "merge-repo".to_string())
+            .await
+            .unwrap();
+
+        let (pr, file_sha) = gh
+            .create_merge_request(merge_request("Application/f1test", "{}"))
+            .await
+            .unwrap();
+        assert_eq!(file_sha, content_sha("{}"));
+        assert_eq!(
+            gh.get_pull_request_by_head("Application/f1test")
+                .await
+                .unwrap()
+                .first()
+                .map(|pr| pr.number),
+            Some(pr.number)
+        );
+        let (_, files) = gh.get_pull_request_files(pr.number).await.unwrap();
+        assert_eq!(files[0].filename, "applications/f1test.json");
+
+        gh.merge_pull_request_and_delete_branch(&pr.number)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fake.file(OWNER, "merge-repo", "main", "applications/f1test.json"),
+            Some("{}".to_string())
+        );
+        assert_eq!(fake.branches(OWNER, "merge-repo"), ["main"]);
+        let main = gh
+            .get_file("applications/f1test.json", "main")
+            .await
+            .unwrap();
+        assert_eq!(main.items[0].decoded_content().unwrap(), "{}");
+    }
+
+    #[tokio::test]
+    async fn refill_with_stale_sha_leaves_no_branch() {
+        let (fake, gh) = setup("refill-repo");
+        fake.put_file(
+            OWNER,
+            "refill-repo",
+            "main",
+            "applications/f1test.json",
+            "{}",
+        );
+
+        let result = gh
+            .create_refill_merge_request(CreateRefillMergeRequestData {
+                issue_link: "https://github.com/fake-owner/issues/1".to_string(),
+                file_content: r#"{"refill":true}"#.to_string(),
+                file_name: "applications/f1test.json".to_string(),
+                branch_name: "f1test-refill".to_string(),
+                commit: "Refill".to_string(),
+                file_sha: "stale".to_string(),
+                application_id: "f1test".to_string(),
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(fake.branches(OWNER, "refill-repo"), ["main"]);
+        assert!(fake.open_pull_requests(OWNER, "refill-repo").is_empty());
+    }
+}
@@ -0,0 +1,494 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use http::header::{ETAG, IF_NONE_MATCH, RETRY_AFTER};
+use http::{HeaderMap, Method, Request, Response, StatusCode};
+use hyper::body::Bytes;
+use hyper::Body;
+use once_cell::sync::Lazy;
+use rand::Rng;
+use serde::Serialize;
+use tower::{BoxError, Layer, Service};
+
+const MAX_RETRIES: u32 = 3;
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+// Waiting longer than this for a rate limit reset is worse than failing the request.
+const MAX_RESET_WAIT: Duration = Duration::from_secs(60);
+const MAX_CACHED_RESPONSES: usize = 2000;
+
+const RATE_LIMIT_LIMIT: &str = "x-ratelimit-limit";
+const RATE_LIMIT_REMAINING: &str = "x-ratelimit-remaining";
+const RATE_LIMIT_USED: &str = "x-ratelimit-used";
+const RATE_LIMIT_RESET: &str = "x-ratelimit-reset";
+const RATE_LIMIT_RESOURCE: &str = "x-ratelimit-resource";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallationRateLimit {
+    pub installation_id: Option<u64>,
+    pub resource: String,
+    pub limit: u64,
+    pub remaining: u64,
+    pub used: u64,
+    pub reset: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    etag: String,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+type RateLimitKey = (Option<u64>, String);
+type ResponseCacheKey = (Option<u64>, String);
+
+static RATE_LIMITS: Lazy<Mutex<HashMap<RateLimitKey, InstallationRateLimit>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static RESPONSE_CACHE: Lazy<Mutex<HashMap<ResponseCacheKey, CachedResponse>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Last rate limit reported by GitHub for every installation and resource
+/// seen by this process.
+pub fn rate_limit_status() -> Vec<InstallationRateLimit> {
+    let mut limits: Vec<InstallationRateLimit> = RATE_LIMITS
+        .lock()
+        .map(|limits| limits.values().cloned().collect())
+        .unwrap_or_default();
+    limits.sort_by(|a, b| {
+        a.installation_id
+            .cmp(&b.installation_id)
+            .then_with(|| a.resource.cmp(&b.resource))
+    });
+    limits
+}
+
+/// Tower layer for the octocrab service stack which tracks `X-RateLimit-*`
+/// headers, retries secondary rate limits and server errors with jittered
+/// backoff and revalidates repeated content reads with `If-None-Match`.
+/// Writes may have been applied when GitHub fails or the connection drops, so
+/// they are only retried when GitHub says when to retry a rate limited request.
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer {
+    installation_id: Option<u64>,
+}
+
+impl RateLimitLayer {
+    pub fn new(installation_id: Option<u64>) -> Self {
+        Self { installation_id }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            installation_id: self.installation_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    installation_id: Option<u64>,
+}
+
+impl<S> Service<Request<String>> for RateLimitService<S>
+where
+    S: Service<Request<String>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+{
+    type Response = Response<Body>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<String>) -> Self::Future {
+        // The clone may not be ready, so keep the one polled by `poll_ready` for this call.
+        let clone = self.inner.clone();
+        let inner = std::mem::replace(&mut self.inner, clone);
+        let installation_id = self.installation_id;
+        Box::pin(send_with_retries(inner, installation_id, request))
+    }
+}
+
+async fn send_with_retries<S>(
+    mut inner: S,
+    installation_id: Option<u64>,
+    request: Request<String>,
+) -> Result<Response<Body>, BoxError>
+where
+    S: Service<Request<String>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+{
+    let idempotent = is_idempotent(request.method());
+    let cache_key = cacheable_key(installation_id, &request);
+    let cached = cache_key.as_ref().and_then(|key| {
+        RESPONSE_CACHE
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(key).cloned())
+    });
+
+    let mut attempt = 0;
+    loop {
+        wait_for_exhausted_limit(installation_id).await;
+
+        let mut attempt_request = clone_request(&request);
+        if let Some(cached) = &cached {
+            if let Ok(etag) = cached.etag.parse() {
+                attempt_request.headers_mut().insert(IF_NONE_MATCH, etag);
+            }
+        }
+
+        let response = match call_inner(&mut inner, attempt_request).await {
+            Ok(response) => response,
+            Err(e) if idempotent && attempt < MAX_RETRIES => {
+                let delay = backoff(attempt);
+                log::warn!("GitHub request failed: {e}. Retrying in {delay:?}");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        record_rate_limit(installation_id, response.headers());
+        let status = response.status();
+
+        if status == StatusCode::NOT_MODIFIED {
+            if let Some(cached) = cached {
+                return Ok(cached_response(cached));
+            }
+            return Ok(response);
+        }
+
+        if status.is_server_error() {
+            if idempotent && attempt < MAX_RETRIES {
+                let delay = backoff(attempt);
+                log::warn!("GitHub responded with {status}. Retrying in {delay:?}");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+                continue;
+            }
+            return Ok(response);
+        }
+
+        if status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS {
+            let (parts, body) = response.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+            if attempt < MAX_RETRIES {
+                if let Some(delay) = rate_limit_delay(&parts.headers, &body, attempt, idempotent) {
+                    log::warn!("GitHub rate limit hit. Retrying in {delay:?}");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                    continue;
+                }
+            }
+            return Ok(Response::from_parts(parts, Body::from(body)));
+        }
+
+        if let (Some(key), true) = (cache_key, status == StatusCode::OK) {
+            if let Some(etag) = response
+                .headers()
+                .get(ETAG)
+                .and_then(|etag| etag.to_str().ok())
+                .map(|etag| etag.to_string())
+            {
+                let (parts, body) = response.into_parts();
+                let body = hyper::body::to_bytes(body).await?;
+                store_cached_response(
+                    key,
+                    CachedResponse {
+                        etag,
+                        headers: parts.headers.clone(),
+                        body: body.clone(),
+                    },
+                );
+                return Ok(Response::from_parts(parts, Body::from(body)));
+            }
+        }
+
+        return Ok(response);
+    }
+}
+
+async fn call_inner<S>(inner: &mut S, request: Request<String>) -> Result<Response<Body>, BoxError>
+where
+    S: Service<Request<String>, Response = Response<Body>>,
+    S::Error: Into<BoxError>,
+{
+    futures::future::poll_fn(|cx| inner.poll_ready(cx))
+        .await
+        .map_err(Into::into)?;
+    inner.call(request).await.map_err(Into::into)
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    method == Method::GET || method == Method::HEAD
+}
+
+fn clone_request(request: &Request<String>) -> Request<String> {
+    let mut cloned = Request::new(request.body().clone());
+    *cloned.method_mut() = request.method().clone();
+    *cloned.uri_mut() = request.uri().clone();
+    *cloned.version_mut() = request.version();
+    *cloned.headers_mut() = request.headers().clone();
+    cloned
+}
+
+/// Only repository content reads are revalidated, they are the bulk of the
+/// repeated requests and their bodies are small.
+fn cacheable_key(
+    installation_id: Option<u64>,
+    request: &Request<String>,
+) -> Option<ResponseCacheKey> {
+    if request.method() != Method::GET || !request.uri().path().contains("/contents") {
+        return None;
+    }
+    Some((installation_id, request.uri().to_string()))
+}
+
+fn cached_response(cached: CachedResponse) -> Response<Body> {
+    let mut response = Response::new(Body::from(cached.body));
+    *response.headers_mut() = cached.headers;
+    response
+}
+
+fn store_cached_response(key: ResponseCacheKey, response: CachedResponse) {
+    if let Ok(mut cache) = RESPONSE_CACHE.lock() {
+        if cache.len() >= MAX_CACHED_RESPONSES && !cache.contains_key(&key) {
+            cache.clear();
+        }
+        cache.insert(key, response);
+    }
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.parse().ok()
+}
+
+fn record_rate_limit(installation_id: Option<u64>, headers: &HeaderMap) {
+    let (Some(limit), Some(remaining), Some(reset)) = (
+        header_u64(headers, RATE_LIMIT_LIMIT),
+        header_u64(headers, RATE_LIMIT_REMAINING),
+        header_u64(headers, RATE_LIMIT_RESET),
+    ) else {
+        return;
+    };
+    let resource = headers
+        .get(RATE_LIMIT_RESOURCE)
+        .and_then(|resource| resource.to_str().ok())
+        .unwrap_or("core")
+        .to_string();
+    let reset = Utc
+        .timestamp_opt(reset as i64, 0)
+        .single()
+        .unwrap_or_else(Utc::now);
+
+    if let Ok(mut limits) = RATE_LIMITS.lock() {
+        limits.insert(
+            (installation_id, resource.clone()),
+            InstallationRateLimit {
+                installation_id,
+                resource,
+                limit,
+                remaining,
+                used: header_u64(headers, RATE_LIMIT_USED)
+                    .unwrap_or(limit.saturating_sub(remaining)),
+                reset,
+                updated_at: Utc::now(),
+            },
+        );
+    }
+}
+
+/// Holds requests back while the core quota of the installation is used up,
+/// as long as the reset is close enough to be worth waiting for.
+async fn wait_for_exhausted_limit(installation_id: Option<u64>) {
+    let reset = RATE_LIMITS.lock().ok().and_then(|limits| {
+        limits
+            .get(&(installation_id, "core".to_string()))
+            .filter(|limit| limit.remaining == 0)
+            .map(|limit| limit.reset)
+    });
+    if let Some(wait) = reset.and_then(|reset| (reset - Utc::now()).to_std().ok()) {
+        if wait <= MAX_RESET_WAIT {
+            log::warn!(
+                "GitHub quota exhausted for installation {installation_id:?}. Waiting {wait:?}"
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Returns how long to wait before retrying a 403/429 response, or `None`
+/// when the response is not caused by a rate limit.
+/// Writes are only retried when GitHub names the wait, with `Retry-After` or an
+/// exhausted limit's `X-RateLimit-Reset`.
+fn rate_limit_delay(
+    headers: &HeaderMap,
+    body: &Bytes,
+    attempt: u32,
+    idempotent: bool,
+) -> Option<Duration> {
+    if let Some(retry_after) = header_u64(headers, RETRY_AFTER.as_str()) {
+        return Some(Duration::from_secs(retry_after).min(MAX_RESET_WAIT));
+    }
+    if header_u64(headers, RATE_LIMIT_REMAINING) == Some(0) {
+        let reset = header_u64(headers, RATE_LIMIT_RESET)?;
+        let wait = Duration::from_secs(reset.saturating_sub(Utc::now().timestamp() as u64));
+        return (wait <= MAX_RESET_WAIT).then_some(wait + backoff(0));
+    }
+    if !idempotent {
+        return None;
+    }
+    let body = String::from_utf8_lossy(body).to_lowercase();
+    body.contains("secondary rate limit")
+        .then(|| backoff(attempt + 1))
+}
+
+fn backoff(attempt: u32) -> Duration {
+    let exponential = BASE_BACKOFF.saturating_mul(2u32.saturating_pow(attempt));
+    let jitter = rand::thread_rng().gen_range(0..=exponential.as_millis() as u64);
+    (exponential + Duration::from_millis(jitter)).min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        for attempt in 0..MAX_RETRIES {
+            let delay = backoff(attempt);
+            let base = BASE_BACKOFF * 2u32.pow(attempt);
+            assert!(delay >= base && delay <= base * 2);
+        }
+        assert_eq!(backoff(20), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn secondary_rate_limit_is_retried() {
+        let body = Bytes::from(r#"{"message":"You have exceeded a secondary rate limit."}"#);
+        assert!(rate_limit_delay(&HeaderMap::new(), &body, 0, true).is_some());
+
+        let body = Bytes::from(r#"{"message":"Resource not accessible by integration"}"#);
+        assert!(rate_limit_delay(&HeaderMap::new(), &body, 0, true).is_none());
+    }
+
+    #[test]
+    fn writes_are_retried_only_when_github_names_the_wait() {
+        let body = Bytes::from(r#"{"message":"You have exceeded a secondary rate limit."}"#);
+        assert!(rate_limit_delay(&HeaderMap::new(), &body, 0, false).is_none());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RATE_LIMIT_REMAINING, "0".parse().unwrap());
+        headers.insert(
+            RATE_LIMIT_RESET,
+            Utc::now().timestamp().to_string().parse().unwrap(),
+        );
+        assert!(rate_limit_delay(&headers, &Bytes::new(), 0, false).is_some());
+    }
+
+    async fn calls_for(method: Method, statuses: Vec<u16>) -> (usize, StatusCode) {
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counted = calls.clone();
+        let service = tower::service_fn(move |_: Request<String>| {
+            let call = counted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let status = statuses[call.min(statuses.len() - 1)];
+            async move {
+                Response::builder()
+                    .status(status)
+                    .header(RETRY_AFTER, "0")
+                    .body(Body::empty())
+                    .map_err(BoxError::from)
+            }
+        });
+        let request = Request::builder()
+            .method(method)
+            .uri("https://api.github.com/repos/o/r/pulls")
+            .body(String::new())
+            .unwrap();
+        let response = send_with_retries(service, Some(737373), request)
+            .await
+            .unwrap();
+        (
+            calls.load(std::sync::atomic::Ordering::SeqCst),
+            response.status(),
+        )
+    }
+
+    #[tokio::test]
+    async fn failed_writes_are_not_repeated() {
+        assert_eq!(
+            calls_for(Method::POST, vec![502, 201]).await,
+            (1, StatusCode::BAD_GATEWAY)
+        );
+        assert_eq!(
+            calls_for(Method::PUT, vec![429, 201]).await,
+            (2, StatusCode::CREATED)
+        );
+    }
+
+    #[test]
+    fn retry_after_header_takes_precedence() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(
+            rate_limit_delay(&headers, &Bytes::new(), 0, false),
+            Some(Duration::from_secs(7))
+        );
+    }
+
+    #[test]
+    fn rate_limit_headers_are_recorded() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RATE_LIMIT_LIMIT, "5000".parse().unwrap());
+        headers.insert(RATE_LIMIT_REMAINING, "4990".parse().unwrap());
+        headers.insert(RATE_LIMIT_USED, "10".parse().unwrap());
+        headers.insert(RATE_LIMIT_RESET, "1700000000".parse().unwrap());
+        record_rate_limit(Some(424242), &headers);
+
+        let status = rate_limit_status();
+        let limit = status
+            .iter()
+            .find(|limit| limit.installation_id == Some(424242))
+            .unwrap();
+        assert_eq!(limit.resource, "core");
+        assert_eq!(limit.remaining, 4990);
+        assert_eq!(limit.used, 10);
+    }
+
+    #[test]
+    fn only_content_reads_are_cached() {
+        let request = Request::get("https://api.github.com/repos/o/r/contents/applications")
+            .body(String::new())
+            .unwrap();
+        assert!(cacheable_key(Some(1), &request).is_some());
+
+        let request = Request::put("https://api.github.com/repos/o/r/contents/applications/a.json")
+            .body(String::new())
+            .unwrap();
+        assert!(cacheable_key(Some(1), &request).is_none());
+
+        let request = Request::get("https://api.github.com/repos/o/r/pulls")
+            .body(String::new())
+            .unwrap();
+        assert!(cacheable_key(Some(1), &request).is_none());
+    }
+}
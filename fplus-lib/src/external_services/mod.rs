@@ -2,4 +2,7 @@ pub mod blockchain;
 pub mod dmob;
 pub mod filecoin;
 pub mod github;
+#[cfg(test)]
+pub mod github_fake;
+pub mod github_rate_limit;
 pub mod similarity_detection;
@@ -2,6 +2,7 @@ use super::github::github_async_new;
 use crate::{
     config::get_env_var_or_default, core::application::file::ApplicationFile, error::LDNError,
 };
+use fplus_database::DbPool;
 use fplus_database::{
     database::{
         applications::get_distinct_applications_by_clients_addresses,
@@ -30,13 +31,14 @@ type RepoSimilarities = HashMap<(Owner, Repo), Vec<(ClientAddress, Similarities)
 type SortedRepoSimilarities = Vec<((Owner, Repo), Vec<(ClientAddress, Similarities)>)>;
 
 pub async fn detect_similar_applications(
+    db: &DbPool,
     client_address: &str,
     comparable_data: &ApplicationComparableData,
     owner: &str,
     repo: &str,
     issue_number: &u64,
 ) -> Result<(), LDNError> {
-    let comparable_applications = get_comparable_applications().await.map_err(|e| {
+    let comparable_applications = get_comparable_applications(db).await.map_err(|e| {
         LDNError::New(format!(
             "Failed to get comparable applications from database: {e}"
         ))
@@ -97,18 +99,14 @@ pub async fn detect_similar_applications(
     let unique_addresses: HashSet<String> = similar_project_desciptions
         .clone()
         .into_iter()
-        .chain(similar_stored_data_desciptions.clone().into_iter())
-        .chain(
-            similar_project_and_stored_data_desciptions
-                .clone()
-                .into_iter(),
-        )
-        .chain(similar_data_set_sample.clone().into_iter())
-        .chain(existing_data_owner_name.clone().into_iter())
+        .chain(similar_stored_data_desciptions.clone())
+        .chain(similar_project_and_stored_data_desciptions.clone())
+        .chain(similar_data_set_sample.clone())
+        .chain(existing_data_owner_name.clone())
         .collect();
 
     let unique_addresses: Vec<String> = unique_addresses.into_iter().collect();
-    let gh = github_async_new(owner.to_string(), repo.to_string()).await?;
+    let gh = github_async_new(db, owner.to_string(), repo.to_string()).await?;
 
     if unique_addresses.is_empty() {
         let comment = "## Similarity Report\n\nNo similar applications found for the issue";
@@ -118,7 +116,7 @@ pub async fn detect_similar_applications(
         return Ok(());
     }
 
-    let applications = get_distinct_applications_by_clients_addresses(unique_addresses)
+    let applications = get_distinct_applications_by_clients_addresses(db, unique_addresses)
         .await
         .map_err(|e| LDNError::New(format!("Failed to get applications from database: {e}")))?;
 
@@ -5,6 +5,16 @@ use serde_json::Value;
 pub struct VerifiedClientResponse {
     #[serde(deserialize_with = "number_to_string")]
     pub count: Option<String>,
+    #[serde(default)]
+    pub data: Vec<VerifiedClient>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifiedClient {
+    /// ID address of the allocator that verified the client.
+    #[serde(default)]
+    pub verifier_address_id: Option<String>,
 }
 
 fn number_to_string<'de, D>(de: D) -> Result<Option<String>, D::Error>
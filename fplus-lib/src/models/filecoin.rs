@@ -3,6 +3,9 @@ use serde::{Deserialize, Serialize};
 pub type StateReadStateResponse = JSONRPCResponse<StateReadStateResult>;
 pub type StateVerifierStatusResponse = JSONRPCResponse<StateVerifierStatusResult>;
 pub type StateVerifiedClientStatusResponse = JSONRPCResponse<StateVerifiedClientStatusResult>;
+/// `null` for addresses which are not verified clients.
+pub type OptionalVerifiedClientStatusResponse = JSONRPCResponse<Option<String>>;
+pub type StateLookupIdResponse = JSONRPCResponse<String>;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JSONRPCResponse<T> {
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use markdown::{mdast::Node, to_mdast, ParseOptions};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::application::file::{Client, Datacap, Project, Version},
+    error::LDNError,
+};
+
+pub mod templates;
+pub mod validation;
+
+use self::templates::{
+    detect_template_version, issue_template, strip_version_marker, IssueTemplate, TemplateFields,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParsedApplicationDataFields {
+    Version,
+    Address,
+    // Client Info
+    Name,
+    Region,
+    Industry,
+    Website,
+    SocialMedia,
+    SocialMediaType,
+    Role,
+    // Project Info
+    ProjectID,
+    ProjectBriefHistory,
+    AssociatedProjects,
+    DataDesc,
+    DataSrc,
+    DataPrepare,
+    DataSampleLink,
+    ConfirmPublicDataset,
+    RetrivalFreq,
+    DataLifeSpan,
+    DataGeographies,
+    DataDistribution,
+    ProviderIDs,
+    FilplusGuideline,
+    // Datacap Info
+    DatacapGroup,
+    Type,
+    TotalRequestedAmount,
+    SingleSizeDataset,
+    Replicas,
+    WeeklyAllocation,
+    CustomMultisig,
+    Identifier,
+    InvalidField,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ParsedIssue {
+    pub version: Version,
+    pub id: String,
+    pub client: Client,
+    pub project: Project,
+    pub datacap: Datacap,
+}
+
+impl ParsedIssue {
+    pub fn from_issue_body(body: &str) -> Result<Self, LDNError> {
+        Self::from_issue(body, &[])
+    }
+
+    /// Parses the issue with the template version detected from its body or labels.
+    pub fn from_issue(body: &str, labels: &[String]) -> Result<Self, LDNError> {
+        let (template, fields) = template_fields(body, labels)?;
+        let client = template.client(&fields);
+        let project = template.project(&fields);
+        let datacap = template.datacap(&fields)?;
+        let id = fields
+            .get(ParsedApplicationDataFields::Address)
+            .ok_or(LDNError::Load(
+                "Failed to get on-chain address for first allocation.".to_string(),
+            ))?
+            .to_string();
+
+        Ok(Self {
+            id,
+            version: Version::Text(template.version().to_string()),
+            client,
+            project,
+            datacap,
+        })
+    }
+}
+
+/// Template of the issue and the raw values of the headings it recognizes.
+pub fn template_fields(
+    body: &str,
+    labels: &[String],
+) -> Result<(Arc<dyn IssueTemplate>, TemplateFields), LDNError> {
+    let version = detect_template_version(body, labels);
+    let template = issue_template(&version)?;
+    let body = strip_version_marker(body);
+
+    let tree: Node = to_mdast(&body, &ParseOptions::default())
+        .map_err(|e| LDNError::Load(format!("Failed to get node: {e}")))?;
+    let mut fields = TemplateFields::default();
+    let children = tree.children().ok_or(LDNError::Load(
+        "Failed to get children from node.".to_string(),
+    ))?;
+    let child_iter = children.iter();
+
+    for chunk in child_iter.collect::<Vec<_>>().chunks_exact(2) {
+        if let (Some(prop_node), Some(value_node)) = (chunk.first(), chunk.get(1)) {
+            match template.field(&prop_node.to_string()) {
+                ParsedApplicationDataFields::InvalidField => {
+                    continue;
+                }
+                field => fields.0.push((field, value_node.to_string())),
+            }
+        }
+    }
+    Ok((template, fields))
+}
+
+// #[cfg(test)]
+// mod tests {
+//     use crate::external_services::github::github_async_new;
+
+//     static OWNER: &str = "keyko-io";
+//     static REPO: &str = "test-philip-second";
+
+//     #[tokio::test]
+//     async fn test_parser() {
+//         let _ = fplus_database::setup().await;
+//         let gh = github_async_new(OWNER.to_string(), REPO.to_string()).await;
+//         let issue = gh.list_issue(37).await.unwrap();
+//         let parsed_ldn = super::ParsedIssue::from_issue_body(&issue.body.unwrap());
+//         dbg!(&parsed_ldn);
+
+//         assert_eq!(parsed_ldn.version, 1);
+//         assert!(!parsed_ldn.id.is_empty());
+
+//         assert!(!parsed_ldn.client.name.is_empty());
+//         assert!(!parsed_ldn.client.industry.is_empty());
+//         assert!(!parsed_ldn.client.region.is_empty());
+//         assert!(!parsed_ldn.client.website.is_empty());
+//         assert!(!parsed_ldn.client.social_media.is_empty());
+//         assert!(!parsed_ldn.client.social_media_type.is_empty());
+//         assert!(!parsed_ldn.client.role.is_empty());
+//         assert!(!parsed_ldn.project.history.is_empty());
+//         assert!(!parsed_ldn.project.associated_projects.is_empty());
+
+//         assert!(!parsed_ldn.datacap.total_requested_amount.is_empty());
+//     }
+// }
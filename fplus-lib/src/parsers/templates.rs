@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::ParsedApplicationDataFields;
+use crate::{
+    config::get_env_var_or_default,
+    core::application::file::{Client, DataType, Datacap, DatacapGroup, Project},
+    error::LDNError,
+};
+
+/// Values of an issue body keyed by the field their heading maps to.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateFields(pub Vec<(ParsedApplicationDataFields, String)>);
+
+impl TemplateFields {
+    pub fn get(&self, field: ParsedApplicationDataFields) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(f, _)| *f == field)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Version of the application issue template.
+///
+/// Every version owns the mapping of its headings to fields and the
+/// conversion of those fields into the application file. Versions only need
+/// to override the conversions that differ from the defaults.
+pub trait IssueTemplate: Send + Sync {
+    fn version(&self) -> &'static str;
+
+    fn field(&self, heading: &str) -> ParsedApplicationDataFields;
+
+    fn client(&self, fields: &TemplateFields) -> Client {
+        let mut client = Client::default();
+        for (field, value) in fields.0.iter().cloned() {
+            match field {
+                ParsedApplicationDataFields::Name => client.name = value,
+                ParsedApplicationDataFields::Region => client.region = value,
+                ParsedApplicationDataFields::Industry => client.industry = value,
+                ParsedApplicationDataFields::Website => client.website = value,
+                ParsedApplicationDataFields::SocialMedia => client.social_media = value,
+                ParsedApplicationDataFields::SocialMediaType => client.social_media_type = value,
+                ParsedApplicationDataFields::Role => client.role = value,
+                _ => {}
+            }
+        }
+        client
+    }
+
+    fn project(&self, fields: &TemplateFields) -> Project {
+        let mut project = Project::default();
+        for (field, value) in fields.0.iter().cloned() {
+            match field {
+                ParsedApplicationDataFields::ProjectBriefHistory => project.history = value,
+                ParsedApplicationDataFields::AssociatedProjects => {
+                    project.associated_projects = value
+                }
+                ParsedApplicationDataFields::DataDesc => project.stored_data_desc = value,
+                ParsedApplicationDataFields::DataSrc => project.previous_stoarge = value,
+                ParsedApplicationDataFields::DataPrepare => project.dataset_prepare = value,
+                ParsedApplicationDataFields::DataSampleLink => project.data_sample_link = value,
+                ParsedApplicationDataFields::ConfirmPublicDataset => project.public_dataset = value,
+                ParsedApplicationDataFields::RetrivalFreq => project.retrival_frequency = value,
+                ParsedApplicationDataFields::DataLifeSpan => project.dataset_life_span = value,
+                ParsedApplicationDataFields::DataGeographies => project.geographis = value,
+                ParsedApplicationDataFields::DataDistribution => project.distribution = value,
+                ParsedApplicationDataFields::ProviderIDs => project.providers = value,
+                ParsedApplicationDataFields::FilplusGuideline => project.filplus_guideline = value,
+                _ => {}
+            }
+        }
+        project
+    }
+
+    fn datacap(&self, fields: &TemplateFields) -> Result<Datacap, LDNError> {
+        let mut datacap = Datacap::default();
+        for (field, value) in fields.0.iter().cloned() {
+            match field {
+                ParsedApplicationDataFields::DatacapGroup => {
+                    datacap._group = DatacapGroup::from_str(&value)
+                        .map_err(|e| LDNError::Load(format!("Failed to get DataCap group: {e}")))?;
+                }
+                ParsedApplicationDataFields::Type => {
+                    datacap.data_type = DataType::from_str(&value)
+                        .map_err(|e| LDNError::Load(format!("Failed to get DataCap type: {e}")))?;
+                }
+                ParsedApplicationDataFields::TotalRequestedAmount => {
+                    datacap.total_requested_amount = value;
+                }
+                ParsedApplicationDataFields::SingleSizeDataset => {
+                    datacap.single_size_dataset = value;
+                }
+                ParsedApplicationDataFields::Replicas => {
+                    datacap.replicas = value.parse::<u8>().map_err(|e| {
+                        LDNError::Load(format!("Failed to parse replicas to u8: {e}"))
+                    })?;
+                }
+                ParsedApplicationDataFields::WeeklyAllocation => {
+                    datacap.weekly_allocation = value;
+                }
+                ParsedApplicationDataFields::CustomMultisig => datacap.custom_multisig = value,
+                ParsedApplicationDataFields::Identifier => datacap.identifier = value,
+                _ => {}
+            }
+        }
+        log::info!("Datacap: {:?}", datacap);
+
+        Ok(datacap)
+    }
+}
+
+pub struct TemplateV1_3;
+
+impl IssueTemplate for TemplateV1_3 {
+    fn version(&self) -> &'static str {
+        "1.3"
+    }
+
+    fn field(&self, heading: &str) -> ParsedApplicationDataFields {
+        match heading {
+	  "On-chain address for first allocation" => ParsedApplicationDataFields::Address,
+	  // Client Info
+	  "Data Owner Name" => ParsedApplicationDataFields::Name,
+	  "Data Owner Country/Region" => ParsedApplicationDataFields::Region,
+	  "Data Owner Industry" => ParsedApplicationDataFields::Industry,
+	  "Dataset Name" => ParsedApplicationDataFields::Name,
+	  "Dataset Country/Region" => ParsedApplicationDataFields::Region,
+	  "Dataset Industry" => ParsedApplicationDataFields::Industry,
+	  "Website" => ParsedApplicationDataFields::Website,
+	  "Social Media Handle" => ParsedApplicationDataFields::SocialMedia,
+	  "Social Media Type" => ParsedApplicationDataFields::SocialMediaType,
+	  "What is your role related to the dataset" => ParsedApplicationDataFields::Role,
+	  // Project Info
+      "Share a brief history of your project and organization" => {
+		ParsedApplicationDataFields::ProjectBriefHistory
+	  }
+	  "Is this project associated with other projects/ecosystem stakeholders?" => {
+		ParsedApplicationDataFields::AssociatedProjects
+	  }
+	  "Describe the data being stored onto Filecoin" => {
+		ParsedApplicationDataFields::DataDesc
+	  },
+	  "Where was the data currently stored in this dataset sourced from"=> {
+		ParsedApplicationDataFields::DataSrc
+	  },
+	  "If you are a data preparer, how will the data be prepared? Please include tooling used and technical details?" => {
+		ParsedApplicationDataFields::DataPrepare
+	  },
+	  "Please share a sample of the data" => {
+		ParsedApplicationDataFields::DataSampleLink
+	  },
+	  "Confirm that this is a public dataset that can be retrieved by anyone on the Network" => {
+		ParsedApplicationDataFields::ConfirmPublicDataset
+	  },
+	  "What is the expected retrieval frequency for this data" => {
+		ParsedApplicationDataFields::RetrivalFreq
+	  },
+	  "For how long do you plan to keep this dataset stored on Filecoin" => {
+		ParsedApplicationDataFields::DataLifeSpan
+	  },
+	  "In which geographies do you plan on making storage deals" => {
+		ParsedApplicationDataFields::DataGeographies
+	  },
+	  "How will you be distributing your data to storage providers" => {
+		ParsedApplicationDataFields::DataDistribution
+	  },
+	  "Please list the provider IDs and location of the storage providers you will be working with." => {
+		ParsedApplicationDataFields::ProviderIDs
+	  },
+      "Can you confirm that you will follow the Fil+ guideline" => {
+		ParsedApplicationDataFields::FilplusGuideline
+	  },
+	  // Datacap info
+	  "Group" => ParsedApplicationDataFields::DatacapGroup,
+	  "Type" => ParsedApplicationDataFields::Type,
+	  "Total amount of DataCap being requested" => ParsedApplicationDataFields::TotalRequestedAmount,
+	  "Expected size of single dataset (one copy)" => ParsedApplicationDataFields::SingleSizeDataset,
+	  "Number of replicas to store" => ParsedApplicationDataFields::Replicas,
+	  "Weekly allocation of DataCap requested" => ParsedApplicationDataFields::WeeklyAllocation,
+	  "Custom multisig" => ParsedApplicationDataFields::CustomMultisig,
+	  "Identifier" => ParsedApplicationDataFields::Identifier,
+	  // Invalid field
+	  _ => ParsedApplicationDataFields::InvalidField,
+	}
+    }
+}
+
+static TEMPLATES: Lazy<RwLock<HashMap<String, Arc<dyn IssueTemplate>>>> = Lazy::new(|| {
+    let mut templates: HashMap<String, Arc<dyn IssueTemplate>> = HashMap::new();
+    templates.insert(TemplateV1_3.version().to_string(), Arc::new(TemplateV1_3));
+    RwLock::new(templates)
+});
+
+/// Hidden marker allocators put in their issue templates,
+/// e.g. `<!-- template-version: 1.3 -->`.
+static BODY_VERSION_MARKER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)<!--\s*template[ _-]version:\s*v?([0-9][0-9.]*)\s*-->").unwrap());
+
+/// Labels set by issue forms, which cannot add hidden content to the body,
+/// e.g. `template:1.3` or `template-version: 1.3`.
+static LABEL_VERSION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)^template(?:[ _-]version)?\s*[:/]\s*v?([0-9][0-9.]*)$").unwrap());
+
+/// Makes a template version available to the parser, replacing an already
+/// registered template with the same version.
+pub fn register_issue_template(template: Arc<dyn IssueTemplate>) {
+    if let Ok(mut templates) = TEMPLATES.write() {
+        templates.insert(template.version().to_string(), template);
+    }
+}
+
+pub fn issue_template(version: &str) -> Result<Arc<dyn IssueTemplate>, LDNError> {
+    TEMPLATES
+        .read()
+        .map_err(|e| LDNError::Load(format!("Issue template registry is poisoned: {e}")))?
+        .get(version)
+        .cloned()
+        .ok_or(LDNError::Load(format!(
+            "Unsupported issue template version: {version}"
+        )))
+}
+
+/// Template version of an issue: the marker in the body, otherwise a version
+/// label, otherwise the `ISSUE_TEMPLATE_VERSION` default.
+pub fn detect_template_version(body: &str, labels: &[String]) -> String {
+    BODY_VERSION_MARKER
+        .captures(body)
+        .or_else(|| {
+            labels
+                .iter()
+                .find_map(|label| LABEL_VERSION.captures(label.trim()))
+        })
+        .map(|captures| captures[1].trim_end_matches('.').to_string())
+        .unwrap_or_else(|| get_env_var_or_default("ISSUE_TEMPLATE_VERSION"))
+}
+
+/// Removes the version marker so it is not mistaken for a heading or value.
+pub fn strip_version_marker(body: &str) -> String {
+    BODY_VERSION_MARKER.replace_all(body, "").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_is_detected_from_body_then_labels() {
+        let labels = vec!["bug".to_string(), "Template: 1.4".to_string()];
+        assert_eq!(
+            detect_template_version("<!-- template-version: 2.0 -->\n### Website", &labels),
+            "2.0"
+        );
+        assert_eq!(detect_template_version("### Website", &labels), "1.4");
+        assert_eq!(detect_template_version("### Website", &[]), "1.3");
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        assert!(issue_template("1.3").is_ok());
+        assert!(issue_template("0.1").is_err());
+    }
+}
@@ -0,0 +1,646 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use fvm_shared::address::Network;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+use super::{templates::TemplateFields, ParsedApplicationDataFields};
+use crate::{
+    config::get_env_var_or_default,
+    core::application::file::{DataType, DatacapGroup},
+    error::LDNError,
+    helpers::{parse_size_to_bytes, process_amount},
+};
+
+/// Value GitHub issue forms put under headings left empty.
+const NO_RESPONSE: &str = "_No response_";
+
+const REQUIRED_FIELDS: [ParsedApplicationDataFields; 6] = [
+    ParsedApplicationDataFields::Address,
+    ParsedApplicationDataFields::Name,
+    ParsedApplicationDataFields::Region,
+    ParsedApplicationDataFields::TotalRequestedAmount,
+    ParsedApplicationDataFields::WeeklyAllocation,
+    ParsedApplicationDataFields::Replicas,
+];
+
+/// Storage provider IDs as clients write them in the template's free text,
+/// e.g. `f01234`, `F0 1234` or `t0-1234`.
+static PROVIDER_ID: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\b[ft]0[\s-]?\d+\b").unwrap());
+
+/// Options of the "Data Owner Country/Region" dropdown of the issue template,
+/// used when `KNOWN_REGIONS` is not set.
+const TEMPLATE_REGIONS: &[&str] = &[
+    "Afghanistan",
+    "Åland Islands",
+    "Albania",
+    "Algeria",
+    "American Samoa",
+    "Andorra",
+    "Angola",
+    "Anguilla",
+    "Antarctica",
+    "Antigua and Barbuda",
+    "Argentina",
+    "Armenia",
+    "Aruba",
+    "Australia",
+    "Austria",
+    "Azerbaijan",
+    "Bahamas",
+    "Bahrain",
+    "Bangladesh",
+    "Barbados",
+    "Belarus",
+    "Belgium",
+    "Belize",
+    "Benin",
+    "Bermuda",
+    "Bhutan",
+    "Bolivia",
+    "Bonaire, Sint Eustatius and Saba",
+    "Bosnia and Herzegovina",
+    "Botswana",
+    "Bouvet Island",
+    "Brazil",
+    "British Indian Ocean Territory",
+    "Brunei Darussalam",
+    "Bulgaria",
+    "Burkina Faso",
+    "Burundi",
+    "Cabo Verde",
+    "Cambodia",
+    "Cameroon",
+    "Canada",
+    "Cayman Islands",
+    "Central African Republic",
+    "Chad",
+    "Chile",
+    "China",
+    "Christmas Island",
+    "Cocos (Keeling) Islands",
+    "Colombia",
+    "Comoros",
+    "Congo",
+    "Congo, Democratic Republic of the",
+    "Cook Islands",
+    "Costa Rica",
+    "Côte d'Ivoire",
+    "Croatia",
+    "Cuba",
+    "Curaçao",
+    "Cyprus",
+    "Czechia",
+    "Denmark",
+    "Djibouti",
+    "Dominica",
+    "Dominican Republic",
+    "Ecuador",
+    "Egypt",
+    "El Salvador",
+    "Equatorial Guinea",
+    "Eritrea",
+    "Estonia",
+    "Eswatini",
+    "Ethiopia",
+    "Falkland Islands (Malvinas)",
+    "Faroe Islands",
+    "Fiji",
+    "Finland",
+    "France",
+    "French Guiana",
+    "French Polynesia",
+    "French Southern Territories",
+    "Gabon",
+    "Gambia",
+    "Georgia",
+    "Germany",
+    "Ghana",
+    "Gibraltar",
+    "Greece",
+    "Greenland",
+    "Grenada",
+    "Guadeloupe",
+    "Guam",
+    "Guatemala",
+    "Guernsey",
+    "Guinea",
+    "Guinea-Bissau",
+    "Guyana",
+    "Haiti",
+    "Heard Island and McDonald Islands",
+    "Holy See",
+    "Honduras",
+    "Hong Kong",
+    "Hungary",
+    "Iceland",
+    "India",
+    "Indonesia",
+    "Iran",
+    "Iraq",
+    "Ireland",
+    "Isle of Man",
+    "Israel",
+    "Italy",
+    "Jamaica",
+    "Japan",
+    "Jersey",
+    "Jordan",
+    "Kazakhstan",
+    "Kenya",
+    "Kiribati",
+    "Korea, Democratic People's Republic of",
+    "Korea, Republic of",
+    "Kuwait",
+    "Kyrgyzstan",
+    "Lao People's Democratic Republic",
+    "Latvia",
+    "Lebanon",
+    "Lesotho",
+    "Liberia",
+    "Libya",
+    "Liechtenstein",
+    "Lithuania",
+    "Luxembourg",
+    "Macao",
+    "Madagascar",
+    "Malawi",
+    "Malaysia",
+    "Maldives",
+    "Mali",
+    "Malta",
+    "Marshall Islands",
+    "Martinique",
+    "Mauritania",
+    "Mauritius",
+    "Mayotte",
+    "Mexico",
+    "Micronesia",
+    "Moldova",
+    "Monaco",
+    "Mongolia",
+    "Montenegro",
+    "Montserrat",
+    "Morocco",
+    "Mozambique",
+    "Myanmar",
+    "Namibia",
+    "Nauru",
+    "Nepal",
+    "Netherlands",
+    "New Caledonia",
+    "New Zealand",
+    "Nicaragua",
+    "Niger",
+    "Nigeria",
+    "Niue",
+    "Norfolk Island",
+    "North Macedonia",
+    "Northern Mariana Islands",
+    "Norway",
+    "Oman",
+    "Pakistan",
+    "Palau",
+    "Palestine, State of",
+    "Panama",
+    "Papua New Guinea",
+    "Paraguay",
+    "Peru",
+    "Philippines",
+    "Pitcairn",
+    "Poland",
+    "Portugal",
+    "Puerto Rico",
+    "Qatar",
+    "Réunion",
+    "Romania",
+    "Russian Federation",
+    "Rwanda",
+    "Saint Barthélemy",
+    "Saint Helena, Ascension and Tristan da Cunha",
+    "Saint Kitts and Nevis",
+    "Saint Lucia",
+    "Saint Martin (French part)",
+    "Saint Pierre and Miquelon",
+    "Saint Vincent and the Grenadines",
+    "Samoa",
+    "San Marino",
+    "Sao Tome and Principe",
+    "Saudi Arabia",
+    "Senegal",
+    "Serbia",
+    "Seychelles",
+    "Sierra Leone",
+    "Singapore",
+    "Sint Maarten (Dutch part)",
+    "Slovakia",
+    "Slovenia",
+    "Solomon Islands",
+    "Somalia",
+    "South Africa",
+    "South Georgia and the South Sandwich Islands",
+    "South Sudan",
+    "Spain",
+    "Sri Lanka",
+    "Sudan",
+    "Suriname",
+    "Svalbard and Jan Mayen",
+    "Sweden",
+    "Switzerland",
+    "Syrian Arab Republic",
+    "Taiwan",
+    "Tajikistan",
+    "Tanzania, United Republic of",
+    "Thailand",
+    "Timor-Leste",
+    "Togo",
+    "Tokelau",
+    "Tonga",
+    "Trinidad and Tobago",
+    "Tunisia",
+    "Türkiye",
+    "Turkmenistan",
+    "Turks and Caicos Islands",
+    "Tuvalu",
+    "Uganda",
+    "Ukraine",
+    "United Arab Emirates",
+    "United Kingdom",
+    "United States",
+    "United States Minor Outlying Islands",
+    "Uruguay",
+    "Uzbekistan",
+    "Vanuatu",
+    "Venezuela",
+    "Viet Nam",
+    "Virgin Islands (British)",
+    "Virgin Islands (U.S.)",
+    "Wallis and Futuna",
+    "Western Sahara",
+    "Yemen",
+    "Zambia",
+    "Zimbabwe",
+];
+
+/// Requirements the allocator sets on top of the issue template.
+#[derive(Debug, Clone, Default)]
+pub struct AllocatorRequirements {
+    pub required_replicas: Option<u32>,
+    pub required_sps: Option<u32>,
+}
+
+impl AllocatorRequirements {
+    /// Reads the requirements from the allocator settings, which hold values
+    /// such as `"4"` or `"4+"`.
+    pub fn from_settings(required_replicas: Option<&str>, required_sps: Option<&str>) -> Self {
+        Self {
+            required_replicas: required_replicas.and_then(leading_number),
+            required_sps: required_sps.and_then(leading_number),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldIssue {
+    pub field: ParsedApplicationDataFields,
+    pub severity: Severity,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub template_version: String,
+    pub issues: Vec<FieldIssue>,
+}
+
+impl ValidationReport {
+    pub fn has_errors(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| issue.severity == Severity::Error)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn error(&mut self, field: ParsedApplicationDataFields, message: String) {
+        self.issues.push(FieldIssue {
+            field,
+            severity: Severity::Error,
+            message,
+        });
+    }
+
+    fn warning(&mut self, field: ParsedApplicationDataFields, message: String) {
+        self.issues.push(FieldIssue {
+            field,
+            severity: Severity::Warning,
+            message,
+        });
+    }
+
+    /// Checklist comment asking the client to fix the reported fields.
+    pub fn to_comment(&self) -> String {
+        let mut comment = String::from("### Application validation\n\n");
+        if self.has_errors() {
+            comment.push_str(
+                "Your application could not be processed. Please edit the issue and fix the fields below.\n",
+            );
+        } else {
+            comment
+                .push_str("Your application was accepted, but please review the fields below.\n");
+        }
+        for (severity, title) in [(Severity::Error, "Errors"), (Severity::Warning, "Warnings")] {
+            let issues: Vec<&FieldIssue> = self
+                .issues
+                .iter()
+                .filter(|issue| issue.severity == severity)
+                .collect();
+            if issues.is_empty() {
+                continue;
+            }
+            comment.push_str(&format!("\n#### {title}\n"));
+            for issue in issues {
+                comment.push_str(&format!(
+                    "- [ ] **{}**: {}\n",
+                    field_label(issue.field),
+                    issue.message
+                ));
+            }
+        }
+        comment
+    }
+}
+
+/// Checks the raw issue fields and collects every problem instead of
+/// stopping at the first one. Template problems are errors; falling short of
+/// the allocator requirements is a warning, as the allocator may still accept
+/// the application.
+pub fn validate_fields(
+    template_version: &str,
+    fields: &TemplateFields,
+    requirements: &AllocatorRequirements,
+) -> ValidationReport {
+    let mut report = ValidationReport {
+        template_version: template_version.to_string(),
+        issues: vec![],
+    };
+    let value = |field| {
+        fields
+            .get(field)
+            .map(str::trim)
+            .filter(|value| !value.is_empty() && *value != NO_RESPONSE)
+    };
+
+    for field in REQUIRED_FIELDS {
+        if value(field).is_none() {
+            report.error(field, "This field is required.".to_string());
+        }
+    }
+
+    if let Some(address) = value(ParsedApplicationDataFields::Address) {
+        if Network::Mainnet.parse_address(address).is_err()
+            && Network::Testnet.parse_address(address).is_err()
+        {
+            report.error(
+                ParsedApplicationDataFields::Address,
+                format!("`{address}` is not a valid Filecoin address."),
+            );
+        }
+    }
+
+    if let Some(region) = value(ParsedApplicationDataFields::Region) {
+        let known_regions = get_env_var_or_default("KNOWN_REGIONS");
+        let is_known = if known_regions.trim().is_empty() {
+            TEMPLATE_REGIONS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(region))
+        } else {
+            known_regions
+                .split(';')
+                .any(|known| known.trim().eq_ignore_ascii_case(region))
+        };
+        if !is_known {
+            report.warning(
+                ParsedApplicationDataFields::Region,
+                format!("`{region}` is not a known region."),
+            );
+        }
+    }
+
+    if let Some(group) = value(ParsedApplicationDataFields::DatacapGroup) {
+        if let Err(e) = DatacapGroup::from_str(group) {
+            report.error(ParsedApplicationDataFields::DatacapGroup, format!("{e}."));
+        }
+    }
+
+    if let Some(data_type) = value(ParsedApplicationDataFields::Type) {
+        if let Err(e) = DataType::from_str(data_type) {
+            report.error(ParsedApplicationDataFields::Type, format!("{e}."));
+        }
+    }
+
+    let mut size = |field| {
+        let amount = value(field)?;
+        match parse_size_to_bytes(&process_amount(amount.to_string())) {
+            Ok(bytes) if bytes > 0 => Some(bytes),
+            _ => {
+                report.error(
+                    field,
+                    format!("`{amount}` is not a valid size, use a value such as `5PiB`."),
+                );
+                None
+            }
+        }
+    };
+    let total = size(ParsedApplicationDataFields::TotalRequestedAmount);
+    let weekly = size(ParsedApplicationDataFields::WeeklyAllocation);
+    if let (Some(total), Some(weekly)) = (total, weekly) {
+        if weekly > total {
+            report.error(
+                ParsedApplicationDataFields::WeeklyAllocation,
+                "Weekly allocation is larger than the total amount requested.".to_string(),
+            );
+        }
+    }
+
+    if let Some(replicas) = value(ParsedApplicationDataFields::Replicas) {
+        match replicas.parse::<u8>() {
+            Ok(replicas) => {
+                if let Some(required) = requirements.required_replicas {
+                    if u32::from(replicas) < required {
+                        report.warning(
+                            ParsedApplicationDataFields::Replicas,
+                            format!("This allocator requires at least {required} replicas, {replicas} requested."),
+                        );
+                    }
+                }
+            }
+            Err(_) => report.error(
+                ParsedApplicationDataFields::Replicas,
+                format!("`{replicas}` is not a valid number of replicas."),
+            ),
+        }
+    }
+
+    if let Some(required) = requirements.required_sps {
+        let providers: HashSet<String> = value(ParsedApplicationDataFields::ProviderIDs)
+            .map(|providers| {
+                PROVIDER_ID
+                    .find_iter(providers)
+                    .map(|id| {
+                        id.as_str()
+                            .chars()
+                            .filter(char::is_ascii_alphanumeric)
+                            .collect::<String>()
+                            .to_lowercase()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        if (providers.len() as u32) < required {
+            report.warning(
+                ParsedApplicationDataFields::ProviderIDs,
+                format!(
+                    "This allocator requires at least {required} storage provider IDs, {} listed.",
+                    providers.len()
+                ),
+            );
+        }
+    }
+
+    report
+}
+
+/// Parses and validates an issue body.
+pub fn validate_issue(
+    body: &str,
+    labels: &[String],
+    requirements: &AllocatorRequirements,
+) -> Result<ValidationReport, LDNError> {
+    let (template, fields) = super::template_fields(body, labels)?;
+    Ok(validate_fields(template.version(), &fields, requirements))
+}
+
+fn leading_number(value: &str) -> Option<u32> {
+    let digits: String = value
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+fn field_label(field: ParsedApplicationDataFields) -> &'static str {
+    match field {
+        ParsedApplicationDataFields::Address => "On-chain address for first allocation",
+        ParsedApplicationDataFields::Name => "Data Owner Name",
+        ParsedApplicationDataFields::Region => "Data Owner Country/Region",
+        ParsedApplicationDataFields::DatacapGroup => "Group",
+        ParsedApplicationDataFields::Type => "Type",
+        ParsedApplicationDataFields::TotalRequestedAmount => {
+            "Total amount of DataCap being requested"
+        }
+        ParsedApplicationDataFields::WeeklyAllocation => "Weekly allocation of DataCap requested",
+        ParsedApplicationDataFields::Replicas => "Number of replicas to store",
+        ParsedApplicationDataFields::ProviderIDs => "Storage provider IDs",
+        _ => "Application",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(values: &[(ParsedApplicationDataFields, &str)]) -> TemplateFields {
+        TemplateFields(
+            values
+                .iter()
+                .map(|(field, value)| (*field, value.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn reports_every_invalid_field() {
+        let fields = fields(&[
+            (ParsedApplicationDataFields::Address, "x1abc"),
+            (ParsedApplicationDataFields::Name, "Dataset"),
+            (ParsedApplicationDataFields::Region, "Europe"),
+            (ParsedApplicationDataFields::TotalRequestedAmount, "5PiB"),
+            (ParsedApplicationDataFields::WeeklyAllocation, "6PiB"),
+            (ParsedApplicationDataFields::Replicas, "2"),
+            (
+                ParsedApplicationDataFields::ProviderIDs,
+                "f01234 Europe, F0 1234, t0-5678 Asia",
+            ),
+        ]);
+        let requirements = AllocatorRequirements::from_settings(Some("4+"), Some("3"));
+        let report = validate_fields("1.3", &fields, &requirements);
+
+        let issues = |severity| -> Vec<ParsedApplicationDataFields> {
+            report
+                .issues
+                .iter()
+                .filter(|issue| issue.severity == severity)
+                .map(|issue| issue.field)
+                .collect()
+        };
+        assert_eq!(
+            issues(Severity::Error),
+            vec![
+                ParsedApplicationDataFields::Address,
+                ParsedApplicationDataFields::WeeklyAllocation,
+            ]
+        );
+        assert_eq!(
+            issues(Severity::Warning),
+            vec![
+                ParsedApplicationDataFields::Region,
+                ParsedApplicationDataFields::Replicas,
+                ParsedApplicationDataFields::ProviderIDs,
+            ]
+        );
+        assert!(report
+            .to_comment()
+            .contains("- [ ] **Number of replicas to store**"));
+    }
+
+    #[test]
+    fn missing_and_unparsable_fields_are_errors() {
+        let fields = fields(&[
+            (
+                ParsedApplicationDataFields::Address,
+                "f1qoxqy3npwcvoqy7gpstm65lejcy7pkd3hqqekna",
+            ),
+            (ParsedApplicationDataFields::Name, "_No response_"),
+            (ParsedApplicationDataFields::Region, "korea, republic of"),
+            (ParsedApplicationDataFields::TotalRequestedAmount, "lots"),
+            (ParsedApplicationDataFields::WeeklyAllocation, "1TiB"),
+            (ParsedApplicationDataFields::Replicas, "four"),
+        ]);
+        let report = validate_fields("1.3", &fields, &AllocatorRequirements::default());
+
+        let fields: Vec<ParsedApplicationDataFields> =
+            report.issues.iter().map(|issue| issue.field).collect();
+        assert_eq!(
+            fields,
+            vec![
+                ParsedApplicationDataFields::Name,
+                ParsedApplicationDataFields::TotalRequestedAmount,
+                ParsedApplicationDataFields::Replicas,
+            ]
+        );
+        assert!(report.has_errors());
+    }
+}
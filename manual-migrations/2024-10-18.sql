@@ -1,2 +0,0 @@
-ALTER TABLE IF EXISTS public.applications
-    ADD COLUMN client_contract_address text;
\ No newline at end of file
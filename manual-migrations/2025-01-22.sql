@@ -1,2 +0,0 @@
-ALTER TABLE IF EXISTS public.applications
-    ADD COLUMN issue_reporter_handle text;
\ No newline at end of file
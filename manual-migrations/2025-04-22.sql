@@ -1,2 +0,0 @@
-ALTER TABLE IF EXISTS public.allocators
-    ADD COLUMN ma_address text;
\ No newline at end of file